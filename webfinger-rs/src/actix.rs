@@ -50,6 +50,47 @@
 //! Actix router-owned `404 Not Found` or method-mismatch responses should add route or middleware
 //! handling at the application boundary where those responses are generated.
 //!
+//! Actix routes `GET` and `HEAD` separately. Mount the same handler for `HEAD` so monitoring tools
+//! can probe the endpoint; the responder omits the JRD body for `HEAD` requests. Mount
+//! [`preflight`] for `OPTIONS` to answer browser CORS preflight requests. Register a
+//! [`CorsPolicy`] with `App::app_data` to restrict which origins may read responses; the responder,
+//! extractor errors, and [`preflight`] all use it, and fall back to [`CorsPolicy::default`]
+//! otherwise:
+//!
+//! ```rust
+//! use actix_web::http::Method;
+//! use actix_web::{App, web};
+//! use webfinger_rs::{AllowOrigin, CorsPolicy, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let policy =
+//!     CorsPolicy::new().allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+//! let app = App::new().app_data(policy).service(
+//!     web::resource(WELL_KNOWN_PATH)
+//!         .route(web::get().to(webfinger))
+//!         .route(web::head().to(webfinger))
+//!         .route(web::method(Method::OPTIONS).to(webfinger_rs::actix::preflight)),
+//! );
+//! # let _ = app;
+//! ```
+//!
+//! Register [`ExtractionLimits`] with `App::app_data` to bound the query length, `resource`
+//! length, number and length of `rel` parameters, and the allowed `resource` schemes; the
+//! extractor applies [`ExtractionLimits::default`] otherwise. Behind a reverse proxy, register a
+//! [`HostPolicy`] the same way to take the host from `Forwarded` or `X-Forwarded-Host` when the
+//! connecting peer is a trusted proxy, and to reject hosts the server does not serve.
+//!
+//! Register a [`CachePolicy`] with `App::app_data` the same way to send `Cache-Control: max-age`
+//! on successful responses. The responder always sends a strong `ETag` and answers matching
+//! `If-None-Match` requests with `304 Not Modified`.
+//!
+//! Register [`ErrorFormat::Problem`] with `App::app_data` to answer extraction errors with RFC 9457
+//! `application/problem+json` bodies whose `code` member identifies the cause, instead of plain
+//! text.
+//!
 //! See also [`WebFingerRequest`] for the extractor impl, [`WebFingerResponse`] for the responder
 //! impl, and the [Actix example] for a runnable server.
 //!
@@ -59,32 +100,44 @@
 
 use std::future::{Ready, ready};
 
+use actix_web::body::BoxBody;
 use actix_web::dev::Payload;
-use actix_web::http::StatusCode;
-use actix_web::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE, HeaderValue};
-use actix_web::web::Json;
+use actix_web::http::header::{
+    ACCEPT, ALLOW, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue, IF_NONE_MATCH, ORIGIN, VARY,
+};
+use actix_web::http::{Method, StatusCode};
 use actix_web::{
     Error as ActixError, FromRequest, HttpRequest, HttpResponse, Responder, ResponseError,
 };
 use tracing::trace;
 
-use crate::http::CORS_ALLOW_ORIGIN;
-use crate::query::{RequestParams, RequestParamsError};
-use crate::{Rel, WebFingerRequest, WebFingerResponse};
-
-const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static(CORS_ALLOW_ORIGIN);
-const JRD_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/jrd+json");
+use crate::{
+    ALLOWED_METHODS, CachePolicy, CorsPolicy, ErrorFormat, ExtractionLimits, HostPolicy,
+    RequestError, ResponseFormat, WebFingerRequest, WebFingerResponse, etag_matches,
+};
 
 impl Responder for WebFingerResponse {
     /// Converts a [`WebFingerResponse`] into an Actix response.
     ///
-    /// This serializes the body as JSON and sets the `Content-Type` header to
-    /// `application/jrd+json`, which is the JRD media type used by WebFinger.
-    /// It also sets `Access-Control-Allow-Origin: *` as recommended by RFC 7033 section 5.
+    /// This renders the body in the format negotiated from the request's `Accept` header (see
+    /// [`ResponseFormat::negotiate`]) and sets the matching `Content-Type` header. Requests without
+    /// a preference receive `application/jrd+json`, the JRD media type used by WebFinger, and
+    /// requests that accept none of the supported formats receive `406 Not Acceptable`.
+    /// It also sets `Access-Control-Allow-Origin: *` as recommended by RFC 7033 section 5, or the
+    /// headers of a [`CorsPolicy`] registered with `App::app_data`. For `HEAD` requests the body is
+    /// empty.
+    ///
+    /// Successful responses carry a strong `ETag` from [`WebFingerResponse::etag`], `Vary: Accept`,
+    /// and the `Cache-Control` header of a [`CachePolicy`] registered with `App::app_data`. A
+    /// request whose `If-None-Match` header matches the entity tag receives `304 Not Modified`.
     ///
     /// Handlers can therefore return [`WebFingerResponse`] directly without manually wrapping it in
     /// [`actix_web::web::Json`] or setting the response header themselves.
     ///
+    /// Actix passes the request to responders, so negotiation needs no extractor here. The Axum
+    /// `IntoResponse` impl cannot see the request and always sends JRD; Axum handlers return
+    /// `webfinger_rs::axum::Negotiated` to get this behavior.
+    ///
     /// See also the [`crate::actix`] module docs and the [Actix example].
     ///
     /// # Example
@@ -112,16 +165,50 @@ impl Responder for WebFingerResponse {
     ///
     /// [Actix example]:
     ///     https://github.com/joshka/webfinger-rs/blob/main/webfinger-rs/examples/actix.rs
-    type Body = <Json<WebFingerResponse> as Responder>::Body;
+    type Body = BoxBody;
 
     fn respond_to(self, request: &HttpRequest) -> HttpResponse<Self::Body> {
-        let mut response = Json(self).respond_to(request);
-        response
-            .headers_mut()
-            .insert(ACCESS_CONTROL_ALLOW_ORIGIN, CORS_ALLOW_ORIGIN_HEADER);
-        response
-            .headers_mut()
-            .insert(CONTENT_TYPE, JRD_CONTENT_TYPE);
+        let accept = request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok());
+        let Some(format) = ResponseFormat::negotiate(accept) else {
+            let mut response = HttpResponse::NotAcceptable().body("not acceptable");
+            insert_policy_headers(response.headers_mut(), cors_headers(request));
+            return response;
+        };
+
+        let etag = self.etag(format);
+        let not_modified = request
+            .headers()
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| etag_matches(value, &etag));
+        let mut response = if not_modified {
+            HttpResponse::NotModified().finish()
+        } else {
+            match format.render(&self) {
+                Ok(body) => HttpResponse::Ok()
+                    .insert_header((CONTENT_TYPE, format.content_type()))
+                    .body(body),
+                Err(error) => {
+                    let mut response = HttpResponse::InternalServerError().body(error.to_string());
+                    insert_policy_headers(response.headers_mut(), cors_headers(request));
+                    return response;
+                }
+            }
+        };
+        if request.method() == Method::HEAD {
+            response = response.map_body(|_, _| BoxBody::new(()));
+        }
+        let headers = response.headers_mut();
+        insert_policy_headers(headers, cors_headers(request));
+        let cache = match request.app_data::<CachePolicy>() {
+            Some(policy) => policy.response_headers(&etag),
+            None => CachePolicy::default().response_headers(&etag),
+        };
+        insert_policy_headers(headers, cache);
+        headers.append(VARY, HeaderValue::from_static("Accept"));
         response
     }
 }
@@ -144,6 +231,10 @@ impl FromRequest for WebFingerRequest {
     /// - If the request has no URI authority and no `Host` header, extraction returns a bad request.
     /// - If the query contains malformed percent encoding, extraction returns a bad request.
     /// - If `resource` is present but cannot be parsed as a URI, extraction returns a bad request.
+    /// - If the request exceeds the [`ExtractionLimits`] registered with `App::app_data`, or the
+    ///   default limits, extraction returns a bad request naming the limit.
+    /// - If the [`HostPolicy`] registered with `App::app_data` rejects the host, extraction returns a
+    ///   bad request.
     ///
     /// See also the [`crate::actix`] module docs and the [Actix example].
     ///
@@ -183,45 +274,105 @@ fn extract_request(req: &HttpRequest) -> Result<WebFingerRequest, ActixError> {
     let host = req
         .uri()
         .host()
-        .or_else(|| req.headers().get("host").and_then(|h| h.to_str().ok()))
-        .map(|h| h.to_string())
-        .ok_or_else(|| bad_request("missing host"))?;
-    let query: RequestParams = req.query_string().parse()?;
-    let rels = query
-        .rel
-        .into_iter()
-        .map(Rel::try_new)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(bad_request)?;
-    Ok(WebFingerRequest {
-        host,
-        resource: query.resource,
-        rels,
-    })
-}
-
-impl From<RequestParamsError> for ActixError {
-    fn from(error: RequestParamsError) -> Self {
-        bad_request(error)
-    }
+        .or_else(|| req.headers().get("host").and_then(|h| h.to_str().ok()));
+    let header_values = |name| {
+        req.headers()
+            .get_all(name)
+            .filter_map(|value| value.to_str().ok())
+    };
+    let default_host_policy = HostPolicy::default();
+    let host_policy = req.app_data().unwrap_or(&default_host_policy);
+    let peer = req.peer_addr().map(|address| address.ip());
+    let host = host_policy
+        .resolve_host(
+            peer,
+            host,
+            header_values("forwarded"),
+            header_values("x-forwarded-host"),
+        )
+        .map_err(|error| bad_request(req, error))?;
+    let default_limits = ExtractionLimits::default();
+    let limits = req.app_data().unwrap_or(&default_limits);
+    WebFingerRequest::from_query_with_limits(&host, req.query_string(), limits)
+        .map_err(|error| bad_request(req, error))
 }
 
-fn bad_request(error: impl ToString) -> ActixError {
+fn bad_request(req: &HttpRequest, error: RequestError) -> ActixError {
+    let format = req.app_data().copied().unwrap_or_default();
+    let (content_type, body) = ErrorFormat::render(format, &error);
     WebFingerBadRequest {
         message: error.to_string(),
+        content_type,
+        body,
+        cors: cors_headers(req),
     }
     .into()
 }
 
+/// Answers a CORS preflight request for the WebFinger endpoint.
+///
+/// Mount this handler for `OPTIONS` requests to [`crate::WELL_KNOWN_PATH`]. It returns
+/// `204 No Content` with `Allow` and the `Access-Control-Allow-Methods`,
+/// `Access-Control-Allow-Headers`, and `Access-Control-Max-Age` headers from the [`CorsPolicy`]
+/// registered with `App::app_data`, or from [`CorsPolicy::default`].
+///
+/// See the [`crate::actix`] module docs for an example.
+pub async fn preflight(req: HttpRequest) -> HttpResponse {
+    let policy = req.app_data::<CorsPolicy>().cloned().unwrap_or_default();
+    let mut response = HttpResponse::NoContent()
+        .insert_header((ALLOW, ALLOWED_METHODS))
+        .finish();
+    insert_policy_headers(
+        response.headers_mut(),
+        policy.preflight_headers(request_origin(&req)),
+    );
+    response
+}
+
+/// Returns the CORS headers for a WebFinger response to `req`.
+fn cors_headers(req: &HttpRequest) -> Vec<(&'static str, String)> {
+    match req.app_data::<CorsPolicy>() {
+        Some(policy) => policy.response_headers(request_origin(req)),
+        None => CorsPolicy::default().response_headers(request_origin(req)),
+    }
+}
+
+fn request_origin(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+}
+
+/// Writes policy headers, appending `Vary` so values set by the handler are kept.
+///
+/// Actix Web has its own header types, so this mirrors [`crate::insert_policy_headers`].
+fn insert_policy_headers(headers: &mut HeaderMap, policy: Vec<(&'static str, String)>) {
+    for (name, value) in policy {
+        let Ok(value) = HeaderValue::try_from(value) else {
+            continue;
+        };
+        let name = HeaderName::from_static(name);
+        if name == VARY {
+            headers.append(name, value);
+        } else {
+            headers.insert(name, value);
+        }
+    }
+}
+
 /// Bad-request response for malformed WebFinger endpoint requests.
 ///
 /// Actix's generic bad-request helper renders the right status and body but does not know that
 /// WebFinger endpoint errors need to be readable from browsers. Keeping this tiny response error
 /// local to the extractor preserves the existing error messages while adding the endpoint CORS
-/// header only to responses this adapter owns.
+/// header only to responses this adapter owns. The body is pre-rendered in the [`ErrorFormat`]
+/// registered with `App::app_data`.
 #[derive(Debug)]
 struct WebFingerBadRequest {
     message: String,
+    content_type: &'static str,
+    body: String,
+    cors: Vec<(&'static str, String)>,
 }
 
 impl std::fmt::Display for WebFingerBadRequest {
@@ -236,9 +387,11 @@ impl ResponseError for WebFingerBadRequest {
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
-            .insert_header((ACCESS_CONTROL_ALLOW_ORIGIN, CORS_ALLOW_ORIGIN_HEADER))
-            .body(self.message.clone())
+        let mut response = HttpResponse::build(self.status_code())
+            .insert_header((CONTENT_TYPE, self.content_type))
+            .body(self.body.clone());
+        insert_policy_headers(response.headers_mut(), self.cors.clone());
+        response
     }
 }
 
@@ -246,11 +399,18 @@ impl ResponseError for WebFingerBadRequest {
 mod tests {
     use actix_web::body::to_bytes;
     use actix_web::http::StatusCode;
+    use actix_web::http::header::{
+        ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+        ACCESS_CONTROL_MAX_AGE, CACHE_CONTROL, ETAG,
+    };
     use actix_web::{App, HttpResponse, test, web};
 
     use super::*;
     use crate::WELL_KNOWN_PATH;
 
+    const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
+    const JRD_CONTENT_TYPE: HeaderValue = HeaderValue::from_static(crate::JRD_CONTENT_TYPE);
+
     type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
 
     /// Returns the extracted resource so tests can assert RFC 7033 query decoding behavior.
@@ -352,6 +512,99 @@ mod tests {
         Ok(())
     }
 
+    /// Answers `HEAD` like `GET` without a body.
+    ///
+    /// Monitoring tools probe endpoints with `HEAD`; the responder keeps the JRD headers and drops
+    /// the document.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-9.3.2>.
+    #[actix_web::test]
+    async fn head_request_returns_headers_without_body() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::head().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::default()
+            .method(Method::HEAD)
+            .uri(WELL_KNOWN_PATH)
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE),
+            Some(&JRD_CONTENT_TYPE),
+        );
+        let body = to_bytes(response.into_body()).await?;
+        assert!(body.is_empty());
+        Ok(())
+    }
+
+    /// Answers browser CORS preflight requests with the default policy.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[actix_web::test]
+    async fn preflight_returns_cors_headers() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::method(Method::OPTIONS).to(preflight));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((ORIGIN, "https://app.example"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(headers.get(ALLOW).unwrap(), ALLOWED_METHODS);
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            ALLOWED_METHODS,
+        );
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(), "Accept");
+        assert_eq!(headers.get(ACCESS_CONTROL_MAX_AGE).unwrap(), "86400");
+        Ok(())
+    }
+
+    /// Applies a [`CorsPolicy`] registered as app data to responses and extractor errors.
+    #[actix_web::test]
+    async fn app_data_policy_restricts_origin() -> Result {
+        let policy = CorsPolicy::new().allow_origin(crate::AllowOrigin::List(vec![
+            "https://app.example".to_string(),
+        ]));
+        let app = App::new()
+            .app_data(policy)
+            .route(WELL_KNOWN_PATH, web::get().to(webfinger_response))
+            .route("/bad", web::get().to(webfinger));
+        let app = test::init_service(app).await;
+
+        let request = test::TestRequest::get()
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((ORIGIN, "https://app.example"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://app.example",
+        );
+        assert_eq!(response.headers().get(VARY).unwrap(), "Origin");
+
+        let request = test::TestRequest::get()
+            .uri("/bad")
+            .insert_header(("host", "example.org"))
+            .insert_header((ORIGIN, "https://other.example"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        assert_eq!(response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN), None);
+        Ok(())
+    }
+
     /// Returns WebFinger responses with the registered JRD media type.
     ///
     /// RFC 7033 section 4.2 defines `application/jrd+json` as the media type for JSON Resource
@@ -768,4 +1021,230 @@ mod tests {
         assert_eq!(body.as_ref(), b"missing host");
         Ok(())
     }
+
+    /// Rejects requests over the limits registered with `App::app_data`.
+    #[actix_web::test]
+    async fn app_data_limits_reject_long_resource() -> Result {
+        let limits = ExtractionLimits::new().max_resource_length(8);
+        let app = App::new()
+            .app_data(limits)
+            .route(WELL_KNOWN_PATH, web::get().to(webfinger));
+        let app = test::init_service(app).await;
+        let uri = format!("{WELL_KNOWN_PATH}?resource=acct%3Acarol%40example.org");
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("host", "example.org"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        let body = to_bytes(response.into_body()).await?;
+        assert_eq!(
+            body.as_ref(),
+            b"resource is too long; maximum is 8 characters"
+        );
+        Ok(())
+    }
+
+    /// Answers extraction errors with problem details when registered with `App::app_data`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3>.
+    #[actix_web::test]
+    async fn app_data_error_format_renders_problem_details() -> Result {
+        let app = App::new()
+            .app_data(ErrorFormat::Problem)
+            .route(WELL_KNOWN_PATH, web::get().to(webfinger));
+        let app = test::init_service(app).await;
+        let uri = format!("{WELL_KNOWN_PATH}?resource=acct%ZZcarol");
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("host", "example.org"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(CONTENT_TYPE).unwrap(),
+            crate::PROBLEM_CONTENT_TYPE
+        );
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = to_bytes(response.into_body()).await?;
+        let problem: crate::Problem = serde_json::from_slice(&body)?;
+        assert_eq!(problem.code.as_deref(), Some("invalid_percent_encoding"));
+        Ok(())
+    }
+
+    /// Takes the host from `X-Forwarded-Host` when the peer is a trusted proxy.
+    #[actix_web::test]
+    async fn app_data_host_policy_trusts_proxy() -> Result {
+        async fn host(request: WebFingerRequest) -> HttpResponse {
+            HttpResponse::Ok().body(request.host)
+        }
+        let proxy: std::net::SocketAddr = "10.0.0.1:40000".parse()?;
+        let policy = HostPolicy::new()
+            .trusted_proxies([proxy.ip()])
+            .allowed_hosts(["example.com"]);
+        let app = App::new()
+            .app_data(policy)
+            .route(WELL_KNOWN_PATH, web::get().to(host));
+        let app = test::init_service(app).await;
+        let uri = format!("{WELL_KNOWN_PATH}?resource=acct%3Acarol%40example.com");
+
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .peer_addr(proxy)
+            .insert_header(("host", "backend.internal"))
+            .insert_header(("x-forwarded-host", "example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        let body = to_bytes(response.into_body()).await?;
+        assert_eq!(body.as_ref(), b"example.com");
+
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .peer_addr("203.0.113.7:40000".parse()?)
+            .insert_header(("host", "backend.internal"))
+            .insert_header(("x-forwarded-host", "example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        let body = to_bytes(response.into_body()).await?;
+        assert_eq!(body.as_ref(), b"host is not allowed: backend.internal");
+        Ok(())
+    }
+
+    /// Serves `application/json` to clients that only accept plain JSON.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-10.2>.
+    #[actix_web::test]
+    async fn responder_serves_plain_json() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::get().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((ACCEPT, "application/json"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/json")),
+        );
+        Ok(())
+    }
+
+    /// Serves an RFC 6415 XRD document to clients that ask for `application/xrd+xml`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[cfg(feature = "xrd")]
+    #[actix_web::test]
+    async fn responder_serves_xrd() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::get().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((ACCEPT, "application/xrd+xml"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/xrd+xml")),
+        );
+        let body = to_bytes(response.into_body()).await?;
+        let body = std::str::from_utf8(&body)?;
+        assert!(
+            body.contains("<Subject>acct:carol@example.com</Subject>"),
+            "{body}"
+        );
+        Ok(())
+    }
+
+    /// Rejects requests that accept none of the supported formats, keeping the CORS header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.5.7>.
+    #[actix_web::test]
+    async fn responder_rejects_unacceptable_format() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::get().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((ACCEPT, "text/html"))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_ACCEPTABLE,
+            "{response:?}"
+        );
+        assert_eq!(
+            response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = to_bytes(response.into_body()).await?;
+        assert_eq!(body.as_ref(), b"not acceptable");
+        Ok(())
+    }
+
+    /// Sends the registered cache lifetime, an entity tag, and `Vary: Accept`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9111.html#section-5.2.2.1>.
+    #[actix_web::test]
+    async fn responder_sets_cache_headers() -> Result {
+        let policy = CachePolicy::new().max_age(std::time::Duration::from_secs(300));
+        let app = App::new()
+            .app_data(policy)
+            .route(WELL_KNOWN_PATH, web::get().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get().uri(WELL_KNOWN_PATH).to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(headers.get(CACHE_CONTROL).unwrap(), "max-age=300");
+        let etag = WebFingerResponse::new("acct:carol@example.com").etag(ResponseFormat::Jrd);
+        assert_eq!(headers.get(ETAG).unwrap(), etag.as_str());
+        assert_eq!(headers.get(VARY).unwrap(), "Accept");
+        Ok(())
+    }
+
+    /// Answers a matching `If-None-Match` revalidation with `304 Not Modified` and no body.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-13.1.2>.
+    #[actix_web::test]
+    async fn responder_answers_not_modified() -> Result {
+        let app = App::new().route(WELL_KNOWN_PATH, web::get().to(webfinger_response));
+        let app = test::init_service(app).await;
+        let etag = WebFingerResponse::new("acct:carol@example.com").etag(ResponseFormat::Jrd);
+        let request = test::TestRequest::get()
+            .uri(WELL_KNOWN_PATH)
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{response:?}");
+        assert_eq!(response.headers().get(ETAG).unwrap(), etag.as_str());
+        assert_eq!(
+            response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = to_bytes(response.into_body()).await?;
+        assert!(body.is_empty());
+        Ok(())
+    }
 }
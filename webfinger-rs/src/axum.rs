@@ -5,7 +5,8 @@
 //! - extract [`WebFingerRequest`] from handlers mounted for `GET` requests to
 //!   [`crate::WELL_KNOWN_PATH`]; and
 //! - return [`WebFingerResponse`] directly from Axum handlers as `application/jrd+json` with the
-//!   WebFinger CORS header.
+//!   WebFinger CORS header, or in the format the request accepts with the [`negotiate`]
+//!   middleware.
 //!
 //! The extractor expects the standard WebFinger query shape from [RFC 7033 section 4.1]:
 //!
@@ -43,12 +44,67 @@
 //! text message for missing or duplicated `resource`, missing host values, invalid percent
 //! encoding, relative resource references, or invalid resource URIs. Those rejection responses
 //! include the same `Access-Control-Allow-Origin: *` header as successful JRD responses so browser
-//! clients can inspect WebFinger endpoint errors.
+//! clients can inspect WebFinger endpoint errors. Add the [`error_format`] middleware with
+//! [`ErrorFormat::Problem`] to send RFC 9457 `application/problem+json` bodies whose `code` member
+//! identifies the cause instead.
 //!
 //! Path and method rejections happen before the extractor runs. Applications that need CORS on
 //! Axum's router-owned `404 Not Found` or `405 Method Not Allowed` responses should add route or
 //! middleware handling at the application boundary where those responses are generated.
 //!
+//! Axum's `get` method router also answers `HEAD` requests by running the handler and dropping the
+//! body, so monitoring tools can probe the endpoint without extra routes. To answer browser CORS
+//! preflight requests, or to restrict which origins may read responses, add the [`cors`]
+//! middleware with a [`CorsPolicy`] as a route layer:
+//!
+//! ```rust
+//! use axum::{Router, middleware, routing::get};
+//! use webfinger_rs::{AllowOrigin, CorsPolicy, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let policy =
+//!     CorsPolicy::new().allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+//! let app = Router::<()>::new()
+//!     .route(WELL_KNOWN_PATH, get(webfinger))
+//!     .route_layer(middleware::from_fn_with_state(policy, webfinger_rs::axum::cors));
+//! # let _ = app;
+//! ```
+//!
+//! Add the [`extraction_limits`] middleware with [`ExtractionLimits`] the same way to bound the
+//! query length, `resource` length, number and length of `rel` parameters, and the allowed
+//! `resource` schemes. Without it the extractor applies [`ExtractionLimits::default`]. Behind a
+//! reverse proxy, add the [`host_policy`] middleware with a [`HostPolicy`] to take the host from
+//! `Forwarded` or `X-Forwarded-Host` sent by trusted proxies and to reject hosts the server does
+//! not serve.
+//!
+//! Successful responses carry a strong `ETag` computed by [`WebFingerResponse::etag`]. Add the
+//! [`cache`] middleware with a [`CachePolicy`] the same way to send `Cache-Control: max-age` and to
+//! answer matching `If-None-Match` requests with `304 Not Modified`.
+//!
+//! Axum converts responses without access to the request, so returning [`WebFingerResponse`] on its
+//! own sends `application/jrd+json`. Add the [`negotiate`] middleware to render it in the format
+//! the request's `Accept` header asks for, with `406 Not Acceptable` for requests that accept none
+//! of the supported formats:
+//!
+//! ```rust
+//! use axum::{Router, middleware, routing::get};
+//! use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let app = Router::<()>::new()
+//!     .route(WELL_KNOWN_PATH, get(webfinger))
+//!     .route_layer(middleware::from_fn(webfinger_rs::axum::negotiate));
+//! # let _ = app;
+//! ```
+//!
+//! Handlers can also negotiate themselves: extract [`ResponseFormat`] and return [`Negotiated`].
+//!
 //! See also [`WebFingerRequest`] for the extractor impl, [`WebFingerResponse`] for the responder
 //! impl, and the [Axum example] for a runnable server.
 //!
@@ -56,20 +112,29 @@
 //! [Axum example]:
 //!     https://github.com/joshka/webfinger-rs/blob/main/webfinger-rs/examples/axum.rs
 
-use axum::Json;
-use axum::extract::FromRequestParts;
+use std::net::IpAddr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::net::SocketAddr;
+
+use axum::body::Body;
+#[cfg(not(target_arch = "wasm32"))]
+use axum::extract::ConnectInfo;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::middleware::Next;
 use axum::response::{IntoResponse, Response as AxumResponse};
-use http::header::{self, HOST};
+use http::header;
 use http::request::Parts;
-use http::{HeaderValue, StatusCode};
+use http::{HeaderValue, Method, StatusCode};
 use tracing::trace;
 
-use crate::http::CORS_ALLOW_ORIGIN;
-use crate::query::{RequestParams, RequestParamsError};
-use crate::{Rel, ResourceError, WebFingerRequest, WebFingerResponse};
+use crate::{
+    ALLOWED_METHODS, CachePolicy, CorsPolicy, ErrorFormat, ExtractionLimits, HostPolicy,
+    PROBLEM_CONTENT_TYPE, Problem, RequestError, ResourceError, ResponseFormat, WebFingerRequest,
+    WebFingerResponse, etag_matches, insert_policy_headers,
+};
 
-const JRD_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/jrd+json");
-const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static(CORS_ALLOW_ORIGIN);
+/// The `Access-Control-Allow-Origin` value of the default [`CorsPolicy`].
+const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
 
 impl IntoResponse for WebFingerResponse {
     /// Converts a [`WebFingerResponse`] into an Axum response.
@@ -81,6 +146,11 @@ impl IntoResponse for WebFingerResponse {
     /// Handlers can therefore return [`WebFingerResponse`] directly without manually wrapping it in
     /// [`axum::Json`] or setting the response header themselves.
     ///
+    /// Axum converts responses without access to the request, so this renders JRD and keeps the
+    /// response in the response extensions. The [`negotiate`] middleware re-renders it in the
+    /// format the request's `Accept` header asks for and adds `Vary: Accept`, as the Actix
+    /// `Responder` impl does on its own.
+    ///
     /// Mount the route at [`crate::WELL_KNOWN_PATH`] so the handler matches the standard WebFinger
     /// endpoint path.
     ///
@@ -116,17 +186,95 @@ impl IntoResponse for WebFingerResponse {
     /// [Axum example]:
     ///     https://github.com/joshka/webfinger-rs/blob/main/webfinger-rs/examples/axum.rs
     fn into_response(self) -> AxumResponse {
-        (
-            [
-                (header::CONTENT_TYPE, JRD_CONTENT_TYPE),
-                (
-                    header::ACCESS_CONTROL_ALLOW_ORIGIN,
-                    CORS_ALLOW_ORIGIN_HEADER,
-                ),
-            ],
-            Json(self),
+        let mut response = render(ResponseFormat::Jrd, &self);
+        // Kept so the `negotiate` middleware can re-render the body.
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// A [`WebFingerResponse`] rendered in a negotiated [`ResponseFormat`].
+///
+/// Extract the format from the request with the [`ResponseFormat`] extractor and return this
+/// wrapper instead of the bare response. The body is rendered with [`ResponseFormat::render`], the
+/// `Content-Type` header is set to [`ResponseFormat::content_type`], and the WebFinger CORS header
+/// and entity tag are included as for [`WebFingerResponse`]. `Vary: Accept` tells caches that the
+/// body depends on the request's `Accept` header. This matches what the Actix `Responder` impl
+/// for [`WebFingerResponse`] does on its own.
+///
+/// See the [`crate::axum`] module docs for an example.
+#[derive(Debug, Clone)]
+pub struct Negotiated(pub ResponseFormat, pub WebFingerResponse);
+
+impl IntoResponse for Negotiated {
+    fn into_response(self) -> AxumResponse {
+        let Negotiated(format, response) = self;
+        let mut response = render(format, &response);
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("Accept"));
+        response
+    }
+}
+
+/// Renders a response body with its content type, entity tag, and the default CORS header.
+fn render(format: ResponseFormat, response: &WebFingerResponse) -> AxumResponse {
+    let cors_header = (
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        CORS_ALLOW_ORIGIN_HEADER,
+    );
+    match format.render(response) {
+        Ok(body) => {
+            let content_type = (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            );
+            let etag = (header::ETAG, response.etag(format));
+            ([content_type, cors_header], [etag], body).into_response()
+        }
+        Err(error) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [cors_header],
+            error.to_string(),
         )
-            .into_response()
+            .into_response(),
+    }
+}
+
+/// Rejection returned by the [`ResponseFormat`] extractor.
+///
+/// The request's `Accept` header excludes every format this crate can render. This converts into a
+/// `406 Not Acceptable` response with the WebFinger CORS header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("not acceptable")]
+pub struct NotAcceptable;
+
+impl IntoResponse for NotAcceptable {
+    fn into_response(self) -> AxumResponse {
+        let cors_header = (
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            CORS_ALLOW_ORIGIN_HEADER,
+        );
+        (StatusCode::NOT_ACCEPTABLE, [cors_header], self.to_string()).into_response()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ResponseFormat {
+    type Rejection = NotAcceptable;
+
+    /// Negotiates the response format from the request's `Accept` header.
+    ///
+    /// See [`ResponseFormat::negotiate`] for the selection rules.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`NotAcceptable`] when the `Accept` header excludes every supported format.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let accept = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok());
+        ResponseFormat::negotiate(accept).ok_or(NotAcceptable)
     }
 }
 
@@ -141,12 +289,17 @@ impl IntoResponse for WebFingerResponse {
 ///   authority;
 /// - [`Rejection::InvalidQueryString`] when the query string is missing `resource`, contains more
 ///   than one `resource`, or contains malformed percent encoding;
-/// - [`Rejection::InvalidResource`] when the `resource` value is not an absolute URI.
+/// - [`Rejection::InvalidResource`] when the `resource` value is not an absolute URI;
+/// - [`Rejection::LimitExceeded`] when the request exceeds the [`ExtractionLimits`];
+/// - [`Rejection::InvalidHost`] when the [`HostPolicy`] rejects the request host.
 #[derive(Debug, thiserror::Error)]
 pub enum Rejection {
     /// The WebFinger query string is missing required data or is malformed.
-    #[error("{0}")]
-    InvalidQueryString(String),
+    ///
+    /// The [`RequestError`] is [`RequestError::MissingResource`],
+    /// [`RequestError::MultipleResources`], or [`RequestError::InvalidPercentEncoding`].
+    #[error(transparent)]
+    InvalidQueryString(RequestError),
 
     /// The `resource` query parameter is not an absolute URI.
     #[error("invalid resource: {0}")]
@@ -159,6 +312,75 @@ pub enum Rejection {
     /// A `rel` query parameter is invalid.
     #[error(transparent)]
     InvalidRel(crate::Error),
+
+    /// The request exceeds the [`ExtractionLimits`].
+    ///
+    /// The [`RequestError`] is one of the variants for which
+    /// [`RequestError::is_limit_exceeded`] returns `true`.
+    #[error(transparent)]
+    LimitExceeded(RequestError),
+
+    /// The [`HostPolicy`] rejects the request host.
+    ///
+    /// The [`RequestError`] is [`RequestError::HostNotAllowed`] or
+    /// [`RequestError::InvalidForwardedHost`].
+    #[error(transparent)]
+    InvalidHost(RequestError),
+}
+
+impl Rejection {
+    /// Returns the stable, machine-readable code for the cause, as [`RequestError::code`] does.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rejection::MissingHost => RequestError::MissingHost.code(),
+            Rejection::InvalidResource(_) => "invalid_resource",
+            Rejection::InvalidRel(_) => "invalid_rel",
+            Rejection::InvalidQueryString(error)
+            | Rejection::LimitExceeded(error)
+            | Rejection::InvalidHost(error) => error.code(),
+        }
+    }
+
+    /// Describes the rejection as an RFC 9457 [`Problem`].
+    pub fn problem(&self) -> Problem {
+        Problem::bad_request_with(self.message(), self.code())
+    }
+
+    /// Converts the rejection into a `400 Bad Request` response with a body in `format`.
+    ///
+    /// [`IntoResponse`] uses [`ErrorFormat::PlainText`]; the [`error_format`] middleware switches
+    /// extractor rejections to another format.
+    pub fn into_response_with(self, format: ErrorFormat) -> AxumResponse {
+        let problem = self.problem();
+        let body = match format {
+            ErrorFormat::Problem => problem.to_json(),
+            ErrorFormat::PlainText => self.message(),
+        };
+        let content_type = (
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        );
+        let cors_header = (
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            CORS_ALLOW_ORIGIN_HEADER,
+        );
+        let mut response =
+            (StatusCode::BAD_REQUEST, [content_type, cors_header], body).into_response();
+        // Kept so the `error_format` middleware can re-render the body.
+        response.extensions_mut().insert(problem);
+        response
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Rejection::MissingHost => "missing host".to_string(),
+            Rejection::InvalidResource(error) => format!("invalid resource: {error}"),
+            Rejection::InvalidRel(error) => error.to_string(),
+            Rejection::InvalidQueryString(error)
+            | Rejection::LimitExceeded(error)
+            | Rejection::InvalidHost(error) => error.to_string(),
+        }
+    }
 }
 
 impl IntoResponse for Rejection {
@@ -166,29 +388,26 @@ impl IntoResponse for Rejection {
     ///
     /// The body is a plain text error message intended to make local debugging and simple server
     /// implementations straightforward. The response includes the WebFinger CORS header so browser
-    /// clients can read malformed-request errors from the endpoint.
+    /// clients can read malformed-request errors from the endpoint. Add the [`error_format`]
+    /// middleware to send RFC 9457 problem details instead.
     ///
     /// See also the [`crate::axum`] module docs.
     fn into_response(self) -> AxumResponse {
-        let message = match self {
-            Rejection::MissingHost => "missing host".to_string(),
-            Rejection::InvalidQueryString(error) => error,
-            Rejection::InvalidResource(error) => format!("invalid resource: {error}"),
-            Rejection::InvalidRel(error) => error.to_string(),
-        };
-        let cors_header = (
-            header::ACCESS_CONTROL_ALLOW_ORIGIN,
-            CORS_ALLOW_ORIGIN_HEADER,
-        );
-        (StatusCode::BAD_REQUEST, [cors_header], message).into_response()
+        self.into_response_with(ErrorFormat::PlainText)
     }
 }
 
-impl From<RequestParamsError> for Rejection {
-    fn from(error: RequestParamsError) -> Self {
+impl From<RequestError> for Rejection {
+    fn from(error: RequestError) -> Self {
         match error {
-            RequestParamsError::InvalidResource(error) => Rejection::InvalidResource(error),
-            error => Rejection::InvalidQueryString(error.to_string()),
+            RequestError::MissingHost => Rejection::MissingHost,
+            RequestError::InvalidResource(error) => Rejection::InvalidResource(error),
+            RequestError::InvalidRel(error) => Rejection::InvalidRel(error),
+            error if error.is_limit_exceeded() => Rejection::LimitExceeded(error),
+            error @ (RequestError::HostNotAllowed(_) | RequestError::InvalidForwardedHost(_)) => {
+                Rejection::InvalidHost(error)
+            }
+            error => Rejection::InvalidQueryString(error),
         }
     }
 }
@@ -205,7 +424,9 @@ impl<S: Send + Sync> FromRequestParts<S> for WebFingerRequest {
     ///
     /// Host resolution follows this order:
     ///
-    /// 1. Use the authority from `parts.uri` when the request URI is absolute.
+    /// 1. Use the forwarded host when the peer is a trusted proxy of the [`HostPolicy`] in the
+    ///    request extensions.
+    /// 1. Otherwise, use the authority from `parts.uri` when the request URI is absolute.
     /// 1. Otherwise, fall back to the HTTP `Host` header.
     ///
     /// The extracted host, parsed resource, and collected relations are used to construct the
@@ -219,6 +440,10 @@ impl<S: Send + Sync> FromRequestParts<S> for WebFingerRequest {
     ///   malformed percent encoding, extraction fails with `Rejection::InvalidQueryString`.
     /// - If `resource` is present but cannot be parsed as a URI, extraction fails with
     ///   `Rejection::InvalidResource`.
+    /// - If the request exceeds the [`ExtractionLimits`] in the request extensions, or the default
+    ///   limits, extraction fails with `Rejection::LimitExceeded`.
+    /// - If the [`HostPolicy`] in the request extensions rejects the host, extraction fails with
+    ///   `Rejection::InvalidHost`.
     ///
     /// See also the [`crate::axum`] module docs and the [Axum example].
     ///
@@ -241,35 +466,296 @@ impl<S: Send + Sync> FromRequestParts<S> for WebFingerRequest {
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         trace!("request parts: {:?}", parts);
 
-        let host = parts
-            .uri
-            .host()
-            .or_else(|| parts.headers.get(HOST).and_then(|host| host.to_str().ok()))
-            .map(str::to_string)
-            .ok_or(Rejection::MissingHost)?;
-
-        let query: RequestParams = parts.uri.query().unwrap_or("").parse()?;
-        let rels = query
-            .rel
-            .into_iter()
-            .map(Rel::try_new)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(Rejection::InvalidRel)?;
-
-        Ok(WebFingerRequest {
-            host,
-            resource: query.resource,
-            rels,
-        })
+        let default_limits = ExtractionLimits::default();
+        let limits = parts.extensions.get().unwrap_or(&default_limits);
+        let default_host_policy = HostPolicy::default();
+        let host_policy = parts.extensions.get().unwrap_or(&default_host_policy);
+        let host = host_policy.resolve_host_from_parts(parts, peer_ip(parts))?;
+        let query = parts.uri.query().unwrap_or_default();
+        Ok(WebFingerRequest::from_query_with_limits(
+            &host, query, limits,
+        )?)
     }
 }
 
+/// Returns the IP address of the connecting peer recorded by
+/// `into_make_service_with_connect_info::<SocketAddr>()`.
+#[cfg(not(target_arch = "wasm32"))]
+fn peer_ip(parts: &Parts) -> Option<IpAddr> {
+    parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())
+}
+
+/// Axum has no `ConnectInfo` on wasm targets, so no peer is known and no proxy is trusted.
+#[cfg(target_arch = "wasm32")]
+fn peer_ip(_parts: &Parts) -> Option<IpAddr> {
+    None
+}
+
+/// Axum middleware that renders WebFinger extraction errors in an [`ErrorFormat`].
+///
+/// Add it with [`axum::middleware::from_fn_with_state`] as a route layer, like [`cors`]. With
+/// [`ErrorFormat::Problem`], `400 Bad Request` responses produced by [`Rejection`] are re-rendered
+/// as `application/problem+json` [`Problem`] documents carrying the cause as a machine-readable
+/// `code`. Other responses, including errors returned by the handler, pass through unchanged.
+///
+/// ```rust
+/// use axum::{Router, middleware, routing::get};
+/// use webfinger_rs::{ErrorFormat, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+///
+/// async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+///     WebFingerResponse::new(request.resource.to_string())
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route(WELL_KNOWN_PATH, get(webfinger))
+///     .route_layer(middleware::from_fn_with_state(
+///         ErrorFormat::Problem,
+///         webfinger_rs::axum::error_format,
+///     ));
+/// # let _ = app;
+/// ```
+pub async fn error_format(
+    State(format): State<ErrorFormat>,
+    request: Request,
+    next: Next,
+) -> AxumResponse {
+    let mut response = next.run(request).await;
+    let Some(problem) = response.extensions_mut().remove::<Problem>() else {
+        return response;
+    };
+    if format == ErrorFormat::Problem {
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(PROBLEM_CONTENT_TYPE),
+        );
+        response.headers_mut().remove(header::CONTENT_LENGTH);
+        *response.body_mut() = Body::from(problem.to_json());
+    }
+    response
+}
+
+/// Axum middleware that renders [`WebFingerResponse`] in the format the request accepts.
+///
+/// Add it with [`axum::middleware::from_fn`] as a route layer. Responses produced by returning
+/// [`WebFingerResponse`] are re-rendered in the [`ResponseFormat`] negotiated from the request's
+/// `Accept` header, with its `Content-Type` and entity tag and `Vary: Accept`; headers the handler
+/// added are kept. Requests that accept none of the supported formats get [`NotAcceptable`].
+/// Other responses, including [`Negotiated`] ones, pass through unchanged.
+///
+/// Add it before the [`cache`] middleware so the cache sees the entity tag of the rendered format.
+/// See the [`crate::axum`] module docs for an example.
+pub async fn negotiate(request: Request, next: Next) -> AxumResponse {
+    let accept = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(str::to_string);
+
+    let mut response = next.run(request).await;
+    let Some(document) = response.extensions_mut().remove::<WebFingerResponse>() else {
+        return response;
+    };
+    let Some(format) = ResponseFormat::negotiate(accept.as_deref()) else {
+        return NotAcceptable.into_response();
+    };
+    let rendered = render(format, &document);
+    if !rendered.status().is_success() {
+        return rendered;
+    }
+    let (rendered, body) = rendered.into_parts();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_LENGTH);
+    for name in [header::CONTENT_TYPE, header::ETAG] {
+        if let Some(value) = rendered.headers.get(&name) {
+            headers.insert(name, value.clone());
+        }
+    }
+    headers.append(header::VARY, HeaderValue::from_static("Accept"));
+    *response.body_mut() = body;
+    response
+}
+
+/// Axum middleware that applies a [`HostPolicy`] to WebFinger routes.
+///
+/// Add it with [`axum::middleware::from_fn_with_state`] as a route layer, like [`cors`]. It stores
+/// the policy in the request extensions, where the [`WebFingerRequest`] extractor reads it; an
+/// `axum::Extension` layer with the policy works the same way. The extractor identifies trusted
+/// proxies by the peer address from [`ConnectInfo<SocketAddr>`], so serve the router with
+/// `into_make_service_with_connect_info::<SocketAddr>()` when trusting proxies. Requests for hosts
+/// the policy does not allow are rejected with [`Rejection::InvalidHost`].
+///
+/// ```rust
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// use axum::{Router, middleware, routing::get};
+/// use webfinger_rs::{HostPolicy, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+///
+/// async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+///     WebFingerResponse::new(request.resource.to_string())
+/// }
+///
+/// let policy = HostPolicy::new()
+///     .trusted_proxies([IpAddr::V4(Ipv4Addr::LOCALHOST)])
+///     .allowed_hosts(["example.com"]);
+/// let app = Router::<()>::new()
+///     .route(WELL_KNOWN_PATH, get(webfinger))
+///     .route_layer(middleware::from_fn_with_state(policy, webfinger_rs::axum::host_policy));
+/// # let _ = app;
+/// ```
+pub async fn host_policy(
+    State(policy): State<HostPolicy>,
+    mut request: Request,
+    next: Next,
+) -> AxumResponse {
+    request.extensions_mut().insert(policy);
+    next.run(request).await
+}
+
+/// Axum middleware that applies [`ExtractionLimits`] to WebFinger routes.
+///
+/// Add it with [`axum::middleware::from_fn_with_state`] as a route layer, like [`cors`]. It stores
+/// the limits in the request extensions, where the [`WebFingerRequest`] extractor reads them; an
+/// `axum::Extension` layer with the limits works the same way. Requests that exceed them are
+/// rejected with [`Rejection::LimitExceeded`].
+///
+/// ```rust
+/// use axum::{Router, middleware, routing::get};
+/// use webfinger_rs::{ExtractionLimits, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+///
+/// async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+///     WebFingerResponse::new(request.resource.to_string())
+/// }
+///
+/// let limits = ExtractionLimits::new().max_rels(4).allowed_schemes(["acct"]);
+/// let app = Router::<()>::new()
+///     .route(WELL_KNOWN_PATH, get(webfinger))
+///     .route_layer(middleware::from_fn_with_state(
+///         limits,
+///         webfinger_rs::axum::extraction_limits,
+///     ));
+/// # let _ = app;
+/// ```
+pub async fn extraction_limits(
+    State(limits): State<ExtractionLimits>,
+    mut request: Request,
+    next: Next,
+) -> AxumResponse {
+    request.extensions_mut().insert(limits);
+    next.run(request).await
+}
+
+/// Axum middleware that applies a [`CorsPolicy`] to WebFinger routes.
+///
+/// Add it with [`axum::middleware::from_fn_with_state`] as a route layer on the
+/// [`crate::WELL_KNOWN_PATH`] route. The middleware:
+///
+/// - answers `OPTIONS` preflight requests with `204 No Content`, `Allow`, and the policy's
+///   `Access-Control-Allow-Methods`, `Access-Control-Allow-Headers`, and `Access-Control-Max-Age`
+///   headers without calling the handler; and
+/// - replaces the default `Access-Control-Allow-Origin: *` header on every other response with the
+///   value the policy allows for the request's `Origin`.
+///
+/// See the [`crate::axum`] module docs for an example.
+pub async fn cors(State(policy): State<CorsPolicy>, request: Request, next: Next) -> AxumResponse {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .map(str::to_string);
+    if request.method() == Method::OPTIONS {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+        insert_policy_headers(headers, policy.preflight_headers(origin.as_deref()));
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.remove(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+    insert_policy_headers(headers, policy.response_headers(origin.as_deref()));
+    response
+}
+
+/// Axum middleware that applies a [`CachePolicy`] to WebFinger routes.
+///
+/// Add it with [`axum::middleware::from_fn_with_state`] as a route layer, like [`cors`]. For
+/// `200 OK` responses that carry an `ETag`, which [`WebFingerResponse`] and [`Negotiated`]
+/// responses always do, the middleware:
+///
+/// - adds the policy's `Cache-Control` header unless the handler already set one, so handlers can
+///   choose a lifetime per resource; and
+/// - answers `GET` and `HEAD` requests whose `If-None-Match` header matches the entity tag with
+///   `304 Not Modified` and no body, keeping the other response headers.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use axum::{Router, middleware, routing::get};
+/// use webfinger_rs::{CachePolicy, WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+///
+/// async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+///     WebFingerResponse::new(request.resource.to_string())
+/// }
+///
+/// let policy = CachePolicy::new().max_age(Duration::from_secs(3600));
+/// let app = Router::<()>::new()
+///     .route(WELL_KNOWN_PATH, get(webfinger))
+///     .route_layer(middleware::from_fn_with_state(policy, webfinger_rs::axum::cache));
+/// # let _ = app;
+/// ```
+pub async fn cache(
+    State(policy): State<CachePolicy>,
+    request: Request,
+    next: Next,
+) -> AxumResponse {
+    let conditional = request.method() == Method::GET || request.method() == Method::HEAD;
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let mut response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let Some(etag) = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+    else {
+        return response;
+    };
+    let headers = response.headers_mut();
+    if !headers.contains_key(header::CACHE_CONTROL)
+        && let Some(cache_control) = policy.cache_control()
+        && let Ok(cache_control) = HeaderValue::try_from(cache_control)
+    {
+        headers.insert(header::CACHE_CONTROL, cache_control);
+    }
+
+    if conditional && if_none_match.is_some_and(|value| etag_matches(&value, &etag)) {
+        let (mut parts, _body) = response.into_parts();
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return AxumResponse::from_parts(parts, Body::empty());
+    }
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
 
-    use axum::body::Body;
+    use axum::Json;
     use axum::routing::get;
+    use http::header::HOST;
     use http::{Method, Request, Response};
     use http_body_util::BodyExt;
     use tower::ServiceExt;
@@ -327,6 +813,16 @@ mod tests {
         axum::Router::new().route(WELL_KNOWN_PATH, get(webfinger))
     }
 
+    /// Builds a test router that negotiates the response format from `Accept`.
+    fn negotiated_app() -> axum::Router {
+        axum::Router::new().route(WELL_KNOWN_PATH, get(webfinger_negotiated))
+    }
+
+    /// Builds a test router that renders plain [`WebFingerResponse`]s in the accepted format.
+    fn negotiating_app() -> axum::Router {
+        app().route_layer(axum::middleware::from_fn(negotiate))
+    }
+
     /// Builds a test router using the relation-echoing WebFinger handler.
     fn rels_app() -> axum::Router {
         axum::Router::new().route(WELL_KNOWN_PATH, get(webfinger_rels))
@@ -337,6 +833,14 @@ mod tests {
         WebFingerResponse::builder(&request.resource).build()
     }
 
+    /// Returns a minimal response in the format negotiated from the request's `Accept` header.
+    async fn webfinger_negotiated(format: ResponseFormat, request: WebFingerRequest) -> Negotiated {
+        Negotiated(
+            format,
+            WebFingerResponse::builder(&request.resource).build(),
+        )
+    }
+
     /// Returns extracted relation filters so tests can assert RFC 7033 repeated `rel` handling.
     ///
     /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
@@ -502,6 +1006,178 @@ mod tests {
         Ok(())
     }
 
+    /// Answers `HEAD` like `GET` without a body.
+    ///
+    /// Monitoring tools probe endpoints with `HEAD`. Axum's `get` method router already serves it,
+    /// so WebFinger routes keep the JRD headers without sending the document.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-9.3.2>.
+    #[tokio::test]
+    async fn head_request_returns_headers_without_body() -> Result {
+        let request = Request::builder()
+            .method(Method::HEAD)
+            .uri(format!("{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}"))
+            .header(HOST, "example.com")
+            .body(Body::empty())?;
+
+        let response = app().oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static(crate::JRD_CONTENT_TYPE)),
+        );
+        assert_eq!(response.into_text().await?, "");
+        Ok(())
+    }
+
+    /// Answers browser CORS preflight requests without running the handler.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[tokio::test]
+    async fn cors_middleware_answers_preflight() -> Result {
+        let app = app().route_layer(axum::middleware::from_fn_with_state(
+            CorsPolicy::default(),
+            cors,
+        ));
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri(WELL_KNOWN_PATH)
+            .header(header::ORIGIN, "https://app.example")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())?;
+
+        let response = app.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(headers.get(header::ALLOW).unwrap(), ALLOWED_METHODS);
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            ALLOWED_METHODS,
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "Accept",
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(),
+            "86400"
+        );
+        Ok(())
+    }
+
+    /// Replaces the default wildcard origin with the configured policy.
+    #[tokio::test]
+    async fn cors_middleware_applies_origin_list() -> Result {
+        let policy = CorsPolicy::new().allow_origin(crate::AllowOrigin::List(vec![
+            "https://app.example".to_string(),
+        ]));
+        let app = app().route_layer(axum::middleware::from_fn_with_state(policy, cors));
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+
+        let request = Request::builder()
+            .uri(&uri)
+            .header(header::ORIGIN, "https://app.example")
+            .body(Body::empty())?;
+        let response = app.clone().oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://app.example",
+        );
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "Origin");
+
+        let request = Request::builder()
+            .uri(&uri)
+            .header(header::ORIGIN, "https://other.example")
+            .body(Body::empty())?;
+        let response = app.oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None,
+        );
+        Ok(())
+    }
+
+    /// Adds the policy's `Cache-Control` lifetime and an entity tag to successful responses.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9111.html#section-5.2.2.1>.
+    #[tokio::test]
+    async fn cache_middleware_sets_cache_control_and_etag() -> Result {
+        let policy = CachePolicy::new().max_age(std::time::Duration::from_secs(300));
+        let app = app().route_layer(axum::middleware::from_fn_with_state(policy, cache));
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder().uri(uri).body(Body::empty())?;
+
+        let response = app.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=300"
+        );
+        let etag = WebFingerResponse::new(VALID_RESOURCE).etag(ResponseFormat::Jrd);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+        Ok(())
+    }
+
+    /// Answers a matching `If-None-Match` revalidation with `304 Not Modified` and no body.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-13.1.2>.
+    #[tokio::test]
+    async fn cache_middleware_answers_not_modified() -> Result {
+        let app = app().route_layer(axum::middleware::from_fn_with_state(
+            CachePolicy::default(),
+            cache,
+        ));
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let etag = WebFingerResponse::new(VALID_RESOURCE).etag(ResponseFormat::Jrd);
+
+        let request = Request::builder()
+            .uri(&uri)
+            .header(header::IF_NONE_MATCH, &etag)
+            .body(Body::empty())?;
+        let response = app.clone().oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{response:?}");
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        assert_eq!(response.into_text().await?, "");
+
+        let request = Request::builder()
+            .uri(&uri)
+            .header(header::IF_NONE_MATCH, "\"stale\"")
+            .body(Body::empty())?;
+        let response = app.oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        Ok(())
+    }
+
+    /// Negotiated responses vary on `Accept` so caches keep one entry per representation.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.5>.
+    #[tokio::test]
+    async fn negotiated_response_varies_on_accept() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder().uri(uri).body(Body::empty())?;
+
+        let response = negotiated_app().oneshot(request).await?;
+
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "Accept");
+        Ok(())
+    }
+
     /// Rejects requests where neither the URI nor `Host` header provides an authority.
     ///
     /// The request host is significant to WebFinger query routing.
@@ -560,10 +1236,160 @@ mod tests {
         Ok(())
     }
 
+    /// Takes the host from `Forwarded` only when the peer is a trusted proxy.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7239.html#section-5.3>.
+    #[tokio::test]
+    async fn host_policy_trusts_forwarded_host_from_proxy() -> Result {
+        async fn host(request: WebFingerRequest) -> String {
+            request.host
+        }
+        let proxy: SocketAddr = "10.0.0.1:40000".parse()?;
+        let client: SocketAddr = "203.0.113.7:40000".parse()?;
+        let policy = HostPolicy::new().trusted_proxies([proxy.ip()]);
+        let app = axum::Router::new()
+            .route(WELL_KNOWN_PATH, get(host))
+            .route_layer(axum::middleware::from_fn_with_state(policy, host_policy));
+
+        for (peer, expected) in [(proxy, "example.com"), (client, "backend.internal")] {
+            let mut request = Request::builder()
+                .uri(format!("{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}"))
+                .header(HOST, "backend.internal")
+                .header(header::FORWARDED, "for=203.0.113.7;host=example.com")
+                .body(Body::empty())?;
+            request.extensions_mut().insert(ConnectInfo(peer));
+
+            let response = app.clone().oneshot(request).await?;
+
+            assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+            assert_eq!(response.into_text().await?, expected);
+        }
+        Ok(())
+    }
+
+    /// Rejects hosts outside the allowed list to prevent host-header poisoning.
+    #[tokio::test]
+    async fn host_policy_rejects_host_not_allowed() -> Result {
+        let policy = HostPolicy::new().allowed_hosts(["example.com"]);
+        let app = app().route_layer(axum::middleware::from_fn_with_state(policy, host_policy));
+        let request = Request::builder()
+            .uri(format!("{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}"))
+            .header(HOST, "evil.example")
+            .body(Body::empty())?;
+
+        let response = app.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        assert_eq!(
+            response.into_text().await?,
+            "host is not allowed: evil.example"
+        );
+        Ok(())
+    }
+
+    /// Rejects requests over the limits stored by the [`extraction_limits`] middleware.
+    #[tokio::test]
+    async fn extraction_limits_middleware_rejects_too_many_rels() -> Result {
+        let limits = ExtractionLimits::new().max_rels(1);
+        let app = rels_app().route_layer(axum::middleware::from_fn_with_state(
+            limits,
+            extraction_limits,
+        ));
+        let uri = format!(
+            "https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}&rel=self&rel=profile"
+        );
+        let request = Request::builder().uri(uri).body(Body::empty())?;
+
+        let response = app.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        let body = response.into_text().await?;
+        assert_eq!(body, "too many relation filters; maximum is 1");
+        Ok(())
+    }
+
+    /// Renders rejections as problem details when the [`error_format`] middleware selects them.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3>.
+    #[tokio::test]
+    async fn error_format_middleware_renders_problem_details() -> Result {
+        let app = app().route_layer(axum::middleware::from_fn_with_state(
+            ErrorFormat::Problem,
+            error_format,
+        ));
+        let carol = "acct%3Acarol%40example.org";
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={carol}&resource={carol}");
+        let request = Request::builder().uri(uri).body(Body::empty())?;
+
+        let response = app.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let problem: Problem = serde_json::from_str(&response.into_text().await?)?;
+        assert_eq!(problem.code.as_deref(), Some("multiple_resources"));
+        assert_eq!(
+            problem.detail.as_deref(),
+            Some("multiple resource parameters")
+        );
+        assert_eq!(problem.status, Some(400));
+        Ok(())
+    }
+
+    /// Gives every rejection the code of the request error it came from.
+    #[test]
+    fn rejection_code_matches_request_error() {
+        for error in [
+            RequestError::MissingHost,
+            RequestError::MissingResource,
+            RequestError::MultipleResources,
+            RequestError::InvalidPercentEncoding,
+            RequestError::InvalidResource(ResourceError::RelativeReference),
+            RequestError::HostNotAllowed("example.net".to_string()),
+            RequestError::TooManyRels { max: 1 },
+        ] {
+            let code = error.code();
+            assert_eq!(Rejection::from(error).code(), code);
+        }
+    }
+
+    /// Keeps the request error of a malformed query, so its code does not depend on the message.
+    #[test]
+    fn query_rejection_preserves_request_error() {
+        assert!(matches!(
+            Rejection::from(RequestError::MultipleResources),
+            Rejection::InvalidQueryString(RequestError::MultipleResources)
+        ));
+    }
+
+    /// Keeps limit violations distinct from malformed queries.
+    #[test]
+    fn limit_rejection_preserves_request_error() {
+        let limits = ExtractionLimits::new().allowed_schemes(["acct"]);
+        let error = WebFingerRequest::from_query_with_limits(
+            "example.com",
+            "resource=https%3A%2F%2Fexample.com%2Fcarol",
+            &limits,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            Rejection::from(error),
+            Rejection::LimitExceeded(RequestError::DisallowedScheme(_))
+        ));
+    }
+
     /// Preserves the typed resource parse error until Axum renders the rejection.
     #[test]
     fn invalid_resource_rejection_preserves_resource_error() {
-        let error = "resource=/relative".parse::<RequestParams>().unwrap_err();
+        let error = WebFingerRequest::from_query("example.com", "resource=/relative").unwrap_err();
         let rejection = Rejection::from(error);
 
         assert!(matches!(
@@ -804,4 +1630,153 @@ mod tests {
         assert_eq!(body, "multiple resource parameters");
         Ok(())
     }
+
+    /// Serves `application/json` to clients that only accept plain JSON.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-10.2>.
+    #[tokio::test]
+    async fn negotiated_response_serves_plain_json() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())?;
+
+        let response = negotiated_app().oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/json"))
+        );
+        let body = response.into_text().await?;
+        assert_eq!(body, r#"{"subject":"acct:carol@example.com","links":[]}"#);
+        Ok(())
+    }
+
+    /// Serves JRD when the client sends no `Accept` header.
+    #[tokio::test]
+    async fn negotiated_response_defaults_to_jrd() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder().uri(uri).body(Body::empty())?;
+
+        let response = negotiated_app().oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/jrd+json"))
+        );
+        Ok(())
+    }
+
+    /// Serves an RFC 6415 XRD document to clients that ask for `application/xrd+xml`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[cfg(feature = "xrd")]
+    #[tokio::test]
+    async fn negotiated_response_serves_xrd() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, "application/xrd+xml")
+            .body(Body::empty())?;
+
+        let response = negotiated_app().oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/xrd+xml"))
+        );
+        let body = response.into_text().await?;
+        assert!(body.contains("<Subject>acct:carol@example.com</Subject>"));
+        Ok(())
+    }
+
+    /// Rejects requests that accept none of the supported formats.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.5.7>.
+    #[tokio::test]
+    async fn unacceptable_format_is_not_acceptable() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())?;
+
+        let response = negotiated_app().oneshot(request).await?;
+
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_ACCEPTABLE,
+            "{response:?}"
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&HeaderValue::from_static("*"))
+        );
+        let body = response.into_text().await?;
+        assert_eq!(body, "not acceptable");
+        Ok(())
+    }
+
+    /// Renders a plain `WebFingerResponse` as XRD when the `negotiate` middleware is installed.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[cfg(feature = "xrd")]
+    #[tokio::test]
+    async fn negotiate_middleware_serves_xrd() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, "application/xrd+xml")
+            .body(Body::empty())?;
+
+        let response = negotiating_app().oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK, "{response:?}");
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/xrd+xml"))
+        );
+        assert_eq!(
+            headers.get(header::VARY),
+            Some(&HeaderValue::from_static("Accept"))
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&HeaderValue::from_static("*"))
+        );
+        let expected = WebFingerResponse::new("acct:carol@example.com").etag(ResponseFormat::Xrd);
+        assert_eq!(
+            headers.get(header::ETAG).map(HeaderValue::as_bytes),
+            Some(expected.as_bytes())
+        );
+        let body = response.into_text().await?;
+        assert!(body.contains("<Subject>acct:carol@example.com</Subject>"));
+        Ok(())
+    }
+
+    /// Rejects requests that accept none of the formats the `negotiate` middleware can render.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.5.7>.
+    #[tokio::test]
+    async fn negotiate_middleware_rejects_unacceptable_formats() -> Result {
+        let uri = format!("https://example.com{WELL_KNOWN_PATH}?resource={VALID_RESOURCE}");
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())?;
+
+        let response = negotiating_app().oneshot(request).await?;
+
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_ACCEPTABLE,
+            "{response:?}"
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,256 @@
+//! HTTP caching headers for WebFinger responses.
+//!
+//! Federated servers look up the same accounts over and over. [RFC 9111] lets them reuse a
+//! response for as long as `Cache-Control: max-age` allows, and [RFC 9110 section 13] lets them
+//! revalidate a stale copy with `If-None-Match`, which the server answers with `304 Not Modified`
+//! when the entity tag still matches.
+//!
+//! [`CachePolicy`] describes the `Cache-Control` header once so every server integration renders
+//! the same value. [`WebFingerResponse::etag`] computes a strong entity tag from the canonical JRD
+//! serialization, and [`etag_matches`] evaluates an `If-None-Match` header against it. Like
+//! [`CorsPolicy`](crate::CorsPolicy), header names are plain lowercase `&str` values because the
+//! integrations depend on different `http` crate versions.
+//!
+//! [RFC 9111]: https://www.rfc-editor.org/rfc/rfc9111.html
+//! [RFC 9110 section 13]: https://www.rfc-editor.org/rfc/rfc9110.html#section-13
+
+use std::io;
+use std::time::Duration;
+
+use crate::{ResponseFormat, WebFingerResponse};
+
+const CACHE_CONTROL: &str = "cache-control";
+const ETAG: &str = "etag";
+
+/// The caching headers sent with successful WebFinger responses.
+///
+/// The default policy sends no `Cache-Control` header, which leaves freshness to the client's
+/// heuristics. Set [`max_age`](Self::max_age) to let peers reuse responses without asking again.
+/// Entity tags are sent regardless of the policy so clients can always revalidate.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use webfinger_rs::CachePolicy;
+///
+/// let policy = CachePolicy::new().max_age(Duration::from_secs(3600));
+///
+/// assert_eq!(policy.cache_control().as_deref(), Some("max-age=3600"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CachePolicy {
+    max_age: Option<Duration>,
+}
+
+impl CachePolicy {
+    /// Creates the default policy, which sends no `Cache-Control` header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long clients and shared caches may reuse a response, sent as
+    /// `Cache-Control: max-age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Omits `Cache-Control` so clients use heuristic freshness.
+    pub fn without_max_age(mut self) -> Self {
+        self.max_age = None;
+        self
+    }
+
+    /// Returns the `Cache-Control` header value, if the policy sets one.
+    pub fn cache_control(&self) -> Option<String> {
+        self.max_age
+            .map(|max_age| format!("max-age={}", max_age.as_secs()))
+    }
+
+    /// Returns the caching headers for a successful response with the given entity tag.
+    ///
+    /// The same headers belong on a `304 Not Modified` response, as required by [RFC 9110 section
+    /// 15.4.5]. Header names are lowercase.
+    ///
+    /// [RFC 9110 section 15.4.5]: https://www.rfc-editor.org/rfc/rfc9110.html#section-15.4.5
+    pub fn response_headers(&self, etag: &str) -> Vec<(&'static str, String)> {
+        let mut headers = vec![(ETAG, etag.to_string())];
+        if let Some(cache_control) = self.cache_control() {
+            headers.push((CACHE_CONTROL, cache_control));
+        }
+        headers
+    }
+}
+
+impl WebFingerResponse {
+    /// Returns a strong entity tag for this response in the given format.
+    ///
+    /// The tag is a hash of the canonical JRD serialization, so it changes whenever any member of
+    /// the response changes and stays the same across processes and restarts. Formats other than
+    /// [`ResponseFormat::Jrd`] get a suffix because each representation needs its own strong tag.
+    /// The returned value includes the surrounding double quotes, ready for the `ETag` header.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{ResponseFormat, WebFingerResponse};
+    ///
+    /// let response = WebFingerResponse::new("acct:carol@example.com");
+    /// let etag = response.etag(ResponseFormat::Jrd);
+    ///
+    /// assert!(etag.starts_with('"') && etag.ends_with('"'));
+    /// assert_eq!(etag, response.clone().etag(ResponseFormat::Jrd));
+    /// assert_ne!(etag, response.etag(ResponseFormat::Json));
+    /// ```
+    pub fn etag(&self, format: ResponseFormat) -> String {
+        let mut hasher = Fnv1a::default();
+        // Writing to the hasher never fails, and JRD members always serialize.
+        let _ = serde_json::to_writer(&mut hasher, self);
+        let suffix = match format {
+            ResponseFormat::Jrd => "",
+            ResponseFormat::Json => "-json",
+            #[cfg(feature = "xrd")]
+            ResponseFormat::Xrd => "-xrd",
+        };
+        format!("\"{:016x}{suffix}\"", hasher.0)
+    }
+}
+
+/// Returns whether an `If-None-Match` header value matches an entity tag.
+///
+/// `If-None-Match` uses the weak comparison function from [RFC 9110 section 8.8.3.2], so a `W/`
+/// prefix on either side is ignored. The value `*` matches any tag. Servers should answer a
+/// matching `GET` or `HEAD` request with `304 Not Modified`.
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::etag_matches;
+///
+/// assert!(etag_matches(r#""a1", "b2""#, r#""b2""#));
+/// assert!(etag_matches(r#"W/"b2""#, r#""b2""#));
+/// assert!(etag_matches("*", r#""b2""#));
+/// assert!(!etag_matches(r#""a1""#, r#""b2""#));
+/// ```
+///
+/// [RFC 9110 section 8.8.3.2]: https://www.rfc-editor.org/rfc/rfc9110.html#section-8.8.3.2
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = opaque_tag(etag);
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || (!candidate.is_empty() && opaque_tag(candidate) == etag)
+    })
+}
+
+/// Strips the weakness indicator so two tags can be compared weakly.
+fn opaque_tag(tag: &str) -> &str {
+    let tag = tag.trim();
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// A 64-bit FNV-1a hasher.
+///
+/// Entity tags must not change between processes, so this avoids `std`'s randomly keyed hashers.
+/// See <https://datatracker.ietf.org/doc/html/draft-eastlake-fnv>.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl io::Write for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Link;
+
+    #[test]
+    fn default_policy_sends_only_etag() {
+        let policy = CachePolicy::default();
+
+        assert_eq!(
+            policy.response_headers("\"abc\""),
+            vec![(ETAG, "\"abc\"".to_string())],
+        );
+    }
+
+    #[test]
+    fn max_age_sets_cache_control() {
+        let policy = CachePolicy::new().max_age(Duration::from_secs(600));
+
+        assert_eq!(
+            policy.response_headers("\"abc\""),
+            vec![
+                (ETAG, "\"abc\"".to_string()),
+                (CACHE_CONTROL, "max-age=600".to_string()),
+            ],
+        );
+        assert_eq!(policy.without_max_age().cache_control(), None);
+    }
+
+    /// Entity tags are stable for equal responses and change with any member.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-8.8.3>.
+    #[test]
+    fn etag_tracks_response_content() {
+        let response = WebFingerResponse::builder("acct:carol@example.com")
+            .link(Link::builder("self").href("https://example.com/carol"))
+            .build();
+        let changed = WebFingerResponse::builder("acct:carol@example.com")
+            .link(Link::builder("self").href("https://example.com/users/carol"))
+            .build();
+
+        assert_eq!(
+            response.etag(ResponseFormat::Jrd),
+            response.clone().etag(ResponseFormat::Jrd)
+        );
+        assert_ne!(
+            response.etag(ResponseFormat::Jrd),
+            changed.etag(ResponseFormat::Jrd)
+        );
+        assert_eq!(response.etag(ResponseFormat::Jrd).len(), 18);
+    }
+
+    /// Each representation of the same response needs its own strong entity tag.
+    #[test]
+    fn etag_differs_per_format() {
+        let response = WebFingerResponse::new("acct:carol@example.com");
+        let jrd = response.etag(ResponseFormat::Jrd);
+        let json = response.etag(ResponseFormat::Json);
+
+        assert_ne!(jrd, json);
+        assert_eq!(json, format!("{}-json\"", jrd.trim_end_matches('"')));
+    }
+
+    /// `If-None-Match` uses weak comparison and accepts lists and `*`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-13.1.2>.
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let etag = r#""0123""#;
+
+        assert!(etag_matches(r#""0123""#, etag));
+        assert!(etag_matches(r#"W/"0123""#, etag));
+        assert!(etag_matches(r#""ffff" , W/"0123""#, etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches(r#""ffff""#, etag));
+        assert!(!etag_matches("", etag));
+        assert!(!etag_matches("0123", etag));
+    }
+}
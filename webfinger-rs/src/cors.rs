@@ -0,0 +1,248 @@
+//! Cross-origin resource sharing (CORS) policy for WebFinger endpoints.
+//!
+//! [RFC 7033 section 5] requires WebFinger servers to include `Access-Control-Allow-Origin` and
+//! recommends the least restrictive value, `*`, for public resources. Browsers also send
+//! `OPTIONS` preflight requests before some cross-origin lookups, which the server must answer
+//! with the allowed methods, request headers, and cache lifetime.
+//!
+//! [`CorsPolicy`] describes those headers once so every server integration renders the same values.
+//! It uses plain `&str` header names and `String` values because the Axum, Actix Web, Tower, and
+//! Worker integrations depend on different `http` crate versions.
+//!
+//! [RFC 7033 section 5]: https://www.rfc-editor.org/rfc/rfc7033.html#section-5
+
+use std::time::Duration;
+
+/// The methods a WebFinger endpoint answers, as sent in `Allow` and
+/// `Access-Control-Allow-Methods`.
+///
+/// `HEAD` is answered like `GET` without a body, and `OPTIONS` answers CORS preflight requests.
+pub const ALLOWED_METHODS: &str = "GET, HEAD, OPTIONS";
+
+const ACCESS_CONTROL_ALLOW_ORIGIN: &str = "access-control-allow-origin";
+const ACCESS_CONTROL_ALLOW_METHODS: &str = "access-control-allow-methods";
+const ACCESS_CONTROL_ALLOW_HEADERS: &str = "access-control-allow-headers";
+const ACCESS_CONTROL_MAX_AGE: &str = "access-control-max-age";
+const VARY: &str = "vary";
+
+/// The CORS headers sent by a WebFinger endpoint.
+///
+/// The default policy allows any origin with `Access-Control-Allow-Origin: *`, allows the
+/// `Accept` request header, and lets browsers cache preflight results for one day. Restrict the
+/// policy with [`AllowOrigin::List`] when the endpoint is not meant to be read from arbitrary
+/// browser origins.
+///
+/// Browsers only send an `Authorization` header cross-origin when the preflight allows it. Add it
+/// with [`allow_headers`](Self::allow_headers), as in the example below, when the endpoint
+/// discloses more to requests with credentials.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use webfinger_rs::{AllowOrigin, CorsPolicy};
+///
+/// let policy = CorsPolicy::new()
+///     .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]))
+///     .allow_headers(["Accept", "Authorization"])
+///     .max_age(Duration::from_secs(600));
+///
+/// assert_eq!(policy.allowed_origin(Some("https://app.example")), Some("https://app.example"));
+/// assert_eq!(policy.allowed_origin(Some("https://other.example")), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    allow_origin: AllowOrigin,
+    allow_headers: Vec<String>,
+    max_age: Option<Duration>,
+}
+
+/// The origins a [`CorsPolicy`] allows to read WebFinger responses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AllowOrigin {
+    /// Allow every origin with `Access-Control-Allow-Origin: *`.
+    ///
+    /// This is the value RFC 7033 section 5 recommends for public WebFinger resources.
+    Any,
+
+    /// Allow only the listed origins, such as `https://app.example`.
+    ///
+    /// A matching request `Origin` is echoed back, and `Vary: Origin` is added so caches do not
+    /// share responses between origins. Requests from other origins receive no
+    /// `Access-Control-Allow-Origin` header.
+    List(Vec<String>),
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self {
+            allow_origin: AllowOrigin::Any,
+            allow_headers: vec!["Accept".to_string()],
+            max_age: Some(Duration::from_secs(86_400)),
+        }
+    }
+}
+
+impl CorsPolicy {
+    /// Creates the default policy: any origin, the `Accept` request header, and a one day
+    /// preflight cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the origins allowed to read WebFinger responses.
+    pub fn allow_origin(mut self, allow_origin: AllowOrigin) -> Self {
+        self.allow_origin = allow_origin;
+        self
+    }
+
+    /// Sets the request headers listed in `Access-Control-Allow-Headers` for preflight requests.
+    pub fn allow_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_headers = headers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how long browsers may cache a preflight response, sent as `Access-Control-Max-Age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Omits `Access-Control-Max-Age` so browsers use their default preflight cache lifetime.
+    pub fn without_max_age(mut self) -> Self {
+        self.max_age = None;
+        self
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` value for a request with the given `Origin`.
+    ///
+    /// Returns `None` when the policy does not allow the origin, in which case no CORS header should
+    /// be sent.
+    pub fn allowed_origin<'a>(&'a self, origin: Option<&'a str>) -> Option<&'a str> {
+        match &self.allow_origin {
+            AllowOrigin::Any => Some("*"),
+            AllowOrigin::List(origins) => {
+                let origin = origin?;
+                origins
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+                    .then_some(origin)
+            }
+        }
+    }
+
+    /// Returns the CORS headers for an ordinary WebFinger response.
+    ///
+    /// `origin` is the request's `Origin` header, if any. Header names are lowercase.
+    pub fn response_headers(&self, origin: Option<&str>) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(allowed) = self.allowed_origin(origin) {
+            headers.push((ACCESS_CONTROL_ALLOW_ORIGIN, allowed.to_string()));
+        }
+        if matches!(self.allow_origin, AllowOrigin::List(_)) {
+            headers.push((VARY, "Origin".to_string()));
+        }
+        headers
+    }
+
+    /// Returns the CORS headers for a response to an `OPTIONS` preflight request.
+    ///
+    /// This adds `Access-Control-Allow-Methods`, `Access-Control-Allow-Headers`, and
+    /// `Access-Control-Max-Age` to the [`response headers`](Self::response_headers).
+    pub fn preflight_headers(&self, origin: Option<&str>) -> Vec<(&'static str, String)> {
+        let mut headers = self.response_headers(origin);
+        headers.push((ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS.to_string()));
+        if !self.allow_headers.is_empty() {
+            headers.push((ACCESS_CONTROL_ALLOW_HEADERS, self.allow_headers.join(", ")));
+        }
+        if let Some(max_age) = self.max_age {
+            headers.push((ACCESS_CONTROL_MAX_AGE, max_age.as_secs().to_string()));
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 7033 recommends the least restrictive origin policy for public WebFinger resources.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-5>.
+    #[test]
+    fn default_policy_allows_any_origin() {
+        let policy = CorsPolicy::default();
+
+        assert_eq!(
+            policy.response_headers(Some("https://app.example")),
+            vec![(ACCESS_CONTROL_ALLOW_ORIGIN, "*".to_string())],
+        );
+    }
+
+    /// Restricted policies echo an allowed origin and vary on it so shared caches stay correct.
+    #[test]
+    fn origin_list_echoes_allowed_origin() {
+        let policy = CorsPolicy::new()
+            .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+
+        assert_eq!(
+            policy.response_headers(Some("https://app.example")),
+            vec![
+                (
+                    ACCESS_CONTROL_ALLOW_ORIGIN,
+                    "https://app.example".to_string()
+                ),
+                (VARY, "Origin".to_string()),
+            ],
+        );
+        assert_eq!(
+            policy.response_headers(Some("https://other.example")),
+            vec![(VARY, "Origin".to_string())],
+        );
+        assert_eq!(
+            policy.response_headers(None),
+            vec![(VARY, "Origin".to_string())]
+        );
+    }
+
+    #[test]
+    fn preflight_headers_include_methods_headers_and_max_age() {
+        let policy = CorsPolicy::new()
+            .allow_headers(["Accept", "Authorization"])
+            .max_age(Duration::from_secs(600));
+
+        assert_eq!(
+            policy.preflight_headers(None),
+            vec![
+                (ACCESS_CONTROL_ALLOW_ORIGIN, "*".to_string()),
+                (ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS.to_string()),
+                (
+                    ACCESS_CONTROL_ALLOW_HEADERS,
+                    "Accept, Authorization".to_string()
+                ),
+                (ACCESS_CONTROL_MAX_AGE, "600".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn preflight_headers_can_omit_max_age() {
+        let policy = CorsPolicy::new()
+            .allow_headers::<_, String>([])
+            .without_max_age();
+
+        assert_eq!(
+            policy.preflight_headers(None),
+            vec![
+                (ACCESS_CONTROL_ALLOW_ORIGIN, "*".to_string()),
+                (ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS.to_string()),
+            ],
+        );
+    }
+}
@@ -29,4 +29,20 @@ pub enum Error {
     /// A WebFinger relation type was not a URI or registered relation type.
     #[error("invalid relation type: {0}")]
     InvalidRel(String),
+
+    /// A JRD `expires` value was not an RFC 3339 timestamp.
+    #[error("invalid expires timestamp: {0}")]
+    InvalidExpires(String),
+
+    /// A JRD link `type` was not a media type.
+    #[error("invalid media type: {0}")]
+    InvalidMediaType(String),
+
+    /// A link title language was not a BCP 47 language tag.
+    #[error("invalid language tag: {0}")]
+    InvalidLanguageTag(String),
+
+    /// An `Accept-Language` language range was malformed.
+    #[error("invalid language range: {0}")]
+    InvalidLanguageRange(String),
 }
@@ -0,0 +1,296 @@
+//! Response media types and `Accept` header negotiation.
+//!
+//! [RFC 7033 section 10.2] registers `application/jrd+json` for WebFinger responses, and servers
+//! must serve it by default. Some clients only ask for `application/json`, and legacy [RFC 6415]
+//! peers ask for `application/xrd+xml`. [`ResponseFormat::negotiate`] picks the representation a
+//! server integration should send for a request's `Accept` header using the proactive negotiation
+//! rules from [RFC 9110 section 12.5.1].
+//!
+//! [RFC 7033 section 10.2]: https://www.rfc-editor.org/rfc/rfc7033.html#section-10.2
+//! [RFC 6415]: https://www.rfc-editor.org/rfc/rfc6415.html
+//! [RFC 9110 section 12.5.1]: https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.1
+
+use crate::WebFingerResponse;
+
+/// The `application/jrd+json` media type registered for WebFinger responses.
+pub const JRD_CONTENT_TYPE: &str = "application/jrd+json";
+
+/// A representation of a [`WebFingerResponse`] that a server can send.
+///
+/// Variants are listed in server preference order. When an `Accept` header ranks several formats
+/// equally, the earliest one wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResponseFormat {
+    /// A JSON Resource Descriptor served as `application/jrd+json`.
+    ///
+    /// This is the default WebFinger representation.
+    Jrd,
+
+    /// The same JRD document served as `application/json`.
+    ///
+    /// This is chosen only when the client does not accept `application/jrd+json` at least as much.
+    Json,
+
+    /// An XRD 1.0 document served as `application/xrd+xml`.
+    ///
+    /// Requires the `xrd` feature.
+    #[cfg(feature = "xrd")]
+    Xrd,
+}
+
+impl ResponseFormat {
+    /// Every supported format in server preference order.
+    const ALL: &[ResponseFormat] = &[
+        ResponseFormat::Jrd,
+        ResponseFormat::Json,
+        #[cfg(feature = "xrd")]
+        ResponseFormat::Xrd,
+    ];
+
+    /// Returns the `Content-Type` header value for this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ResponseFormat::Jrd => JRD_CONTENT_TYPE,
+            ResponseFormat::Json => "application/json",
+            #[cfg(feature = "xrd")]
+            ResponseFormat::Xrd => crate::xrd::XRD_CONTENT_TYPE,
+        }
+    }
+
+    /// Picks the format to send for a request's `Accept` header.
+    ///
+    /// A missing or empty header, or one with no parseable media ranges, selects
+    /// [`ResponseFormat::Jrd`]. Otherwise each supported format takes the quality value of the most
+    /// specific matching media range (`type/subtype` over `type/*` over `*/*`), and the format with
+    /// the highest non-zero quality wins, with ties going to the earlier variant.
+    ///
+    /// Returns `None` when the header excludes every supported format. Servers should answer
+    /// `406 Not Acceptable` in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::ResponseFormat;
+    ///
+    /// assert_eq!(ResponseFormat::negotiate(None), Some(ResponseFormat::Jrd));
+    /// assert_eq!(ResponseFormat::negotiate(Some("*/*")), Some(ResponseFormat::Jrd));
+    /// assert_eq!(
+    ///     ResponseFormat::negotiate(Some("application/json")),
+    ///     Some(ResponseFormat::Json)
+    /// );
+    /// assert_eq!(ResponseFormat::negotiate(Some("text/html")), None);
+    /// ```
+    pub fn negotiate(accept: Option<&str>) -> Option<ResponseFormat> {
+        let ranges: Vec<_> = accept
+            .map(|accept| accept.split(',').filter_map(MediaRange::parse).collect())
+            .unwrap_or_default();
+        if ranges.is_empty() {
+            return Some(ResponseFormat::Jrd);
+        }
+
+        let mut best: Option<(ResponseFormat, u16)> = None;
+        for &format in Self::ALL {
+            let quality = format.quality(&ranges);
+            if quality > 0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+
+    /// Serializes a response in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if JSON serialization fails. XRD rendering cannot fail.
+    pub fn render(self, response: &WebFingerResponse) -> Result<Vec<u8>, serde_json::Error> {
+        match self {
+            ResponseFormat::Jrd | ResponseFormat::Json => serde_json::to_vec(response),
+            #[cfg(feature = "xrd")]
+            ResponseFormat::Xrd => Ok(response.to_xrd().into_bytes()),
+        }
+    }
+
+    /// Returns the quality, in thousandths, that `ranges` assigns to this format.
+    fn quality(self, ranges: &[MediaRange<'_>]) -> u16 {
+        let (r#type, subtype) = self
+            .content_type()
+            .split_once('/')
+            .expect("content types contain a slash");
+        ranges
+            .iter()
+            .filter_map(|range| {
+                range
+                    .specificity(r#type, subtype)
+                    .map(|specificity| (specificity, range.quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or(0, |(_, quality)| quality)
+    }
+}
+
+/// One media range from an `Accept` header, such as `application/*;q=0.5`.
+#[derive(Debug)]
+struct MediaRange<'a> {
+    r#type: &'a str,
+    subtype: &'a str,
+    /// The `q` parameter in thousandths, so `q=0.5` is `500`.
+    quality: u16,
+}
+
+impl<'a> MediaRange<'a> {
+    fn parse(input: &'a str) -> Option<Self> {
+        let mut params = input.split(';');
+        let (r#type, subtype) = params.next()?.trim().split_once('/')?;
+        let (r#type, subtype) = (r#type.trim(), subtype.trim());
+        if r#type.is_empty() || subtype.is_empty() || (r#type == "*" && subtype != "*") {
+            return None;
+        }
+        let mut quality = 1000;
+        for param in params {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            if name.trim().eq_ignore_ascii_case("q") {
+                quality = parse_quality(value.trim())?;
+            }
+        }
+        Some(Self {
+            r#type,
+            subtype,
+            quality,
+        })
+    }
+
+    /// Returns how specifically this range matches a media type, or `None` if it does not match.
+    fn specificity(&self, r#type: &str, subtype: &str) -> Option<u8> {
+        if self.r#type == "*" {
+            Some(0)
+        } else if !self.r#type.eq_ignore_ascii_case(r#type) {
+            None
+        } else if self.subtype == "*" {
+            Some(1)
+        } else if self.subtype.eq_ignore_ascii_case(subtype) {
+            Some(2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses an RFC 9110 `qvalue` into thousandths.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-12.4.2>.
+pub(crate) fn parse_quality(value: &str) -> Option<u16> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 3 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let fraction = format!("{fraction:0<3}").parse::<u16>().ok()?;
+    match whole {
+        "0" => Some(fraction),
+        "1" if fraction == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves JRD when the client states no preference.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-10.2>.
+    #[test]
+    fn defaults_to_jrd() {
+        assert_eq!(ResponseFormat::negotiate(None), Some(ResponseFormat::Jrd));
+        assert_eq!(
+            ResponseFormat::negotiate(Some("")),
+            Some(ResponseFormat::Jrd)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("*/*")),
+            Some(ResponseFormat::Jrd)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/*")),
+            Some(ResponseFormat::Jrd)
+        );
+    }
+
+    #[test]
+    fn prefers_jrd_when_json_and_jrd_tie() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/json, application/jrd+json")),
+            Some(ResponseFormat::Jrd)
+        );
+    }
+
+    #[test]
+    fn serves_plain_json_when_only_json_is_acceptable() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/json")),
+            Some(ResponseFormat::Json)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/jrd+json;q=0.5, application/json")),
+            Some(ResponseFormat::Json)
+        );
+    }
+
+    /// A more specific range overrides a wildcard, so `q=0` excludes JRD even under `*/*`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.1>.
+    #[test]
+    fn specific_ranges_override_wildcards() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/jrd+json;q=0, */*;q=0.1")),
+            Some(ResponseFormat::Json)
+        );
+    }
+
+    #[test]
+    fn rejects_when_nothing_is_acceptable() {
+        assert_eq!(ResponseFormat::negotiate(Some("text/html")), None);
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/jrd+json;q=0, application/json;q=0")),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_ranges() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("nonsense, */json, text/*;q=2")),
+            Some(ResponseFormat::Jrd)
+        );
+    }
+
+    #[test]
+    fn media_types_are_case_insensitive() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("Application/JSON")),
+            Some(ResponseFormat::Json)
+        );
+    }
+
+    #[cfg(feature = "xrd")]
+    #[test]
+    fn serves_xrd_when_requested() {
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/xrd+xml")),
+            Some(ResponseFormat::Xrd)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/xrd+xml, application/jrd+json;q=0.9")),
+            Some(ResponseFormat::Xrd)
+        );
+    }
+
+    #[cfg(not(feature = "xrd"))]
+    #[test]
+    fn rejects_xrd_without_feature() {
+        assert_eq!(ResponseFormat::negotiate(Some("application/xrd+xml")), None);
+    }
+}
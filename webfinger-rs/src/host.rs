@@ -0,0 +1,400 @@
+//! Host resolution for incoming WebFinger requests behind reverse proxies.
+//!
+//! WebFinger answers are scoped to the host that received the query, so server integrations copy
+//! it into [`WebFingerRequest::host`](crate::WebFingerRequest::host). Without configuration that
+//! is the request URI authority or the raw `Host` header, which a client can set to anything.
+//!
+//! [`HostPolicy`] makes the host reliable in two ways. Requests from configured trusted proxies
+//! may override the host with the [RFC 7239] `Forwarded` header or the de facto
+//! `X-Forwarded-Host` header; both are ignored for every other peer. An allowed-hosts list then
+//! rejects requests for hosts the server does not serve, which prevents host-header poisoning of
+//! anything derived from the host.
+//!
+//! [RFC 7239]: https://www.rfc-editor.org/rfc/rfc7239.html
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use http::uri::Authority;
+
+use crate::RequestError;
+
+/// How server integrations determine and check the host of an incoming request.
+///
+/// The default policy trusts no proxies and allows every host, which keeps the URI authority or
+/// `Host` header as sent.
+///
+/// When the connecting peer address is one of the [`trusted_proxies`](Self::trusted_proxies) or
+/// falls in a [`trusted_network`](Self::trusted_network), the host comes from the `host` parameter
+/// of the last `Forwarded` element, or else the last `X-Forwarded-Host` value, since those are the
+/// values the nearest proxy added. A malformed forwarded host is rejected rather than ignored.
+///
+/// Hosts in [`allowed_hosts`](Self::allowed_hosts) compare case-insensitively. An entry without a
+/// port matches the host on any port; an entry with a port matches only that port.
+///
+/// Server integrations read the policy from framework state the same way as
+/// [`ExtractionLimits`](crate::ExtractionLimits): `App::app_data` in Actix Web,
+/// [`axum::host_policy`](crate::axum::host_policy) middleware or an `Extension` layer in Axum,
+/// `Rocket::manage` in Rocket, `EndpointExt::data` in Poem, and the request extensions in Salvo.
+/// Axum only knows the peer address when the server is started with
+/// `into_make_service_with_connect_info::<SocketAddr>()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// use webfinger_rs::{HostPolicy, RequestError};
+///
+/// let policy = HostPolicy::new()
+///     .trusted_network(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8)
+///     .allowed_hosts(["example.com"]);
+/// let proxy = Some(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)));
+/// let client = Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)));
+/// let forwarded = ["for=203.0.113.7;host=example.com;proto=https"];
+///
+/// let host = policy.resolve_host(proxy, Some("backend.internal:8080"), forwarded, [])?;
+/// assert_eq!(host, "example.com");
+///
+/// let error = policy
+///     .resolve_host(client, Some("backend.internal:8080"), forwarded, [])
+///     .unwrap_err();
+/// assert!(matches!(error, RequestError::HostNotAllowed(host) if host == "backend.internal:8080"));
+/// # Ok::<(), RequestError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostPolicy {
+    trusted_proxies: Vec<(IpAddr, u8)>,
+    allowed_hosts: Option<Vec<String>>,
+}
+
+impl HostPolicy {
+    /// Creates the default policy, which trusts no proxies and allows every host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts forwarded host headers from the given proxy addresses.
+    pub fn trusted_proxies<I>(mut self, proxies: I) -> Self
+    where
+        I: IntoIterator<Item = IpAddr>,
+    {
+        self.trusted_proxies
+            .extend(proxies.into_iter().map(|proxy| {
+                let prefix_len = if proxy.is_ipv4() { 32 } else { 128 };
+                (proxy, prefix_len)
+            }));
+        self
+    }
+
+    /// Trusts forwarded host headers from every address in the network `address/prefix_len`.
+    ///
+    /// Prefix lengths longer than the address family allows are treated as a single address.
+    pub fn trusted_network(mut self, address: IpAddr, prefix_len: u8) -> Self {
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        self.trusted_proxies.push((address, prefix_len.min(max)));
+        self
+    }
+
+    /// Rejects requests for hosts other than the given ones, such as `example.com` or
+    /// `example.com:8443`.
+    pub fn allowed_hosts<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_hosts = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Allows requests for every host.
+    pub fn allow_any_host(mut self) -> Self {
+        self.allowed_hosts = None;
+        self
+    }
+
+    /// Returns whether forwarded host headers from `peer` are trusted.
+    pub fn trusts(&self, peer: IpAddr) -> bool {
+        let peer = peer.to_canonical();
+        self.trusted_proxies
+            .iter()
+            .any(|&(network, prefix_len)| in_network(peer, network.to_canonical(), prefix_len))
+    }
+
+    /// Returns whether the policy allows requests for `host`.
+    pub fn allows_host(&self, host: &str) -> bool {
+        let Some(allowed_hosts) = &self.allowed_hosts else {
+            return true;
+        };
+        let (name, port) = split_port(host);
+        allowed_hosts.iter().any(|allowed| {
+            let (allowed_name, allowed_port) = split_port(allowed);
+            allowed_name.eq_ignore_ascii_case(name)
+                && (allowed_port.is_none() || allowed_port == port)
+        })
+    }
+
+    /// Determines the host of a request and checks it against the allowed hosts.
+    ///
+    /// `peer` is the address of the connecting client, `host` is the request URI authority or the
+    /// `Host` header, and `forwarded` and `x_forwarded_host` are the values of every `Forwarded`
+    /// and `X-Forwarded-Host` header line in order. The forwarded headers are only read when
+    /// `peer` is trusted.
+    ///
+    /// # Errors
+    ///
+    /// - [`RequestError::MissingHost`] when no host is available.
+    /// - [`RequestError::InvalidForwardedHost`] when a trusted proxy forwarded a malformed host.
+    /// - [`RequestError::HostNotAllowed`] when the host is not one of the allowed hosts.
+    pub fn resolve_host<'a, F, X>(
+        &self,
+        peer: Option<IpAddr>,
+        host: Option<&'a str>,
+        forwarded: F,
+        x_forwarded_host: X,
+    ) -> Result<String, RequestError>
+    where
+        F: IntoIterator<Item = &'a str>,
+        X: IntoIterator<Item = &'a str>,
+    {
+        let forwarded_host = match peer {
+            Some(peer) if self.trusts(peer) => {
+                forwarded_host(forwarded).or_else(|| last_list_value(x_forwarded_host))
+            }
+            _ => None,
+        };
+        let host = match forwarded_host {
+            Some(host) => {
+                if !is_host(&host) {
+                    return Err(RequestError::InvalidForwardedHost(host));
+                }
+                host
+            }
+            None => host
+                .filter(|host| !host.is_empty())
+                .ok_or(RequestError::MissingHost)?
+                .to_string(),
+        };
+        if !self.allows_host(&host) {
+            return Err(RequestError::HostNotAllowed(host));
+        }
+        Ok(host)
+    }
+}
+
+/// Returns whether `address` is in the network `network/prefix_len`.
+fn in_network(address: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Returns the `host` parameter of the last `Forwarded` element, if it has one.
+///
+/// The header is a comma-separated list of elements, each a semicolon-separated list of
+/// `name=value` pairs whose values may be quoted strings. Repeated header lines form one list.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7239.html#section-4>.
+fn forwarded_host<'a>(headers: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut last_element = None;
+    for header in headers {
+        for element in split_unquoted(header, ',') {
+            if !element.trim().is_empty() {
+                last_element = Some(element);
+            }
+        }
+    }
+    split_unquoted(last_element?, ';').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("host") {
+            return None;
+        }
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(quoted) => unquote(quoted.strip_suffix('"')?),
+            None => value.to_string(),
+        };
+        Some(value)
+    })
+}
+
+/// Returns the last value of a comma-separated header list such as `X-Forwarded-Host`.
+fn last_list_value<'a>(headers: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    headers
+        .into_iter()
+        .flat_map(|header| header.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .last()
+        .map(str::to_string)
+}
+
+/// Splits `value` on `separator` outside of quoted strings.
+fn split_unquoted(value: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, char) in value.char_indices() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if char == separator && !quoted => {
+                parts.push(&value[start..index]);
+                start = index + char.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts.into_iter()
+}
+
+/// Removes quoted-pair escapes from the contents of a quoted string.
+fn unquote(value: &str) -> String {
+    let mut unquoted = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unquoted.extend(chars.next()),
+            char => unquoted.push(char),
+        }
+    }
+    unquoted
+}
+
+/// Returns whether `host` is a `host[:port]` authority without user information.
+fn is_host(host: &str) -> bool {
+    !host.is_empty() && !host.contains('@') && Authority::from_str(host).is_ok()
+}
+
+/// Splits the port from a `host[:port]` authority, keeping IPv6 literals in brackets intact.
+fn split_port(host: &str) -> (&str, Option<&str>) {
+    let port_start = match host.rfind(']') {
+        Some(bracket) => host[bracket..].find(':').map(|colon| bracket + colon),
+        None => host.rfind(':'),
+    };
+    match port_start {
+        Some(colon) => (&host[..colon], Some(&host[colon + 1..])),
+        None => (host, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    const PROXY: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7));
+
+    fn policy() -> HostPolicy {
+        HostPolicy::new().trusted_proxies([PROXY])
+    }
+
+    /// Ignores forwarded headers from peers that are not trusted proxies.
+    #[test]
+    fn untrusted_peer_keeps_host_header() -> Result {
+        let host = policy().resolve_host(
+            Some(CLIENT),
+            Some("example.com"),
+            ["host=evil.example"],
+            ["evil.example"],
+        )?;
+
+        assert_eq!(host, "example.com");
+        Ok(())
+    }
+
+    /// Reads the `host` parameter of the last `Forwarded` element from a trusted proxy.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7239.html#section-5.3>.
+    #[test]
+    fn trusted_proxy_uses_last_forwarded_host() -> Result {
+        let forwarded = [
+            r#"for=192.0.2.60;host="first.example", for=198.51.100.17"#,
+            r#"for="[2001:db8::1]";proto=https;host="example.com:8443""#,
+        ];
+
+        let host = policy().resolve_host(Some(PROXY), Some("backend"), forwarded, [])?;
+
+        assert_eq!(host, "example.com:8443");
+        Ok(())
+    }
+
+    /// Falls back to the last `X-Forwarded-Host` value when `Forwarded` has no host.
+    #[test]
+    fn trusted_proxy_falls_back_to_x_forwarded_host() -> Result {
+        let host = policy().resolve_host(
+            Some(PROXY),
+            Some("backend"),
+            ["for=192.0.2.60"],
+            ["first.example, example.com"],
+        )?;
+
+        assert_eq!(host, "example.com");
+        Ok(())
+    }
+
+    /// Rejects malformed hosts forwarded by a trusted proxy.
+    #[test]
+    fn rejects_malformed_forwarded_host() {
+        let error = policy()
+            .resolve_host(Some(PROXY), Some("backend"), [], ["user@example.com"])
+            .unwrap_err();
+
+        assert!(matches!(error, RequestError::InvalidForwardedHost(_)));
+    }
+
+    /// Matches peers by network prefix, including IPv4-mapped IPv6 addresses.
+    #[test]
+    fn trusts_networks() {
+        let policy = HostPolicy::new()
+            .trusted_network(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8)
+            .trusted_network(IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0)), 8);
+
+        assert!(policy.trusts(IpAddr::V4(Ipv4Addr::new(10, 200, 3, 4))));
+        assert!(policy.trusts(IpAddr::V6(Ipv4Addr::new(10, 1, 1, 1).to_ipv6_mapped())));
+        assert!(policy.trusts(IpAddr::V6(Ipv6Addr::new(0xfd12, 0, 0, 0, 0, 0, 0, 1))));
+        assert!(!policy.trusts(CLIENT));
+    }
+
+    /// Compares allowed hosts case-insensitively, with ports only when the entry has one.
+    #[test]
+    fn allowed_hosts_match_name_and_optional_port() {
+        let policy = HostPolicy::new().allowed_hosts(["Example.com", "[::1]:8443"]);
+
+        assert!(policy.allows_host("example.COM"));
+        assert!(policy.allows_host("example.com:8080"));
+        assert!(policy.allows_host("[::1]:8443"));
+        assert!(!policy.allows_host("[::1]:8080"));
+        assert!(!policy.allows_host("other.example"));
+    }
+
+    /// Rejects hosts outside the allowed list to prevent host-header poisoning.
+    #[test]
+    fn rejects_host_not_allowed() {
+        let error = HostPolicy::new()
+            .allowed_hosts(["example.com"])
+            .resolve_host(None, Some("evil.example"), [], [])
+            .unwrap_err();
+
+        assert!(matches!(error, RequestError::HostNotAllowed(host) if host == "evil.example"));
+    }
+}
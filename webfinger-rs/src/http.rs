@@ -1,12 +1,19 @@
+use std::net::IpAddr;
 use std::str::FromStr;
 
 use http::Uri;
+use http::header::{FORWARDED, HOST, HeaderMap, HeaderName, HeaderValue, VARY};
+use http::request::Parts;
 use http::uri::{InvalidUri, PathAndQuery, Scheme};
 use percent_encoding::{AsciiSet, utf8_percent_encode};
 
-use crate::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+use crate::{
+    CorsPolicy, ExtractionLimits, HostPolicy, RequestError, WELL_KNOWN_PATH, WebFingerRequest,
+    WebFingerResponse,
+};
 
-pub(crate) const CORS_ALLOW_ORIGIN: &str = "*";
+/// The de facto standard header older proxies use instead of `Forwarded`.
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
 
 /// The set of bytes to percent-encode in WebFinger query parameter values.
 ///
@@ -55,6 +62,116 @@ const QUERY: AsciiSet = percent_encoding::CONTROLS
     .add(b'|')
     .add(b'}');
 
+impl WebFingerRequest {
+    /// Extracts an incoming WebFinger request from HTTP request parts.
+    ///
+    /// This is the framework-neutral extraction used by the first-party server integrations. The
+    /// host comes from the request URI authority when the URI is absolute, otherwise from the
+    /// `Host` header. The query string is parsed with RFC 7033 semantics: exactly one percent-encoded
+    /// `resource`, repeated `rel` parameters, and literal `+`.
+    ///
+    /// Path and method matching are left to the caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RequestError`] when the host is missing, the query is malformed, the `resource` is
+    /// not an absolute URI, a `rel` value is not a valid relation type, or the request exceeds the
+    /// default [`ExtractionLimits`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::WebFingerRequest;
+    ///
+    /// let request = http::Request::builder()
+    ///     .uri("/.well-known/webfinger?resource=acct%3Acarol%40example.com")
+    ///     .header("host", "example.com")
+    ///     .body(())?;
+    /// let (parts, ()) = request.into_parts();
+    ///
+    /// let request = WebFingerRequest::from_http_parts(&parts)?;
+    /// assert_eq!(request.host, "example.com");
+    /// assert_eq!(request.resource.as_str(), "acct:carol@example.com");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_http_parts(parts: &Parts) -> Result<Self, RequestError> {
+        Self::from_http_parts_with_limits(parts, &ExtractionLimits::default())
+    }
+
+    /// Extracts an incoming WebFinger request like [`from_http_parts`](Self::from_http_parts),
+    /// enforcing the given [`ExtractionLimits`] instead of the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`from_http_parts`](Self::from_http_parts) errors, or the [`RequestError`]
+    /// variant for the first limit the request exceeds.
+    pub fn from_http_parts_with_limits(
+        parts: &Parts,
+        limits: &ExtractionLimits,
+    ) -> Result<Self, RequestError> {
+        let host = parts
+            .uri
+            .host()
+            .or_else(|| parts.headers.get(HOST).and_then(|host| host.to_str().ok()));
+        Self::from_host_and_query(host, parts.uri.query().unwrap_or(""), limits)
+    }
+}
+
+impl HostPolicy {
+    /// Determines the host of a request from its HTTP parts and checks it against the policy.
+    ///
+    /// The host is the request URI authority when the URI is absolute, otherwise the `Host`
+    /// header, unless `peer` is a trusted proxy that sent `Forwarded` or `X-Forwarded-Host`. See
+    /// [`resolve_host`](Self::resolve_host) for the rules and errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`resolve_host`](Self::resolve_host) errors.
+    pub fn resolve_host_from_parts(
+        &self,
+        parts: &Parts,
+        peer: Option<IpAddr>,
+    ) -> Result<String, RequestError> {
+        let host = parts
+            .uri
+            .host()
+            .or_else(|| parts.headers.get(HOST).and_then(|host| host.to_str().ok()));
+        let header_values = |name| {
+            parts
+                .headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+        };
+        self.resolve_host(
+            peer,
+            host,
+            header_values(FORWARDED),
+            header_values(X_FORWARDED_HOST),
+        )
+    }
+}
+
+/// Writes [`CorsPolicy`] or [`CachePolicy`](crate::CachePolicy) headers into a response.
+///
+/// Policy headers replace values already set, except `Vary`, which is appended so the values a
+/// handler set are kept. Values that are not valid header values are skipped. This is the shared
+/// implementation for integrations built on the [`http`] crate; frameworks with their own header
+/// types carry an equivalent.
+pub fn insert_policy_headers(headers: &mut HeaderMap, policy: Vec<(&'static str, String)>) {
+    for (name, value) in policy {
+        let Ok(value) = HeaderValue::try_from(value) else {
+            continue;
+        };
+        let name = HeaderName::from_static(name);
+        if name == VARY {
+            headers.append(name, value);
+        } else {
+            headers.insert(name, value);
+        }
+    }
+}
+
 impl TryFrom<&WebFingerRequest> for PathAndQuery {
     type Error = InvalidUri;
 
@@ -95,10 +212,11 @@ impl TryFrom<&WebFingerRequest> for Uri {
 impl TryFrom<&WebFingerResponse> for http::Response<()> {
     type Error = http::Error;
     fn try_from(_: &WebFingerResponse) -> Result<http::Response<()>, http::Error> {
-        http::Response::builder()
-            .header("Content-Type", "application/jrd+json")
-            .header("Access-Control-Allow-Origin", CORS_ALLOW_ORIGIN)
-            .body(())
+        let mut builder = http::Response::builder().header("Content-Type", "application/jrd+json");
+        for (name, value) in CorsPolicy::default().response_headers(None) {
+            builder = builder.header(name, value);
+        }
+        builder.body(())
     }
 }
 
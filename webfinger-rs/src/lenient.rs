@@ -0,0 +1,626 @@
+//! Lenient JRD parsing that salvages what it can.
+//!
+//! Real WebFinger servers return JRDs with relative `href` values, empty `rel` values, numeric
+//! property values, or `"links": null`. Strict deserialization rejects the whole document for any
+//! of these, which is right for a server validating its own output but unhelpful for clients and
+//! debugging tools that want whatever the document does say.
+//!
+//! [`WebFingerResponse::from_json_lenient`] keeps every member that is valid, drops or coerces the
+//! rest, and describes each change as a warning [`Finding`] pointing at the original value. Only a
+//! document without a usable `subject` is rejected, because a response cannot exist without one.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::validate::escape_pointer;
+use crate::{
+    Expires, Extensions, Finding, FindingKind, JrdUri, Link, MediaType, Rel, Severity,
+    WebFingerResponse,
+};
+
+/// The response members parsed into typed fields rather than [`Extensions`].
+const RESPONSE_MEMBERS: &[&str] = &["subject", "aliases", "properties", "links", "expires"];
+
+/// The link members parsed into typed fields rather than [`Extensions`].
+const LINK_MEMBERS: &[&str] = &["rel", "type", "href", "template", "titles", "properties"];
+
+/// Error type for [`WebFingerResponse::from_json_lenient`].
+///
+/// These are the problems lenient parsing cannot recover from.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum LenientError {
+    /// The input is not valid JSON.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The input is JSON, but not a JSON object.
+    #[error("a JRD must be a JSON object")]
+    NotObject,
+
+    /// The document has no `subject` member.
+    #[error("missing subject")]
+    MissingSubject,
+
+    /// The `subject` member is not an absolute URI string.
+    #[error("invalid subject: {0}")]
+    InvalidSubject(String),
+}
+
+impl WebFingerResponse {
+    /// Parses a JRD document, keeping every valid member and reporting what was dropped or coerced.
+    ///
+    /// Recovery rules:
+    ///
+    /// - `aliases`, `properties`, `links`, and link `titles` that are `null` or have the wrong JSON
+    ///   type are treated as absent.
+    /// - Aliases and property names that are not absolute URIs are dropped.
+    /// - Numeric and boolean property values are converted to strings; arrays and objects are
+    ///   dropped.
+    /// - Links without a valid `rel` are dropped. Within a kept link, a relative `href`, a `type`
+    ///   that is not a media type, or a `template` or title that is not a string is dropped.
+    /// - An `expires` value that is not an RFC 3339 timestamp is dropped.
+    ///
+    /// Every change is returned as a [`Severity::Warning`] finding whose pointer locates the value
+    /// in the input. Unknown members are kept in [`extensions`](WebFingerResponse::extensions)
+    /// without a warning, as in strict parsing. Use
+    /// [`validate_json`](crate::validate_json) for a full conformance report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is not a JSON object or has no absolute URI `subject`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{FindingKind, WebFingerResponse};
+    ///
+    /// let (response, warnings) = WebFingerResponse::from_json_lenient(
+    ///     r#"{
+    ///         "subject": "acct:carol@example.com",
+    ///         "links": [
+    ///             {"rel": "self", "href": "https://example.com/carol"},
+    ///             {"rel": "", "href": "https://example.com/ignored"},
+    ///             {"rel": "avatar", "href": "/carol.png"}
+    ///         ]
+    ///     }"#,
+    /// )?;
+    ///
+    /// assert_eq!(response.links.len(), 2);
+    /// assert_eq!(response.links[1].href, None);
+    /// assert_eq!(warnings[0].kind, FindingKind::InvalidRel);
+    /// assert_eq!(warnings[1].pointer, "/links/2/href");
+    /// # Ok::<(), webfinger_rs::LenientError>(())
+    /// ```
+    pub fn from_json_lenient(input: &str) -> Result<(Self, Vec<Finding>), LenientError> {
+        let document: Value = serde_json::from_str(input)?;
+        let Value::Object(document) = document else {
+            return Err(LenientError::NotObject);
+        };
+        let subject = match document.get("subject") {
+            None => return Err(LenientError::MissingSubject),
+            Some(Value::String(subject)) => JrdUri::try_new(subject)
+                .map_err(|_| LenientError::InvalidSubject(subject.clone()))?,
+            Some(subject) => return Err(LenientError::InvalidSubject(subject.to_string())),
+        };
+
+        let mut warnings = Vec::new();
+        let aliases = document
+            .get("aliases")
+            .and_then(|aliases| array(aliases, "/aliases", &mut warnings))
+            .map(|aliases| {
+                aliases
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, alias)| {
+                        uri(alias, &format!("/aliases/{index}"), &mut warnings)
+                    })
+                    .collect()
+            });
+        let properties = document
+            .get("properties")
+            .and_then(|properties| properties_map(properties, "/properties", &mut warnings));
+        let links = document
+            .get("links")
+            .and_then(|links| array(links, "/links", &mut warnings))
+            .map(|links| {
+                links
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, link)| {
+                        self::link(link, &format!("/links/{index}"), &mut warnings)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let expires = document
+            .get("expires")
+            .and_then(|expires| self::expires(expires, &mut warnings));
+
+        let response = WebFingerResponse {
+            subject,
+            aliases,
+            properties,
+            links,
+            expires,
+            extensions: extensions(&document, RESPONSE_MEMBERS),
+        };
+        Ok((response, warnings))
+    }
+}
+
+fn link(value: &Value, pointer: &str, warnings: &mut Vec<Finding>) -> Option<Link> {
+    let members = object(value, pointer, warnings)?;
+    let rel = match members.get("rel") {
+        None => {
+            warnings.push(warning(
+                FindingKind::MissingRel,
+                pointer,
+                "dropped link without `rel`",
+            ));
+            return None;
+        }
+        Some(Value::String(rel)) => match Rel::try_new(rel) {
+            Ok(rel) => rel,
+            Err(_) => {
+                warnings.push(warning(
+                    FindingKind::InvalidRel,
+                    format!("{pointer}/rel"),
+                    format!("dropped link with invalid `rel` `{rel}`"),
+                ));
+                return None;
+            }
+        },
+        Some(_) => {
+            warnings.push(warning(
+                FindingKind::InvalidType,
+                format!("{pointer}/rel"),
+                "dropped link whose `rel` is not a string",
+            ));
+            return None;
+        }
+    };
+
+    let mut link = Link::new(rel);
+    link.r#type = string(members, "type", pointer, warnings).and_then(|r#type| {
+        MediaType::try_new(&r#type)
+            .inspect_err(|_| {
+                warnings.push(warning(
+                    FindingKind::MalformedMediaType,
+                    format!("{pointer}/type"),
+                    format!("dropped `type` `{type}`, which is not a media type"),
+                ));
+            })
+            .ok()
+    });
+    link.href = members
+        .get("href")
+        .and_then(|href| uri(href, &format!("{pointer}/href"), warnings));
+    link.template = string(members, "template", pointer, warnings);
+    link.titles = members
+        .get("titles")
+        .and_then(|titles| self::titles(titles, &format!("{pointer}/titles"), warnings));
+    link.properties = members.get("properties").and_then(|properties| {
+        properties_map(properties, &format!("{pointer}/properties"), warnings)
+    });
+    link.extensions = extensions(members, LINK_MEMBERS);
+    Some(link)
+}
+
+fn expires(value: &Value, warnings: &mut Vec<Finding>) -> Option<Expires> {
+    let Value::String(timestamp) = value else {
+        warnings.push(wrong_type(value, "/expires", "a string"));
+        return None;
+    };
+    match Expires::try_new(timestamp) {
+        Ok(expires) => Some(expires),
+        Err(_) => {
+            warnings.push(warning(
+                FindingKind::InvalidExpires,
+                "/expires",
+                format!("dropped `expires` `{timestamp}`, which is not an RFC 3339 timestamp"),
+            ));
+            None
+        }
+    }
+}
+
+/// Collects the members that are not parsed into typed fields.
+fn extensions(members: &Map<String, Value>, modeled: &[&str]) -> Extensions {
+    members
+        .iter()
+        .filter(|(name, _)| !modeled.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn titles(
+    value: &Value,
+    pointer: &str,
+    warnings: &mut Vec<Finding>,
+) -> Option<BTreeMap<String, String>> {
+    let titles = object(value, pointer, warnings)?;
+    let titles = titles
+        .iter()
+        .filter_map(|(language, title)| match title {
+            Value::String(title) => Some((language.clone(), title.clone())),
+            _ => {
+                warnings.push(warning(
+                    FindingKind::InvalidType,
+                    format!("{pointer}/{}", escape_pointer(language)),
+                    "dropped title that is not a string",
+                ));
+                None
+            }
+        })
+        .collect();
+    Some(titles)
+}
+
+fn properties_map(
+    value: &Value,
+    pointer: &str,
+    warnings: &mut Vec<Finding>,
+) -> Option<BTreeMap<JrdUri, Option<String>>> {
+    let properties = object(value, pointer, warnings)?;
+    let properties = properties
+        .iter()
+        .filter_map(|(name, value)| {
+            let pointer = format!("{pointer}/{}", escape_pointer(name));
+            let Ok(name) = JrdUri::try_new(name) else {
+                warnings.push(warning(
+                    FindingKind::NonUriPropertyKey,
+                    pointer,
+                    format!("dropped property `{name}` whose name is not an absolute URI"),
+                ));
+                return None;
+            };
+            let value = match value {
+                Value::Null => None,
+                Value::String(value) => Some(value.clone()),
+                Value::Number(_) | Value::Bool(_) => {
+                    warnings.push(warning(
+                        FindingKind::InvalidPropertyValue,
+                        pointer,
+                        format!("converted property value {value} to a string"),
+                    ));
+                    Some(value.to_string())
+                }
+                Value::Array(_) | Value::Object(_) => {
+                    warnings.push(warning(
+                        FindingKind::InvalidPropertyValue,
+                        pointer,
+                        "dropped property whose value is not a string or null",
+                    ));
+                    return None;
+                }
+            };
+            Some((name, value))
+        })
+        .collect();
+    Some(properties)
+}
+
+/// Reads an optional string member of a link, dropping values of any other JSON type.
+fn string(
+    members: &Map<String, Value>,
+    name: &str,
+    pointer: &str,
+    warnings: &mut Vec<Finding>,
+) -> Option<String> {
+    match members.get(name)? {
+        Value::String(value) => Some(value.clone()),
+        _ => {
+            warnings.push(warning(
+                FindingKind::InvalidType,
+                format!("{pointer}/{name}"),
+                format!("dropped `{name}` that is not a string"),
+            ));
+            None
+        }
+    }
+}
+
+fn uri(value: &Value, pointer: &str, warnings: &mut Vec<Finding>) -> Option<JrdUri> {
+    let (kind, message) = match value {
+        Value::String(uri) => match JrdUri::try_new(uri) {
+            Ok(uri) => return Some(uri),
+            Err(_) => {
+                let kind = if pointer.ends_with("/href") {
+                    FindingKind::RelativeHref
+                } else {
+                    FindingKind::InvalidUri
+                };
+                (
+                    kind,
+                    format!("dropped `{uri}`, which is not an absolute URI"),
+                )
+            }
+        },
+        _ => (
+            FindingKind::InvalidType,
+            "dropped URI value that is not a string".to_string(),
+        ),
+    };
+    warnings.push(warning(kind, pointer, message));
+    None
+}
+
+fn array<'a>(value: &'a Value, pointer: &str, warnings: &mut Vec<Finding>) -> Option<&'a [Value]> {
+    match value {
+        Value::Array(values) => Some(values),
+        _ => {
+            warnings.push(wrong_type(value, pointer, "an array"));
+            None
+        }
+    }
+}
+
+fn object<'a>(
+    value: &'a Value,
+    pointer: &str,
+    warnings: &mut Vec<Finding>,
+) -> Option<&'a Map<String, Value>> {
+    match value {
+        Value::Object(members) => Some(members),
+        _ => {
+            warnings.push(wrong_type(value, pointer, "an object"));
+            None
+        }
+    }
+}
+
+fn wrong_type(value: &Value, pointer: &str, expected: &str) -> Finding {
+    let found = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    warning(
+        FindingKind::InvalidType,
+        pointer,
+        format!("ignored {found} where {expected} was expected"),
+    )
+}
+
+fn warning(kind: FindingKind, pointer: impl Into<String>, message: impl Into<String>) -> Finding {
+    Finding::new(Severity::Warning, kind, pointer, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    fn kinds(findings: &[Finding]) -> Vec<(FindingKind, &str)> {
+        findings
+            .iter()
+            .map(|finding| (finding.kind, finding.pointer.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn conforming_document_matches_strict_parsing() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "expires": "2030-01-30T09:30:00Z",
+            "aliases": ["https://example.com/carol"],
+            "properties": {"http://example.com/ns/role": null},
+            "links": [{
+                "rel": "self",
+                "type": "application/activity+json",
+                "href": "https://example.com/users/carol",
+                "titles": {"en": "Carol"},
+                "properties": {"http://example.com/ns/verified": "true"},
+            }],
+        })
+        .to_string();
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document)?;
+
+        assert_eq!(warnings, vec![]);
+        assert_eq!(response, serde_json::from_str(&document)?);
+        Ok(())
+    }
+
+    /// `null` and wrongly typed collections are treated as absent rather than failing the parse.
+    #[test]
+    fn treats_null_collections_as_absent() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "aliases": null,
+            "properties": [],
+            "links": null,
+        });
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document.to_string())?;
+
+        assert_eq!(response, WebFingerResponse::new("acct:carol@example.com"));
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (FindingKind::InvalidType, "/aliases"),
+                (FindingKind::InvalidType, "/properties"),
+                (FindingKind::InvalidType, "/links"),
+            ],
+        );
+        assert_eq!(
+            warnings[0].message,
+            "ignored null where an array was expected"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn drops_links_without_a_valid_rel() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [
+                {"href": "https://example.com/a"},
+                {"rel": "", "href": "https://example.com/b"},
+                {"rel": 1},
+                "self",
+                {"rel": "self", "href": "https://example.com/c"},
+            ],
+        });
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document.to_string())?;
+
+        assert_eq!(response.links.len(), 1);
+        assert_eq!(response.links[0].rel.as_ref(), "self");
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (FindingKind::MissingRel, "/links/0"),
+                (FindingKind::InvalidRel, "/links/1/rel"),
+                (FindingKind::InvalidType, "/links/2/rel"),
+                (FindingKind::InvalidType, "/links/3"),
+            ],
+        );
+        Ok(())
+    }
+
+    /// A relative `href` is dropped, but the rest of the link is kept.
+    #[test]
+    fn keeps_links_with_relative_hrefs() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "aliases": ["/carol", "https://example.com/carol"],
+            "links": [{
+                "rel": "http://webfinger.net/rel/avatar",
+                "type": "png",
+                "href": "/carol.png",
+                "titles": {"en": "Avatar", "de": false},
+            }],
+        });
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document.to_string())?;
+
+        assert_eq!(
+            response.aliases,
+            Some(vec![JrdUri::new("https://example.com/carol")])
+        );
+        let link = &response.links[0];
+        assert_eq!(link.r#type, None);
+        assert_eq!(link.href, None);
+        assert_eq!(
+            link.titles,
+            Some(BTreeMap::from([("en".to_string(), "Avatar".to_string())]))
+        );
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (FindingKind::InvalidUri, "/aliases/0"),
+                (FindingKind::MalformedMediaType, "/links/0/type"),
+                (FindingKind::RelativeHref, "/links/0/href"),
+                (FindingKind::InvalidType, "/links/0/titles/de"),
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn coerces_scalar_property_values() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "properties": {
+                "http://example.com/ns/age": 42,
+                "http://example.com/ns/admin": true,
+                "http://example.com/ns/tags": ["a"],
+                "display-name": "Carol",
+            },
+        });
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document.to_string())?;
+
+        assert_eq!(
+            response.properties,
+            Some(BTreeMap::from([
+                (
+                    JrdUri::new("http://example.com/ns/admin"),
+                    Some("true".to_string())
+                ),
+                (
+                    JrdUri::new("http://example.com/ns/age"),
+                    Some("42".to_string())
+                ),
+            ])),
+        );
+        assert_eq!(
+            kinds(&warnings),
+            vec![
+                (FindingKind::NonUriPropertyKey, "/properties/display-name"),
+                (
+                    FindingKind::InvalidPropertyValue,
+                    "/properties/http:~1~1example.com~1ns~1admin"
+                ),
+                (
+                    FindingKind::InvalidPropertyValue,
+                    "/properties/http:~1~1example.com~1ns~1age"
+                ),
+                (
+                    FindingKind::InvalidPropertyValue,
+                    "/properties/http:~1~1example.com~1ns~1tags"
+                ),
+            ],
+        );
+        assert!(
+            warnings
+                .iter()
+                .all(|warning| warning.severity == Severity::Warning)
+        );
+        Ok(())
+    }
+
+    /// Unmodeled members are kept at both levels, and a bad `expires` does not fail the parse.
+    #[test]
+    fn keeps_extensions_and_drops_invalid_expires() -> Result {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "expires": "tomorrow",
+            "x-vendor": {"tier": 2},
+            "links": [{"rel": "self", "x-order": 1}],
+        });
+
+        let (response, warnings) = WebFingerResponse::from_json_lenient(&document.to_string())?;
+
+        assert_eq!(response.expires, None);
+        assert_eq!(
+            response.extensions.get("x-vendor"),
+            Some(&json!({"tier": 2}))
+        );
+        assert_eq!(response.links[0].extensions.get("x-order"), Some(&json!(1)));
+        assert_eq!(
+            kinds(&warnings),
+            vec![(FindingKind::InvalidExpires, "/expires")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_documents_without_a_usable_subject() {
+        assert!(matches!(
+            WebFingerResponse::from_json_lenient("{"),
+            Err(LenientError::Json(_))
+        ));
+        assert!(matches!(
+            WebFingerResponse::from_json_lenient("[]"),
+            Err(LenientError::NotObject)
+        ));
+        assert!(matches!(
+            WebFingerResponse::from_json_lenient(r#"{"links": []}"#),
+            Err(LenientError::MissingSubject)
+        ));
+        assert!(matches!(
+            WebFingerResponse::from_json_lenient(r#"{"subject": "carol"}"#),
+            Err(LenientError::InvalidSubject(subject)) if subject == "carol"
+        ));
+    }
+}
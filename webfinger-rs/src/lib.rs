@@ -1,6 +1,6 @@
 //! `webfinger-rs` is a transport-agnostic [WebFinger] implementation for Rust, centered on the
 //! request and response types defined by [RFC 7033] with first-party integrations for [Reqwest],
-//! [Axum], and [Actix Web].
+//! [Axum], [Actix Web], [Rocket], [Poem], and [Salvo].
 //!
 //! WebFinger is used to discover information about people or other entities on the internet using
 //! URI-based identifiers such as `acct:carol@example.com`. In practice, it is commonly used for
@@ -14,7 +14,9 @@
 //!
 //! - Reusable request and response types shaped around RFC 7033.
 //! - Optional Reqwest client execution via [`WebFingerRequest::execute_reqwest`].
-//! - Optional Axum and Actix Web extractor/responder integrations.
+//! - Optional Axum, Actix Web, Rocket, Poem, and Salvo extractor/responder integrations.
+//! - [`WebFingerRequest::from_query`] and [`WebFingerRequest::from_http_parts`] for frameworks
+//!   without a first-party integration.
 //! - A permissive dual license (`MIT OR Apache-2.0`) that fits typical library and application
 //!   usage.
 //!
@@ -23,6 +25,9 @@
 //! [Reqwest]: https://crates.io/crates/reqwest
 //! [Axum]: https://crates.io/crates/axum
 //! [Actix Web]: https://crates.io/crates/actix-web
+//! [Rocket]: https://crates.io/crates/rocket
+//! [Poem]: https://crates.io/crates/poem
+//! [Salvo]: https://crates.io/crates/salvo
 //! [OpenID Connect Discovery]: https://openid.net/specs/openid-connect-discovery-1_0.html
 //! [Mastodon]: https://docs.joinmastodon.org/spec/webfinger/
 //! [ActivityPub]: https://www.w3.org/TR/activitypub/
@@ -39,8 +44,18 @@
 //! cargo add webfinger-rs --features reqwest
 //! cargo add webfinger-rs --features axum
 //! cargo add webfinger-rs --features actix
+//! cargo add webfinger-rs --features rocket
+//! cargo add webfinger-rs --features poem
+//! cargo add webfinger-rs --features salvo
+//! cargo add webfinger-rs --features xrd
 //! ```
 //!
+//! The `xrd` feature adds [RFC 6415] XRD 1.0 rendering and parsing with `WebFingerResponse::to_xrd`
+//! and `WebFingerResponse::from_xrd`, and lets
+//! server integrations answer `Accept: application/xrd+xml` (see [`ResponseFormat`]).
+//!
+//! [RFC 6415]: https://www.rfc-editor.org/rfc/rfc6415.html
+//!
 //! The related CLI tool, [`webfinger-cli`], is useful for trying servers by hand:
 //!
 //! ```shell
@@ -58,6 +73,9 @@
 //! | `reqwest` | Client execution helpers and Reqwest request/response conversions |
 //! | `axum` | [`WebFingerRequest`] extraction and [`WebFingerResponse`] responses in Axum via [`webfinger_rs::axum`] |
 //! | `actix` | [`WebFingerRequest`] extraction and [`WebFingerResponse`] responses in Actix Web via [`webfinger_rs::actix`] |
+//! | `rocket` | [`WebFingerRequest`] request guard and [`WebFingerResponse`] responses in Rocket via [`webfinger_rs::rocket`] |
+//! | `poem` | [`WebFingerRequest`] extraction and [`WebFingerResponse`] responses in Poem via [`webfinger_rs::poem`] |
+//! | `salvo` | [`WebFingerRequest`] extraction and [`WebFingerResponse`] responses in Salvo via [`webfinger_rs::salvo`] |
 //!
 //! # Primary types
 //!
@@ -66,9 +84,21 @@
 //! - [`WebFingerResponse`] models the JSON Resource Descriptor returned by a WebFinger endpoint.
 //!   Return one from server handlers or parse one from a Reqwest response.
 //! - [`Link`] and [`Rel`] model JRD link objects and relation filters so servers can apply the
-//!   same relation-filtering rules that clients request.
+//!   same relation-filtering rules that clients request. [`MediaType`] parses link types so
+//!   [`MediaType::is_activitypub`] and [`MediaType::matches`] compare them correctly.
 //! - [`Resource`] and [`JrdUri`] validate URI-valued protocol fields before they enter requests or
 //!   JRD responses.
+//! - [`Expires`] carries the RFC 6415 `expires` timestamp as a cache hint, and [`Extensions`]
+//!   keeps JRD members the crate does not model so they survive a round trip.
+//! - [`ExtractionLimits`] bounds the query, `resource`, and `rel` sizes and the `resource` schemes
+//!   that server integrations accept, and [`HostPolicy`] trusts forwarded host headers only from
+//!   configured proxies and rejects hosts the server does not serve.
+//! - [`ErrorFormat::Problem`] answers rejected requests with an RFC 9457 [`Problem`] document
+//!   whose `code` identifies the cause, instead of a plain text message.
+//! - [`validate_json`] and [`WebFingerResponse::validate`] check a whole JRD document and report
+//!   every conformance problem as a [`Finding`] with a severity and JSON pointer.
+//!   [`WebFingerResponse::from_json_lenient`] salvages what it can from a nonconforming document
+//!   and reports what it dropped the same way.
 //!
 //! # Protocol overview
 //!
@@ -282,6 +312,9 @@
 //! [Actix example]:
 //!     https://github.com/joshka/webfinger-rs/blob/main/webfinger-rs/examples/actix.rs
 //! [`webfinger_rs::actix`]: https://docs.rs/webfinger-rs/latest/webfinger_rs/actix/
+//! [`webfinger_rs::rocket`]: https://docs.rs/webfinger-rs/latest/webfinger_rs/rocket/
+//! [`webfinger_rs::poem`]: https://docs.rs/webfinger-rs/latest/webfinger_rs/poem/
+//! [`webfinger_rs::salvo`]: https://docs.rs/webfinger-rs/latest/webfinger_rs/salvo/
 //!
 //! # Compatibility
 //!
@@ -290,6 +323,9 @@
 //! - Reqwest `0.13`
 //! - Axum `0.8`
 //! - Actix Web `4`
+//! - Rocket `0.5`
+//! - Poem `3`
+//! - Salvo `0.85`
 //!
 //! The crate is currently pre-`0.1`, so API and compatibility adjustments may still land in minor
 //! releases while the integration surface settles. These version notes describe the currently
@@ -298,7 +334,8 @@
 //! # Limitations
 //!
 //! - Client execution is currently implemented only for Reqwest.
-//! - Server integrations are currently implemented only for Axum and Actix Web.
+//! - Server integrations are currently implemented for Axum, Actix Web, Rocket, Poem, and Salvo.
+//!   Other frameworks can parse requests with [`WebFingerRequest::from_query`].
 //! - The crate focuses on RFC 7033 request/response handling and framework integration, not a full
 //!   identity stack around WebFinger.
 //! - The crate docs aim to stay grounded in RFC 7033, but they document the current implementation
@@ -341,23 +378,53 @@
 #![deny(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub use crate::cache::{CachePolicy, etag_matches};
+pub use crate::cors::{ALLOWED_METHODS, AllowOrigin, CorsPolicy};
 pub use crate::error::Error;
+pub use crate::format::{JRD_CONTENT_TYPE, ResponseFormat};
+pub use crate::host::HostPolicy;
+pub use crate::http::insert_policy_headers;
+pub use crate::lenient::LenientError;
+pub use crate::limits::ExtractionLimits;
+pub use crate::problem::{ErrorFormat, PROBLEM_CONTENT_TYPE, Problem};
+pub use crate::query::RequestError;
+#[cfg(feature = "reqwest")]
+pub use crate::reqwest::webfinger_reqwest_client;
 pub use crate::types::{
-    JrdUri, Link, LinkBuilder, Rel, Request as WebFingerRequest, RequestBuilder, Resource,
-    ResourceError, Response as WebFingerResponse, ResponseBuilder, Title,
+    Expires, Extensions, JrdUri, LanguageRange, Link, LinkBuilder, MediaType, Rel,
+    Request as WebFingerRequest, RequestBuilder, Resource, ResourceError,
+    Response as WebFingerResponse, ResponseBuilder, Title,
 };
+pub use crate::validate::{Finding, FindingKind, Severity, validate_json};
+#[cfg(feature = "xrd")]
+pub use crate::xrd::{XRD_CONTENT_TYPE, XrdError};
 
 #[cfg(feature = "actix")]
 pub mod actix;
 #[cfg(feature = "axum")]
 pub mod axum;
+mod cache;
+mod cors;
 mod error;
+mod format;
+mod host;
 mod http;
-#[cfg(any(feature = "actix", feature = "axum", test))]
+mod lenient;
+mod limits;
+#[cfg(feature = "poem")]
+pub mod poem;
+mod problem;
 mod query;
 #[cfg(feature = "reqwest")]
 mod reqwest;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(feature = "salvo")]
+pub mod salvo;
 mod types;
+mod validate;
+#[cfg(feature = "xrd")]
+mod xrd;
 
 /// The well-known path for WebFinger requests (`/.well-known/webfinger`).
 ///
@@ -0,0 +1,190 @@
+//! Size and scheme limits for incoming WebFinger requests.
+//!
+//! WebFinger endpoints are public and answer unauthenticated queries, so a server should bound the
+//! work a single request can ask for before it looks anything up. [`ExtractionLimits`] describes
+//! those bounds once so every server integration rejects oversized or unwanted requests the same
+//! way, with a distinct [`RequestError`] variant for each limit.
+
+use crate::RequestError;
+use crate::query::RequestParams;
+
+/// Limits applied while extracting a [`WebFingerRequest`](crate::WebFingerRequest).
+///
+/// The default limits allow an 8 KiB query string, a 2048 character `resource`, and up to 16 `rel`
+/// parameters of at most 512 characters each, and accept every resource scheme. They are generous
+/// enough for ordinary account and profile lookups while keeping a single request cheap to parse.
+///
+/// Lengths of `resource` and `rel` are counted in characters after percent-decoding; the query
+/// length is counted in bytes as received.
+///
+/// Server integrations read the limits from framework state: `App::app_data` in Actix Web,
+/// [`axum::extraction_limits`](crate::axum::extraction_limits) middleware or an `Extension` layer
+/// in Axum, `Rocket::manage` in Rocket, `EndpointExt::data` in Poem, and the request extensions in
+/// Salvo. Without registered limits they use [`ExtractionLimits::default`].
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::{ExtractionLimits, RequestError, WebFingerRequest};
+///
+/// let limits = ExtractionLimits::new().max_rels(1).allowed_schemes(["acct"]);
+///
+/// let error = WebFingerRequest::from_query_with_limits(
+///     "example.com",
+///     "resource=https%3A%2F%2Fexample.com%2Fcarol",
+///     &limits,
+/// )
+/// .unwrap_err();
+/// assert!(matches!(error, RequestError::DisallowedScheme(scheme) if scheme == "https"));
+///
+/// let error = WebFingerRequest::from_query_with_limits(
+///     "example.com",
+///     "resource=acct%3Acarol%40example.com&rel=self&rel=profile",
+///     &limits,
+/// )
+/// .unwrap_err();
+/// assert!(matches!(error, RequestError::TooManyRels { max: 1 }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractionLimits {
+    max_query_length: usize,
+    max_resource_length: usize,
+    max_rels: usize,
+    max_rel_length: usize,
+    allowed_schemes: Option<Vec<String>>,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self {
+            max_query_length: 8 * 1024,
+            max_resource_length: 2_048,
+            max_rels: 16,
+            max_rel_length: 512,
+            allowed_schemes: None,
+        }
+    }
+}
+
+impl ExtractionLimits {
+    /// Creates the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum length of the raw query string, in bytes.
+    pub fn max_query_length(mut self, max: usize) -> Self {
+        self.max_query_length = max;
+        self
+    }
+
+    /// Sets the maximum length of the decoded `resource` value, in characters.
+    pub fn max_resource_length(mut self, max: usize) -> Self {
+        self.max_resource_length = max;
+        self
+    }
+
+    /// Sets the maximum number of `rel` parameters.
+    pub fn max_rels(mut self, max: usize) -> Self {
+        self.max_rels = max;
+        self
+    }
+
+    /// Sets the maximum length of each decoded `rel` value, in characters.
+    pub fn max_rel_length(mut self, max: usize) -> Self {
+        self.max_rel_length = max;
+        self
+    }
+
+    /// Restricts `resource` to the given URI schemes, such as `acct` and `https`.
+    ///
+    /// Schemes compare case-insensitively, as required by RFC 3986 section 3.1.
+    pub fn allowed_schemes<I, S>(mut self, schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_schemes = Some(schemes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Accepts `resource` values with any URI scheme.
+    pub fn allow_any_scheme(mut self) -> Self {
+        self.allowed_schemes = None;
+        self
+    }
+
+    /// Returns whether a `resource` with the given URI scheme is accepted.
+    pub fn allows_scheme(&self, scheme: &str) -> bool {
+        self.allowed_schemes.as_ref().is_none_or(|schemes| {
+            schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        })
+    }
+
+    /// Rejects a raw query string longer than the query limit.
+    pub(crate) fn check_query(&self, query: &str) -> Result<(), RequestError> {
+        if query.len() > self.max_query_length {
+            return Err(RequestError::QueryTooLong {
+                max: self.max_query_length,
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects decoded query parameters that exceed the resource, scheme, or `rel` limits.
+    pub(crate) fn check_params(&self, params: &RequestParams) -> Result<(), RequestError> {
+        let resource = params.resource.as_str();
+        if resource.chars().count() > self.max_resource_length {
+            return Err(RequestError::ResourceTooLong {
+                max: self.max_resource_length,
+            });
+        }
+        let scheme = resource
+            .split_once(':')
+            .map_or(resource, |(scheme, _)| scheme);
+        if !self.allows_scheme(scheme) {
+            return Err(RequestError::DisallowedScheme(scheme.to_ascii_lowercase()));
+        }
+        if params.rel.len() > self.max_rels {
+            return Err(RequestError::TooManyRels { max: self.max_rels });
+        }
+        if params
+            .rel
+            .iter()
+            .any(|rel| rel.chars().count() > self.max_rel_length)
+        {
+            return Err(RequestError::RelTooLong {
+                max: self.max_rel_length,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_allows_any_scheme() {
+        let limits = ExtractionLimits::default();
+
+        assert!(limits.allows_scheme("acct"));
+        assert!(limits.allows_scheme("did"));
+    }
+
+    /// Compares schemes case-insensitively.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc3986.html#section-3.1>.
+    #[test]
+    fn allowed_schemes_ignore_case() {
+        let limits = ExtractionLimits::new().allowed_schemes(["acct", "https"]);
+
+        assert!(limits.allows_scheme("ACCT"));
+        assert!(limits.allows_scheme("https"));
+        assert!(!limits.allows_scheme("mailto"));
+        assert!(limits.allow_any_scheme().allows_scheme("mailto"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Poem integration for WebFinger request extraction and JRD responses.
+//!
+//! Enable the `poem` feature to:
+//!
+//! - extract [`WebFingerRequest`] in handlers mounted for `GET` requests to
+//!   [`crate::WELL_KNOWN_PATH`];
+//! - return [`WebFingerResponse`] directly from Poem handlers as `application/jrd+json` with the
+//!   WebFinger CORS header; and
+//! - answer browser CORS preflight requests with the [`preflight`] endpoint.
+//!
+//! The extractor parses the query with [`WebFingerRequest::from_query`], so it accepts the same
+//! RFC 7033 query shape as the other server integrations: exactly one percent-encoded `resource`,
+//! repeated `rel` parameters, and literal `+`. Malformed requests are answered with
+//! `400 Bad Request`, the error message, and the CORS header so browser clients can read them.
+//!
+//! ```rust
+//! use poem::{Route, handler, get};
+//! use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! #[handler]
+//! fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let app = Route::new().at(
+//!     WELL_KNOWN_PATH,
+//!     get(webfinger).head(webfinger).options(webfinger_rs::poem::preflight),
+//! );
+//! # let _ = app;
+//! ```
+//!
+//! Add a [`CorsPolicy`] with `EndpointExt::data` to restrict which origins may read extraction
+//! errors and preflight responses, and [`ExtractionLimits`] or a [`HostPolicy`] the same way to
+//! bound request sizes or to trust proxies and restrict hosts. Poem converts handler return values
+//! without access to the request, so the [`WebFingerResponse`] responder always renders JRD with
+//! the headers of [`CorsPolicy::default`] and a strong `ETag`; it does not negotiate XRD or answer
+//! `If-None-Match`. Return a response built from [`ResponseFormat`] and [`CachePolicy`] in the
+//! handler when those are needed.
+//!
+//! [`ResponseFormat`]: crate::ResponseFormat
+//! [`CachePolicy`]: crate::CachePolicy
+
+use poem::http::StatusCode;
+use poem::http::header::{CONTENT_TYPE, ETAG};
+use poem::{FromRequest, IntoResponse, Request, RequestBody, Response};
+use tracing::trace;
+
+use crate::{
+    ALLOWED_METHODS, CorsPolicy, ExtractionLimits, HostPolicy, JRD_CONTENT_TYPE, ResponseFormat,
+    WebFingerRequest, WebFingerResponse, insert_policy_headers,
+};
+
+impl IntoResponse for WebFingerResponse {
+    /// Converts a [`WebFingerResponse`] into a Poem response.
+    ///
+    /// This renders the body as `application/jrd+json`, the JRD media type used by WebFinger, and
+    /// sets `Access-Control-Allow-Origin: *` as recommended by RFC 7033 section 5 together with a
+    /// strong `ETag` from [`WebFingerResponse::etag`].
+    fn into_response(self) -> Response {
+        let format = ResponseFormat::Jrd;
+        let body = match format.render(&self) {
+            Ok(body) => body,
+            Err(error) => {
+                let mut response = Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(error.to_string());
+                insert_policy_headers(
+                    response.headers_mut(),
+                    CorsPolicy::default().response_headers(None),
+                );
+                return response;
+            }
+        };
+        let mut response = Response::builder()
+            .header(CONTENT_TYPE, JRD_CONTENT_TYPE)
+            .header(ETAG, self.etag(format))
+            .body(body);
+        insert_policy_headers(
+            response.headers_mut(),
+            CorsPolicy::default().response_headers(None),
+        );
+        response
+    }
+}
+
+impl<'a> FromRequest<'a> for WebFingerRequest {
+    /// Extracts a [`WebFingerRequest`] from a Poem request.
+    ///
+    /// The host comes from the request URI authority when the URI is absolute, otherwise from the
+    /// `Host` header, unless the [`HostPolicy`] added with `EndpointExt::data` trusts the
+    /// connecting peer and it forwarded a host. The raw query string is parsed with
+    /// [`WebFingerRequest::from_query_with_limits`] and the [`ExtractionLimits`] added with
+    /// `EndpointExt::data`, or the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns `400 Bad Request` with the [`RequestError`](crate::RequestError) message and the
+    /// CORS headers when the host is missing, the query is malformed, the `resource` is not an
+    /// absolute URI, or a `rel` value is invalid.
+    async fn from_request(request: &'a Request, _body: &mut RequestBody) -> poem::Result<Self> {
+        trace!(?request, "extracting WebFingerRequest from request");
+        let host = request.uri().host().or_else(|| request.header("host"));
+        let header_values = |name| {
+            request
+                .headers()
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+        };
+        let default_host_policy = HostPolicy::default();
+        let host_policy = request.data().unwrap_or(&default_host_policy);
+        let host = host_policy.resolve_host(
+            request
+                .remote_addr()
+                .as_socket_addr()
+                .map(|address| address.ip()),
+            host,
+            header_values("forwarded"),
+            header_values("x-forwarded-host"),
+        );
+        let query = request.uri().query().unwrap_or_default();
+        let default_limits = ExtractionLimits::default();
+        let limits = request.data().unwrap_or(&default_limits);
+        let webfinger =
+            host.and_then(|host| WebFingerRequest::from_query_with_limits(&host, query, limits));
+        webfinger.map_err(|error| {
+            let mut response = Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(error.to_string());
+            insert_policy_headers(response.headers_mut(), cors_headers(request));
+            poem::Error::from_response(response)
+        })
+    }
+}
+
+/// Answers a CORS preflight request for the WebFinger endpoint.
+///
+/// Mount this endpoint for `OPTIONS` requests to [`crate::WELL_KNOWN_PATH`]. It returns
+/// `204 No Content` with `Allow` and the preflight headers of the [`CorsPolicy`] added with
+/// `EndpointExt::data`, or of [`CorsPolicy::default`].
+///
+/// See the [`crate::poem`] module docs for an example.
+#[poem::handler]
+pub fn preflight(request: &Request) -> Response {
+    let origin = request.header("origin");
+    let headers = match request.data::<CorsPolicy>() {
+        Some(policy) => policy.preflight_headers(origin),
+        None => CorsPolicy::default().preflight_headers(origin),
+    };
+    let mut response = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("allow", ALLOWED_METHODS)
+        .finish();
+    insert_policy_headers(response.headers_mut(), headers);
+    response
+}
+
+/// Returns the CORS headers for a WebFinger response to `request`.
+fn cors_headers(request: &Request) -> Vec<(&'static str, String)> {
+    let origin = request.header("origin");
+    match request.data::<CorsPolicy>() {
+        Some(policy) => policy.response_headers(origin),
+        None => CorsPolicy::default().response_headers(origin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poem::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ALLOW};
+    use poem::http::{Method, Uri};
+    use poem::{Endpoint, EndpointExt, Route, get, handler};
+
+    use super::*;
+    use crate::{AllowOrigin, WELL_KNOWN_PATH};
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    /// Returns the extracted host, resource, and relation filters so tests can assert decoding.
+    #[handler]
+    fn webfinger(request: WebFingerRequest) -> String {
+        let mut values = vec![request.host, request.resource.to_string()];
+        values.extend(request.rels.iter().map(ToString::to_string));
+        values.join(" ")
+    }
+
+    /// Returns a minimal JRD so tests can assert responder-owned WebFinger headers.
+    #[handler]
+    fn webfinger_response() -> WebFingerResponse {
+        WebFingerResponse::new("acct:carol@example.com")
+    }
+
+    fn app() -> Route {
+        Route::new()
+            .at(WELL_KNOWN_PATH, get(webfinger).options(preflight))
+            .at("/jrd", get(webfinger_response))
+    }
+
+    fn get_request(uri: &'static str) -> Request {
+        Request::builder()
+            .uri(Uri::from_static(uri))
+            .header("host", "example.org")
+            .finish()
+    }
+
+    /// Parses the RFC 7033 query shape: repeated `rel`, percent-decoding, and literal `+`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[tokio::test]
+    async fn extracts_request() -> Result {
+        let request = get_request(
+            "/.well-known/webfinger?resource=acct%3Acarol+work%40example.org&rel=self&rel=profile",
+        );
+
+        let response = app().get_response(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.into_body().into_string().await?,
+            "example.org acct:carol+work@example.org self profile",
+        );
+        Ok(())
+    }
+
+    /// Rejects a missing `resource` with the error message and the RFC 7033 CORS header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-5>.
+    #[tokio::test]
+    async fn malformed_request_is_bad_request_with_cors() -> Result {
+        let response = app().get_response(get_request(WELL_KNOWN_PATH)).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "*",
+        );
+        assert_eq!(
+            response.into_body().into_string().await?,
+            "missing resource parameter",
+        );
+        Ok(())
+    }
+
+    /// Rejects relative resource references.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[tokio::test]
+    async fn relative_resource_is_bad_request() {
+        let request = get_request("/.well-known/webfinger?resource=carol");
+
+        let response = app().get_response(request).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Serves JRD with the CORS header and an entity tag.
+    #[tokio::test]
+    async fn responder_sets_jrd_headers() {
+        let response = app().get_response(get_request("/jrd")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers.get(CONTENT_TYPE).unwrap(), JRD_CONTENT_TYPE);
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert!(headers.contains_key(ETAG));
+    }
+
+    /// Answers browser CORS preflight requests with a policy added as endpoint data.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[tokio::test]
+    async fn preflight_uses_data_policy() {
+        let policy = CorsPolicy::new()
+            .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+        let app = app().data(policy);
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri(Uri::from_static(WELL_KNOWN_PATH))
+            .header("origin", "https://app.example")
+            .finish();
+
+        let response = app.get_response(request).await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers.get(ALLOW).unwrap(), ALLOWED_METHODS);
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://app.example",
+        );
+    }
+}
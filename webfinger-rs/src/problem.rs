@@ -0,0 +1,184 @@
+//! RFC 9457 problem details for rejected WebFinger requests.
+//!
+//! Server integrations answer malformed requests with `400 Bad Request` and, by default, a plain
+//! text message such as `missing host`. That is easy to read with `curl` but leaves clients parsing
+//! English to tell causes apart. [`ErrorFormat::Problem`] switches those responses to an
+//! `application/problem+json` document described by [RFC 9457], which carries a stable
+//! machine-readable `code` from [`RequestError::code`] next to the human-readable `detail`.
+//!
+//! [RFC 9457]: https://www.rfc-editor.org/rfc/rfc9457.html
+
+use serde::{Deserialize, Serialize};
+
+use crate::RequestError;
+
+/// The media type of RFC 9457 problem details serialized as JSON.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-8.1>.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// The body format of WebFinger error responses.
+///
+/// Server integrations read the format from framework state like the other policies:
+/// `App::app_data` in Actix Web, the [`axum::error_format`](crate::axum::error_format) middleware
+/// in Axum, and `with_error_format` on the `webfinger-service` runtimes. Without a registered
+/// format they send [`ErrorFormat::PlainText`].
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::{ErrorFormat, PROBLEM_CONTENT_TYPE, RequestError};
+///
+/// let (content_type, body) = ErrorFormat::Problem.render(&RequestError::MissingResource);
+/// assert_eq!(content_type, PROBLEM_CONTENT_TYPE);
+/// assert!(body.contains(r#""code":"missing_resource""#));
+///
+/// let (content_type, body) = ErrorFormat::PlainText.render(&RequestError::MissingResource);
+/// assert_eq!(content_type, "text/plain; charset=utf-8");
+/// assert_eq!(body, "missing resource parameter");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorFormat {
+    /// A plain text message, such as `missing host`.
+    #[default]
+    PlainText,
+
+    /// An `application/problem+json` [`Problem`] document.
+    Problem,
+}
+
+impl ErrorFormat {
+    /// Returns the `Content-Type` of error responses in this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ErrorFormat::PlainText => "text/plain; charset=utf-8",
+            ErrorFormat::Problem => PROBLEM_CONTENT_TYPE,
+        }
+    }
+
+    /// Renders a `400 Bad Request` body for a rejected request, returning the content type and body.
+    pub fn render(self, error: &RequestError) -> (&'static str, String) {
+        let body = match self {
+            ErrorFormat::PlainText => error.to_string(),
+            ErrorFormat::Problem => Problem::bad_request(error).to_json(),
+        };
+        (self.content_type(), body)
+    }
+}
+
+/// An RFC 9457 problem details document for a rejected WebFinger request.
+///
+/// The problem `type` is `about:blank`, so `title` is the HTTP status phrase as RFC 9457 section
+/// 4.2.1 requires. The cause is carried by the `code` extension member, one of the stable values
+/// returned by [`RequestError::code`], and `detail` holds the same message as the plain text
+/// format.
+///
+/// The type also deserializes, so clients can read the problem from an error response body.
+///
+/// ```json
+/// {
+///   "type": "about:blank",
+///   "title": "Bad Request",
+///   "status": 400,
+///   "detail": "missing resource parameter",
+///   "code": "missing_resource"
+/// }
+/// ```
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Problem {
+    /// A URI reference identifying the problem type.
+    #[serde(rename = "type", default = "about_blank")]
+    pub problem_type: String,
+
+    /// A short summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// The HTTP status code of the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// A human-readable explanation of this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// A machine-readable code for the cause, such as `missing_host`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_string()
+}
+
+impl Problem {
+    /// Describes a request rejected with `400 Bad Request`.
+    pub fn bad_request(error: &RequestError) -> Self {
+        Self::bad_request_with(error.to_string(), error.code())
+    }
+
+    /// Describes a `400 Bad Request` from an already rendered message and code.
+    pub(crate) fn bad_request_with(detail: String, code: &str) -> Self {
+        Self {
+            problem_type: about_blank(),
+            title: Some("Bad Request".to_string()),
+            status: Some(400),
+            detail: Some(detail),
+            code: Some(code.to_string()),
+        }
+    }
+
+    /// Serializes the problem as an `application/problem+json` body.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("problem details serialize to JSON")
+    }
+}
+
+impl From<&RequestError> for Problem {
+    fn from(error: &RequestError) -> Self {
+        Problem::bad_request(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the RFC 9457 members with the cause as an extension member.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3.2>.
+    #[test]
+    fn bad_request_serializes_problem_members() -> Result<(), serde_json::Error> {
+        let problem = Problem::bad_request(&RequestError::MissingHost);
+
+        let json: serde_json::Value = serde_json::from_str(&problem.to_json())?;
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "missing host",
+                "code": "missing_host",
+            }),
+        );
+        Ok(())
+    }
+
+    /// Treats a missing `type` as `about:blank` when reading a problem.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3.1.1>.
+    #[test]
+    fn deserializes_sparse_problem() -> Result<(), serde_json::Error> {
+        let problem: Problem = serde_json::from_str(r#"{"detail":"nope"}"#)?;
+
+        assert_eq!(problem.problem_type, "about:blank");
+        assert_eq!(problem.detail.as_deref(), Some("nope"));
+        assert_eq!(problem.code, None);
+        Ok(())
+    }
+}
@@ -1,11 +1,11 @@
-//! Internal parsing for incoming WebFinger query strings.
+//! Parsing for incoming WebFinger query strings.
 
 use std::str::FromStr;
 
 use percent_encoding::percent_decode_str;
 use thiserror::Error;
 
-use crate::{Resource, ResourceError};
+use crate::{ExtractionLimits, Rel, Resource, ResourceError, WebFingerRequest};
 
 /// The query parameters for a WebFinger request.
 ///
@@ -62,6 +62,86 @@ impl FromStr for RequestParams {
     }
 }
 
+impl WebFingerRequest {
+    /// Parses an incoming WebFinger request from the host that received it and its raw query.
+    ///
+    /// `query` is the query component without the leading `?`, exactly as received. It is parsed
+    /// with RFC 7033 semantics rather than as an HTML form: exactly one percent-encoded `resource`
+    /// is required, `rel` may be repeated and keeps its order, `+` stays a literal plus, malformed
+    /// percent escapes are rejected, and unknown parameters are ignored.
+    ///
+    /// Use this from server frameworks without a first-party integration;
+    /// [`from_http_parts`](Self::from_http_parts) does the same for [`http::request::Parts`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RequestError`] when `host` is empty, the query is malformed, the `resource` is
+    /// not an absolute URI, a `rel` value is not a valid relation type, or the request exceeds the
+    /// default [`ExtractionLimits`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::WebFingerRequest;
+    ///
+    /// let request = WebFingerRequest::from_query(
+    ///     "example.com",
+    ///     "resource=acct%3Acarol%40example.com&rel=self&rel=http%3A%2F%2Fwebfinger.net%2Frel%2Favatar",
+    /// )?;
+    ///
+    /// assert_eq!(request.host, "example.com");
+    /// assert_eq!(request.resource.as_str(), "acct:carol@example.com");
+    /// assert_eq!(request.rels.len(), 2);
+    /// # Ok::<(), webfinger_rs::RequestError>(())
+    /// ```
+    pub fn from_query(host: &str, query: &str) -> Result<Self, RequestError> {
+        Self::from_query_with_limits(host, query, &ExtractionLimits::default())
+    }
+
+    /// Parses an incoming WebFinger request like [`from_query`](Self::from_query), enforcing the
+    /// given [`ExtractionLimits`] instead of the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`from_query`](Self::from_query) errors, or the [`RequestError`] variant for the
+    /// first limit the request exceeds.
+    pub fn from_query_with_limits(
+        host: &str,
+        query: &str,
+        limits: &ExtractionLimits,
+    ) -> Result<Self, RequestError> {
+        Self::from_host_and_query(Some(host).filter(|host| !host.is_empty()), query, limits)
+    }
+
+    /// Builds a request from an incoming host and raw query string.
+    ///
+    /// This is the shared extraction path for server integrations: the query length is checked
+    /// before parsing, the query is parsed with [`RequestParams`], the decoded values are checked
+    /// against `limits`, each `rel` value is validated as a [`Rel`], and the host is required
+    /// because WebFinger lookups are scoped to the authority that received them.
+    pub(crate) fn from_host_and_query(
+        host: Option<&str>,
+        query: &str,
+        limits: &ExtractionLimits,
+    ) -> Result<Self, RequestError> {
+        let host = host.ok_or(RequestError::MissingHost)?.to_string();
+        limits.check_query(query)?;
+        let query: RequestParams = query.parse()?;
+        limits.check_params(&query)?;
+        let rels = query
+            .rel
+            .into_iter()
+            .map(Rel::try_new)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(RequestError::InvalidRel)?;
+        Ok(WebFingerRequest {
+            host,
+            resource: query.resource,
+            rels,
+        })
+    }
+}
+
 /// Decodes one RFC 3986 query parameter component.
 ///
 /// The `percent-encoding` crate leaves malformed percent escapes as literal `%` bytes. WebFinger
@@ -122,6 +202,126 @@ pub(crate) enum RequestParamsError {
     InvalidResource(#[from] ResourceError),
 }
 
+/// Errors that can occur while extracting a [`WebFingerRequest`] from an incoming HTTP request.
+///
+/// Server integrations render every variant as `400 Bad Request`. The variants stay distinct so
+/// applications and logs can tell a missing host from a malformed query without parsing messages.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RequestError {
+    /// Neither the request URI nor the `Host` header provided an authority.
+    #[error("missing host")]
+    MissingHost,
+
+    /// The required `resource` parameter is missing.
+    #[error("missing resource parameter")]
+    MissingResource,
+
+    /// More than one `resource` parameter was provided.
+    #[error("multiple resource parameters")]
+    MultipleResources,
+
+    /// A query parameter contains malformed percent encoding or invalid UTF-8 after decoding.
+    #[error("invalid percent-encoded query parameter")]
+    InvalidPercentEncoding,
+
+    /// The `resource` query parameter is not an absolute URI.
+    #[error("invalid resource: {0}")]
+    InvalidResource(#[source] ResourceError),
+
+    /// A `rel` query parameter is not a valid relation type.
+    #[error(transparent)]
+    InvalidRel(crate::Error),
+
+    /// A trusted proxy forwarded a host that is not a valid `host[:port]` authority.
+    #[error("invalid forwarded host: {0}")]
+    InvalidForwardedHost(String),
+
+    /// The request is for a host the [`HostPolicy`](crate::HostPolicy) does not allow.
+    #[error("host is not allowed: {0}")]
+    HostNotAllowed(String),
+
+    /// The raw query string is longer than [`ExtractionLimits`] allow.
+    #[error("query is too long; maximum is {max} bytes")]
+    QueryTooLong {
+        /// The maximum query length, in bytes.
+        max: usize,
+    },
+
+    /// The decoded `resource` is longer than [`ExtractionLimits`] allow.
+    #[error("resource is too long; maximum is {max} characters")]
+    ResourceTooLong {
+        /// The maximum resource length, in characters.
+        max: usize,
+    },
+
+    /// The `resource` uses a URI scheme [`ExtractionLimits`] do not allow.
+    #[error("resource scheme is not allowed: {0}")]
+    DisallowedScheme(String),
+
+    /// The request has more `rel` parameters than [`ExtractionLimits`] allow.
+    #[error("too many relation filters; maximum is {max}")]
+    TooManyRels {
+        /// The maximum number of `rel` parameters.
+        max: usize,
+    },
+
+    /// A decoded `rel` is longer than [`ExtractionLimits`] allow.
+    #[error("relation filter is too long; maximum is {max} characters")]
+    RelTooLong {
+        /// The maximum relation length, in characters.
+        max: usize,
+    },
+}
+
+impl RequestError {
+    /// Returns whether the request was rejected by [`ExtractionLimits`] rather than for being
+    /// malformed.
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            RequestError::QueryTooLong { .. }
+                | RequestError::ResourceTooLong { .. }
+                | RequestError::DisallowedScheme(_)
+                | RequestError::TooManyRels { .. }
+                | RequestError::RelTooLong { .. }
+        )
+    }
+
+    /// Returns a stable, machine-readable code for the cause, such as `missing_host`.
+    ///
+    /// Codes are `snake_case` and do not change between releases, so clients can branch on them
+    /// instead of the message. They appear as the `code` member of a [`Problem`](crate::Problem).
+    pub fn code(&self) -> &'static str {
+        match self {
+            RequestError::MissingHost => "missing_host",
+            RequestError::MissingResource => "missing_resource",
+            RequestError::MultipleResources => "multiple_resources",
+            RequestError::InvalidPercentEncoding => "invalid_percent_encoding",
+            RequestError::InvalidResource(_) => "invalid_resource",
+            RequestError::InvalidRel(_) => "invalid_rel",
+            RequestError::InvalidForwardedHost(_) => "invalid_forwarded_host",
+            RequestError::HostNotAllowed(_) => "host_not_allowed",
+            RequestError::QueryTooLong { .. } => "query_too_long",
+            RequestError::ResourceTooLong { .. } => "resource_too_long",
+            RequestError::DisallowedScheme(_) => "disallowed_scheme",
+            RequestError::TooManyRels { .. } => "too_many_rels",
+            RequestError::RelTooLong { .. } => "rel_too_long",
+        }
+    }
+}
+
+impl From<RequestParamsError> for RequestError {
+    fn from(error: RequestParamsError) -> Self {
+        match error {
+            RequestParamsError::MissingResource => RequestError::MissingResource,
+            RequestParamsError::MultipleResources => RequestError::MultipleResources,
+            RequestParamsError::InvalidPercentEncoding => RequestError::InvalidPercentEncoding,
+            RequestParamsError::InvalidResource(error) => RequestError::InvalidResource(error),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,4 +612,132 @@ mod tests {
             Some("resource must be an absolute URI".to_string())
         );
     }
+
+    /// Requires a host before building a request.
+    ///
+    /// WebFinger lookups are scoped to the authority that received them, so extraction fails
+    /// instead of inventing an empty host.
+    #[test]
+    fn from_host_and_query_rejects_missing_host() {
+        let error = WebFingerRequest::from_host_and_query(
+            None,
+            "resource=acct%3Acarol%40example.org",
+            &ExtractionLimits::default(),
+        )
+        .unwrap_err();
+
+        assert!(matches!(error, RequestError::MissingHost));
+    }
+
+    /// Treats an empty host as missing in the public parser.
+    #[test]
+    fn from_query_rejects_empty_host() {
+        let error =
+            WebFingerRequest::from_query("", "resource=acct%3Acarol%40example.org").unwrap_err();
+
+        assert!(matches!(error, RequestError::MissingHost));
+    }
+
+    /// Keeps `+` literal and preserves repeated `rel` order through the public parser.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[test]
+    fn from_query_parses_rfc_query() -> Result<(), RequestError> {
+        let request = WebFingerRequest::from_query(
+            "example.org",
+            "resource=acct%3Acarol+work%40example.org&rel=self&x=1&rel=profile",
+        )?;
+
+        assert_eq!(request.host, "example.org");
+        assert_eq!(request.resource.as_str(), "acct:carol+work@example.org");
+        assert_eq!(
+            request
+                .rels
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<&str>>(),
+            ["self", "profile"]
+        );
+        Ok(())
+    }
+
+    /// Validates relation filters after query decoding.
+    #[test]
+    fn from_host_and_query_rejects_invalid_rel() {
+        let error = WebFingerRequest::from_host_and_query(
+            Some("example.org"),
+            "resource=acct%3Acarol%40example.org&rel=author%20avatar",
+            &ExtractionLimits::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "invalid relation type: author avatar");
+    }
+
+    /// Rejects an oversized query before parsing it.
+    #[test]
+    fn rejects_query_over_limit() {
+        let limits = ExtractionLimits::new().max_query_length(16);
+        let error = WebFingerRequest::from_query_with_limits(
+            "example.org",
+            "resource=acct%3Acarol%40example.org",
+            &limits,
+        )
+        .unwrap_err();
+
+        assert!(matches!(error, RequestError::QueryTooLong { max: 16 }));
+        assert!(error.is_limit_exceeded());
+    }
+
+    /// Counts the decoded resource length rather than its percent-encoded form.
+    #[test]
+    fn limits_decoded_resource_length() -> Result<(), RequestError> {
+        let query = "resource=acct%3Acarol%40example.org";
+        let limits = ExtractionLimits::new().max_resource_length(22);
+        WebFingerRequest::from_query_with_limits("example.org", query, &limits)?;
+
+        let limits = ExtractionLimits::new().max_resource_length(21);
+        let error =
+            WebFingerRequest::from_query_with_limits("example.org", query, &limits).unwrap_err();
+
+        assert!(matches!(error, RequestError::ResourceTooLong { max: 21 }));
+        Ok(())
+    }
+
+    /// Rejects resources outside the allowed schemes.
+    #[test]
+    fn rejects_disallowed_scheme() {
+        let limits = ExtractionLimits::new().allowed_schemes(["acct"]);
+        let error = WebFingerRequest::from_query_with_limits(
+            "example.org",
+            "resource=mailto%3Acarol%40example.org",
+            &limits,
+        )
+        .unwrap_err();
+
+        assert!(matches!(error, RequestError::DisallowedScheme(scheme) if scheme == "mailto"));
+    }
+
+    /// Rejects too many and too long relation filters with distinct errors.
+    #[test]
+    fn limits_rel_count_and_length() {
+        let limits = ExtractionLimits::new().max_rels(2).max_rel_length(8);
+        let resource = "resource=acct%3Acarol%40example.org";
+
+        let error = WebFingerRequest::from_query_with_limits(
+            "example.org",
+            &format!("{resource}&rel=a&rel=b&rel=c"),
+            &limits,
+        )
+        .unwrap_err();
+        assert!(matches!(error, RequestError::TooManyRels { max: 2 }));
+
+        let error = WebFingerRequest::from_query_with_limits(
+            "example.org",
+            &format!("{resource}&rel=profile-page"),
+            &limits,
+        )
+        .unwrap_err();
+        assert!(matches!(error, RequestError::RelTooLong { max: 8 }));
+    }
 }
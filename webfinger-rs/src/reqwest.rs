@@ -17,7 +17,20 @@ fn install_default_crypto_provider() {
     });
 }
 
-fn webfinger_reqwest_client() -> Result<reqwest::Client, reqwest::Error> {
+/// Builds the [`reqwest::Client`] that [`WebFingerRequest::execute_reqwest`] uses.
+///
+/// The client only sends HTTPS requests and follows only HTTPS redirects, as RFC 7033 requires.
+/// Building it installs the rustls `aws-lc-rs` crypto provider as the process default unless one
+/// is already installed. Pass it to [`WebFingerRequest::execute_reqwest_with_client`] to share one
+/// connection pool across lookups.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-9.1>.
+///
+/// # Errors
+///
+/// Returns the [`reqwest::Error`] raised when the client cannot be built, for example because the
+/// TLS backend cannot be initialized.
+pub fn webfinger_reqwest_client() -> Result<reqwest::Client, reqwest::Error> {
     install_default_crypto_provider();
     reqwest::Client::builder().https_only(true).build()
 }
@@ -0,0 +1,430 @@
+//! Rocket integration for WebFinger request extraction and JRD responses.
+//!
+//! Enable the `rocket` feature to:
+//!
+//! - extract [`WebFingerRequest`] as a request guard in handlers mounted for `GET` requests to
+//!   [`crate::WELL_KNOWN_PATH`];
+//! - return [`WebFingerResponse`] directly from Rocket handlers as `application/jrd+json` with the
+//!   WebFinger CORS header;
+//! - answer malformed requests with [`bad_request`], a `400` catcher that keeps the extraction
+//!   error message and adds the same CORS header; and
+//! - answer browser CORS preflight requests with the [`preflight`] route.
+//!
+//! The request guard parses the query with [`WebFingerRequest::from_query`], so it accepts the
+//! same RFC 7033 query shape as the Axum and Actix Web extractors: exactly one percent-encoded
+//! `resource`, repeated `rel` parameters, and literal `+`.
+//!
+//! ```rust
+//! use rocket::{catchers, get, routes};
+//! use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! #[get("/.well-known/webfinger")]
+//! fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let rocket = rocket::build()
+//!     .mount("/", routes![webfinger, webfinger_rs::rocket::preflight])
+//!     .register(WELL_KNOWN_PATH, catchers![webfinger_rs::rocket::bad_request]);
+//! # let _ = rocket;
+//! ```
+//!
+//! Rocket routes failed request guards to its error catchers rather than to the handler. Register
+//! [`bad_request`] under [`crate::WELL_KNOWN_PATH`] so extraction failures keep their message and
+//! CORS header; without it Rocket's default `400` page is served instead.
+//!
+//! Rocket answers `HEAD` requests with the matching `GET` route and strips the body, so no extra
+//! route is needed for monitoring probes. Register a [`CorsPolicy`], [`CachePolicy`],
+//! [`ExtractionLimits`], or [`HostPolicy`] with `Rocket::manage` to restrict origins, send
+//! `Cache-Control: max-age`, bound request sizes, or trust proxies and restrict hosts; the request
+//! guard, responder, catcher, and [`preflight`] use them and fall back to the defaults otherwise.
+//! The responder always sends a strong `ETag` and answers matching `If-None-Match` requests with
+//! `304 Not Modified`.
+//!
+//! As with the other server integrations, scheme enforcement is left to the server or reverse
+//! proxy that terminates TLS.
+
+use std::io::Cursor;
+
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use tracing::trace;
+
+use crate::{
+    ALLOWED_METHODS, CachePolicy, CorsPolicy, ExtractionLimits, HostPolicy, RequestError,
+    ResponseFormat, WebFingerRequest, WebFingerResponse, etag_matches,
+};
+
+impl<'r> Responder<'r, 'static> for WebFingerResponse {
+    /// Converts a [`WebFingerResponse`] into a Rocket response.
+    ///
+    /// This renders the body in the format negotiated from the request's `Accept` header (see
+    /// [`ResponseFormat::negotiate`]) and sets the matching `Content-Type` header. Requests that
+    /// accept none of the supported formats receive `406 Not Acceptable`.
+    ///
+    /// Successful responses carry the headers of the managed [`CorsPolicy`], a strong `ETag`,
+    /// `Vary: Accept`, and the `Cache-Control` header of the managed [`CachePolicy`]. A request
+    /// whose `If-None-Match` header matches the entity tag receives `304 Not Modified`.
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let accept = request.headers().get_one("Accept");
+        let Some(format) = ResponseFormat::negotiate(accept) else {
+            return Ok(text_response(
+                request,
+                Status::NotAcceptable,
+                "not acceptable",
+            ));
+        };
+
+        let etag = self.etag(format);
+        let not_modified = request
+            .headers()
+            .get_one("If-None-Match")
+            .is_some_and(|value| etag_matches(value, &etag));
+        let mut builder = Response::build();
+        if not_modified {
+            builder.status(Status::NotModified);
+        } else {
+            match format.render(&self) {
+                Ok(body) => {
+                    builder
+                        .status(Status::Ok)
+                        .raw_header("Content-Type", format.content_type())
+                        .sized_body(body.len(), Cursor::new(body));
+                }
+                Err(error) => {
+                    let message = error.to_string();
+                    return Ok(text_response(request, Status::InternalServerError, message));
+                }
+            }
+        }
+        let mut response = builder.finalize();
+        insert_policy_headers(&mut response, cors_headers(request));
+        let cache = match request.rocket().state::<CachePolicy>() {
+            Some(policy) => policy.response_headers(&etag),
+            None => CachePolicy::default().response_headers(&etag),
+        };
+        insert_policy_headers(&mut response, cache);
+        response.adjoin_raw_header("Vary", "Accept");
+        Ok(response)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebFingerRequest {
+    /// The reason the request is not a valid WebFinger request.
+    type Error = RequestError;
+
+    /// Extracts a [`WebFingerRequest`] from a Rocket request.
+    ///
+    /// The host comes from the `Host` header, or from the host Rocket parsed for the connection
+    /// when the header is absent, unless the managed [`HostPolicy`] trusts the connecting peer and
+    /// it forwarded a host. The raw query string is parsed with [`WebFingerRequest::from_query`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with `400 Bad Request` and the [`RequestError`] when the host is missing, the query is
+    /// malformed, the `resource` is not an absolute URI, or a `rel` value is invalid. The error
+    /// message is kept for the [`bad_request`] catcher.
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        trace!(?request, "extracting WebFingerRequest from request");
+        let host = match request.headers().get_one("Host") {
+            Some(host) => Some(host.to_string()),
+            None => request.host().map(ToString::to_string),
+        };
+        let default_host_policy = HostPolicy::default();
+        let host_policy = request.rocket().state().unwrap_or(&default_host_policy);
+        let headers = request.headers();
+        let host = host_policy.resolve_host(
+            request.remote().map(|address| address.ip()),
+            host.as_deref(),
+            headers.get("Forwarded"),
+            headers.get("X-Forwarded-Host"),
+        );
+        let query = request.uri().query().map_or("", |query| query.as_str());
+        let default_limits = ExtractionLimits::default();
+        let limits = request.rocket().state().unwrap_or(&default_limits);
+        let webfinger =
+            host.and_then(|host| WebFingerRequest::from_query_with_limits(&host, query, limits));
+        match webfinger {
+            Ok(webfinger) => Outcome::Success(webfinger),
+            Err(error) => {
+                request.local_cache(|| RejectionMessage(Some(error.to_string())));
+                Outcome::Error((Status::BadRequest, error))
+            }
+        }
+    }
+}
+
+/// The extraction error message for the current request, read by [`bad_request`].
+struct RejectionMessage(Option<String>);
+
+/// Catches `400 Bad Request` responses for the WebFinger endpoint.
+///
+/// Register this catcher under [`crate::WELL_KNOWN_PATH`]. It renders the [`RequestError`] message
+/// from a failed [`WebFingerRequest`] guard as plain text, with the headers of the managed
+/// [`CorsPolicy`] so browser clients can read the error.
+///
+/// See the [`crate::rocket`] module docs for an example.
+#[rocket::catch(400)]
+pub fn bad_request(request: &Request<'_>) -> Rejection {
+    let RejectionMessage(message) = request.local_cache(|| RejectionMessage(None));
+    Rejection {
+        status: Status::BadRequest,
+        message: message.clone().unwrap_or_else(|| "bad request".to_string()),
+    }
+}
+
+/// Answers a CORS preflight request for the WebFinger endpoint.
+///
+/// Mount this route at `/`; it matches `OPTIONS` requests to [`crate::WELL_KNOWN_PATH`] and returns
+/// `204 No Content` with `Allow` and the preflight headers of the managed [`CorsPolicy`], or of
+/// [`CorsPolicy::default`].
+///
+/// See the [`crate::rocket`] module docs for an example.
+#[rocket::options("/.well-known/webfinger")]
+pub fn preflight() -> Preflight {
+    Preflight
+}
+
+/// A plain-text WebFinger error response with the endpoint CORS headers.
+///
+/// Returned by the [`bad_request`] catcher.
+#[derive(Debug)]
+pub struct Rejection {
+    status: Status,
+    message: String,
+}
+
+impl<'r> Responder<'r, 'static> for Rejection {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        Ok(text_response(request, self.status, self.message))
+    }
+}
+
+/// An empty `204 No Content` response carrying the CORS preflight headers.
+///
+/// Returned by the [`preflight`] route.
+#[derive(Debug)]
+pub struct Preflight;
+
+impl<'r> Responder<'r, 'static> for Preflight {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let origin = request.headers().get_one("Origin");
+        let headers = match request.rocket().state::<CorsPolicy>() {
+            Some(policy) => policy.preflight_headers(origin),
+            None => CorsPolicy::default().preflight_headers(origin),
+        };
+        let mut response = Response::build()
+            .status(Status::NoContent)
+            .raw_header("Allow", ALLOWED_METHODS)
+            .finalize();
+        insert_policy_headers(&mut response, headers);
+        Ok(response)
+    }
+}
+
+/// Builds a plain-text response with the CORS headers for `request`.
+fn text_response(
+    request: &Request<'_>,
+    status: Status,
+    message: impl Into<String>,
+) -> Response<'static> {
+    let message = message.into();
+    let mut response = Response::build()
+        .status(status)
+        .raw_header("Content-Type", "text/plain; charset=utf-8")
+        .sized_body(message.len(), Cursor::new(message))
+        .finalize();
+    insert_policy_headers(&mut response, cors_headers(request));
+    response
+}
+
+/// Returns the CORS headers for a WebFinger response to `request`.
+fn cors_headers(request: &Request<'_>) -> Vec<(&'static str, String)> {
+    let origin = request.headers().get_one("Origin");
+    match request.rocket().state::<CorsPolicy>() {
+        Some(policy) => policy.response_headers(origin),
+        None => CorsPolicy::default().response_headers(origin),
+    }
+}
+
+/// Writes policy headers, appending `Vary` so values set by the handler are kept.
+///
+/// Rocket has its own header types, so this mirrors [`crate::insert_policy_headers`].
+fn insert_policy_headers(response: &mut Response<'_>, policy: Vec<(&'static str, String)>) {
+    for (name, value) in policy {
+        if name.eq_ignore_ascii_case("vary") {
+            response.adjoin_header(Header::new(name, value));
+        } else {
+            response.set_header(Header::new(name, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::Method;
+    use rocket::local::asynchronous::Client;
+    use rocket::{Build, Rocket, catchers, get, routes};
+
+    use super::*;
+    use crate::{AllowOrigin, WELL_KNOWN_PATH};
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    /// Returns the extracted resource and relation filters so tests can assert query decoding.
+    #[get("/.well-known/webfinger")]
+    fn webfinger(request: WebFingerRequest) -> String {
+        let mut values = vec![request.host, request.resource.to_string()];
+        values.extend(request.rels.iter().map(ToString::to_string));
+        values.join(" ")
+    }
+
+    /// Returns a minimal JRD so tests can assert responder-owned WebFinger headers.
+    #[get("/jrd")]
+    fn webfinger_response() -> WebFingerResponse {
+        WebFingerResponse::new("acct:carol@example.com")
+    }
+
+    fn rocket() -> Rocket<Build> {
+        rocket::build()
+            .mount("/", routes![webfinger, webfinger_response, preflight])
+            .register(WELL_KNOWN_PATH, catchers![bad_request])
+    }
+
+    /// Parses the RFC 7033 query shape: repeated `rel`, percent-decoding, and literal `+`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[rocket::async_test]
+    async fn extracts_request() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let response = client
+            .get("/.well-known/webfinger?resource=acct%3Acarol+work%40example.org&rel=self&rel=profile")
+            .header(Header::new("Host", "example.org"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.into_string().await.as_deref(),
+            Some("example.org acct:carol+work@example.org self profile"),
+        );
+        Ok(())
+    }
+
+    /// Rejects a missing `resource` with the error message and the RFC 7033 CORS header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-5>.
+    #[rocket::async_test]
+    async fn malformed_request_is_bad_request_with_cors() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let response = client
+            .get(WELL_KNOWN_PATH)
+            .header(Header::new("Host", "example.org"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.headers().get_one("Access-Control-Allow-Origin"),
+            Some("*"),
+        );
+        assert_eq!(
+            response.into_string().await.as_deref(),
+            Some("missing resource parameter"),
+        );
+        Ok(())
+    }
+
+    /// Rejects relative resource references.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[rocket::async_test]
+    async fn relative_resource_is_bad_request() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let response = client
+            .get("/.well-known/webfinger?resource=carol")
+            .header(Header::new("Host", "example.org"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        Ok(())
+    }
+
+    /// Serves JRD with the CORS header, an entity tag, and `Vary: Accept`.
+    #[rocket::async_test]
+    async fn responder_sets_jrd_headers() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let response = client.get("/jrd").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get_one("Content-Type"),
+            Some(crate::JRD_CONTENT_TYPE)
+        );
+        assert_eq!(headers.get_one("Access-Control-Allow-Origin"), Some("*"));
+        assert_eq!(headers.get_one("Vary"), Some("Accept"));
+        assert!(headers.get_one("ETag").is_some());
+        Ok(())
+    }
+
+    /// Answers a matching `If-None-Match` with `304 Not Modified`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-13.1.2>.
+    #[rocket::async_test]
+    async fn responder_answers_not_modified() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let first = client.get("/jrd").dispatch().await;
+        let etag = first.headers().get_one("ETag").unwrap().to_string();
+
+        let response = client
+            .get("/jrd")
+            .header(Header::new("If-None-Match", etag))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotModified);
+        Ok(())
+    }
+
+    /// Rejects requests that accept none of the supported formats.
+    #[rocket::async_test]
+    async fn responder_rejects_unacceptable_format() -> Result {
+        let client = Client::tracked(rocket()).await?;
+        let response = client
+            .get("/jrd")
+            .header(Header::new("Accept", "text/html"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotAcceptable);
+        Ok(())
+    }
+
+    /// Answers browser CORS preflight requests with a managed policy.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[rocket::async_test]
+    async fn preflight_uses_managed_policy() -> Result {
+        let policy = CorsPolicy::new()
+            .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+        let client = Client::tracked(rocket().manage(policy)).await?;
+        let response = client
+            .req(Method::Options, WELL_KNOWN_PATH)
+            .header(Header::new("Origin", "https://app.example"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NoContent);
+        let headers = response.headers();
+        assert_eq!(headers.get_one("Allow"), Some(ALLOWED_METHODS));
+        assert_eq!(
+            headers.get_one("Access-Control-Allow-Origin"),
+            Some("https://app.example"),
+        );
+        Ok(())
+    }
+}
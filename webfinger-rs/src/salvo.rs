@@ -0,0 +1,431 @@
+//! Salvo integration for WebFinger request extraction and responses.
+//!
+//! Enable the `salvo` feature to:
+//!
+//! - extract [`WebFingerRequest`] in handlers mounted for `GET` requests to
+//!   [`crate::WELL_KNOWN_PATH`];
+//! - return [`WebFingerResponse`] directly from Salvo handlers, rendered in the format negotiated
+//!   from the request's `Accept` header with the WebFinger CORS header; and
+//! - answer browser CORS preflight requests with the [`preflight`] handler.
+//!
+//! The extractor parses the query with [`WebFingerRequest::from_query_with_limits`], so it accepts
+//! the same RFC 7033 query shape as the other server integrations: exactly one percent-encoded
+//! `resource`, repeated `rel` parameters, and literal `+`. Malformed requests are answered with
+//! [`Rejection`]: `400 Bad Request`, the error message, and the CORS headers so browser clients can
+//! read them.
+//!
+//! ```rust
+//! use salvo::prelude::*;
+//! use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
+//!
+//! #[handler]
+//! async fn webfinger(request: WebFingerRequest) -> WebFingerResponse {
+//!     WebFingerResponse::new(request.resource.to_string())
+//! }
+//!
+//! let router = Router::with_path(WELL_KNOWN_PATH)
+//!     .get(webfinger)
+//!     .head(webfinger)
+//!     .options(webfinger_rs::salvo::preflight);
+//! # let _ = router;
+//! ```
+//!
+//! Salvo extractors see only the request, so configuration is read from the request extensions
+//! rather than the [`Depot`]. Insert a [`CorsPolicy`], [`CachePolicy`],
+//! [`ExtractionLimits`], or [`HostPolicy`] with `Request::extensions_mut` in a hoop to restrict
+//! origins, send `Cache-Control: max-age`, bound request sizes, or trust proxies and restrict
+//! hosts; the extractor, responder, and [`preflight`] use them and fall back to the defaults
+//! otherwise. Like the Actix Web and Rocket responders, the responder sends a strong `ETag` and
+//! `Vary: Accept`, and answers matching `If-None-Match` requests with `304 Not Modified`.
+
+use salvo::extract::Metadata;
+use salvo::http::StatusCode;
+use salvo::http::header::{
+    ACCEPT, ALLOW, CONTENT_TYPE, HOST, HeaderValue, IF_NONE_MATCH, ORIGIN, VARY,
+};
+use salvo::{Depot, Extractible, Request, Response, Scribe, Writer};
+use tracing::trace;
+
+use crate::{
+    ALLOWED_METHODS, CachePolicy, CorsPolicy, ExtractionLimits, HostPolicy, RequestError,
+    ResponseFormat, WebFingerRequest, WebFingerResponse, etag_matches, insert_policy_headers,
+};
+
+/// The metadata Salvo keeps for extractible types; the request has no fields of its own to list.
+static METADATA: Metadata = Metadata::new("WebFingerRequest");
+
+#[salvo::async_trait]
+impl Writer for WebFingerResponse {
+    /// Writes a [`WebFingerResponse`] to a Salvo response.
+    ///
+    /// This renders the body in the format negotiated from the request's `Accept` header (see
+    /// [`ResponseFormat::negotiate`]) and sets the matching `Content-Type` header. Requests that
+    /// accept none of the supported formats receive `406 Not Acceptable`.
+    ///
+    /// Successful responses carry the headers of the request's [`CorsPolicy`], a strong `ETag`,
+    /// `Vary: Accept`, and the `Cache-Control` header of the request's [`CachePolicy`]. A request
+    /// whose `If-None-Match` header matches the entity tag receives `304 Not Modified`.
+    async fn write(self, request: &mut Request, _depot: &mut Depot, response: &mut Response) {
+        let accept = request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok());
+        let Some(format) = ResponseFormat::negotiate(accept) else {
+            text_response(
+                request,
+                response,
+                StatusCode::NOT_ACCEPTABLE,
+                "not acceptable",
+            );
+            return;
+        };
+
+        let etag = self.etag(format);
+        let not_modified = request
+            .headers()
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| etag_matches(value, &etag));
+        if not_modified {
+            response.status_code(StatusCode::NOT_MODIFIED);
+        } else {
+            match format.render(&self) {
+                Ok(body) => {
+                    response.status_code(StatusCode::OK);
+                    response.headers_mut().insert(
+                        CONTENT_TYPE,
+                        HeaderValue::from_static(format.content_type()),
+                    );
+                    response.body(body);
+                }
+                Err(error) => {
+                    let message = error.to_string();
+                    text_response(
+                        request,
+                        response,
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        message,
+                    );
+                    return;
+                }
+            }
+        }
+        let headers = response.headers_mut();
+        insert_policy_headers(headers, cors_headers(request));
+        let cache = match request.extensions().get::<CachePolicy>() {
+            Some(policy) => policy.response_headers(&etag),
+            None => CachePolicy::default().response_headers(&etag),
+        };
+        insert_policy_headers(headers, cache);
+        headers.append(VARY, HeaderValue::from_static("Accept"));
+    }
+}
+
+impl<'ex> Extractible<'ex> for WebFingerRequest {
+    fn metadata() -> &'static Metadata {
+        &METADATA
+    }
+
+    /// Extracts a [`WebFingerRequest`] from a Salvo request.
+    ///
+    /// The host comes from the request URI authority when the URI is absolute, otherwise from the
+    /// `Host` header, unless the request's [`HostPolicy`] trusts the connecting peer and it
+    /// forwarded a host. The raw query string is parsed with
+    /// [`WebFingerRequest::from_query_with_limits`] and the request's [`ExtractionLimits`], or the
+    /// defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Rejection`] when the host is missing, the query is malformed, the `resource` is
+    /// not an absolute URI, or a `rel` value is invalid.
+    #[allow(refining_impl_trait)]
+    async fn extract(request: &'ex mut Request) -> Result<Self, Rejection> {
+        trace!(?request, "extracting WebFingerRequest from request");
+        let host = request
+            .uri()
+            .authority()
+            .map(|authority| authority.as_str())
+            .or_else(|| {
+                request
+                    .headers()
+                    .get(HOST)
+                    .and_then(|host| host.to_str().ok())
+            });
+        let header_values = |name| {
+            request
+                .headers()
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+        };
+        let default_host_policy = HostPolicy::default();
+        let host_policy = request.extensions().get().unwrap_or(&default_host_policy);
+        let host = host_policy.resolve_host(
+            request
+                .remote_addr()
+                .clone()
+                .into_std()
+                .map(|address| address.ip()),
+            host,
+            header_values("forwarded"),
+            header_values("x-forwarded-host"),
+        );
+        let query = request.uri().query().unwrap_or_default();
+        let default_limits = ExtractionLimits::default();
+        let limits = request.extensions().get().unwrap_or(&default_limits);
+        host.and_then(|host| WebFingerRequest::from_query_with_limits(&host, query, limits))
+            .map_err(|error| Rejection {
+                error,
+                cors: cors_headers(request),
+            })
+    }
+}
+
+/// The response to a request the [`WebFingerRequest`] extractor rejected.
+///
+/// Renders `400 Bad Request` with the [`RequestError`] message as plain text and the CORS headers
+/// of the request's [`CorsPolicy`], so browser clients can read the error.
+#[derive(Debug)]
+pub struct Rejection {
+    error: RequestError,
+    cors: Vec<(&'static str, String)>,
+}
+
+impl Rejection {
+    /// Returns why the request was rejected.
+    pub fn error(&self) -> &RequestError {
+        &self.error
+    }
+}
+
+impl Scribe for Rejection {
+    fn render(self, response: &mut Response) {
+        response.status_code(StatusCode::BAD_REQUEST);
+        let headers = response.headers_mut();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        insert_policy_headers(headers, self.cors);
+        response.body(self.error.to_string());
+    }
+}
+
+/// Answers a CORS preflight request for the WebFinger endpoint.
+///
+/// Mount this handler for `OPTIONS` requests to [`crate::WELL_KNOWN_PATH`]. It returns
+/// `204 No Content` with `Allow` and the preflight headers of the request's [`CorsPolicy`], or of
+/// [`CorsPolicy::default`].
+///
+/// See the [`crate::salvo`] module docs for an example.
+#[salvo::handler]
+pub async fn preflight(request: &mut Request, response: &mut Response) {
+    let origin = request
+        .headers()
+        .get(ORIGIN)
+        .and_then(|origin| origin.to_str().ok());
+    let headers = match request.extensions().get::<CorsPolicy>() {
+        Some(policy) => policy.preflight_headers(origin),
+        None => CorsPolicy::default().preflight_headers(origin),
+    };
+    response.status_code(StatusCode::NO_CONTENT);
+    let response_headers = response.headers_mut();
+    response_headers.insert(ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+    insert_policy_headers(response_headers, headers);
+}
+
+/// Writes a plain-text response with the CORS headers for `request`.
+fn text_response(
+    request: &Request,
+    response: &mut Response,
+    status: StatusCode,
+    message: impl Into<String>,
+) {
+    response.status_code(status);
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    insert_policy_headers(headers, cors_headers(request));
+    response.body(message.into());
+}
+
+/// Returns the CORS headers for a WebFinger response to `request`.
+fn cors_headers(request: &Request) -> Vec<(&'static str, String)> {
+    let origin = request
+        .headers()
+        .get(ORIGIN)
+        .and_then(|origin| origin.to_str().ok());
+    match request.extensions().get::<CorsPolicy>() {
+        Some(policy) => policy.response_headers(origin),
+        None => CorsPolicy::default().response_headers(origin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use salvo::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ETAG};
+    use salvo::test::{ResponseExt, TestClient};
+    use salvo::{Router, Service, handler};
+
+    use super::*;
+    use crate::{AllowOrigin, JRD_CONTENT_TYPE, WELL_KNOWN_PATH};
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    /// Returns the extracted host, resource, and relation filters so tests can assert decoding.
+    #[handler]
+    async fn webfinger(request: WebFingerRequest) -> String {
+        let mut values = vec![request.host, request.resource.to_string()];
+        values.extend(request.rels.iter().map(ToString::to_string));
+        values.join(" ")
+    }
+
+    /// Returns a minimal JRD so tests can assert responder-owned WebFinger headers.
+    #[handler]
+    async fn webfinger_response() -> WebFingerResponse {
+        WebFingerResponse::new("acct:carol@example.com")
+    }
+
+    /// Adds a CORS policy that only allows one origin, as an application hoop would.
+    #[handler]
+    async fn restrict_origin(request: &mut Request) {
+        request.extensions_mut().insert(
+            CorsPolicy::new()
+                .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()])),
+        );
+    }
+
+    fn service() -> Service {
+        Service::new(
+            Router::new()
+                .push(
+                    Router::with_path(WELL_KNOWN_PATH)
+                        .get(webfinger)
+                        .options(preflight),
+                )
+                .push(Router::with_path("jrd").get(webfinger_response)),
+        )
+    }
+
+    fn url(path: &str) -> String {
+        format!("http://example.org{path}")
+    }
+
+    /// Parses the RFC 7033 query shape: repeated `rel`, percent-decoding, and literal `+`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[tokio::test]
+    async fn extracts_request() -> Result {
+        let mut response = TestClient::get(url(
+            "/.well-known/webfinger?resource=acct%3Acarol+work%40example.org&rel=self&rel=profile",
+        ))
+        .send(&service())
+        .await;
+
+        assert_eq!(response.status_code, Some(StatusCode::OK));
+        assert_eq!(
+            response.take_string().await?,
+            "example.org acct:carol+work@example.org self profile",
+        );
+        Ok(())
+    }
+
+    /// Rejects a missing `resource` with the error message and the RFC 7033 CORS header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-5>.
+    #[tokio::test]
+    async fn malformed_request_is_bad_request_with_cors() -> Result {
+        let mut response = TestClient::get(url(WELL_KNOWN_PATH)).send(&service()).await;
+
+        assert_eq!(response.status_code, Some(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "*",
+        );
+        assert_eq!(response.take_string().await?, "missing resource parameter");
+        Ok(())
+    }
+
+    /// Rejects relative resource references.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.1>.
+    #[tokio::test]
+    async fn relative_resource_is_bad_request() {
+        let response = TestClient::get(url("/.well-known/webfinger?resource=carol"))
+            .send(&service())
+            .await;
+
+        assert_eq!(response.status_code, Some(StatusCode::BAD_REQUEST));
+    }
+
+    /// Serves JRD with the CORS header, an entity tag, and `Vary: Accept`.
+    #[tokio::test]
+    async fn responder_sets_jrd_headers() {
+        let response = TestClient::get(url("/jrd")).send(&service()).await;
+
+        assert_eq!(response.status_code, Some(StatusCode::OK));
+        let headers = response.headers();
+        assert_eq!(headers.get(CONTENT_TYPE).unwrap(), JRD_CONTENT_TYPE);
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert_eq!(headers.get(VARY).unwrap(), "Accept");
+        assert!(headers.contains_key(ETAG));
+    }
+
+    /// Negotiates plain JSON from `Accept`, as the Actix Web and Rocket responders do.
+    #[tokio::test]
+    async fn responder_negotiates_format() {
+        let response = TestClient::get(url("/jrd"))
+            .add_header(ACCEPT, "application/json", true)
+            .send(&service())
+            .await;
+
+        assert_eq!(response.status_code, Some(StatusCode::OK));
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+
+    /// Answers a matching `If-None-Match` with `304 Not Modified`.
+    #[tokio::test]
+    async fn responder_answers_matching_if_none_match() {
+        let response = TestClient::get(url("/jrd")).send(&service()).await;
+        let etag = response.headers().get(ETAG).unwrap().clone();
+
+        let response = TestClient::get(url("/jrd"))
+            .add_header(IF_NONE_MATCH, etag, true)
+            .send(&service())
+            .await;
+
+        assert_eq!(response.status_code, Some(StatusCode::NOT_MODIFIED));
+    }
+
+    /// Answers browser CORS preflight requests with a policy added in a hoop.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[tokio::test]
+    async fn preflight_uses_request_policy() {
+        let service = Service::new(
+            Router::with_hoop(restrict_origin).push(
+                Router::with_path(WELL_KNOWN_PATH)
+                    .get(webfinger)
+                    .options(preflight),
+            ),
+        );
+
+        let response = TestClient::options(url(WELL_KNOWN_PATH))
+            .add_header(ORIGIN, "https://app.example", true)
+            .send(&service)
+            .await;
+
+        assert_eq!(response.status_code, Some(StatusCode::NO_CONTENT));
+        let headers = response.headers();
+        assert_eq!(headers.get(ALLOW).unwrap(), ALLOWED_METHODS);
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://app.example",
+        );
+    }
+}
@@ -10,6 +10,10 @@
 //! - [`Rel`] is used where RFC 7033 requires a single link relation type rather than arbitrary
 //!   text.
 //! - [`Link`] and [`LinkBuilder`] model link objects from [RFC 7033 section 4.4.4].
+//! - [`MediaType`] parses the link `type` so media types compare case- and parameter-aware.
+//! - [`LanguageRange`] picks link titles for a request's `Accept-Language` preferences.
+//! - [`Expires`] models the RFC 6415 `expires` member, and [`Extensions`] keeps JRD members the
+//!   crate does not model.
 //!
 //! The public crate root re-exports these types under the common `WebFingerRequest` and
 //! `WebFingerResponse` names, so most users can import from `webfinger_rs` directly.
@@ -18,15 +22,25 @@
 //! [RFC 7033 section 4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4
 //! [RFC 7033 section 4.4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4
 
+pub use expires::Expires;
+pub use extensions::Extensions;
 pub use jrd_uri::JrdUri;
+pub(crate) use jrd_uri::is_absolute_uri;
+pub use language::LanguageRange;
+pub(crate) use language::{is_language_tag, lookup as lookup_language};
 pub use link::{Link, LinkBuilder, Title};
+pub use media_type::MediaType;
 pub use rel::Rel;
 pub use request::{Builder as RequestBuilder, Request};
 pub use resource::{Resource, ResourceError};
 pub use response::{Builder as ResponseBuilder, Response};
 
+mod expires;
+mod extensions;
 mod jrd_uri;
+mod language;
 mod link;
+mod media_type;
 mod rel;
 mod request;
 mod resource;
@@ -0,0 +1,506 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Error;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// The latest instant a four-digit RFC 3339 year can express, `9999-12-31T23:59:59Z`.
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+/// The earliest instant a four-digit RFC 3339 year can express, `0000-01-01T00:00:00Z`.
+const MIN_SECONDS: i64 = -62_167_219_200;
+
+/// The time after which a WebFinger response should be considered stale.
+///
+/// RFC 6415 defines an `expires` JRD member, inherited from the XRD `Expires` element, whose value
+/// is an [RFC 3339] timestamp such as `2026-10-18T12:00:00Z`. Servers can send it to tell clients
+/// how long a response stays valid, and clients can use it as a cache hint alongside HTTP caching
+/// headers.
+///
+/// The timestamp text is stored as received so a response round-trips without reformatting.
+/// Ordering compares the instants the timestamps describe.
+///
+/// See [RFC 6415 section 3.1.1] and [appendix A].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// use webfinger_rs::Expires;
+///
+/// let expires = Expires::try_new("2026-10-18T12:00:00+02:00")?;
+/// assert_eq!(
+///     expires.system_time(),
+///     UNIX_EPOCH + Duration::from_secs(1_792_317_600)
+/// );
+///
+/// let from_time = Expires::from(UNIX_EPOCH + Duration::from_secs(1_792_317_600));
+/// assert_eq!(from_time.as_ref(), "2026-10-18T10:00:00Z");
+/// assert_eq!(from_time, expires.system_time().into());
+/// # Ok::<(), webfinger_rs::Error>(())
+/// ```
+///
+/// [RFC 3339]: https://www.rfc-editor.org/rfc/rfc3339.html#section-5.6
+/// [RFC 6415 section 3.1.1]: https://www.rfc-editor.org/rfc/rfc6415.html#section-3.1.1
+/// [appendix A]: https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Expires(String);
+
+impl Expires {
+    /// Creates an expiry time from an RFC 3339 timestamp.
+    ///
+    /// This constructor is intended for timestamps controlled by the application. Use
+    /// [`Expires::try_new`] for fallible construction from external input, or convert a
+    /// [`SystemTime`] with [`From`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timestamp` is not an RFC 3339 `date-time`.
+    pub fn new<S: AsRef<str>>(timestamp: S) -> Self {
+        Self::try_new(timestamp).expect("invalid WebFinger expires timestamp")
+    }
+
+    /// Tries to create an expiry time from an RFC 3339 timestamp.
+    ///
+    /// Both `Z` and numeric offsets are accepted, as are fractional seconds and the lowercase `t`
+    /// and `z` separators that RFC 3339 permits.
+    pub fn try_new<S: AsRef<str>>(timestamp: S) -> Result<Self, Error> {
+        let timestamp = timestamp.as_ref();
+        match parse_rfc3339(timestamp) {
+            Some(_) => Ok(Self(timestamp.to_string())),
+            None => Err(Error::InvalidExpires(timestamp.to_string())),
+        }
+    }
+
+    /// Returns the instant this timestamp describes.
+    pub fn system_time(&self) -> SystemTime {
+        parse_rfc3339(&self.0).expect("validated RFC 3339 timestamp")
+    }
+
+    /// Returns whether the response has expired at `now`.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        now >= self.system_time()
+    }
+
+    /// Returns how long the response stays valid after `now`, or zero if it has expired.
+    ///
+    /// Clients can use this as a cache lifetime when the HTTP response carries no
+    /// `Cache-Control: max-age`.
+    pub fn time_to_live(&self, now: SystemTime) -> Duration {
+        self.system_time()
+            .duration_since(now)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// Formats the instant as an RFC 3339 UTC timestamp.
+///
+/// Fractional seconds are written only when present. Instants outside the years 0000 to 9999,
+/// which RFC 3339 cannot express, are clamped to the nearest representable second.
+impl From<SystemTime> for Expires {
+    fn from(time: SystemTime) -> Self {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => (
+                i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+                after.subsec_nanos(),
+            ),
+            Err(before) => {
+                let before = before.duration();
+                let mut seconds = -i64::try_from(before.as_secs()).unwrap_or(i64::MAX);
+                let mut nanos = before.subsec_nanos();
+                if nanos > 0 {
+                    seconds -= 1;
+                    nanos = 1_000_000_000 - nanos;
+                }
+                (seconds, nanos)
+            }
+        };
+        let (seconds, nanos) = if seconds > MAX_SECONDS {
+            (MAX_SECONDS, 0)
+        } else if seconds < MIN_SECONDS {
+            (MIN_SECONDS, 0)
+        } else {
+            (seconds, nanos)
+        };
+
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        let time_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+        let mut timestamp = format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            time_of_day / 3600,
+            time_of_day % 3600 / 60,
+            time_of_day % 60,
+        );
+        if nanos > 0 {
+            let fraction = format!("{nanos:09}");
+            timestamp.push('.');
+            timestamp.push_str(fraction.trim_end_matches('0'));
+        }
+        timestamp.push('Z');
+        Self(timestamp)
+    }
+}
+
+impl From<Expires> for SystemTime {
+    fn from(expires: Expires) -> Self {
+        expires.system_time()
+    }
+}
+
+impl PartialOrd for Expires {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Expires {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.system_time()
+            .cmp(&other.system_time())
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl fmt::Display for Expires {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Expires {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Expires").field(&self.0).finish()
+    }
+}
+
+impl FromStr for Expires {
+    type Err = Error;
+
+    fn from_str(timestamp: &str) -> Result<Self, Self::Err> {
+        Self::try_new(timestamp)
+    }
+}
+
+impl TryFrom<&str> for Expires {
+    type Error = Error;
+
+    fn try_from(timestamp: &str) -> Result<Self, Self::Error> {
+        Self::try_new(timestamp)
+    }
+}
+
+impl TryFrom<String> for Expires {
+    type Error = Error;
+
+    fn try_from(timestamp: String) -> Result<Self, Self::Error> {
+        Self::try_new(timestamp)
+    }
+}
+
+impl AsRef<str> for Expires {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Serialize for Expires {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expires {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(ExpiresVisitor)
+    }
+}
+
+struct ExpiresVisitor;
+
+impl Visitor<'_> for ExpiresVisitor {
+    type Value = Expires;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an RFC 3339 timestamp string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Expires::try_new(value).map_err(E::custom)
+    }
+}
+
+/// Parses an RFC 3339 `date-time` into the instant it describes.
+///
+/// See [RFC 3339 section 5.6] for the grammar. A leap second (`:60`) is accepted and counted as
+/// the first second of the next minute, because [`SystemTime`] has no leap seconds.
+///
+/// [RFC 3339 section 5.6]: https://www.rfc-editor.org/rfc/rfc3339.html#section-5.6
+fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    let hour = digits(&bytes[11..13])?;
+    let minute = digits(&bytes[14..16])?;
+    let second = digits(&bytes[17..19])?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &bytes[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let length = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if length == 0 {
+            return None;
+        }
+        // Digits beyond nanosecond precision are valid RFC 3339 but cannot be represented.
+        for (index, digit) in fraction[..length.min(9)].iter().enumerate() {
+            nanos += u32::from(digit - b'0') * 10u32.pow(8 - index as u32);
+        }
+        rest = &fraction[length..];
+    }
+    let offset = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let hours = digits(&[*h1, *h2])?;
+            let minutes = digits(&[*m1, *m2])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'-' { -offset } else { offset }
+        }
+        _ => return None,
+    };
+
+    let seconds =
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second
+            - offset;
+    let time = if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds.unsigned_abs()))?
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))?
+    };
+    time.checked_add(Duration::from_nanos(u64::from(nanos)))
+}
+
+fn digits(bytes: &[u8]) -> Option<i64> {
+    bytes.iter().try_fold(0, |value, byte| {
+        byte.is_ascii_digit()
+            .then(|| value * 10 + i64::from(byte - b'0'))
+    })
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to a proleptic Gregorian date.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the proleptic Gregorian date `days` after 1970-01-01.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Debug, Display};
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    fn assert_common_traits<T>()
+    where
+        T: Clone
+            + Debug
+            + Display
+            + Eq
+            + Ord
+            + Hash
+            + Send
+            + Sync
+            + Serialize
+            + for<'de> Deserialize<'de>,
+    {
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn implements_applicable_common_traits() {
+        assert_common_traits::<Expires>();
+    }
+
+    /// RFC 3339 timestamps with `Z`, numeric offsets, and fractional seconds describe instants.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc3339.html#section-5.8>.
+    #[test]
+    fn parses_rfc3339_timestamps() {
+        assert_eq!(Expires::new("1970-01-01T00:00:00Z").system_time(), at(0));
+        assert_eq!(
+            Expires::new("1985-04-12T23:20:50.52Z").system_time(),
+            at(482_196_050) + Duration::from_millis(520)
+        );
+        assert_eq!(
+            Expires::new("1996-12-19T16:39:57-08:00").system_time(),
+            at(851_042_397)
+        );
+        assert_eq!(
+            Expires::new("2024-02-29t12:00:00z").system_time(),
+            at(1_709_208_000)
+        );
+        assert_eq!(
+            Expires::new("1969-12-31T23:59:59Z").system_time(),
+            UNIX_EPOCH - Duration::from_secs(1)
+        );
+    }
+
+    /// RFC 3339 permits a leap second at the end of a minute.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc3339.html#section-5.7>.
+    #[test]
+    fn accepts_leap_seconds() {
+        assert_eq!(
+            Expires::new("1990-12-31T23:59:60Z").system_time(),
+            Expires::new("1991-01-01T00:00:00Z").system_time()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for timestamp in [
+            "",
+            "2026-10-18",
+            "2026-10-18T12:00:00",
+            "2026-10-18 12:00:00Z",
+            "2026-13-01T00:00:00Z",
+            "2026-02-29T00:00:00Z",
+            "2026-10-18T24:00:00Z",
+            "2026-10-18T12:00:00.Z",
+            "2026-10-18T12:00:00+0200",
+            "2026-10-18T12:00:00+24:00",
+            "Sun, 18 Oct 2026 12:00:00 GMT",
+        ] {
+            let error = Expires::try_new(timestamp).expect_err(timestamp);
+
+            assert!(error.to_string().contains("invalid expires timestamp"));
+        }
+    }
+
+    #[test]
+    fn formats_system_times_as_utc() {
+        assert_eq!(Expires::from(at(0)).as_ref(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            Expires::from(at(951_782_400) + Duration::from_millis(250)).as_ref(),
+            "2000-02-29T00:00:00.25Z"
+        );
+        assert_eq!(
+            Expires::from(UNIX_EPOCH - Duration::from_millis(500)).as_ref(),
+            "1969-12-31T23:59:59.5Z"
+        );
+    }
+
+    /// Timestamps keep their original text through serialization.
+    #[test]
+    fn round_trips_original_text() -> Result<(), serde_json::Error> {
+        let json = r#""2026-10-18T12:00:00.000+02:00""#;
+
+        let expires: Expires = serde_json::from_str(json)?;
+
+        assert_eq!(serde_json::to_string(&expires)?, json);
+        Ok(())
+    }
+
+    #[test]
+    fn orders_by_instant() {
+        let earlier = Expires::new("2026-10-18T12:00:00+02:00");
+        let later = Expires::new("2026-10-18T11:00:00Z");
+
+        assert!(earlier < later);
+    }
+
+    #[test]
+    fn reports_expiry_relative_to_now() {
+        let expires = Expires::from(at(1_000));
+
+        assert!(!expires.is_expired_at(at(999)));
+        assert!(expires.is_expired_at(at(1_000)));
+        assert_eq!(expires.time_to_live(at(400)), Duration::from_secs(600));
+        assert_eq!(expires.time_to_live(at(2_000)), Duration::ZERO);
+    }
+
+    #[test]
+    fn deserialization_rejects_invalid_timestamps() {
+        let error = serde_json::from_str::<Expires>(r#""tomorrow""#).expect_err("invalid");
+
+        assert!(error.to_string().contains("invalid expires timestamp"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// JRD members that this crate does not model.
+///
+/// RFC 6415 and later profiles allow JRD documents to carry members beyond the ones defined by
+/// RFC 7033. [`Response`](crate::WebFingerResponse) and [`Link`](crate::Link) flatten an
+/// `Extensions` map into their JSON object so those members survive deserialization and
+/// serialization unchanged instead of being dropped.
+///
+/// `Extensions` dereferences to a `BTreeMap<String, serde_json::Value>`, so the usual map methods
+/// are available. Keys must not repeat a member name the containing struct already models; such
+/// entries are serialized twice and the document is rejected when read back.
+///
+/// `serde_json::Value` has no ordering of its own. Comparison and hashing use each value's compact
+/// JSON text, which keeps the ordering traits on `Response` and `Link` consistent with equality.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use webfinger_rs::WebFingerResponse;
+///
+/// let json = r#"{"subject": "acct:carol@example.com", "x-vendor": {"tier": 2}}"#;
+/// let response: WebFingerResponse = serde_json::from_str(json)?;
+///
+/// assert_eq!(response.extensions.get("x-vendor"), Some(&json!({"tier": 2})));
+/// assert_eq!(serde_json::to_value(&response)?["x-vendor"], json!({"tier": 2}));
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Extensions(BTreeMap<String, Value>);
+
+impl Extensions {
+    /// Creates an empty extension map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn canonical(&self) -> impl Iterator<Item = (&str, String)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_string()))
+    }
+}
+
+impl Deref for Extensions {
+    type Target = BTreeMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Extensions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Extensions {
+    fn from(map: BTreeMap<String, Value>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<Extensions> for BTreeMap<String, Value> {
+    fn from(extensions: Extensions) -> Self {
+        extensions.0
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Value)> for Extensions {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Extensions {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = std::collections::btree_map::Iter<'a, String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Extensions {
+    type Item = (String, Value);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Extensions {}
+
+impl PartialOrd for Extensions {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Extensions {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical().cmp(other.canonical())
+    }
+}
+
+impl Hash for Extensions {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.len().hash(state);
+        for entry in self.canonical() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(&self.0).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn orders_by_name_then_json_text() {
+        let a = Extensions::from_iter([("a", json!(1))]);
+        let b = Extensions::from_iter([("a", json!(2))]);
+        let c = Extensions::from_iter([("b", json!(0))]);
+
+        assert!(a < b);
+        assert!(b < c);
+        assert_eq!(a, Extensions::from_iter([("a", json!(1))]));
+    }
+
+    #[test]
+    fn serializes_as_plain_object() -> Result<(), serde_json::Error> {
+        let extensions = Extensions::from_iter([("x-vendor", json!({"tier": 2}))]);
+
+        assert_eq!(
+            serde_json::to_value(&extensions)?,
+            json!({"x-vendor": {"tier": 2}})
+        );
+        Ok(())
+    }
+}
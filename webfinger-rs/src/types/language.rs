@@ -0,0 +1,352 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::format::parse_quality;
+
+/// Irregular grandfathered tags from RFC 5646 section 2.2.8, which do not follow the `langtag`
+/// syntax but are still well-formed.
+const IRREGULAR_LANGUAGE_TAGS: &[&str] = &[
+    "en-GB-oed",
+    "i-ami",
+    "i-bnn",
+    "i-default",
+    "i-enochian",
+    "i-hak",
+    "i-klingon",
+    "i-lux",
+    "i-mingo",
+    "i-navajo",
+    "i-pwn",
+    "i-tao",
+    "i-tay",
+    "i-tsu",
+    "sgn-BE-FR",
+    "sgn-BE-NL",
+    "sgn-CH-DE",
+];
+
+/// A basic language range from an `Accept-Language` header, used to pick a link title.
+///
+/// RFC 4647 defines a language range as a language tag prefix such as `en`, `en-US`, or
+/// `zh-Hant`, or the wildcard `*`. A slice of ranges in preference order is a language priority
+/// list: pass one to [`Link::title_for`] to choose a title, or to
+/// [`WebFingerResponse::select_titles`] to keep only the chosen title of each link.
+///
+/// [`LanguageRange::parse_accept_language`] builds that list from a request header.
+///
+/// See [RFC 4647 section 2.1] and [RFC 9110 section 12.5.4].
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::{LanguageRange, Link};
+///
+/// let link = Link::builder("http://webfinger.net/rel/profile-page")
+///     .title("en", "Profile")
+///     .title("fr", "Profil")
+///     .build();
+/// let ranges = LanguageRange::parse_accept_language("fr-CA, en;q=0.8");
+///
+/// assert_eq!(link.title_for(&ranges), Some("Profil"));
+/// ```
+///
+/// [`Link::title_for`]: crate::Link::title_for
+/// [`WebFingerResponse::select_titles`]: crate::WebFingerResponse::select_titles
+/// [RFC 4647 section 2.1]: https://www.rfc-editor.org/rfc/rfc4647.html#section-2.1
+/// [RFC 9110 section 12.5.4]: https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.4
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LanguageRange(String);
+
+impl LanguageRange {
+    /// Creates a language range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not `*` or a sequence of one to eight letter primary subtag followed by
+    /// hyphen-separated subtags of one to eight letters or digits. Use [`LanguageRange::try_new`]
+    /// for input from requests.
+    pub fn new<S: AsRef<str>>(range: S) -> Self {
+        Self::try_new(range).expect("invalid language range")
+    }
+
+    /// Tries to create a language range.
+    pub fn try_new<S: AsRef<str>>(range: S) -> Result<Self, Error> {
+        let range = range.as_ref();
+        if is_language_range(range) {
+            Ok(Self(range.to_string()))
+        } else {
+            Err(Error::InvalidLanguageRange(range.to_string()))
+        }
+    }
+
+    /// Parses an `Accept-Language` header value into a priority list.
+    ///
+    /// Ranges are ordered by descending quality, keeping header order for equal qualities. Ranges
+    /// with `q=0`, which the client refuses, and malformed entries are left out, so a header that
+    /// cannot be parsed behaves like one that was not sent.
+    pub fn parse_accept_language(header: &str) -> Vec<Self> {
+        let mut ranges: Vec<(u16, Self)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut params = entry.split(';');
+                let range = Self::try_new(params.next()?.trim()).ok()?;
+                let mut quality = 1000;
+                for param in params {
+                    let (name, value) = param.split_once('=')?;
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        quality = parse_quality(value.trim())?;
+                    }
+                }
+                (quality > 0).then_some((quality, range))
+            })
+            .collect();
+        ranges.sort_by(|(a, _), (b, _)| b.cmp(a));
+        ranges.into_iter().map(|(_, range)| range).collect()
+    }
+
+    /// Returns whether this is the `*` wildcard.
+    pub fn is_wildcard(&self) -> bool {
+        self.0 == "*"
+    }
+}
+
+/// Returns the key of `tags` that RFC 4647 lookup selects for `ranges`.
+///
+/// Each non-wildcard range is tried in order, first as is and then with trailing subtags removed,
+/// against the tags case-insensitively. When no range matches, `und` and then the first tag are
+/// used as defaults.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc4647.html#section-3.4>.
+pub(crate) fn lookup<'a, I>(tags: I, ranges: &[LanguageRange]) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+    I::IntoIter: Clone,
+{
+    let tags = tags.into_iter();
+    for range in ranges.iter().filter(|range| !range.is_wildcard()) {
+        let mut prefix = range.0.as_str();
+        loop {
+            if let Some(tag) = tags.clone().find(|tag| tag.eq_ignore_ascii_case(prefix)) {
+                return Some(tag);
+            }
+            let Some((shorter, _)) = prefix.rsplit_once('-') else {
+                break;
+            };
+            // A single-character subtag cannot stand alone, so it is removed with the next one.
+            prefix = match shorter.rsplit_once('-') {
+                Some((shorter_still, singleton)) if singleton.len() == 1 => shorter_still,
+                _ => shorter,
+            };
+        }
+    }
+    tags.clone()
+        .find(|tag| tag.eq_ignore_ascii_case("und"))
+        .or_else(|| tags.clone().next())
+}
+
+impl fmt::Display for LanguageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for LanguageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LanguageRange").field(&self.0).finish()
+    }
+}
+
+impl FromStr for LanguageRange {
+    type Err = Error;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        Self::try_new(range)
+    }
+}
+
+impl TryFrom<&str> for LanguageRange {
+    type Error = Error;
+
+    fn try_from(range: &str) -> Result<Self, Self::Error> {
+        Self::try_new(range)
+    }
+}
+
+impl AsRef<str> for LanguageRange {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Returns whether `range` is an RFC 4647 `language-range`.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc4647.html#section-2.1>.
+fn is_language_range(range: &str) -> bool {
+    if range == "*" {
+        return true;
+    }
+    let mut subtags = range.split('-');
+    subtags
+        .next()
+        .is_some_and(|primary| (1..=8).contains(&primary.len()) && is_alphabetic(primary))
+        && subtags.all(|subtag| (1..=8).contains(&subtag.len()) && is_alphanumeric(subtag))
+}
+
+/// Returns whether `tag` is a well-formed BCP 47 language tag.
+///
+/// This checks the syntax from RFC 5646 section 2.1, including private-use and grandfathered tags,
+/// but not whether the subtags are registered. Comparison is case-insensitive.
+pub(crate) fn is_language_tag(tag: &str) -> bool {
+    if IRREGULAR_LANGUAGE_TAGS
+        .iter()
+        .any(|irregular| irregular.eq_ignore_ascii_case(tag))
+    {
+        return true;
+    }
+    let subtags: Vec<&str> = tag.split('-').collect();
+    if subtags
+        .iter()
+        .any(|subtag| subtag.is_empty() || subtag.len() > 8 || !is_alphanumeric(subtag))
+    {
+        return false;
+    }
+    if subtags[0].eq_ignore_ascii_case("x") {
+        return subtags.len() > 1;
+    }
+
+    let mut rest = subtags.iter().copied().peekable();
+    let language = rest.next().unwrap_or_default();
+    if !(2..=8).contains(&language.len()) || !is_alphabetic(language) {
+        return false;
+    }
+    if language.len() <= 3 {
+        // Up to three extended language subtags may follow a short primary language.
+        for _ in 0..3 {
+            if rest.next_if(|s| s.len() == 3 && is_alphabetic(s)).is_none() {
+                break;
+            }
+        }
+    }
+    rest.next_if(|s| s.len() == 4 && is_alphabetic(s));
+    rest.next_if(|s| {
+        (s.len() == 2 && is_alphabetic(s))
+            || (s.len() == 3 && s.bytes().all(|b| b.is_ascii_digit()))
+    });
+    while rest
+        .next_if(|s| s.len() >= 5 || (s.len() == 4 && s.as_bytes()[0].is_ascii_digit()))
+        .is_some()
+    {}
+    while rest
+        .next_if(|s| s.len() == 1 && !s.eq_ignore_ascii_case("x"))
+        .is_some()
+    {
+        // Each extension singleton needs at least one subtag of two to eight characters.
+        if rest.next_if(|s| s.len() >= 2).is_none() {
+            return false;
+        }
+        while rest.next_if(|s| s.len() >= 2).is_some() {}
+    }
+    match rest.next() {
+        None => true,
+        // Private use subtags only need the length and characters checked above.
+        Some(subtag) => subtag.eq_ignore_ascii_case("x") && rest.peek().is_some(),
+    }
+}
+
+fn is_alphabetic(value: &str) -> bool {
+    value.bytes().all(|byte| byte.is_ascii_alphabetic())
+}
+
+fn is_alphanumeric(value: &str) -> bool {
+    value.bytes().all(|byte| byte.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(header: &str) -> Vec<LanguageRange> {
+        LanguageRange::parse_accept_language(header)
+    }
+
+    /// See <https://www.rfc-editor.org/rfc/rfc5646.html#section-2.1>.
+    #[test]
+    fn recognizes_well_formed_language_tags() {
+        for tag in [
+            "en",
+            "en-US",
+            "zh-Hant-TW",
+            "sr-Latn-RS",
+            "es-419",
+            "de-CH-1901",
+            "zh-cmn-Hans-CN",
+            "en-a-bbb-x-private",
+            "x-whatever",
+            "i-klingon",
+            "EN-gb",
+        ] {
+            assert!(is_language_tag(tag), "{tag}");
+        }
+        for tag in [
+            "",
+            "e",
+            "en_US",
+            "en-",
+            "-en",
+            "en--US",
+            "123",
+            "en-a",
+            "en-x",
+            "en-US-1",
+            "toolongtag",
+        ] {
+            assert!(!is_language_tag(tag), "{tag}");
+        }
+    }
+
+    /// Ranges are ordered by quality, and refused or malformed ranges are dropped.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.4>.
+    #[test]
+    fn parses_accept_language_by_quality() {
+        assert_eq!(
+            ranges("da, en-gb;q=0.8, en;q=0.7, fr;q=0, de_DE, *;q=0.1"),
+            vec![
+                LanguageRange::new("da"),
+                LanguageRange::new("en-gb"),
+                LanguageRange::new("en"),
+                LanguageRange::new("*"),
+            ],
+        );
+        assert_eq!(
+            ranges("en;q=0.5, de"),
+            vec![LanguageRange::new("de"), LanguageRange::new("en"),]
+        );
+        assert!(ranges("").is_empty());
+    }
+
+    /// Lookup removes subtags from the end of a range until a tag matches, dropping a trailing
+    /// singleton together with the subtag before it.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc4647.html#section-3.4>.
+    #[test]
+    fn lookup_truncates_ranges() {
+        let tags = ["de", "en", "zh-Hant"];
+
+        assert_eq!(lookup(tags, &ranges("EN-us")), Some("en"));
+        assert_eq!(
+            lookup(tags, &ranges("zh-Hant-CN-x-private")),
+            Some("zh-Hant")
+        );
+        assert_eq!(lookup(tags, &ranges("fr, de;q=0.5")), Some("de"));
+        assert_eq!(lookup(["en-us"], &ranges("en, fr")), Some("en-us"));
+    }
+
+    #[test]
+    fn lookup_falls_back_to_und_then_first_tag() {
+        assert_eq!(lookup(["de", "und"], &ranges("fr, *")), Some("und"));
+        assert_eq!(lookup(["de", "fr"], &ranges("ja")), Some("de"));
+        assert_eq!(lookup([], &ranges("ja")), None);
+    }
+}
@@ -4,7 +4,8 @@ use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use crate::{JrdUri, Rel};
+use crate::types::{is_language_tag, lookup_language};
+use crate::{Error, Extensions, JrdUri, LanguageRange, MediaType, Rel};
 
 /// A link in the WebFinger response.
 ///
@@ -17,11 +18,13 @@ use crate::{JrdUri, Rel};
 ///
 /// - [`rel`](Self::rel) is a [`Rel`] so the required relation string is validated as one relation
 ///   type.
+/// - [`type`](Self::type) is a parsed [`MediaType`].
 /// - [`href`](Self::href) is a [`JrdUri`] because RFC 7033 defines it as a URI string.
 /// - [`template`](Self::template) is a URI template string.
 /// - [`titles`](Self::titles) is a language-keyed object, matching the RFC JSON form.
 /// - [`properties`](Self::properties) uses [`JrdUri`] keys and `Option<String>` values so JSON
 ///   `null` is representable.
+/// - [`extensions`](Self::extensions) holds any other link members so they are not lost.
 ///
 /// Use [`Link::builder`] for ordinary construction from string literals or application values. Use
 /// [`Link::new`] when you already have a validated [`Rel`].
@@ -59,14 +62,14 @@ pub struct Link {
 
     /// The media type of the link.
     ///
-    /// RFC 7033 leaves this as a media type string. The crate stores it as `String` because it is
-    /// advisory metadata for the linked representation, not one of the WebFinger URI-valued
-    /// fields.
+    /// RFC 7033 defines this as a media type string. [`MediaType`] keeps the original text for
+    /// serialization and parses it so callers can compare types with [`MediaType::matches`] or
+    /// [`MediaType::is_activitypub`] instead of comparing strings.
     ///
     /// See [RFC 7033 section 4.4.4.2].
     ///
     /// [RFC 7033 section 4.4.4.2]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.2
-    pub r#type: Option<String>,
+    pub r#type: Option<MediaType>,
 
     /// The target URI of the link.
     ///
@@ -116,6 +119,13 @@ pub struct Link {
     ///
     /// [RFC 7033 section 4.4.4.5]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.5
     pub properties: Option<BTreeMap<JrdUri, Option<String>>>,
+
+    /// Link members not modeled by the fields above.
+    ///
+    /// These are flattened into the link object, so vendor or profile-specific members survive a
+    /// deserialize and serialize round trip.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 impl Link {
@@ -132,6 +142,56 @@ impl Link {
             template: None,
             titles: None,
             properties: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// Returns the title that best matches a language priority list.
+    ///
+    /// The title is chosen with the RFC 4647 lookup scheme: each range in `ranges` is tried in
+    /// order, first as is and then with trailing subtags removed, against the title languages
+    /// case-insensitively, so `en-US` selects an `en` title. The `*` range is skipped. When no range
+    /// matches, including when `ranges` is empty, the `und` title is returned if there is one, and
+    /// otherwise the first title in language order.
+    ///
+    /// Returns `None` only when the link has no titles.
+    ///
+    /// See [RFC 4647 section 3.4].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{LanguageRange, Link};
+    ///
+    /// let link = Link::builder("author")
+    ///     .title("und", "Carol")
+    ///     .title("de", "Carola")
+    ///     .build();
+    ///
+    /// assert_eq!(link.title_for(&[LanguageRange::new("de-AT")]), Some("Carola"));
+    /// assert_eq!(link.title_for(&[LanguageRange::new("fr")]), Some("Carol"));
+    /// ```
+    ///
+    /// [RFC 4647 section 3.4]: https://www.rfc-editor.org/rfc/rfc4647.html#section-3.4
+    pub fn title_for(&self, ranges: &[LanguageRange]) -> Option<&str> {
+        let titles = self.titles.as_ref()?;
+        let language = lookup_language(titles.keys().map(String::as_str), ranges)?;
+        titles.get(language).map(String::as_str)
+    }
+
+    /// Removes every title except the one [`Link::title_for`] selects for `ranges`.
+    ///
+    /// An empty `ranges` states no preference, so all titles are kept.
+    pub fn select_title(&mut self, ranges: &[LanguageRange]) {
+        if ranges.is_empty() {
+            return;
+        }
+        let Some(titles) = &mut self.titles else {
+            return;
+        };
+        if let Some(language) = lookup_language(titles.keys().map(String::as_str), ranges) {
+            let language = language.to_string();
+            titles.retain(|key, _| *key == language);
         }
     }
 
@@ -187,11 +247,12 @@ impl LinkBuilder {
 
     /// Sets the media type of the link.
     ///
-    /// This writes the optional `type` member from [RFC 7033 section 4.4.4.2].
+    /// The value is validated as a [`MediaType`] and serialized as the optional `type` member from
+    /// [RFC 7033 section 4.4.4.2].
     ///
     /// [RFC 7033 section 4.4.4.2]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.2
-    pub fn r#type<S: Into<String>>(mut self, r#type: S) -> Self {
-        self.link.r#type = Some(r#type.into());
+    pub fn r#type<S: AsRef<str>>(mut self, r#type: S) -> Self {
+        self.link.r#type = Some(MediaType::new(r#type));
         self
     }
 
@@ -221,9 +282,15 @@ impl LinkBuilder {
     /// RFC 7033 serializes titles as an object keyed by language tag, so repeated calls insert or
     /// replace entries in that object.
     ///
+    /// # Panics
+    ///
+    /// Panics if `language` is not a well-formed BCP 47 language tag. `und` is accepted for a
+    /// title without a language.
+    ///
     /// [RFC 7033 section 4.4.4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.4
     pub fn title<L: Into<String>, V: Into<String>>(mut self, language: L, value: V) -> Self {
         let title = Title::new(language, value);
+        check_language_tag(&title.language).expect("invalid WebFinger title language");
         self.link
             .titles
             .get_or_insert_with(BTreeMap::new)
@@ -237,6 +304,10 @@ impl LinkBuilder {
     /// `BTreeMap` or `HashMap`. Keys and values are converted into owned strings and stored as the
     /// JSON object described by [RFC 7033 section 4.4.4.4].
     ///
+    /// # Panics
+    ///
+    /// Panics if a language is not a well-formed BCP 47 language tag, as for [`LinkBuilder::title`].
+    ///
     /// [RFC 7033 section 4.4.4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.4
     pub fn titles<I, L, V>(mut self, titles: I) -> Self
     where
@@ -246,7 +317,11 @@ impl LinkBuilder {
     {
         let titles = titles
             .into_iter()
-            .map(|(language, value)| (language.into(), value.into()))
+            .map(|(language, value)| {
+                let language = language.into();
+                check_language_tag(&language).expect("invalid WebFinger title language");
+                (language, value.into())
+            })
             .collect();
         self.link.titles = Some(titles);
         self
@@ -294,6 +369,19 @@ impl LinkBuilder {
         self
     }
 
+    /// Adds a link member that the link does not model.
+    ///
+    /// The name must not be one of the modeled members (`rel`, `type`, `href`, `template`,
+    /// `titles`, or `properties`); such a document would repeat that member.
+    pub fn extension<K: Into<String>, V: Into<serde_json::Value>>(
+        mut self,
+        name: K,
+        value: V,
+    ) -> Self {
+        self.link.extensions.insert(name.into(), value.into());
+        self
+    }
+
     /// Builds the link.
     ///
     /// This can be omitted if the link is being converted to a `Link` directly from the builder as
@@ -336,10 +424,21 @@ impl Debug for Link {
         if let Some(properties) = &self.properties {
             debug = debug.field("properties", &properties);
         }
+        if !self.extensions.is_empty() {
+            debug = debug.field("extensions", &self.extensions);
+        }
         debug.finish()
     }
 }
 
+fn check_language_tag(language: &str) -> Result<(), Error> {
+    if is_language_tag(language) {
+        Ok(())
+    } else {
+        Err(Error::InvalidLanguageTag(language.to_string()))
+    }
+}
+
 /// A title in the WebFinger response.
 ///
 /// RFC 7033 serializes titles as a JSON object, not as a list of title objects. `Title` is a small
@@ -348,6 +447,7 @@ impl Debug for Link {
 ///
 /// The language is stored as `String` because RFC 7033 points at language tags but does not require
 /// WebFinger implementations to enforce a particular registry or normalization policy here.
+/// [`LinkBuilder::title`] still checks that it is a well-formed BCP 47 tag.
 ///
 /// See [RFC 7033 section 4.4.4.4].
 ///
@@ -388,6 +488,15 @@ impl Title {
             value: value.into(),
         }
     }
+
+    /// Tries to create a title pair, checking that the language is a well-formed BCP 47 tag.
+    ///
+    /// This is the check [`LinkBuilder::title`] panics on; use it when handling untrusted input.
+    pub fn try_new<L: Into<String>, V: Into<String>>(language: L, value: V) -> Result<Self, Error> {
+        let title = Self::new(language, value);
+        check_language_tag(&title.language)?;
+        Ok(title)
+    }
 }
 
 #[cfg(test)]
@@ -479,6 +588,24 @@ mod tests {
         Ok(())
     }
 
+    /// Link types are parsed on the way in, so malformed media types are rejected and valid ones
+    /// can be compared without string matching.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.2>.
+    #[test]
+    fn deserializes_media_type() -> Result {
+        let json = r#"{"rel":"self","type":"application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\""}"#;
+
+        let link: Link = serde_json::from_str(json)?;
+        let error = serde_json::from_str::<Link>(r#"{"rel":"self","type":"json"}"#)
+            .expect_err("malformed type");
+
+        assert!(link.r#type.as_ref().is_some_and(MediaType::is_activitypub));
+        assert_eq!(serde_json::to_string(&link)?, json);
+        assert!(error.to_string().contains("invalid media type"));
+        Ok(())
+    }
+
     #[test]
     fn deserializes_template() -> Result {
         let json = r#"
@@ -540,6 +667,7 @@ mod tests {
                 template: None,
                 titles: None,
                 properties: None,
+                extensions: Extensions::new(),
             },
         );
     }
@@ -607,7 +735,7 @@ mod tests {
 
         assert_eq!(
             format!("{link:?}"),
-            r#"Link { rel: Rel("author"), type: "text/html", href: JrdUri("https://example.com/people/carol"), titles: {"en-us": "Carol"}, properties: {JrdUri("https://example.com/ns/role"): Some("editor")} }"#
+            r#"Link { rel: Rel("author"), type: MediaType("text/html"), href: JrdUri("https://example.com/people/carol"), titles: {"en-us": "Carol"}, properties: {JrdUri("https://example.com/ns/role"): Some("editor")} }"#
         );
     }
 
@@ -645,6 +773,16 @@ mod tests {
         );
     }
 
+    /// Checks title languages without panicking, for titles read from untrusted input.
+    #[test]
+    fn title_try_new_rejects_malformed_language() {
+        assert!(Title::try_new("en-US", "Carol").is_ok());
+        assert!(matches!(
+            Title::try_new("en_US", "Carol"),
+            Err(Error::InvalidLanguageTag(language)) if language == "en_US"
+        ));
+    }
+
     /// Rejects array-shaped localized titles.
     ///
     /// This guards the RFC JRD object shape for `titles` rather than accepting a more generic
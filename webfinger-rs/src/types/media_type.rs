@@ -0,0 +1,461 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Error;
+
+/// The JSON-LD profile URI that identifies ActivityStreams documents.
+const ACTIVITY_STREAMS_PROFILE: &str = "https://www.w3.org/ns/activitystreams";
+
+/// A media type used as the JRD link `type`.
+///
+/// RFC 7033 describes the link `type` member as a media type such as `text/html` or
+/// `application/activity+json; charset=utf-8`. Comparing those as strings goes wrong as soon as a
+/// peer changes case, adds whitespace, or quotes a parameter value, so `MediaType` parses the value
+/// once and offers comparisons that follow the media type rules:
+///
+/// - the type, subtype, and parameter names are compared case-insensitively;
+/// - parameter values are compared after removing quotes, case-sensitively except for `charset`;
+/// - [`MediaType::matches`] ignores parameters that the other media type does not mention.
+///
+/// The original text is kept and serialized unchanged, so equality, ordering, and hashing compare
+/// the text. Use [`MediaType::matches`] for a semantic comparison.
+///
+/// See [RFC 7033 section 4.4.4.2] and the media type grammar in [RFC 9110 section 8.3.1].
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::MediaType;
+///
+/// let r#type = MediaType::try_new(
+///     r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#,
+/// )?;
+///
+/// assert_eq!(r#type.essence(), "application/ld+json");
+/// assert_eq!(r#type.suffix(), Some("json"));
+/// assert!(r#type.matches(&MediaType::new("Application/LD+JSON")));
+/// assert!(r#type.is_activitypub());
+/// assert!(MediaType::new("application/activity+json").is_activitypub());
+/// # Ok::<(), webfinger_rs::Error>(())
+/// ```
+///
+/// [RFC 7033 section 4.4.4.2]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.2
+/// [RFC 9110 section 8.3.1]: https://www.rfc-editor.org/rfc/rfc9110.html#section-8.3.1
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MediaType {
+    text: String,
+    /// The lowercase `type/subtype`.
+    essence: String,
+    /// Parameters in order of appearance, with lowercase names and unquoted values.
+    parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// The ActivityPub media type, `application/activity+json`.
+    pub const ACTIVITY_JSON: &'static str = "application/activity+json";
+
+    /// The JSON-LD media type with the ActivityStreams profile, which ActivityPub also accepts.
+    pub const LD_JSON_ACTIVITY_STREAMS: &'static str =
+        r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#;
+
+    /// Creates a media type.
+    ///
+    /// This constructor is intended for media types controlled by the application. Use
+    /// [`MediaType::try_new`] for fallible construction from external input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `media_type` is not a `type/subtype` media type with well-formed parameters.
+    pub fn new<S: AsRef<str>>(media_type: S) -> Self {
+        Self::try_new(media_type).expect("invalid WebFinger link media type")
+    }
+
+    /// Tries to create a media type.
+    ///
+    /// The value must be a `type/subtype` pair of RFC 6838 restricted names, optionally followed by
+    /// `;`-separated `name=value` parameters whose values are tokens or quoted strings. Wildcards
+    /// such as `*/*` are media ranges, not media types, and are rejected.
+    pub fn try_new<S: AsRef<str>>(media_type: S) -> Result<Self, Error> {
+        let text = media_type.as_ref();
+        parse(text).ok_or_else(|| Error::InvalidMediaType(text.to_string()))
+    }
+
+    /// Returns the lowercase `type/subtype`, without parameters.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// Returns the lowercase top-level type, such as `application`.
+    pub fn r#type(&self) -> &str {
+        self.essence
+            .split_once('/')
+            .map_or("", |(r#type, _)| r#type)
+    }
+
+    /// Returns the lowercase subtype, such as `activity+json`.
+    pub fn subtype(&self) -> &str {
+        self.essence
+            .split_once('/')
+            .map_or("", |(_, subtype)| subtype)
+    }
+
+    /// Returns the structured syntax suffix of the subtype, such as `json` for `ld+json`.
+    ///
+    /// See [RFC 6838 section 4.2.8].
+    ///
+    /// [RFC 6838 section 4.2.8]: https://www.rfc-editor.org/rfc/rfc6838.html#section-4.2.8
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype().rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+
+    /// Returns the unquoted value of the named parameter, matching the name case-insensitively.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the parameters in order of appearance, with lowercase names and unquoted values.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns whether this media type satisfies `other`.
+    ///
+    /// The essences must be equal, and every parameter of `other` must be present here with an
+    /// equal value. Parameters that only this media type has are ignored, so
+    /// `text/html; charset=utf-8` matches `text/html` but not the other way round.
+    pub fn matches(&self, other: &MediaType) -> bool {
+        self.essence == other.essence
+            && other.parameters().all(|(name, expected)| {
+                self.parameter(name).is_some_and(|value| {
+                    if name == "charset" {
+                        value.eq_ignore_ascii_case(expected)
+                    } else {
+                        value == expected
+                    }
+                })
+            })
+    }
+
+    /// Returns whether this is one of the media types ActivityPub uses for actor and object
+    /// documents.
+    ///
+    /// That is `application/activity+json`, or `application/ld+json` whose `profile` parameter
+    /// lists the ActivityStreams namespace. `profile` is a space-separated list of URIs, so other
+    /// profiles may appear alongside it.
+    ///
+    /// See [ActivityPub section 3.2].
+    ///
+    /// [ActivityPub section 3.2]: https://www.w3.org/TR/activitypub/#retrieving-objects
+    pub fn is_activitypub(&self) -> bool {
+        match self.essence.as_str() {
+            Self::ACTIVITY_JSON => true,
+            "application/ld+json" => self.parameter("profile").is_some_and(|profile| {
+                profile
+                    .split_ascii_whitespace()
+                    .any(|profile| profile == ACTIVITY_STREAMS_PROFILE)
+            }),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl fmt::Debug for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MediaType").field(&self.text).finish()
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = Error;
+
+    fn from_str(media_type: &str) -> Result<Self, Self::Err> {
+        Self::try_new(media_type)
+    }
+}
+
+impl TryFrom<&str> for MediaType {
+    type Error = Error;
+
+    fn try_from(media_type: &str) -> Result<Self, Self::Error> {
+        Self::try_new(media_type)
+    }
+}
+
+impl TryFrom<String> for MediaType {
+    type Error = Error;
+
+    fn try_from(media_type: String) -> Result<Self, Self::Error> {
+        Self::try_new(media_type)
+    }
+}
+
+impl From<MediaType> for String {
+    fn from(media_type: MediaType) -> Self {
+        media_type.text
+    }
+}
+
+impl AsRef<str> for MediaType {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl Serialize for MediaType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for MediaType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(MediaTypeVisitor)
+    }
+}
+
+struct MediaTypeVisitor;
+
+impl Visitor<'_> for MediaTypeVisitor {
+    type Value = MediaType;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a media type string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        MediaType::try_new(value).map_err(E::custom)
+    }
+}
+
+fn parse(text: &str) -> Option<MediaType> {
+    let (essence, mut rest) = match text.find(';') {
+        Some(index) => (&text[..index], &text[index..]),
+        None => (text, ""),
+    };
+    let (r#type, subtype) = essence.trim_end_matches([' ', '\t']).split_once('/')?;
+    if !is_restricted_name(r#type) || !is_restricted_name(subtype) {
+        return None;
+    }
+
+    let mut parameters = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([' ', '\t']);
+        let Some(after_separator) = rest.strip_prefix(';') else {
+            break;
+        };
+        rest = after_separator.trim_start_matches([' ', '\t']);
+        if rest.is_empty() || rest.starts_with(';') {
+            // Empty parameters are allowed by the `*( OWS ";" [ OWS parameter ] )` grammar.
+            continue;
+        }
+        let (name, after_name) = rest.split_once('=')?;
+        if !is_token(name) {
+            return None;
+        }
+        let (value, after_value) = match after_name.strip_prefix('"') {
+            Some(quoted) => quoted_string(quoted)?,
+            None => {
+                let end = after_name
+                    .find([';', ' ', '\t'])
+                    .unwrap_or(after_name.len());
+                let value = &after_name[..end];
+                if !is_token(value) {
+                    return None;
+                }
+                (value.to_string(), &after_name[end..])
+            }
+        };
+        parameters.push((name.to_ascii_lowercase(), value));
+        rest = after_value;
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    Some(MediaType {
+        text: text.to_string(),
+        essence: format!(
+            "{}/{}",
+            r#type.to_ascii_lowercase(),
+            subtype.to_ascii_lowercase()
+        ),
+        parameters,
+    })
+}
+
+/// Reads a quoted string after its opening quote, returning the unescaped value and the rest.
+fn quoted_string(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '"' => return Some((value, &input[index + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            char if char.is_control() && char != '\t' => return None,
+            char => value.push(char),
+        }
+    }
+    None
+}
+
+/// Returns whether `name` is an RFC 6838 `restricted-name`.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc6838.html#section-4.2>.
+fn is_restricted_name(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    (1..=127).contains(&name.len())
+        && bytes
+            .next()
+            .is_some_and(|byte| byte.is_ascii_alphanumeric())
+        && bytes.all(|byte| byte.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&byte))
+}
+
+/// Returns whether `value` is an RFC 9110 `token`.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-5.6.2>.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Debug, Display};
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    fn assert_common_traits<T>()
+    where
+        T: Clone
+            + Debug
+            + Display
+            + Eq
+            + Ord
+            + Hash
+            + Send
+            + Sync
+            + Serialize
+            + for<'de> Deserialize<'de>,
+    {
+    }
+
+    #[test]
+    fn implements_applicable_common_traits() {
+        assert_common_traits::<MediaType>();
+    }
+
+    #[test]
+    fn parses_type_subtype_and_parameters() {
+        let media_type = MediaType::new(r#"Text/HTML ; Charset="UTF-8";level=1"#);
+
+        assert_eq!(media_type.essence(), "text/html");
+        assert_eq!(media_type.r#type(), "text");
+        assert_eq!(media_type.subtype(), "html");
+        assert_eq!(media_type.suffix(), None);
+        assert_eq!(media_type.parameter("charset"), Some("UTF-8"));
+        assert_eq!(
+            media_type.parameters().collect::<Vec<_>>(),
+            vec![("charset", "UTF-8"), ("level", "1")]
+        );
+        assert_eq!(
+            media_type.to_string(),
+            r#"Text/HTML ; Charset="UTF-8";level=1"#
+        );
+    }
+
+    #[test]
+    fn unescapes_quoted_parameter_values() {
+        let media_type = MediaType::new(r#"application/example; note="a \"b\"; c""#);
+
+        assert_eq!(media_type.parameter("note"), Some(r#"a "b"; c"#));
+    }
+
+    /// RFC 7033 link types are media types, so ranges and free text are rejected.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.2>.
+    #[test]
+    fn rejects_malformed_media_types() {
+        for media_type in [
+            "",
+            "json",
+            "text/",
+            "/html",
+            "*/*",
+            "text/*",
+            "text/html; charset",
+            "text/html; charset=",
+            "text/html; charset=\"utf-8",
+            "text/html; charset=utf 8",
+            "text html",
+        ] {
+            let error = MediaType::try_new(media_type).expect_err(media_type);
+
+            assert!(error.to_string().contains("invalid media type"));
+        }
+    }
+
+    #[test]
+    fn matches_case_insensitively_and_ignores_extra_parameters() {
+        let media_type = MediaType::new("Text/HTML; charset=UTF-8; level=1");
+
+        assert!(media_type.matches(&MediaType::new("text/html")));
+        assert!(media_type.matches(&MediaType::new("text/html; CHARSET=\"utf-8\"")));
+        assert!(!media_type.matches(&MediaType::new("text/html; level=2")));
+        assert!(!media_type.matches(&MediaType::new("text/plain")));
+        assert!(!MediaType::new("text/html").matches(&media_type));
+    }
+
+    /// ActivityPub accepts `application/activity+json` and JSON-LD with the ActivityStreams
+    /// profile.
+    ///
+    /// See <https://www.w3.org/TR/activitypub/#retrieving-objects>.
+    #[test]
+    fn recognizes_activitypub_media_types() {
+        assert!(MediaType::new(MediaType::ACTIVITY_JSON).is_activitypub());
+        assert!(MediaType::new("Application/Activity+JSON; charset=utf-8").is_activitypub());
+        assert!(MediaType::new(MediaType::LD_JSON_ACTIVITY_STREAMS).is_activitypub());
+        assert!(
+            MediaType::new(
+                r#"application/ld+json;profile="https://example.com/p https://www.w3.org/ns/activitystreams""#
+            )
+            .is_activitypub()
+        );
+        assert!(!MediaType::new("application/ld+json").is_activitypub());
+        assert!(!MediaType::new("application/json").is_activitypub());
+    }
+
+    #[test]
+    fn deserialization_rejects_invalid_media_types() {
+        let error = serde_json::from_str::<MediaType>(r#""json""#).expect_err("invalid");
+
+        assert!(error.to_string().contains("invalid media type"));
+    }
+}
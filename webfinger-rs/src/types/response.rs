@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
 use crate::Error;
-use crate::{JrdUri, Link};
+use crate::{Expires, Extensions, JrdUri, LanguageRange, Link};
 
 /// A WebFinger response.
 ///
@@ -20,6 +20,8 @@ use crate::{JrdUri, Link};
 ///   string-or-null values.
 /// - [`links`](Self::links) is the JRD link array. Missing `links` deserializes as an empty
 ///   vector.
+/// - [`expires`](Self::expires) is the optional RFC 6415 expiry timestamp.
+/// - [`extensions`](Self::extensions) holds any other top-level members so they are not lost.
 ///
 /// The response serializes to the RFC JSON shape. It uses typed wrappers for URI-valued and
 /// relation-valued fields while keeping builder methods string-friendly for application code.
@@ -145,6 +147,24 @@ pub struct Response {
     /// [RFC 7033 section 4.4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4
     #[serde(default)]
     pub links: Vec<Link>,
+
+    /// The time after which the response should be considered stale.
+    ///
+    /// RFC 6415 defines this member as an RFC 3339 timestamp. Clients can use
+    /// [`Expires::time_to_live`] as a cache lifetime when the HTTP response has no explicit
+    /// caching headers.
+    ///
+    /// See [RFC 6415 appendix A].
+    ///
+    /// [RFC 6415 appendix A]: https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A
+    pub expires: Option<Expires>,
+
+    /// Top-level JRD members not modeled by the fields above.
+    ///
+    /// These are flattened into the JSON object, so vendor or profile-specific members survive a
+    /// deserialize and serialize round trip.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 impl Response {
@@ -159,6 +179,8 @@ impl Response {
             aliases: None,
             properties: None,
             links: Vec::new(),
+            expires: None,
+            extensions: Extensions::new(),
         }
     }
 
@@ -201,6 +223,36 @@ impl Response {
     pub fn try_builder<S: AsRef<str>>(subject: S) -> Result<Builder, Error> {
         Ok(Builder::new(JrdUri::try_new(subject)?))
     }
+
+    /// Keeps only the best title of each link for a language priority list.
+    ///
+    /// Servers can call this with the request's parsed `Accept-Language` header so clients receive
+    /// one title per link in their preferred language. See [`Link::title_for`] for the selection
+    /// rules. An empty `ranges` keeps every title.
+    ///
+    /// A response trimmed this way depends on `Accept-Language`, so send `Vary: Accept-Language`
+    /// with it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{LanguageRange, Link, WebFingerResponse};
+    ///
+    /// let mut response = WebFingerResponse::builder("acct:carol@example.com")
+    ///     .link(Link::builder("author").title("en", "Carol").title("fr", "Carole"))
+    ///     .build();
+    ///
+    /// response.select_titles(&LanguageRange::parse_accept_language("fr-CH, en;q=0.5"));
+    ///
+    /// let titles = response.links[0].titles.as_ref().unwrap();
+    /// assert_eq!(titles.len(), 1);
+    /// assert_eq!(titles["fr"], "Carole");
+    /// ```
+    pub fn select_titles(&mut self, ranges: &[LanguageRange]) {
+        for link in &mut self.links {
+            link.select_title(ranges);
+        }
+    }
 }
 
 impl fmt::Display for Response {
@@ -309,6 +361,30 @@ impl Builder {
         self
     }
 
+    /// Sets the time after which the response should be considered stale.
+    ///
+    /// Accepts an [`Expires`] or a [`SystemTime`](std::time::SystemTime). The value is serialized
+    /// as the `expires` member from [RFC 6415 appendix A].
+    ///
+    /// [RFC 6415 appendix A]: https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A
+    pub fn expires<E: Into<Expires>>(mut self, expires: E) -> Self {
+        self.response.expires = Some(expires.into());
+        self
+    }
+
+    /// Adds a top-level JRD member that the response does not model.
+    ///
+    /// The name must not be one of the modeled members (`subject`, `aliases`, `properties`,
+    /// `links`, or `expires`); such a document would repeat that member.
+    pub fn extension<K: Into<String>, V: Into<serde_json::Value>>(
+        mut self,
+        name: K,
+        value: V,
+    ) -> Self {
+        self.response.extensions.insert(name.into(), value.into());
+        self
+    }
+
     /// Builds the response.
     pub fn build(self) -> Response {
         self.response
@@ -333,7 +409,14 @@ impl Debug for Response {
         if let Some(properties) = &self.properties {
             debug = debug.field("properties", &properties);
         }
-        debug.field("links", &self.links).finish()
+        debug = debug.field("links", &self.links);
+        if let Some(expires) = &self.expires {
+            debug = debug.field("expires", &expires);
+        }
+        if !self.extensions.is_empty() {
+            debug = debug.field("extensions", &self.extensions);
+        }
+        debug.finish()
     }
 }
 
@@ -526,6 +609,8 @@ mod tests {
                 aliases: None,
                 properties: None,
                 links: Vec::new(),
+                expires: None,
+                extensions: Extensions::new(),
             },
         );
     }
@@ -664,4 +749,66 @@ mod tests {
         assert!(response.links.is_empty());
         Ok(())
     }
+
+    /// Keeps members the crate does not model at the response and link level.
+    ///
+    /// RFC 6415 and later profiles add JRD members; dropping them would corrupt documents that
+    /// pass through this crate, such as proxies and caches.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[test]
+    fn round_trips_unknown_members() -> Result {
+        let json = json!({
+            "subject": "acct:carol@example.com",
+            "expires": "2030-01-30T09:30:00.5+01:00",
+            "x-vendor": {"tier": 2, "tags": ["a", "b"]},
+            "links": [{"rel": "self", "href": "https://example.com/carol", "x-order": 1}],
+        });
+
+        let response = serde_json::from_value::<Response>(json.clone())?;
+
+        assert_eq!(
+            response.expires,
+            Some(Expires::new("2030-01-30T09:30:00.5+01:00"))
+        );
+        assert_eq!(
+            response.extensions.get("x-vendor"),
+            Some(&json!({"tier": 2, "tags": ["a", "b"]}))
+        );
+        assert_eq!(response.links[0].extensions.get("x-order"), Some(&json!(1)));
+        assert_eq!(serde_json::to_value(&response)?, json);
+        Ok(())
+    }
+
+    #[test]
+    fn builder_sets_expires_and_extensions() -> Result {
+        let response = Response::builder("acct:carol@example.com")
+            .expires(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_900_000_000))
+            .extension("x-vendor", true)
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&response)?,
+            json!({
+                "subject": "acct:carol@example.com",
+                "links": [],
+                "expires": "2030-03-17T17:46:40Z",
+                "x-vendor": true,
+            })
+        );
+        assert_eq!(
+            format!("{response:?}"),
+            r#"Response { subject: JrdUri("acct:carol@example.com"), links: [], expires: Expires("2030-03-17T17:46:40Z"), extensions: {"x-vendor": Bool(true)} }"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_expires() {
+        let json = r#"{"subject":"acct:carol@example.com","expires":"tomorrow"}"#;
+
+        let error = serde_json::from_str::<Response>(json).expect_err("invalid expires");
+
+        assert!(error.to_string().contains("invalid expires timestamp"));
+    }
 }
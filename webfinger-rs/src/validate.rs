@@ -0,0 +1,757 @@
+//! JRD conformance checks with structured findings.
+//!
+//! Deserializing a [`WebFingerResponse`] either fails on the first problem or silently accepts a
+//! document that other implementations may reject. [`validate_json`] and
+//! [`WebFingerResponse::validate`] instead walk the whole document and report every problem they
+//! find as a [`Finding`] with a [`Severity`] and an [RFC 6901] JSON pointer to the offending value,
+//! so tools can show all problems at once and point at each one.
+//!
+//! The checks follow the JRD members defined in [RFC 7033 section 4.4]:
+//!
+//! | Finding                                       | Severity  |
+//! | --------------------------------------------- | --------- |
+//! | input is not JSON, or has the wrong JSON type | error     |
+//! | missing `subject`                             | error     |
+//! | `subject` or alias is not an absolute URI     | error     |
+//! | `expires` is not an RFC 3339 timestamp        | error     |
+//! | link without `rel`, or with an invalid `rel`  | error     |
+//! | relative `href`                               | error     |
+//! | link with both `href` and `template`          | error     |
+//! | property name that is not an absolute URI     | error     |
+//! | property value that is not a string or `null` | error     |
+//! | title key that is not a BCP 47 language tag   | warning   |
+//! | malformed link `type` media type              | warning   |
+//! | duplicate link                                | warning   |
+//! | unknown top-level member                      | info      |
+//!
+//! [RFC 6901]: https://www.rfc-editor.org/rfc/rfc6901.html
+//! [RFC 7033 section 4.4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::types::{is_absolute_uri, is_language_tag};
+use crate::{Expires, MediaType, Rel, WebFingerResponse};
+
+/// The top-level members defined for a JRD by RFC 7033 section 4.4 and RFC 6415 appendix A.
+const KNOWN_MEMBERS: &[&str] = &["subject", "aliases", "properties", "links", "expires"];
+
+/// One problem found in a JRD document.
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::{FindingKind, Severity, validate_json};
+///
+/// let findings = validate_json(
+///     r#"{"subject": "acct:carol@example.com", "links": [{"rel": "self", "href": "/carol"}]}"#,
+/// );
+///
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].severity, Severity::Error);
+/// assert_eq!(findings[0].kind, FindingKind::RelativeHref);
+/// assert_eq!(findings[0].pointer, "/links/0/href");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Finding {
+    /// How serious the problem is.
+    pub severity: Severity,
+
+    /// Which check produced the finding.
+    pub kind: FindingKind,
+
+    /// An RFC 6901 JSON pointer to the offending value, or `""` for the whole document.
+    pub pointer: String,
+
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
+/// How serious a [`Finding`] is.
+///
+/// Severities are ordered, so `finding.severity >= Severity::Warning` selects warnings and errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The document is valid, but contains something clients will ignore.
+    Info,
+
+    /// The document parses, but some clients may misinterpret or reject it.
+    Warning,
+
+    /// The document violates RFC 7033 and will not deserialize into a [`WebFingerResponse`].
+    Error,
+}
+
+/// The check that produced a [`Finding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FindingKind {
+    /// The input is not valid JSON.
+    InvalidJson,
+
+    /// A member has the wrong JSON type, such as a `links` value that is not an array.
+    InvalidType,
+
+    /// The document has no `subject` member.
+    MissingSubject,
+
+    /// The `subject` or an alias is not an absolute URI.
+    InvalidUri,
+
+    /// The `expires` member is not an RFC 3339 timestamp string.
+    InvalidExpires,
+
+    /// A link has no `rel` member.
+    MissingRel,
+
+    /// A link `rel` is neither an absolute URI nor a registered relation type.
+    InvalidRel,
+
+    /// A link `href` is not an absolute URI.
+    RelativeHref,
+
+    /// A link has both `href` and `template`, which RFC 6415 section 3.1.1.1 forbids.
+    HrefAndTemplate,
+
+    /// A link is identical to an earlier link.
+    DuplicateLink,
+
+    /// A `titles` key is neither a BCP 47 language tag nor `und`.
+    InvalidTitleLanguage,
+
+    /// A property name is not an absolute URI.
+    NonUriPropertyKey,
+
+    /// A property value is neither a string nor `null`.
+    InvalidPropertyValue,
+
+    /// A link `type` is not a `type/subtype` media type.
+    MalformedMediaType,
+
+    /// The document has a top-level member that neither RFC 7033 nor RFC 6415 defines.
+    UnknownMember,
+}
+
+impl Finding {
+    pub(crate) fn new(
+        severity: Severity,
+        kind: FindingKind,
+        pointer: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{} at {}: {}", self.severity, self.pointer, self.message)
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Checks a raw JRD document and returns every finding.
+///
+/// Findings come in a fixed order rather than the order of the input text: `subject`, `expires`,
+/// `aliases`, `properties`, and `links`, with array entries by index and the keys of `titles` and
+/// `properties` sorted by name. Unknown members come last, also sorted by name.
+///
+/// Unlike deserializing a [`WebFingerResponse`], this keeps going after the first problem and also
+/// reports problems that deserialization accepts, such as duplicate links or unknown members. An
+/// empty result means the document is conforming.
+///
+/// # Examples
+///
+/// ```rust
+/// use webfinger_rs::{Severity, validate_json};
+///
+/// let findings = validate_json(r#"{"subject": "acct:carol@example.com", "x-vendor": true}"#);
+///
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].severity, Severity::Info);
+/// assert_eq!(findings[0].pointer, "/x-vendor");
+/// ```
+pub fn validate_json(input: &str) -> Vec<Finding> {
+    match serde_json::from_str::<Value>(input) {
+        Ok(document) => validate_document(&document),
+        Err(error) => vec![Finding::new(
+            Severity::Error,
+            FindingKind::InvalidJson,
+            "",
+            format!("invalid JSON: {error}"),
+        )],
+    }
+}
+
+impl WebFingerResponse {
+    /// Checks this response and returns every finding, in the order [`validate_json`] reports
+    /// them.
+    ///
+    /// The Rust types already reject relative URIs, but a response built in code can still contain
+    /// duplicate links, links with both `href` and `template`, malformed media types, or title keys
+    /// that are not language tags. This runs the same checks as [`validate_json`] on the serialized
+    /// response.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{FindingKind, Link, WebFingerResponse};
+    ///
+    /// let response = WebFingerResponse::builder("acct:carol@example.com")
+    ///     .link(Link::builder("self").href("https://example.com/carol"))
+    ///     .link(Link::builder("self").href("https://example.com/carol"))
+    ///     .build();
+    ///
+    /// let findings = response.validate();
+    /// assert_eq!(findings.len(), 1);
+    /// assert_eq!(findings[0].kind, FindingKind::DuplicateLink);
+    /// ```
+    pub fn validate(&self) -> Vec<Finding> {
+        match serde_json::to_value(self) {
+            Ok(document) => validate_document(&document),
+            // JRD members are strings, maps and arrays, which always serialize.
+            Err(error) => vec![Finding::new(
+                Severity::Error,
+                FindingKind::InvalidJson,
+                "",
+                format!("response does not serialize: {error}"),
+            )],
+        }
+    }
+}
+
+fn validate_document(document: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(document) = document.as_object() else {
+        findings.push(Finding::new(
+            Severity::Error,
+            FindingKind::InvalidType,
+            "",
+            "a JRD must be a JSON object",
+        ));
+        return findings;
+    };
+
+    match document.get("subject") {
+        None => findings.push(Finding::new(
+            Severity::Error,
+            FindingKind::MissingSubject,
+            "",
+            "missing `subject`",
+        )),
+        Some(subject) => check_uri(subject, "/subject", "subject", &mut findings),
+    }
+    if let Some(expires) = document.get("expires") {
+        match expires.as_str() {
+            Some(timestamp) if Expires::try_new(timestamp).is_ok() => {}
+            Some(timestamp) => findings.push(Finding::new(
+                Severity::Error,
+                FindingKind::InvalidExpires,
+                "/expires",
+                format!("`expires` `{timestamp}` is not an RFC 3339 timestamp"),
+            )),
+            None => findings.push(invalid_type("/expires", "`expires` must be a string")),
+        }
+    }
+    if let Some(aliases) = document.get("aliases") {
+        match aliases.as_array() {
+            Some(aliases) => {
+                for (index, alias) in aliases.iter().enumerate() {
+                    let pointer = format!("/aliases/{index}");
+                    check_uri(alias, &pointer, "alias", &mut findings);
+                }
+            }
+            None => findings.push(invalid_type("/aliases", "`aliases` must be an array")),
+        }
+    }
+    if let Some(properties) = document.get("properties") {
+        check_properties(properties, "/properties", &mut findings);
+    }
+    if let Some(links) = document.get("links") {
+        match links.as_array() {
+            Some(links) => check_links(links, &mut findings),
+            None => findings.push(invalid_type("/links", "`links` must be an array")),
+        }
+    }
+    for name in document.keys() {
+        if !KNOWN_MEMBERS.contains(&name.as_str()) {
+            findings.push(Finding::new(
+                Severity::Info,
+                FindingKind::UnknownMember,
+                pointer_to(&[name]),
+                format!("unknown member `{name}` is ignored by clients"),
+            ));
+        }
+    }
+    findings
+}
+
+fn check_links(links: &[Value], findings: &mut Vec<Finding>) {
+    for (index, link) in links.iter().enumerate() {
+        let pointer = format!("/links/{index}");
+        let Some(members) = link.as_object() else {
+            findings.push(invalid_type(&pointer, "a link must be a JSON object"));
+            continue;
+        };
+        check_link(members, &pointer, findings);
+        if let Some(first) = links[..index].iter().position(|earlier| earlier == link) {
+            findings.push(Finding::new(
+                Severity::Warning,
+                FindingKind::DuplicateLink,
+                &pointer,
+                format!("link duplicates /links/{first}"),
+            ));
+        }
+    }
+}
+
+fn check_link(link: &Map<String, Value>, pointer: &str, findings: &mut Vec<Finding>) {
+    match link.get("rel") {
+        None => findings.push(Finding::new(
+            Severity::Error,
+            FindingKind::MissingRel,
+            pointer,
+            "link is missing `rel`",
+        )),
+        Some(Value::String(rel)) if Rel::try_new(rel).is_ok() => {}
+        Some(Value::String(rel)) => findings.push(Finding::new(
+            Severity::Error,
+            FindingKind::InvalidRel,
+            format!("{pointer}/rel"),
+            format!("`{rel}` is neither an absolute URI nor a registered relation type"),
+        )),
+        Some(_) => findings.push(invalid_type(
+            &format!("{pointer}/rel"),
+            "`rel` must be a string",
+        )),
+    }
+    if let Some(r#type) = link.get("type") {
+        let type_pointer = format!("{pointer}/type");
+        match r#type.as_str() {
+            Some(r#type) if MediaType::try_new(r#type).is_ok() => {}
+            Some(r#type) => findings.push(Finding::new(
+                Severity::Warning,
+                FindingKind::MalformedMediaType,
+                type_pointer,
+                format!("`{type}` is not a media type"),
+            )),
+            None => findings.push(invalid_type(&type_pointer, "`type` must be a string")),
+        }
+    }
+    if let Some(href) = link.get("href") {
+        let href_pointer = format!("{pointer}/href");
+        match href.as_str() {
+            Some(href) if is_absolute_uri(href) => {}
+            Some(href) => findings.push(Finding::new(
+                Severity::Error,
+                FindingKind::RelativeHref,
+                href_pointer,
+                format!("href `{href}` is not an absolute URI"),
+            )),
+            None => findings.push(invalid_type(&href_pointer, "`href` must be a string")),
+        }
+    }
+    if let Some(template) = link.get("template") {
+        if !template.is_string() {
+            findings.push(invalid_type(
+                &format!("{pointer}/template"),
+                "`template` must be a string",
+            ));
+        }
+        if link.contains_key("href") {
+            findings.push(Finding::new(
+                Severity::Error,
+                FindingKind::HrefAndTemplate,
+                pointer,
+                "link has both `href` and `template`",
+            ));
+        }
+    }
+    if let Some(titles) = link.get("titles") {
+        check_titles(titles, &format!("{pointer}/titles"), findings);
+    }
+    if let Some(properties) = link.get("properties") {
+        check_properties(properties, &format!("{pointer}/properties"), findings);
+    }
+}
+
+fn check_titles(titles: &Value, pointer: &str, findings: &mut Vec<Finding>) {
+    let Some(titles) = titles.as_object() else {
+        findings.push(invalid_type(pointer, "`titles` must be an object"));
+        return;
+    };
+    for (language, title) in titles {
+        let title_pointer = format!("{pointer}/{}", escape_pointer(language));
+        if !language.eq_ignore_ascii_case("und") && !is_language_tag(language) {
+            findings.push(Finding::new(
+                Severity::Warning,
+                FindingKind::InvalidTitleLanguage,
+                &title_pointer,
+                format!("title key `{language}` is not a BCP 47 language tag or `und`"),
+            ));
+        }
+        if !title.is_string() {
+            findings.push(invalid_type(&title_pointer, "a title must be a string"));
+        }
+    }
+}
+
+fn check_properties(properties: &Value, pointer: &str, findings: &mut Vec<Finding>) {
+    let Some(properties) = properties.as_object() else {
+        findings.push(invalid_type(pointer, "`properties` must be an object"));
+        return;
+    };
+    for (name, value) in properties {
+        let property_pointer = format!("{pointer}/{}", escape_pointer(name));
+        if !is_absolute_uri(name) {
+            findings.push(Finding::new(
+                Severity::Error,
+                FindingKind::NonUriPropertyKey,
+                &property_pointer,
+                format!("property name `{name}` is not an absolute URI"),
+            ));
+        }
+        if !(value.is_string() || value.is_null()) {
+            findings.push(Finding::new(
+                Severity::Error,
+                FindingKind::InvalidPropertyValue,
+                &property_pointer,
+                "a property value must be a string or null",
+            ));
+        }
+    }
+}
+
+fn check_uri(value: &Value, pointer: &str, what: &str, findings: &mut Vec<Finding>) {
+    match value.as_str() {
+        Some(uri) if is_absolute_uri(uri) => {}
+        Some(uri) => findings.push(Finding::new(
+            Severity::Error,
+            FindingKind::InvalidUri,
+            pointer,
+            format!("{what} `{uri}` is not an absolute URI"),
+        )),
+        None => findings.push(invalid_type(pointer, format!("{what} must be a string"))),
+    }
+}
+
+fn invalid_type(pointer: &str, message: impl Into<String>) -> Finding {
+    Finding::new(Severity::Error, FindingKind::InvalidType, pointer, message)
+}
+
+fn pointer_to(tokens: &[&str]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", escape_pointer(token)))
+        .collect()
+}
+
+/// Escapes a reference token as required by RFC 6901 section 3.
+pub(crate) fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Link;
+
+    fn kinds(findings: &[Finding]) -> Vec<(FindingKind, &str)> {
+        findings
+            .iter()
+            .map(|finding| (finding.kind, finding.pointer.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn conforming_document_has_no_findings() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "aliases": ["https://example.com/carol"],
+            "properties": {"http://example.com/ns/role": null},
+            "links": [
+                {
+                    "rel": "self",
+                    "type": "application/activity+json; charset=utf-8",
+                    "href": "https://example.com/users/carol",
+                    "titles": {"en-US": "Carol", "und": "Carol"},
+                },
+                {
+                    "rel": "http://ostatus.org/schema/1.0/subscribe",
+                    "template": "https://example.com/authorize?uri={uri}",
+                },
+            ],
+        });
+
+        assert_eq!(validate_json(&document.to_string()), vec![]);
+    }
+
+    #[test]
+    fn reports_invalid_json() {
+        let findings = validate_json("{");
+
+        assert_eq!(kinds(&findings), vec![(FindingKind::InvalidJson, "")]);
+        assert_eq!(findings[0].severity, Severity::Error);
+    }
+
+    /// RFC 7033 requires `subject` in every returned JRD.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.1>.
+    #[test]
+    fn reports_missing_subject() {
+        let findings = validate_json(r#"{"links": []}"#);
+
+        assert_eq!(kinds(&findings), vec![(FindingKind::MissingSubject, "")]);
+    }
+
+    #[test]
+    fn reports_relative_uris() {
+        let document = json!({
+            "subject": "carol",
+            "aliases": ["https://example.com/carol", "/carol"],
+            "links": [{"rel": "self", "href": "/users/carol"}],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::InvalidUri, "/subject"),
+                (FindingKind::InvalidUri, "/aliases/1"),
+                (FindingKind::RelativeHref, "/links/0/href"),
+            ],
+        );
+    }
+
+    /// RFC 6415 forbids a link from carrying both `href` and `template`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-3.1.1.1>.
+    #[test]
+    fn reports_href_and_template() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [{
+                "rel": "lrdd",
+                "href": "https://example.com/carol",
+                "template": "https://example.com/lookup?uri={uri}",
+            }],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![(FindingKind::HrefAndTemplate, "/links/0")],
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_links_against_first_occurrence() {
+        let link = json!({"rel": "self", "href": "https://example.com/carol"});
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [link, {"rel": "profile"}, link],
+        });
+
+        let findings = validate_json(&document.to_string());
+
+        assert_eq!(
+            kinds(&findings),
+            vec![(FindingKind::DuplicateLink, "/links/2")]
+        );
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].message, "link duplicates /links/0");
+    }
+
+    /// Title keys are BCP 47 language tags or `und`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.4.4>.
+    #[test]
+    fn reports_invalid_title_languages() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [{
+                "rel": "self",
+                "titles": {"en": "Carol", "e": "Carol", "en_US": "Carol"},
+            }],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::InvalidTitleLanguage, "/links/0/titles/e"),
+                (FindingKind::InvalidTitleLanguage, "/links/0/titles/en_US"),
+            ],
+        );
+    }
+
+    /// Property names contain `/`, which JSON pointers escape as `~1`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6901.html#section-3>.
+    #[test]
+    fn reports_property_problems_with_escaped_pointers() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "properties": {
+                "display-name": "Carol",
+                "http://example.com/ns/age": 42,
+            },
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::NonUriPropertyKey, "/properties/display-name"),
+                (
+                    FindingKind::InvalidPropertyValue,
+                    "/properties/http:~1~1example.com~1ns~1age"
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn reports_malformed_media_types() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [
+                {"rel": "self", "type": "activity+json"},
+                {"rel": "self", "type": "text/html; charset"},
+            ],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::MalformedMediaType, "/links/0/type"),
+                (FindingKind::MalformedMediaType, "/links/1/type"),
+            ],
+        );
+    }
+
+    /// RFC 6415 defines `expires`; a timestamp clients cannot parse is an error.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[test]
+    fn reports_invalid_expires() {
+        let valid = json!({"subject": "acct:carol@example.com", "expires": "2030-01-30T09:30:00Z"});
+        let invalid = json!({"subject": "acct:carol@example.com", "expires": "next week"});
+        let wrong_type = json!({"subject": "acct:carol@example.com", "expires": 1_900_000_000});
+
+        assert!(validate_json(&valid.to_string()).is_empty());
+        assert_eq!(
+            kinds(&validate_json(&invalid.to_string())),
+            vec![(FindingKind::InvalidExpires, "/expires")]
+        );
+        assert_eq!(
+            kinds(&validate_json(&wrong_type.to_string())),
+            vec![(FindingKind::InvalidType, "/expires")]
+        );
+    }
+
+    /// Clients ignore members RFC 7033 does not define, so these are informational.
+    #[test]
+    fn reports_unknown_members_as_info() {
+        let findings = validate_json(r#"{"subject": "acct:carol@example.com", "a/b": 1}"#);
+
+        assert_eq!(
+            kinds(&findings),
+            vec![(FindingKind::UnknownMember, "/a~1b")]
+        );
+        assert_eq!(findings[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn reports_invalid_rels() {
+        let document = json!({
+            "subject": "acct:carol@example.com",
+            "links": [{"rel": ""}, {"rel": "not a rel"}],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::InvalidRel, "/links/0/rel"),
+                (FindingKind::InvalidRel, "/links/1/rel"),
+            ],
+        );
+    }
+
+    #[test]
+    fn reports_wrong_json_types() {
+        let document = json!({
+            "subject": 1,
+            "aliases": "https://example.com/carol",
+            "links": [{"href": "https://example.com/carol"}, "self"],
+        });
+
+        assert_eq!(
+            kinds(&validate_json(&document.to_string())),
+            vec![
+                (FindingKind::InvalidType, "/subject"),
+                (FindingKind::InvalidType, "/aliases"),
+                (FindingKind::MissingRel, "/links/0"),
+                (FindingKind::InvalidType, "/links/1"),
+            ],
+        );
+        assert_eq!(
+            kinds(&validate_json("[]")),
+            vec![(FindingKind::InvalidType, "")]
+        );
+    }
+
+    #[test]
+    fn validates_typed_responses() {
+        let mut link = Link::builder("self")
+            .href("https://example.com/carol")
+            .template("https://example.com/lookup?uri={uri}")
+            .build();
+        // The builder rejects malformed languages, but the public field does not.
+        link.titles = Some([("en_US".to_string(), "Carol".to_string())].into());
+        let response = WebFingerResponse::builder("acct:carol@example.com")
+            .link(link)
+            .build();
+
+        assert_eq!(
+            kinds(&response.validate()),
+            vec![
+                (FindingKind::HrefAndTemplate, "/links/0"),
+                (FindingKind::InvalidTitleLanguage, "/links/0/titles/en_US"),
+            ],
+        );
+    }
+
+    #[test]
+    fn findings_display_severity_and_pointer() {
+        let finding = Finding::new(
+            Severity::Warning,
+            FindingKind::DuplicateLink,
+            "/links/1",
+            "link duplicates /links/0",
+        );
+
+        assert_eq!(
+            finding.to_string(),
+            "warning at /links/1: link duplicates /links/0"
+        );
+    }
+}
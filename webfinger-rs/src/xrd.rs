@@ -0,0 +1,493 @@
+//! XRD 1.0 representation of WebFinger responses.
+//!
+//! Enable the `xrd` feature to render a [`WebFingerResponse`] as an Extensible Resource Descriptor
+//! (XRD) document with [`WebFingerResponse::to_xrd`], or to parse one with
+//! [`WebFingerResponse::from_xrd`]. XRD is the XML format that [RFC 6415] host-meta and older
+//! OStatus and diaspora peers use instead of JRD. RFC 6415 appendix A defines the JRD mapping used
+//! here:
+//!
+//! | JRD                        | XRD                                            |
+//! | -------------------------- | ---------------------------------------------- |
+//! | `expires`                  | `<Expires>`                                    |
+//! | `subject`                  | `<Subject>`                                    |
+//! | `aliases`                  | one `<Alias>` per alias                        |
+//! | `properties`               | `<Property type="...">`, `xsi:nil` for `null`  |
+//! | `links`                    | `<Link rel type href template>`                |
+//! | link `titles`              | `<Title xml:lang="...">`, no `xml:lang` for `und` |
+//!
+//! The mapping is lossless for responses whose optional members are either absent or non-empty:
+//! XRD cannot tell an empty `aliases` array or `properties` object apart from a missing one, so
+//! parsing produces `None` for both. JRD extension members have no XRD form and are not written.
+//! Parsing ignores XRD elements with no JRD equivalent, such as signatures.
+//!
+//! [RFC 6415]: https://www.rfc-editor.org/rfc/rfc6415.html
+//! [`WebFingerResponse`]: crate::WebFingerResponse
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use roxmltree::{Document, Node};
+
+use crate::{Expires, Extensions, JrdUri, Link, MediaType, Rel, WebFingerResponse};
+
+/// The `application/xrd+xml` media type for XRD documents.
+pub const XRD_CONTENT_TYPE: &str = "application/xrd+xml";
+
+/// The XRD 1.0 XML namespace.
+pub(crate) const XRD_NAMESPACE: &str = "http://docs.oasis-open.org/ns/xri/xrd-1.0";
+
+/// The XML Schema instance namespace, used for `xsi:nil` property values.
+pub(crate) const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// The namespace of the predefined `xml:` attribute prefix.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The JRD title language key for titles without a language, written without `xml:lang` in XRD.
+pub(crate) const UNDEFINED_LANGUAGE: &str = "und";
+
+/// Error type for parsing XRD documents with [`WebFingerResponse::from_xrd`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum XrdError {
+    /// The input is not well-formed XML.
+    #[error("malformed XML: {0}")]
+    Xml(String),
+
+    /// The root element is not an `XRD` element in the XRD 1.0 namespace.
+    #[error("root element is not an XRD 1.0 document")]
+    NotXrd,
+
+    /// The document has no `Subject` element, which a WebFinger response requires.
+    #[error("missing Subject element")]
+    MissingSubject,
+
+    /// A `Link` element has no `rel` attribute.
+    #[error("Link element is missing the rel attribute")]
+    MissingRel,
+
+    /// A `Property` element has no `type` attribute.
+    #[error("Property element is missing the type attribute")]
+    MissingPropertyType,
+
+    /// A subject, alias, relation type, link type or target, or property type is not valid JRD.
+    #[error(transparent)]
+    Invalid(#[from] crate::Error),
+}
+
+impl WebFingerResponse {
+    /// Renders this response as an XRD 1.0 XML document.
+    ///
+    /// The mapping follows [RFC 6415 appendix A]. Properties with a `None` value are written with
+    /// `xsi:nil="true"`, and titles whose language is `und` are written without `xml:lang`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::{Link, WebFingerResponse};
+    ///
+    /// let response = WebFingerResponse::builder("acct:carol@example.com")
+    ///     .alias("https://example.com/profile/carol")
+    ///     .link(Link::builder("http://webfinger.net/rel/profile-page").href("https://example.com/carol"))
+    ///     .build();
+    ///
+    /// let xrd = response.to_xrd();
+    /// assert!(xrd.contains("<Subject>acct:carol@example.com</Subject>"));
+    /// assert!(xrd.contains(r#"<Link rel="http://webfinger.net/rel/profile-page""#));
+    /// ```
+    ///
+    /// [RFC 6415 appendix A]: https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A
+    pub fn to_xrd(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        xml.push('\n');
+        let _ = writeln!(
+            xml,
+            r#"<XRD xmlns="{XRD_NAMESPACE}" xmlns:xsi="{XSI_NAMESPACE}">"#
+        );
+        if let Some(expires) = &self.expires {
+            let _ = writeln!(xml, "  <Expires>{}</Expires>", escape(expires.as_ref()));
+        }
+        let _ = writeln!(
+            xml,
+            "  <Subject>{}</Subject>",
+            escape(self.subject.as_ref())
+        );
+        for alias in self.aliases.iter().flatten() {
+            let _ = writeln!(xml, "  <Alias>{}</Alias>", escape(alias.as_ref()));
+        }
+        write_properties(&mut xml, "  ", self.properties.as_ref());
+        for link in &self.links {
+            write_link(&mut xml, link);
+        }
+        xml.push_str("</XRD>\n");
+        xml
+    }
+
+    /// Parses an XRD 1.0 XML document into a response.
+    ///
+    /// This is the inverse of [`WebFingerResponse::to_xrd`]. `Property` elements with
+    /// `xsi:nil="true"` become `None` values, and `Title` elements without `xml:lang` use the `und`
+    /// language key. Subjects, aliases, and link targets are validated like their JRD equivalents.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`XrdError`] if the input is not well-formed XML, is not an XRD document, lacks a
+    /// `Subject`, or contains values that are not valid JRD.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use webfinger_rs::WebFingerResponse;
+    ///
+    /// let xrd = r#"<?xml version="1.0" encoding="UTF-8"?>
+    /// <XRD xmlns="http://docs.oasis-open.org/ns/xri/xrd-1.0">
+    ///   <Subject>acct:carol@example.com</Subject>
+    ///   <Link rel="http://webfinger.net/rel/profile-page" href="https://example.com/carol"/>
+    /// </XRD>"#;
+    ///
+    /// let response = WebFingerResponse::from_xrd(xrd)?;
+    /// assert_eq!(response.subject.as_ref(), "acct:carol@example.com");
+    /// assert_eq!(response.links.len(), 1);
+    /// # Ok::<(), webfinger_rs::XrdError>(())
+    /// ```
+    pub fn from_xrd(xml: &str) -> Result<Self, XrdError> {
+        let document = Document::parse(xml).map_err(|error| XrdError::Xml(error.to_string()))?;
+        let root = document.root_element();
+        if !is_xrd_element(root, "XRD") {
+            return Err(XrdError::NotXrd);
+        }
+
+        let subject = xrd_children(root, "Subject")
+            .next()
+            .ok_or(XrdError::MissingSubject)?;
+        let subject = JrdUri::try_new(text(subject).trim())?;
+        let expires = xrd_children(root, "Expires")
+            .next()
+            .map(|expires| Expires::try_new(text(expires).trim()))
+            .transpose()?;
+        let aliases = xrd_children(root, "Alias")
+            .map(|alias| JrdUri::try_new(text(alias).trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let properties = parse_properties(root)?;
+        let links = xrd_children(root, "Link")
+            .map(parse_link)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(WebFingerResponse {
+            subject,
+            aliases: (!aliases.is_empty()).then_some(aliases),
+            properties,
+            links,
+            expires,
+            extensions: Extensions::new(),
+        })
+    }
+}
+
+fn parse_link(node: Node<'_, '_>) -> Result<Link, XrdError> {
+    let rel = node.attribute("rel").ok_or(XrdError::MissingRel)?;
+    let mut link = Link::new(Rel::try_new(rel)?);
+    link.r#type = node.attribute("type").map(MediaType::try_new).transpose()?;
+    link.href = node.attribute("href").map(JrdUri::try_new).transpose()?;
+    link.template = node.attribute("template").map(str::to_string);
+
+    let titles = xrd_children(node, "Title")
+        .map(|title| {
+            let language = title
+                .attribute((XML_NAMESPACE, "lang"))
+                .unwrap_or(UNDEFINED_LANGUAGE);
+            (language.to_string(), text(title))
+        })
+        .collect::<BTreeMap<_, _>>();
+    link.titles = (!titles.is_empty()).then_some(titles);
+    link.properties = parse_properties(node)?;
+    Ok(link)
+}
+
+fn parse_properties(
+    node: Node<'_, '_>,
+) -> Result<Option<BTreeMap<JrdUri, Option<String>>>, XrdError> {
+    let properties = xrd_children(node, "Property")
+        .map(|property| {
+            let key = property
+                .attribute("type")
+                .ok_or(XrdError::MissingPropertyType)?;
+            let nil = matches!(
+                property.attribute((XSI_NAMESPACE, "nil")),
+                Some("true" | "1")
+            );
+            let value = (!nil).then(|| text(property));
+            Ok((JrdUri::try_new(key)?, value))
+        })
+        .collect::<Result<BTreeMap<_, _>, XrdError>>()?;
+    Ok((!properties.is_empty()).then_some(properties))
+}
+
+/// Returns the child elements of `node` with the given local name in the XRD namespace.
+fn xrd_children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| is_xrd_element(*child, name))
+}
+
+fn is_xrd_element(node: Node<'_, '_>, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().namespace() == Some(XRD_NAMESPACE)
+        && node.tag_name().name() == name
+}
+
+/// Returns the character data of an element, including text split by comments or CDATA sections.
+fn text(node: Node<'_, '_>) -> String {
+    node.children()
+        .filter(Node::is_text)
+        .filter_map(|child| child.text())
+        .collect()
+}
+
+fn write_link(xml: &mut String, link: &Link) {
+    let _ = write!(xml, r#"  <Link rel="{}""#, escape(link.rel.as_ref()));
+    if let Some(r#type) = &link.r#type {
+        let _ = write!(xml, r#" type="{}""#, escape(r#type.as_ref()));
+    }
+    if let Some(href) = &link.href {
+        let _ = write!(xml, r#" href="{}""#, escape(href.as_ref()));
+    }
+    if let Some(template) = &link.template {
+        let _ = write!(xml, r#" template="{}""#, escape(template));
+    }
+    let titles = link.titles.as_ref().filter(|titles| !titles.is_empty());
+    let properties = link.properties.as_ref().filter(|props| !props.is_empty());
+    if titles.is_none() && properties.is_none() {
+        xml.push_str("/>\n");
+        return;
+    }
+
+    xml.push_str(">\n");
+    for (language, title) in titles.into_iter().flatten() {
+        if language == UNDEFINED_LANGUAGE {
+            let _ = writeln!(xml, "    <Title>{}</Title>", escape(title));
+        } else {
+            let _ = writeln!(
+                xml,
+                r#"    <Title xml:lang="{}">{}</Title>"#,
+                escape(language),
+                escape(title)
+            );
+        }
+    }
+    write_properties(xml, "    ", properties);
+    xml.push_str("  </Link>\n");
+}
+
+fn write_properties(
+    xml: &mut String,
+    indent: &str,
+    properties: Option<&BTreeMap<JrdUri, Option<String>>>,
+) {
+    for (key, value) in properties.into_iter().flatten() {
+        let key = escape(key.as_ref());
+        match value {
+            Some(value) => {
+                let _ = writeln!(
+                    xml,
+                    r#"{indent}<Property type="{key}">{}</Property>"#,
+                    escape(value)
+                );
+            }
+            None => {
+                let _ = writeln!(xml, r#"{indent}<Property type="{key}" xsi:nil="true"/>"#);
+            }
+        }
+    }
+}
+
+/// Escapes text for use in XML character data and double-quoted attribute values.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Link;
+
+    type Result<T = (), E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+    /// Writes every JRD member using the RFC 6415 appendix A element and attribute names.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[test]
+    fn renders_rfc_6415_mapping() {
+        let response = WebFingerResponse::builder("acct:carol@example.com")
+            .alias("https://example.com/profile/carol")
+            .property("https://example.com/ns/role", "developer")
+            .null_property("https://example.com/ns/old-role")
+            .link(
+                Link::builder("http://webfinger.net/rel/profile-page")
+                    .r#type("text/html")
+                    .href("https://example.com/profile/carol")
+                    .title("en-us", "Carol's Profile")
+                    .title("und", "Carol")
+                    .null_property("https://example.com/ns/legacy"),
+            )
+            .link(
+                Link::builder("http://ostatus.org/schema/1.0/subscribe")
+                    .template("https://example.com/follow?uri={uri}"),
+            )
+            .build();
+
+        assert_eq!(
+            response.to_xrd(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<XRD xmlns="http://docs.oasis-open.org/ns/xri/xrd-1.0" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <Subject>acct:carol@example.com</Subject>
+  <Alias>https://example.com/profile/carol</Alias>
+  <Property type="https://example.com/ns/old-role" xsi:nil="true"/>
+  <Property type="https://example.com/ns/role">developer</Property>
+  <Link rel="http://webfinger.net/rel/profile-page" type="text/html" href="https://example.com/profile/carol">
+    <Title xml:lang="en-us">Carol&apos;s Profile</Title>
+    <Title>Carol</Title>
+    <Property type="https://example.com/ns/legacy" xsi:nil="true"/>
+  </Link>
+  <Link rel="http://ostatus.org/schema/1.0/subscribe" template="https://example.com/follow?uri={uri}"/>
+</XRD>
+"#
+        );
+    }
+
+    #[test]
+    fn escapes_markup_in_text_and_attributes() {
+        let response = WebFingerResponse::builder("https://example.com/?a=1&b=2")
+            .property("https://example.com/ns/bio", "<b>Carol</b> & friends")
+            .link(Link::builder("self").template(r#"https://example.com/"{uri}""#))
+            .build();
+
+        let xrd = response.to_xrd();
+
+        assert!(xrd.contains("<Subject>https://example.com/?a=1&amp;b=2</Subject>"));
+        assert!(xrd.contains(">&lt;b&gt;Carol&lt;/b&gt; &amp; friends</Property>"));
+        assert!(xrd.contains(r#"template="https://example.com/&quot;{uri}&quot;""#));
+    }
+
+    /// Converting a JRD document to XRD and back yields the same response and the same JSON.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[test]
+    fn jrd_round_trips_through_xrd() -> Result {
+        let jrd = r#"{
+            "subject": "acct:carol@example.com",
+            "expires": "2030-01-30T09:30:00+01:00",
+            "aliases": ["https://example.com/profile/carol", "https://example.com/~carol"],
+            "properties": {
+                "https://example.com/ns/role": "developer",
+                "https://example.com/ns/old-role": null
+            },
+            "links": [
+                {
+                    "rel": "http://webfinger.net/rel/profile-page",
+                    "type": "text/html",
+                    "href": "https://example.com/profile/carol",
+                    "titles": {"en-us": "Carol's <Profile>", "und": "Carol & co"},
+                    "properties": {"https://example.com/ns/legacy": null}
+                },
+                {
+                    "rel": "http://ostatus.org/schema/1.0/subscribe",
+                    "template": "https://example.com/follow?uri={uri}&x=\"y\""
+                },
+                {"rel": "self"}
+            ]
+        }"#;
+        let response: WebFingerResponse = serde_json::from_str(jrd)?;
+
+        let parsed = WebFingerResponse::from_xrd(&response.to_xrd())?;
+
+        assert_eq!(parsed, response);
+        assert_eq!(
+            serde_json::to_value(&parsed)?,
+            serde_json::from_str::<serde_json::Value>(jrd)?
+        );
+        Ok(())
+    }
+
+    /// Reads XRD documents written by other implementations, including prefixed namespaces,
+    /// surrounding whitespace, and elements with no JRD equivalent.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-3.1>.
+    #[test]
+    fn parses_foreign_xrd_document() -> Result {
+        let xrd = r#"<?xml version='1.0' encoding='UTF-8'?>
+<xrd:XRD xmlns:xrd="http://docs.oasis-open.org/ns/xri/xrd-1.0"
+         xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <xrd:Expires>2030-01-30T09:30:00Z</xrd:Expires>
+  <xrd:Subject>
+    acct:carol@example.com
+  </xrd:Subject>
+  <xrd:Property type="https://example.com/ns/flag" xsi:nil="1"/>
+  <xrd:Property type="https://example.com/ns/empty"/>
+  <xrd:Link rel="lrdd" type="application/xrd+xml" template="https://example.com/lrdd?uri={uri}">
+    <xrd:Title xml:lang="fr">Profil de <![CDATA[Carol]]></xrd:Title>
+  </xrd:Link>
+</xrd:XRD>"#;
+
+        let response = WebFingerResponse::from_xrd(xrd)?;
+
+        let expected = WebFingerResponse::builder("acct:carol@example.com")
+            .expires(Expires::new("2030-01-30T09:30:00Z"))
+            .null_property("https://example.com/ns/flag")
+            .property("https://example.com/ns/empty", "")
+            .link(
+                Link::builder("lrdd")
+                    .r#type("application/xrd+xml")
+                    .template("https://example.com/lrdd?uri={uri}")
+                    .title("fr", "Profil de Carol"),
+            )
+            .build();
+        assert_eq!(response, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_xrd() {
+        let error = WebFingerResponse::from_xrd("<html><body/></html>").unwrap_err();
+        assert!(matches!(error, XrdError::NotXrd), "{error:?}");
+
+        let error = WebFingerResponse::from_xrd("<XRD>").unwrap_err();
+        assert!(matches!(error, XrdError::Xml(_)), "{error:?}");
+    }
+
+    /// A WebFinger response must identify its subject, and the subject must be an absolute URI.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.4.1>.
+    #[test]
+    fn rejects_missing_or_relative_subject() {
+        let missing = format!(r#"<XRD xmlns="{XRD_NAMESPACE}"><Alias>acct:a@b</Alias></XRD>"#);
+        let error = WebFingerResponse::from_xrd(&missing).unwrap_err();
+        assert!(matches!(error, XrdError::MissingSubject), "{error:?}");
+
+        let relative = format!(r#"<XRD xmlns="{XRD_NAMESPACE}"><Subject>/carol</Subject></XRD>"#);
+        let error = WebFingerResponse::from_xrd(&relative).unwrap_err();
+        assert!(matches!(error, XrdError::Invalid(_)), "{error:?}");
+    }
+
+    #[test]
+    fn rejects_links_without_rel() {
+        let xrd = format!(
+            r#"<XRD xmlns="{XRD_NAMESPACE}"><Subject>acct:a@b</Subject><Link href="https://b/"/></XRD>"#
+        );
+        let error = WebFingerResponse::from_xrd(&xrd).unwrap_err();
+        assert!(matches!(error, XrdError::MissingRel), "{error:?}");
+    }
+}
@@ -5,36 +5,48 @@
 //! simple native deployments, and tests that need the same HTTP mapping as the Cloudflare Worker
 //! without running Wrangler.
 //!
-//! The router accepts only `GET /.well-known/webfinger`, plus `GET /health` for local health
-//! checks. It maps malformed WebFinger queries to `400`, unknown resources to `404`, unsupported
-//! methods to `405`, and successful responses to `application/jrd+json`.
+//! The router accepts `GET`, `HEAD`, and CORS preflight `OPTIONS` requests for
+//! `/.well-known/webfinger` and the RFC 6415 host-meta documents `/.well-known/host-meta` (XRD) and
+//! `/.well-known/host-meta.json` (JSON), NodeInfo discovery at `/.well-known/nodeinfo` with its
+//! `/nodeinfo/2.x` schema documents, the ActivityPub actors of configured resources at the paths
+//! of their `id`s, plus `GET /health` for local health checks. WebFinger
+//! handling comes from [`webfinger_service::tower::WebFingerService`]: it maps malformed WebFinger
+//! queries to `400`, unknown resources to `404`, unsupported methods to `405`, provider redirects
+//! to `307` or `308` and removed resources to `410`, and successful responses to
+//! `application/jrd+json`.
 
-use std::convert::Infallible;
+use std::net::SocketAddr;
 
-use axum::body::Body;
-use axum::extract::FromRequestParts;
-use axum::http::{Method, Request, StatusCode, header};
-use axum::response::{IntoResponse, Response};
-use tower::service_fn;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::Method;
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::get;
 use tower_http::trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer};
 use tracing::{Level, info};
-use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest};
-use webfinger_service::StaticConfigProvider;
+use webfinger_service::tower::WebFingerService;
+use webfinger_service::{RemoteAddr, StaticConfigProvider};
 
 /// Builds a native Axum router for a static configuration provider.
 ///
-/// The returned router uses a fallback service so it can make method and path decisions in one
-/// place. It also installs a Tower HTTP trace layer; configure `tracing-subscriber` in the binary
-/// or test harness to see request and response logs.
+/// The router serves `/health` directly and hands every other request to
+/// [`WebFingerService`], which owns WebFinger path, method, query, and status code handling, and
+/// serves host-meta with the config's `[host_meta]` links, NodeInfo from its `[nodeinfo]` table,
+/// and resource actors at their paths. It also installs a Tower HTTP trace layer; configure
+/// `tracing-subscriber` in the binary or test harness to see request and response logs.
+///
+/// Serve the router with `into_make_service_with_connect_info::<SocketAddr>()` to pass the client
+/// address to the provider's [`webfinger_service::RequestContext`].
 pub fn axum_router(provider: StaticConfigProvider) -> axum::Router {
     axum::Router::new()
-        .fallback_service(service_fn(move |request: Request<Body>| {
-            let provider = provider.clone();
-            async move {
-                let response = serve_static_http(provider, request).await;
-                Ok::<_, Infallible>(response)
-            }
-        }))
+        .route("/health", get(health))
+        .fallback_service(
+            WebFingerService::new(provider)
+                .with_host_meta(true)
+                .with_nodeinfo(true)
+                .with_actors(true),
+        )
+        .layer(middleware::from_fn(remote_addr))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
@@ -43,54 +55,29 @@ pub fn axum_router(provider: StaticConfigProvider) -> axum::Router {
         )
 }
 
-async fn serve_static_http<B>(provider: StaticConfigProvider, request: Request<B>) -> Response {
-    let method = request.method().clone();
-    let path = request.uri().path().to_string();
-    if method != Method::GET {
-        log_webfinger_request(&method, &path, "method_not_allowed");
-        return (
-            StatusCode::METHOD_NOT_ALLOWED,
-            [(header::ALLOW, Method::GET.as_str())],
-            "method not allowed",
-        )
-            .into_response();
-    }
-    if path == "/health" {
-        log_webfinger_request(&method, &path, "health");
-        return "OK".into_response();
-    }
-    if path != WELL_KNOWN_PATH {
-        log_webfinger_request(&method, &path, "not_found");
-        return (StatusCode::NOT_FOUND, "not found").into_response();
-    }
-
-    let (mut parts, _body) = request.into_parts();
-    let request = match WebFingerRequest::from_request_parts(&mut parts, &()).await {
-        Ok(request) => {
-            log_webfinger_request(&method, &path, "lookup");
-            request
-        }
-        Err(rejection) => {
-            log_webfinger_request(&method, &path, "bad_request");
-            return rejection.into_response();
-        }
-    };
-    match provider.resolve_config(&request) {
-        Some(response) => response.into_response(),
-        None => (StatusCode::NOT_FOUND, "resource not found").into_response(),
+/// Copies the peer address from Axum's connection info into the extension the WebFinger service
+/// reads for its request context.
+async fn remote_addr(mut request: Request, next: Next) -> Response {
+    if let Some(ConnectInfo(address)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        let address = RemoteAddr(address.ip());
+        request.extensions_mut().insert(address);
     }
+    next.run(request).await
 }
 
-fn log_webfinger_request(method: &Method, path: &str, outcome: &str) {
-    info!(method = %method, path, outcome, "webfinger service request");
+async fn health() -> &'static str {
+    info!(method = %Method::GET, path = "/health", outcome = "health", "webfinger service request");
+    "OK"
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::body::to_bytes;
-    use axum::http::header;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{StatusCode, header};
+    use axum::response::Response;
     use http::Request;
+    use tower::ServiceExt;
     use webfinger_rs::WebFingerResponse;
     use webfinger_service::EXAMPLE_CONFIG;
 
@@ -111,10 +98,13 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = serve_static_http(provider, request).await;
+        let response = axum_router(provider).oneshot(request).await.unwrap();
 
         assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
-        assert_eq!(response.headers().get(header::ALLOW).unwrap(), "GET");
+        assert_eq!(
+            response.headers().get(header::ALLOW).unwrap(),
+            "GET, HEAD, OPTIONS"
+        );
     }
 
     #[tokio::test]
@@ -137,7 +127,151 @@ mod tests {
         assert_eq!(response.subject.as_ref(), "acct:alice@example.com");
     }
 
-    async fn call(uri: &str) -> axum::response::Response {
+    #[tokio::test]
+    async fn answers_cors_preflight() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/.well-known/webfinger")
+            .header(header::ORIGIN, "https://app.example")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = axum_router(provider).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+                .unwrap(),
+            "GET, HEAD, OPTIONS",
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_host_meta_with_lrdd_template() {
+        let response = call("/.well-known/host-meta.json").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document: WebFingerResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            document.links[0].template.as_deref(),
+            Some("https://example.com/.well-known/webfinger?resource={uri}"),
+        );
+    }
+
+    #[tokio::test]
+    async fn nodeinfo_without_config_is_not_found() {
+        let response = call("/.well-known/nodeinfo").await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "*"
+        );
+    }
+
+    /// A WebFinger lookup leads an ActivityPub client to the actor through the JRD `self` link.
+    #[tokio::test]
+    async fn webfinger_self_link_resolves_to_actor() {
+        let router = axum_router(
+            StaticConfigProvider::from_toml(
+                r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[resources.actor]
+id = "https://example.com/users/alice"
+also_known_as = ["https://social.example/users/alice"]
+moved_to = "https://social.example/users/alice"
+"#,
+            )
+            .unwrap(),
+        );
+        let lookup = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com&rel=self")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.clone().oneshot(lookup).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let jrd: WebFingerResponse = serde_json::from_slice(&body).unwrap();
+        let link = &jrd.links[0];
+        assert_eq!(
+            link.r#type.as_ref().unwrap().as_ref(),
+            "application/activity+json"
+        );
+        let href = link
+            .href
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .parse::<http::Uri>()
+            .unwrap();
+        let fetch = Request::builder()
+            .uri(href.path())
+            .header("host", "example.com")
+            .header(header::ACCEPT, "application/activity+json")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.oneshot(fetch).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/activity+json"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let actor: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(actor["id"], "https://example.com/users/alice");
+        assert_eq!(actor["preferredUsername"], "alice");
+        assert_eq!(
+            actor["alsoKnownAs"],
+            serde_json::json!(["https://social.example/users/alice"])
+        );
+        assert_eq!(actor["movedTo"], "https://social.example/users/alice");
+    }
+
+    #[tokio::test]
+    async fn serves_health_check() {
+        let response = call("/health").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None,
+        );
+    }
+
+    #[tokio::test]
+    async fn copies_connect_info_into_remote_addr() {
+        let router = axum::Router::new()
+            .route(
+                "/",
+                get(|request: Request<Body>| async move {
+                    let RemoteAddr(address) = request.extensions().get::<RemoteAddr>().unwrap();
+                    address.to_string()
+                }),
+            )
+            .layer(middleware::from_fn(remote_addr));
+        let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let peer: SocketAddr = "203.0.113.7:40000".parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+
+        let response = router.oneshot(request).await.unwrap();
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "203.0.113.7");
+    }
+
+    async fn call(uri: &str) -> Response {
         let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
         let request = Request::builder()
             .uri(uri)
@@ -145,6 +279,6 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        serve_static_http(provider, request).await
+        axum_router(provider).oneshot(request).await.unwrap()
     }
 }
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use clap::Parser;
+use webfinger_service::ConfigError;
 
 const DEFAULT_CONFIG_FILE: &str = "webfinger-service/webfinger.toml";
 const EXAMPLE_CONFIG_FILE: &str = "webfinger-service/webfinger.example.toml";
@@ -10,7 +11,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     use std::net::SocketAddr;
 
     use tracing::info;
-    use webfinger_service::StaticConfigProvider;
+    use webfinger_service::{Config, StaticConfigProvider};
     use webfinger_service_axum::axum_router;
 
     let _ = tracing_subscriber::fmt()
@@ -21,8 +22,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
     let config_path = args.config_path();
-    let config = read_config(config_path)?;
-    let provider = StaticConfigProvider::from_toml(&config)?;
+    let config =
+        Config::from_path(config_path).map_err(|error| config_error(config_path, &error))?;
+    let provider = StaticConfigProvider::new(config);
 
     let addr: SocketAddr = format!("{}:{}", args.host, args.port).parse()?;
     let listener = tokio::net::TcpListener::bind(addr)
@@ -34,14 +36,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config_path.display()
     );
     info!(%addr, config = %config_path.display(), "serving webfinger service");
-    axum::serve(listener, axum_router(provider)).await?;
+    axum::serve(
+        listener,
+        axum_router(provider).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 struct Args {
-    /// Configuration TOML file to serve.
+    /// Configuration file or directory to serve: TOML, JSON, or YAML by extension, or a directory
+    /// of `*.toml` configs and `*.json` JRDs. `${NAME}` references are read from the environment.
     #[arg(
         long,
         env = "WEBFINGER_CONFIG_FILE",
@@ -73,13 +80,27 @@ impl Args {
     }
 }
 
-fn read_config(path: &Path) -> Result<String, CliError> {
-    std::fs::read_to_string(path).map_err(|error| {
-        CliError(format!(
-            "could not read WebFinger config from {}: {error}",
+fn config_error(path: &Path, error: &ConfigError) -> CliError {
+    let (path, error) = match error {
+        ConfigError::Io { .. } => {
+            return CliError(format!("could not load WebFinger config: {error}"));
+        }
+        ConfigError::File { path, error } => (path.as_path(), &**error),
+        error => (path, error),
+    };
+    match error
+        .location()
+        .and_then(|location| location.line().zip(location.column()))
+    {
+        Some((line, column)) => CliError(format!(
+            "invalid WebFinger config at {}:{line}:{column}\n{error}",
             path.display()
-        ))
-    })
+        )),
+        None => CliError(format!(
+            "invalid WebFinger config in {}\n{error}",
+            path.display()
+        )),
+    }
 }
 
 fn bind_error(addr: std::net::SocketAddr, error: std::io::Error) -> CliError {
@@ -133,6 +154,19 @@ mod tests {
         assert!(message.contains("PORT=<free-port>"));
     }
 
+    #[test]
+    fn config_error_names_file_position_and_value() {
+        let error = webfinger_service::Config::from_toml(
+            "[[resources]]\nresource = \"acct:alice@example.com\"\naliases = [\"not a uri\"]\n",
+        )
+        .unwrap_err();
+
+        let message = config_error(Path::new("webfinger.toml"), &error).to_string();
+        assert!(message.starts_with("invalid WebFinger config at webfinger.toml:3:12\n"));
+        assert!(message.contains("`resources[0].aliases[0]`"));
+        assert!(message.contains("3 | aliases = [\"not a uri\"]"));
+    }
+
     #[test]
     fn empty_default_config_parses() {
         webfinger_service::StaticConfigProvider::from_toml(include_str!(
@@ -1,6 +1,13 @@
-use webfinger_rs::{WebFingerRequest, WebFingerResponse};
-use webfinger_service::{ProviderError, WEBFINGER_CONFIG_KEY, WebFingerProvider};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use webfinger_rs::{CachePolicy, Link, WebFingerRequest, WebFingerResponse};
+use webfinger_service::{
+    Actor, Config, NodeInfo, ProviderError, RequestContext, Resolution, WEBFINGER_CONFIG_KEY,
+    WebFingerProvider,
+};
 use worker::Env;
+use worker::send::SendFuture;
 
 /// The default Workers KV binding name for WebFinger configuration.
 ///
@@ -15,6 +22,10 @@ pub const WEBFINGER_CONFIG_BINDING: &str = "WEBFINGER_CONFIG";
 /// Cloudflare dashboard KV editor as the source of truth: changing the `webfinger.toml` value
 /// updates future requests without rebuilding the Worker.
 ///
+/// [`WebFingerProvider::resolution`] attaches the `[cache]` and per-resource `max_age` values of
+/// the config it read. The most recently parsed config is also kept so
+/// [`WebFingerProvider::cache_policy`] can report them for the lookup it follows.
+///
 /// Use [`KvConfigProvider::from_env`] for the conventional `WEBFINGER_CONFIG` binding and
 /// `webfinger.toml` key. Use [`KvConfigProvider::from_env_binding`] when embedding this provider in
 /// a Worker with different binding or key names.
@@ -22,6 +33,7 @@ pub const WEBFINGER_CONFIG_BINDING: &str = "WEBFINGER_CONFIG";
 pub struct KvConfigProvider {
     kv: worker::kv::KvStore,
     key: String,
+    last_config: Arc<Mutex<Option<Config>>>,
 }
 
 impl std::fmt::Debug for KvConfigProvider {
@@ -61,15 +73,109 @@ impl KvConfigProvider {
         Ok(Self {
             kv,
             key: key.to_string(),
+            last_config: Arc::default(),
         })
     }
 }
 
 impl WebFingerProvider for KvConfigProvider {
-    async fn resolve<'a>(
+    /// Reads and parses the configured KV key, then resolves the request against it.
+    ///
+    /// KV futures are not `Send`. Workers run on a single thread, so the lookup is wrapped in
+    /// [`SendFuture`] to satisfy the [`WebFingerProvider`] bound.
+    fn resolve<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+    ) -> impl Future<Output = Result<Option<WebFingerResponse>, ProviderError>> + Send + 'a {
+        SendFuture::new(self.resolve_kv(request))
+    }
+
+    /// Returns the cache policy from the config read by the preceding lookup.
+    fn cache_policy(&self, request: &WebFingerRequest) -> Option<CachePolicy> {
+        let last_config = self.last_config.lock().ok()?;
+        last_config.as_ref()?.cache_policy(request)
+    }
+
+    /// Reads the configured KV key once and resolves the request and its cache policy against it.
+    fn resolution<'a>(
         &'a self,
         request: &'a WebFingerRequest,
+    ) -> impl Future<Output = Result<Resolution, ProviderError>> + Send + 'a {
+        SendFuture::new(async move {
+            self.resolution_kv(request, &RequestContext::default())
+                .await
+        })
+    }
+
+    /// Like [`resolution`](Self::resolution), but discloses token-only resources and links when
+    /// the context's bearer token matches the config, and keeps the context's query string in
+    /// redirects.
+    fn resolve_with_context<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+        context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Resolution, ProviderError>> + Send + 'a {
+        SendFuture::new(self.resolution_kv(request, context))
+    }
+
+    /// Reads the configured KV key and returns its `[host_meta]` links.
+    fn host_meta_links<'a>(
+        &'a self,
+        context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Vec<Link>, ProviderError>> + Send + 'a {
+        SendFuture::new(async move {
+            let config = self.read_config().await?;
+            Ok(config.host_meta_links(context.bearer_token()))
+        })
+    }
+
+    /// Reads the configured KV key and returns its `[nodeinfo]` table.
+    fn nodeinfo<'a>(
+        &'a self,
+        _context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Option<NodeInfo>, ProviderError>> + Send + 'a {
+        SendFuture::new(async move {
+            let config = self.read_config().await?;
+            Ok(config.nodeinfo().cloned())
+        })
+    }
+
+    /// Reads the configured KV key and returns the actor at `path` the context may see.
+    fn actor<'a>(
+        &'a self,
+        path: &'a str,
+        context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Option<Actor>, ProviderError>> + Send + 'a {
+        SendFuture::new(async move {
+            let config = self.read_config().await?;
+            Ok(config.actor(path, context.bearer_token()).cloned())
+        })
+    }
+}
+
+impl KvConfigProvider {
+    async fn resolve_kv(
+        &self,
+        request: &WebFingerRequest,
     ) -> Result<Option<WebFingerResponse>, ProviderError> {
+        let config = self.read_config().await?;
+        let response = config.resolve(request);
+        self.remember(config);
+        Ok(response)
+    }
+
+    async fn resolution_kv(
+        &self,
+        request: &WebFingerRequest,
+        context: &RequestContext,
+    ) -> Result<Resolution, ProviderError> {
+        let config = self.read_config().await?;
+        let resolution = config.resolution(request, context);
+        self.remember(config);
+        Ok(resolution)
+    }
+
+    async fn read_config(&self) -> Result<Config, ProviderError> {
         let input = self
             .kv
             .get(&self.key)
@@ -82,7 +188,12 @@ impl WebFingerProvider for KvConfigProvider {
             .ok_or_else(|| ProviderError::MissingConfig {
                 key: self.key.clone(),
             })?;
-        let config = webfinger_service::Config::from_toml(&input)?;
-        Ok(config.resolve(request))
+        Ok(Config::from_toml(&input)?)
+    }
+
+    fn remember(&self, config: Config) {
+        if let Ok(mut last_config) = self.last_config.lock() {
+            *last_config = Some(config);
+        }
     }
 }
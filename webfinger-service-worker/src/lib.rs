@@ -1,40 +1,65 @@
 //! Cloudflare Worker runtime for serving WebFinger responses from editable KV configuration.
 //!
-//! Shared TOML parsing, provider traits, and relation filtering live in `webfinger-service`.
-//! This crate owns the Cloudflare Worker boundary: KV configuration, HTTP mapping, wasm logging,
-//! and the `fetch` entrypoint.
+//! Shared TOML parsing, provider traits, relation filtering, and the HTTP mapping live in
+//! `webfinger-service`. This crate owns the Cloudflare Worker boundary: KV configuration, the
+//! client address, the `/health` route, wasm logging, and the `fetch` entrypoint.
 //!
 //! The default Worker entrypoint reads TOML from Workers KV binding `WEBFINGER_CONFIG` and key
 //! `webfinger.toml`. Custom Workers can reuse the same HTTP mapping by constructing
 //! [`Worker::new`] with any [`webfinger_service::WebFingerProvider`] implementation or by calling
 //! [`serve_with_provider`].
 //!
+//! Requests are answered by [`webfinger_service::tower::WebFingerService`] with host-meta,
+//! NodeInfo, and actors enabled, so the Worker maps methods, status codes, content negotiation,
+//! CORS, caching, and provider results exactly like the Tower and Axum servers. See the
+//! [`webfinger_service::tower`] module docs for the full mapping. In short:
+//!
+//! - `/.well-known/webfinger` answers lookups in the format negotiated from the `Accept` header,
+//!   with `ETag`, `Vary`, and the `Cache-Control` of the provider's
+//!   [`webfinger_service::WebFingerProvider::cache_policy`] or of [`Worker::with_cache`].
+//! - `/.well-known/host-meta` and `/.well-known/host-meta.json` serve the RFC 6415 host-meta
+//!   documents with the provider's [`webfinger_service::WebFingerProvider::host_meta_links`].
+//! - `/.well-known/nodeinfo` and `/nodeinfo/2.0` or `/nodeinfo/2.1` serve the provider's
+//!   [`webfinger_service::WebFingerProvider::nodeinfo`].
+//! - Any other path serves the provider's [`webfinger_service::WebFingerProvider::actor`], or
+//!   `404 Not Found` without CORS headers.
+//!
+//! For [`KvConfigProvider`] those come from the KV configuration: the `[cache]` table and
+//! per-resource `max_age` values, the `[host_meta]` links, the `[nodeinfo]` table, and resource
+//! `actor` tables.
+//!
+//! WebFinger responses use `Access-Control-Allow-Origin: *` by default because the endpoint is
+//! designed for public browser-readable discovery; use [`Worker::with_cors`] to restrict the
+//! allowed origins. `/health` answers `GET` with `OK` and never carries CORS headers, so adding
+//! another route does not accidentally make it publicly readable from browsers.
+//!
+//! Providers receive a [`webfinger_service::RequestContext`] with the request headers and query
+//! string, the parsed `Authorization` header, and the client address from Cloudflare's
+//! `CF-Connecting-IP` header, which also feeds [`Worker::with_host_policy`].
+//!
 //! Public HTTP error bodies intentionally avoid detailed provider/configuration failures, except
 //! for the missing setup key message. Detailed failures are logged through `tracing` for Wrangler
 //! tail and Cloudflare Worker logs.
-//!
-//! WebFinger responses use `Access-Control-Allow-Origin: *` because the endpoint is designed for
-//! public browser-readable discovery. The Worker applies that header to responses generated for
-//! `/.well-known/webfinger`, including malformed queries, unknown resources, unsupported methods,
-//! and provider failures. It does not add the WebFinger CORS header to unrelated paths such as
-//! `/health`, so adding another route does not accidentally make that route publicly readable from
-//! browsers.
 
 mod kv;
 mod observability;
 
-use axum::extract::FromRequestParts;
-use axum::http::{HeaderValue, Method, StatusCode, header};
+use std::net::IpAddr;
+
+use axum::body::Body;
+use axum::http::header::HeaderName;
+use axum::http::request::Parts;
+use axum::http::{Method, StatusCode, header};
 use axum::response::{IntoResponse, Response};
-use thiserror::Error;
-use tracing::{error, info, instrument};
-use webfinger_rs::{WELL_KNOWN_PATH, WebFingerRequest, WebFingerResponse};
-use webfinger_service::{ProviderError, WebFingerProvider};
+use tracing::info;
+use webfinger_rs::{CachePolicy, CorsPolicy, ErrorFormat, ExtractionLimits, HostPolicy};
+use webfinger_service::tower::WebFingerService;
+use webfinger_service::{RemoteAddr, WebFingerProvider};
 use worker::{Context, Env, HttpRequest};
 
 pub use crate::kv::{KvConfigProvider, WEBFINGER_CONFIG_BINDING};
 
-const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
+const CF_CONNECTING_IP: HeaderName = HeaderName::from_static("cf-connecting-ip");
 
 /// A WebFinger Worker backed by a caller-provided provider.
 ///
@@ -43,13 +68,74 @@ const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
 /// extraction, response headers, status codes, and logging.
 #[derive(Debug, Clone)]
 pub struct Worker<P> {
-    provider: P,
+    service: WebFingerService<P>,
 }
 
 impl<P> Worker<P> {
     /// Creates a Worker from a provider.
     pub fn new(provider: P) -> Self {
-        Self { provider }
+        let service = WebFingerService::new(provider)
+            .with_host_meta(true)
+            .with_nodeinfo(true)
+            .with_actors(true);
+        Self { service }
+    }
+
+    /// Replaces the default CORS policy, which allows any origin.
+    pub fn with_cors(self, cors: CorsPolicy) -> Self {
+        Self {
+            service: self.service.with_cors(cors),
+        }
+    }
+
+    /// Replaces the default cache policy, which sends no `Cache-Control` header.
+    ///
+    /// A policy returned by the provider's `cache_policy` takes precedence for that response.
+    pub fn with_cache(self, cache: CachePolicy) -> Self {
+        Self {
+            service: self.service.with_cache(cache),
+        }
+    }
+
+    /// Trims each link's `titles` to the best match for the request's `Accept-Language`.
+    ///
+    /// Disabled by default, which returns every title. When enabled, responses carry
+    /// `Vary: Accept-Language`. See [`webfinger_rs::WebFingerResponse::select_titles`] for the
+    /// selection rules.
+    pub fn with_title_selection(self, enabled: bool) -> Self {
+        Self {
+            service: self.service.with_title_selection(enabled),
+        }
+    }
+
+    /// Replaces the default [`ExtractionLimits`] applied to WebFinger lookup queries.
+    ///
+    /// Lookups that exceed a limit are answered with `400 Bad Request`.
+    pub fn with_limits(self, limits: ExtractionLimits) -> Self {
+        Self {
+            service: self.service.with_limits(limits),
+        }
+    }
+
+    /// Replaces the default [`HostPolicy`], which trusts no proxies and allows every host.
+    ///
+    /// Requests for hosts the policy does not allow are answered with `400 Bad Request`. The
+    /// connecting address the policy checks for trusted proxies is Cloudflare's
+    /// `CF-Connecting-IP`.
+    pub fn with_host_policy(self, policy: HostPolicy) -> Self {
+        Self {
+            service: self.service.with_host_policy(policy),
+        }
+    }
+
+    /// Replaces the default plain text body of `400 Bad Request` responses.
+    ///
+    /// With [`ErrorFormat::Problem`], malformed requests are answered with an RFC 9457
+    /// `application/problem+json` document whose `code` member identifies the cause.
+    pub fn with_error_format(self, format: ErrorFormat) -> Self {
+        Self {
+            service: self.service.with_error_format(format),
+        }
     }
 }
 
@@ -62,7 +148,17 @@ where
     /// This method is useful from custom `#[worker::event(fetch)]` functions after the caller has
     /// constructed a provider from bindings or other Worker state.
     pub async fn serve(&self, request: HttpRequest) -> Response {
-        serve_with_provider(&self.provider, request).await
+        let (mut parts, body) = request.into_parts();
+        if parts.uri.path() == "/health" {
+            return health(&parts.method);
+        }
+        if let Some(address) = connecting_ip(&parts) {
+            parts.extensions.insert(RemoteAddr(address));
+        }
+        self.service
+            .serve(HttpRequest::from_parts(parts, body))
+            .await
+            .map(Body::new)
     }
 }
 
@@ -84,113 +180,49 @@ pub async fn serve(request: HttpRequest, env: Env, _ctx: Context) -> worker::Res
 
 /// Serves one HTTP request with a caller-provided WebFinger provider.
 ///
-/// This is the lowest-level reusable HTTP mapping in the Worker crate. It accepts only
-/// `GET /.well-known/webfinger`, plus `GET /health`, and maps provider results into WebFinger HTTP
-/// responses. Use [`Worker`] when you want to hold a provider value and serve multiple requests
+/// This serves a clone of `provider` with the default [`Worker`] configuration. Use [`Worker`]
+/// when you want to hold a provider value, configure CORS or caching, and serve multiple requests
 /// through the same wrapper.
 pub async fn serve_with_provider<P>(provider: &P, request: HttpRequest) -> Response
 where
-    P: WebFingerProvider,
+    P: WebFingerProvider + Clone,
 {
-    let method = request.method().clone();
-    let path = request.uri().path().to_string();
-    if method != Method::GET {
-        log_webfinger_request(&method, &path, "method_not_allowed");
-        let response = (
-            StatusCode::METHOD_NOT_ALLOWED,
-            [(header::ALLOW, Method::GET.as_str())],
-            "method not allowed",
-        )
-            .into_response();
-        return if path == WELL_KNOWN_PATH {
-            with_webfinger_cors(response)
-        } else {
-            response
-        };
-    }
-    if path == "/health" {
-        log_webfinger_request(&method, &path, "health");
-        return "OK".into_response();
-    }
-    if path != WELL_KNOWN_PATH {
-        log_webfinger_request(&method, &path, "not_found");
-        return (StatusCode::NOT_FOUND, "not found").into_response();
-    }
-
-    let (mut parts, _body) = request.into_parts();
-    let request = match WebFingerRequest::from_request_parts(&mut parts, &()).await {
-        Ok(request) => {
-            log_webfinger_request(&method, &path, "lookup");
-            request
-        }
-        Err(rejection) => {
-            log_webfinger_request(&method, &path, "bad_request");
-            return with_webfinger_cors(rejection.into_response());
-        }
-    };
-    webfinger(provider, request).await.into_response()
+    Worker::new(provider.clone()).serve(request).await
 }
 
-/// Adds the public WebFinger CORS header to a response already known to belong to the endpoint.
-fn with_webfinger_cors(mut response: Response) -> Response {
-    response.headers_mut().insert(
-        header::ACCESS_CONTROL_ALLOW_ORIGIN,
-        CORS_ALLOW_ORIGIN_HEADER,
-    );
-    response
+/// Answers the `/health` route, which has no CORS headers.
+fn health(method: &Method) -> Response {
+    if method == Method::GET {
+        log_webfinger_request(method, "/health", "health");
+        return "OK".into_response();
+    }
+    log_webfinger_request(method, "/health", "method_not_allowed");
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, "GET")],
+        "method not allowed",
+    )
+        .into_response()
 }
 
-#[instrument(skip(provider, request), fields(resource = %request.resource))]
-async fn webfinger<P>(
-    provider: &P,
-    request: WebFingerRequest,
-) -> Result<WebFingerResponse, HttpError>
-where
-    P: WebFingerProvider,
-{
-    match provider.resolve(&request).await? {
-        Some(response) => {
-            info!("resolved webfinger response");
-            Ok(response)
-        }
-        None => Err(HttpError::NotFound),
-    }
+/// Returns the client address from `CF-Connecting-IP`.
+///
+/// Cloudflare sets that header on every request that reaches a Worker and overwrites any value the
+/// client sent.
+///
+/// See <https://developers.cloudflare.com/fundamentals/reference/http-headers/#cf-connecting-ip>.
+fn connecting_ip(parts: &Parts) -> Option<IpAddr> {
+    parts
+        .headers
+        .get(CF_CONNECTING_IP)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
 }
 
 fn log_webfinger_request(method: &Method, path: &str, outcome: &str) {
     info!(method = %method, path, outcome, "webfinger service request");
 }
 
-#[derive(Debug, Error)]
-enum HttpError {
-    #[error("resource not found")]
-    NotFound,
-
-    #[error(transparent)]
-    Provider(#[from] ProviderError),
-}
-
-impl IntoResponse for HttpError {
-    fn into_response(self) -> Response {
-        let response = match self {
-            HttpError::NotFound => (StatusCode::NOT_FOUND, "resource not found").into_response(),
-            HttpError::Provider(error) => {
-                error!(?error, "webfinger provider failed");
-                let message = match error {
-                    ProviderError::MissingConfig { key } => format!(
-                        "WebFinger is not configured. Add TOML configuration to key `{key}`."
-                    ),
-                    _ => {
-                        "WebFinger provider failed. Check the Worker logs for details.".to_string()
-                    }
-                };
-                (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
-            }
-        };
-        with_webfinger_cors(response)
-    }
-}
-
 /// Cloudflare Worker lifecycle entrypoint for logging setup.
 #[worker::event(start)]
 fn start() {
@@ -208,10 +240,19 @@ async fn fetch(request: HttpRequest, env: Env, ctx: Context) -> worker::Result<R
 mod tests {
     use super::*;
     use axum::body::to_bytes;
-    use axum::http::{Request, header};
-    use webfinger_service::{EXAMPLE_CONFIG, StaticConfigProvider, WEBFINGER_CONFIG_KEY};
+    use axum::http::{HeaderValue, Request, header};
+    use webfinger_rs::{
+        ALLOWED_METHODS, AllowOrigin, PROBLEM_CONTENT_TYPE, Problem, WebFingerRequest,
+        WebFingerResponse,
+    };
+    use webfinger_service::{
+        EXAMPLE_CONFIG, NODEINFO_PATH, NodeInfoVersion, ProviderError, RequestContext, Resolution,
+        StaticConfigProvider, WEBFINGER_CONFIG_KEY,
+    };
     use worker::Body;
 
+    const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
+
     #[tokio::test]
     async fn maps_unknown_resource_to_not_found() {
         let response = call("/.well-known/webfinger?resource=acct:bob@example.com").await;
@@ -261,7 +302,10 @@ mod tests {
         let response = Worker::new(provider).serve(request).await;
 
         assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
-        assert_eq!(response.headers().get(header::ALLOW).unwrap(), "GET");
+        assert_eq!(
+            response.headers().get(header::ALLOW).unwrap(),
+            ALLOWED_METHODS
+        );
     }
 
     #[tokio::test]
@@ -302,6 +346,125 @@ mod tests {
         );
     }
 
+    /// `HEAD` returns the `GET` status and headers without a body.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-9.3.2>.
+    #[tokio::test]
+    async fn head_request_omits_body() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .method(Method::HEAD)
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/jrd+json"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    /// Browsers send `OPTIONS` preflight requests before some cross-origin lookups.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[tokio::test]
+    async fn options_request_answers_preflight() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/.well-known/webfinger")
+            .header(header::ORIGIN, "https://app.example")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            ALLOWED_METHODS
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(),
+            "86400"
+        );
+    }
+
+    /// Title selection keeps the best title for `Accept-Language` and varies on that header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc4647.html#section-3.4>.
+    #[tokio::test]
+    async fn title_selection_trims_titles_for_accept_language() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+            [[resources]]
+            resource = "acct:alice@example.com"
+
+            [[resources.links]]
+            rel = "http://webfinger.net/rel/profile-page"
+            titles = { en = "Profile", de = "Profil" }
+            "#,
+        )
+        .unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .header(header::ACCEPT_LANGUAGE, "de-AT, en;q=0.5")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider)
+            .with_title_selection(true)
+            .serve(request)
+            .await;
+
+        let vary: Vec<_> = response.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept", "Accept-Language", "Authorization"]);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response: WebFingerResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            response.links[0].titles,
+            Some([("de".to_string(), "Profil".to_string())].into())
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_cors_policy_restricts_origin() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let cors = CorsPolicy::new()
+            .allow_origin(AllowOrigin::List(vec!["https://app.example".to_string()]));
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .header(header::ORIGIN, "https://app.example")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).with_cors(cors).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://app.example",
+        );
+        let vary: Vec<_> = response.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept", "Authorization", "Origin"]);
+    }
+
     #[tokio::test]
     async fn maps_malformed_query_to_bad_request() {
         let response = call("/.well-known/webfinger").await;
@@ -309,6 +472,34 @@ mod tests {
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[tokio::test]
+    async fn problem_error_format_renders_problem_details() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com&rel=%ZZ")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider)
+            .with_error_format(ErrorFormat::Problem)
+            .serve(request)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE,
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem.code.as_deref(), Some("invalid_percent_encoding"));
+    }
+
     #[tokio::test]
     async fn malformed_query_sets_cors_header() {
         let response = call("/.well-known/webfinger").await;
@@ -393,8 +584,7 @@ mod tests {
 
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let body = String::from_utf8(body.to_vec()).unwrap();
-        assert!(body.contains("Check the Worker logs"));
-        assert!(!body.contains("acct:alice@example.com"));
+        assert_eq!(body, "WebFinger provider failed");
     }
 
     #[tokio::test]
@@ -414,6 +604,361 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn accept_xrd_serves_xrd() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .header(header::ACCEPT, "application/xrd+xml")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/xrd+xml")),
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("<Subject>acct:alice@example.com</Subject>"));
+    }
+
+    #[tokio::test]
+    async fn unacceptable_format_is_not_acceptable() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+    }
+
+    #[tokio::test]
+    async fn successful_response_sets_etag_and_vary() {
+        let response = call("/.well-known/webfinger?resource=acct:alice@example.com").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(header::ETAG));
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "Accept");
+        assert_eq!(response.headers().get(header::CACHE_CONTROL), None);
+    }
+
+    /// A resource's own `max_age` wins over the global `[cache]` table and the Worker policy.
+    #[tokio::test]
+    async fn provider_cache_policy_overrides_worker_policy() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[cache]
+max_age = 600
+
+[[resources]]
+resource = "acct:alice@example.com"
+max_age = 60
+"#,
+        )
+        .unwrap();
+        let cache = CachePolicy::new().max_age(std::time::Duration::from_secs(300));
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).with_cache(cache).serve(request).await;
+
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=60"
+        );
+    }
+
+    /// Revalidation with a matching `If-None-Match` is answered with `304 Not Modified` and no
+    /// body, keeping the caching headers.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.4.5>.
+    #[tokio::test]
+    async fn matching_if_none_match_is_not_modified() {
+        let uri = "/.well-known/webfinger?resource=acct:alice@example.com";
+        let etag = call(uri).await.headers()[header::ETAG].clone();
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let request = Request::builder()
+            .uri(uri)
+            .header("host", "example.com")
+            .header(header::IF_NONE_MATCH, etag.clone())
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG), Some(&etag));
+        assert_eq!(response.headers().get(header::CONTENT_TYPE), None);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    /// A provider redirect becomes `307 Temporary Redirect` with the WebFinger CORS header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+    #[tokio::test]
+    async fn provider_redirect_is_temporary_redirect() {
+        let location =
+            "https://social.example/.well-known/webfinger?resource=acct:alice@example.com";
+        let provider = FixedProvider(Resolution::Redirect(location.to_string()));
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers().get(header::LOCATION).unwrap(), location);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&HeaderValue::from_static("*")),
+        );
+    }
+
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-3>.
+    #[tokio::test]
+    async fn serves_host_meta_xrd_with_site_links() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[host_meta.links]]
+rel = "http://nodeinfo.diaspora.software/ns/schema/2.1"
+href = "https://example.com/nodeinfo/2.1"
+"#,
+        )
+        .unwrap();
+
+        let response = Worker::new(provider)
+            .serve(
+                Request::builder()
+                    .uri("/.well-known/host-meta")
+                    .header("host", "example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/xrd+xml"
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&HeaderValue::from_static("*")),
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document = WebFingerResponse::from_xrd(std::str::from_utf8(&body).unwrap()).unwrap();
+        assert_eq!(
+            document.links[0].template.as_deref(),
+            Some("https://example.com/.well-known/webfinger?resource={uri}"),
+        );
+        assert_eq!(
+            document.links[1].href.as_ref().unwrap().as_ref(),
+            "https://example.com/nodeinfo/2.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_nodeinfo_schema_document() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[nodeinfo]
+software = { name = "webfinger-rs", version = "1.0.0" }
+protocols = ["activitypub"]
+"#,
+        )
+        .unwrap();
+
+        let response = Worker::new(provider)
+            .serve(
+                Request::builder()
+                    .uri("/nodeinfo/2.0")
+                    .header("host", "example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            NodeInfoVersion::V2_0.content_type()
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(document["version"], "2.0");
+        assert_eq!(document["protocols"], serde_json::json!(["activitypub"]));
+    }
+
+    #[tokio::test]
+    async fn serves_resource_actor_at_its_path() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[resources]]
+resource = "acct:alice@example.com"
+actor = { id = "https://example.com/users/alice", moved_to = "https://social.example/users/alice" }
+"#,
+        )
+        .unwrap();
+
+        let response = Worker::new(provider)
+            .serve(
+                Request::builder()
+                    .uri("/users/alice")
+                    .header("host", "example.com")
+                    .header(header::ACCEPT, "application/ld+json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let actor: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(actor["movedTo"], "https://social.example/users/alice");
+    }
+
+    #[tokio::test]
+    async fn nodeinfo_without_config_is_not_found() {
+        let response = call(NODEINFO_PATH).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// A permanent host redirect rule answers `308 Permanent Redirect` with the original query.
+    #[tokio::test]
+    async fn config_host_redirect_is_permanent_redirect() {
+        let provider = StaticConfigProvider::from_toml(
+            "[[redirects]]\nhost = \"example.com\"\ntarget = \"https://new.example\"\npermanent = true\n",
+        )
+        .unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com&rel=self&rel=profile")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "https://new.example/.well-known/webfinger\
+             ?resource=acct:alice@example.com&rel=self&rel=profile",
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_gone_is_gone() {
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(FixedProvider(Resolution::Gone))
+            .serve(request)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::GONE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "resource gone");
+    }
+
+    /// Answers to a bearer token vary on `Authorization` and are private to the client.
+    #[tokio::test]
+    async fn token_responses_vary_on_authorization_and_are_private() {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:bob@example.com"
+visibility = "token"
+"#,
+        )
+        .unwrap();
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:bob@example.com")
+            .header("host", "example.com")
+            .header(header::AUTHORIZATION, "Bearer s3cr3t")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(provider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let vary: Vec<_> = response.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept", "Authorization"]);
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "private"
+        );
+    }
+
+    /// The host policy trusts proxies by the address Cloudflare reports in `CF-Connecting-IP`.
+    #[tokio::test]
+    async fn configured_host_policy_resolves_forwarded_host() {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let policy = HostPolicy::new()
+            .trusted_proxies(["2001:db8::7".parse::<IpAddr>().unwrap()])
+            .allowed_hosts(["example.com"]);
+        let worker = Worker::new(provider).with_host_policy(policy);
+        let request = |connecting_ip: &str| {
+            Request::builder()
+                .uri("/.well-known/host-meta")
+                .header("host", "worker.example.workers.dev")
+                .header("x-forwarded-host", "example.com")
+                .header("cf-connecting-ip", connecting_ip)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let proxied = worker.serve(request("2001:db8::7")).await;
+        let direct = worker.serve(request("203.0.113.7")).await;
+
+        assert_eq!(proxied.status(), StatusCode::OK);
+        assert_eq!(direct.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Providers see the client address Cloudflare reports in `CF-Connecting-IP`.
+    #[tokio::test]
+    async fn provider_receives_cf_connecting_ip() {
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header("host", "example.com")
+            .header("cf-connecting-ip", "2001:db8::7")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = Worker::new(RemoteAddrProvider).serve(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     async fn call(uri: &str) -> Response {
         let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
         let request = Request::builder()
@@ -438,6 +983,48 @@ mod tests {
         }
     }
 
+    struct FixedProvider(Resolution);
+
+    impl WebFingerProvider for FixedProvider {
+        async fn resolve<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> Result<Option<WebFingerResponse>, ProviderError> {
+            Ok(self.0.clone().into_response())
+        }
+
+        async fn resolution<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> Result<Resolution, ProviderError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    struct RemoteAddrProvider;
+
+    impl WebFingerProvider for RemoteAddrProvider {
+        async fn resolve<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> Result<Option<WebFingerResponse>, ProviderError> {
+            Ok(None)
+        }
+
+        async fn resolve_with_context<'a>(
+            &'a self,
+            request: &'a WebFingerRequest,
+            context: &'a RequestContext,
+        ) -> Result<Resolution, ProviderError> {
+            if context.remote_addr != Some("2001:db8::7".parse().unwrap()) {
+                return Ok(Resolution::NotFound);
+            }
+            StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?
+                .resolution(request)
+                .await
+        }
+    }
+
     struct InvalidConfigProvider;
 
     impl WebFingerProvider for InvalidConfigProvider {
@@ -0,0 +1,333 @@
+//! Minimal static ActivityPub actor documents.
+//!
+//! A vanity domain that only publishes WebFinger records has nothing for ActivityPub software to
+//! fetch once a lookup succeeds. A configured actor fills that gap with a static document, usually
+//! one that points at the person's main account through `alsoKnownAs` or `movedTo`, and the JRD
+//! of its resource gains the `self` link that ActivityPub clients follow to find it.
+//!
+//! See <https://www.w3.org/TR/activitypub/#actor-objects>.
+
+use serde::Serialize;
+use webfinger_rs::{JrdUri, Link, MediaType, Rel};
+
+/// The `application/activity+json` media type of ActivityPub documents.
+///
+/// See <https://www.w3.org/TR/activitypub/#retrieving-objects>.
+pub const ACTIVITY_JSON_CONTENT_TYPE: &str = "application/activity+json";
+
+/// The JSON-LD media type with the ActivityStreams profile, which clients may ask for instead.
+const LD_JSON_CONTENT_TYPE: &str =
+    r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#;
+
+const ACTIVITY_STREAMS_CONTEXT: &str = "https://www.w3.org/ns/activitystreams";
+
+/// The ActivityStreams actor types an actor may use.
+///
+/// See <https://www.w3.org/TR/activitystreams-vocabulary/#actor-types>.
+const ACTOR_TYPES: &[&str] = &["Application", "Group", "Organization", "Person", "Service"];
+
+/// A static ActivityPub actor, parsed from a resource's `actor` configuration table.
+///
+/// The actor is served at the path of its `id`. Values are validated when the configuration is
+/// loaded, so [`to_json`](Self::to_json) renders the configured document unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor {
+    pub(crate) id: String,
+    pub(crate) path: String,
+    pub(crate) kind: String,
+    pub(crate) preferred_username: Option<String>,
+    pub(crate) name: Option<String>,
+    pub(crate) summary: Option<String>,
+    pub(crate) url: Option<String>,
+    pub(crate) inbox: Option<String>,
+    pub(crate) outbox: Option<String>,
+    pub(crate) also_known_as: Vec<String>,
+    pub(crate) moved_to: Option<String>,
+}
+
+impl Actor {
+    /// Returns the actor's `id`, the URL its document is served at.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the path component of the actor's `id`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Renders the actor document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&Document {
+            context: (
+                ACTIVITY_STREAMS_CONTEXT,
+                Extensions {
+                    also_known_as: Term {
+                        id: "as:alsoKnownAs",
+                        r#type: "@id",
+                    },
+                    moved_to: Term {
+                        id: "as:movedTo",
+                        r#type: "@id",
+                    },
+                },
+            ),
+            id: &self.id,
+            r#type: &self.kind,
+            preferred_username: self.preferred_username.as_deref(),
+            name: self.name.as_deref(),
+            summary: self.summary.as_deref(),
+            url: self.url.as_deref(),
+            inbox: self.inbox.as_deref(),
+            outbox: self.outbox.as_deref(),
+            also_known_as: &self.also_known_as,
+            moved_to: self.moved_to.as_deref(),
+        })
+    }
+
+    /// Returns the JRD `self` link that points ActivityPub clients at this actor.
+    ///
+    /// See <https://docs.joinmastodon.org/spec/webfinger/>.
+    pub(crate) fn self_link(&self) -> Result<Link, webfinger_rs::Error> {
+        let mut link = Link::new(Rel::try_new("self")?);
+        link.r#type = Some(MediaType::try_new(ACTIVITY_JSON_CONTENT_TYPE)?);
+        link.href = Some(JrdUri::try_new(&self.id)?);
+        Ok(link)
+    }
+
+    /// Validates the parts of an `actor` table that ActivityPub constrains beyond their types.
+    ///
+    /// Sets [`path`](Self::path) from the `id`.
+    pub(crate) fn validate(&mut self) -> Result<(), String> {
+        let id = self
+            .id
+            .parse::<http::Uri>()
+            .ok()
+            .filter(|uri| uri.scheme_str() == Some("https") && uri.authority().is_some())
+            .filter(|uri| !matches!(uri.path(), "" | "/") && uri.query().is_none())
+            .ok_or_else(|| {
+                format!(
+                    "id `{}` must be an https URL with a path and no query",
+                    self.id
+                )
+            })?;
+        self.path = id.path().to_string();
+        if !ACTOR_TYPES.contains(&self.kind.as_str()) {
+            return Err(format!(
+                "type `{}` must be one of {}",
+                self.kind,
+                ACTOR_TYPES.join(", ")
+            ));
+        }
+        let urls = [
+            ("url", self.url.as_ref()),
+            ("inbox", self.inbox.as_ref()),
+            ("outbox", self.outbox.as_ref()),
+            ("moved_to", self.moved_to.as_ref()),
+        ];
+        let aliases = self
+            .also_known_as
+            .iter()
+            .map(|alias| ("also_known_as", Some(alias)));
+        for (field, url) in urls.into_iter().chain(aliases) {
+            if let Some(url) = url
+                && !is_http_url(url)
+            {
+                return Err(format!("{field} `{url}` must be an http or https URL"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.parse::<http::Uri>().is_ok_and(|uri| {
+        uri.authority().is_some() && matches!(uri.scheme_str(), Some("http" | "https"))
+    })
+}
+
+/// Picks the `Content-Type` to send an actor document with for a request's `Accept` header.
+///
+/// A missing or empty header selects [`ACTIVITY_JSON_CONTENT_TYPE`]. Otherwise the media range
+/// with the highest non-zero quality wins: `application/ld+json` selects the JSON-LD type with
+/// the ActivityStreams profile, and `application/activity+json`, `application/json`,
+/// `application/*`, and `*/*` select [`ACTIVITY_JSON_CONTENT_TYPE`].
+///
+/// Returns `None` when no range accepts the document. Servers should answer
+/// `406 Not Acceptable` in that case.
+pub fn actor_content_type(accept: Option<&str>) -> Option<&'static str> {
+    let mut ranges = accept
+        .unwrap_or_default()
+        .split(',')
+        .filter(|range| !range.trim().is_empty())
+        .peekable();
+    if ranges.peek().is_none() {
+        return Some(ACTIVITY_JSON_CONTENT_TYPE);
+    }
+    let mut best: Option<(&'static str, f32)> = None;
+    for range in ranges {
+        let mut params = range.split(';');
+        let media_type = params.next().unwrap_or_default().trim();
+        let quality = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .map_or(Some(1.0), |(_, value)| value.trim().parse::<f32>().ok());
+        let content_type = if media_type.eq_ignore_ascii_case("application/ld+json") {
+            LD_JSON_CONTENT_TYPE
+        } else if [
+            "application/activity+json",
+            "application/json",
+            "application/*",
+            "*/*",
+        ]
+        .iter()
+        .any(|accepted| media_type.eq_ignore_ascii_case(accepted))
+        {
+            ACTIVITY_JSON_CONTENT_TYPE
+        } else {
+            continue;
+        };
+        if let Some(quality) = quality
+            && quality > 0.0
+            && best.is_none_or(|(_, best)| quality > best)
+        {
+            best = Some((content_type, quality));
+        }
+    }
+    best.map(|(content_type, _)| content_type)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    #[serde(rename = "@context")]
+    context: (&'static str, Extensions),
+    id: &'a str,
+    r#type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preferred_username: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inbox: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outbox: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    also_known_as: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    moved_to: Option<&'a str>,
+}
+
+/// The JSON-LD terms for the actor properties that ActivityStreams leaves to extensions.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Extensions {
+    also_known_as: Term,
+    moved_to: Term,
+}
+
+#[derive(Serialize)]
+struct Term {
+    #[serde(rename = "@id")]
+    id: &'static str,
+    #[serde(rename = "@type")]
+    r#type: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actor() -> Actor {
+        Actor {
+            id: "https://example.com/users/alice".to_string(),
+            path: String::new(),
+            kind: "Person".to_string(),
+            preferred_username: Some("alice".to_string()),
+            name: None,
+            summary: None,
+            url: None,
+            inbox: None,
+            outbox: None,
+            also_known_as: vec!["https://social.example/users/alice".to_string()],
+            moved_to: Some("https://social.example/users/alice".to_string()),
+        }
+    }
+
+    #[test]
+    fn renders_aliases_and_move_target() {
+        let mut actor = actor();
+        actor.validate().unwrap();
+
+        let document: serde_json::Value =
+            serde_json::from_slice(&actor.to_json().unwrap()).unwrap();
+
+        assert_eq!(actor.path(), "/users/alice");
+        assert_eq!(document["@context"][0], ACTIVITY_STREAMS_CONTEXT);
+        assert_eq!(document["@context"][1]["movedTo"]["@id"], "as:movedTo");
+        assert_eq!(document["type"], "Person");
+        assert_eq!(document["preferredUsername"], "alice");
+        assert_eq!(
+            document["alsoKnownAs"],
+            serde_json::json!(["https://social.example/users/alice"])
+        );
+        assert_eq!(document["movedTo"], "https://social.example/users/alice");
+        assert!(document.get("name").is_none());
+    }
+
+    #[test]
+    fn self_link_points_at_the_actor() {
+        let link = actor().self_link().unwrap();
+
+        assert_eq!(link.rel.as_ref(), "self");
+        assert_eq!(link.r#type.unwrap().as_ref(), ACTIVITY_JSON_CONTENT_TYPE);
+        assert_eq!(
+            link.href.unwrap().as_ref(),
+            "https://example.com/users/alice"
+        );
+    }
+
+    #[test]
+    fn rejects_ids_that_cannot_be_served() {
+        for id in [
+            "http://example.com/users/alice",
+            "https://example.com",
+            "https://example.com/users/alice?format=json",
+            "/users/alice",
+        ] {
+            let mut actor = actor();
+            actor.id = id.to_string();
+
+            assert!(actor.validate().is_err(), "{id}");
+        }
+    }
+
+    #[test]
+    fn negotiates_activity_streams_media_types() {
+        assert_eq!(actor_content_type(None), Some(ACTIVITY_JSON_CONTENT_TYPE));
+        assert_eq!(
+            actor_content_type(Some("application/activity+json")),
+            Some(ACTIVITY_JSON_CONTENT_TYPE)
+        );
+        assert_eq!(
+            actor_content_type(Some(
+                r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#
+            )),
+            Some(LD_JSON_CONTENT_TYPE)
+        );
+        assert_eq!(
+            actor_content_type(Some("application/activity+json;q=0.5, application/ld+json")),
+            Some(LD_JSON_CONTENT_TYPE)
+        );
+        assert_eq!(actor_content_type(Some("text/html")), None);
+        assert_eq!(actor_content_type(Some("application/json;q=0")), None);
+    }
+}
@@ -1,57 +1,445 @@
 use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use http::Uri;
+use http::uri::{PathAndQuery, Scheme};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-use webfinger_rs::{JrdUri, Link, Rel, WebFingerRequest, WebFingerResponse};
+use toml::Spanned;
+use webfinger_rs::{
+    CachePolicy, JrdUri, Link, MediaType, Rel, Title, WELL_KNOWN_PATH, WebFingerRequest,
+    WebFingerResponse,
+};
+
+use crate::nodeinfo::{NodeInfo, Software, Usage, Users};
+use crate::{ACTIVITY_JSON_CONTENT_TYPE, Actor, RequestContext, Resolution};
+
+mod load;
+mod value;
+
+pub use self::load::interpolate;
 
 /// WebFinger resources loaded from TOML configuration.
 ///
-/// `Config` is the in-memory representation used by [`StaticConfigProvider`](crate::StaticConfigProvider)
-/// and by runtime providers that load TOML from another store before resolving a request.
+/// `Config` is the in-memory representation used by
+/// [`StaticConfigProvider`](crate::StaticConfigProvider) and by runtime providers that load TOML
+/// from another store before resolving a request.
 ///
 /// Resources are keyed by their exact `resource` string. A request for
 /// `acct:alice@example.com` does not match `acct:Alice@example.com`, an alias URL, or any inferred
 /// account domain. Relation filtering is applied during resolution: when a request contains one or
 /// more `rel` parameters, the returned response contains only links with matching relation values.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// An optional `[cache]` table sets a global `max_age` in seconds, and each resource may override
+/// it with its own `max_age`. Runtimes send the result as `Cache-Control: max-age`.
+///
+/// Resources and links are public unless they set `visibility = "token"`. Token-only content is
+/// disclosed only to requests whose bearer token matches one of the `[[tokens]]` entries, which
+/// store the hex SHA-256 digest of a token rather than the token itself. Anonymous requests for a
+/// token-only resource resolve to `None`, like an unknown resource, so the resource cannot be
+/// enumerated. See [RFC 7033 section 9.2] on the privacy of WebFinger data.
+///
+/// Redirect rules in `[[redirects]]` send queries for a whole host, or for resources matching a
+/// pattern, to another WebFinger server. They are checked in order before any resource, and the
+/// first match answers with a redirect to the target's `/.well-known/webfinger` that keeps the
+/// original query string. A config may hold only redirect rules.
+///
+/// Links in the optional `[host_meta]` table are published in the RFC 6415 host-meta documents
+/// after the `lrdd` template; see [`host_meta`](crate::host_meta). An optional `[nodeinfo]` table
+/// describes the server for NodeInfo discovery; see [`NodeInfo`].
+///
+/// A resource's optional `actor` table describes a static ActivityPub [`Actor`]. The resource gains
+/// a `self` link to the actor's `id`, and the actor shares the resource's visibility.
+///
+/// [RFC 7033 section 9.2]: https://www.rfc-editor.org/rfc/rfc7033.html#section-9.2
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Config {
-    resources: BTreeMap<String, WebFingerResponse>,
+    resources: BTreeMap<String, Resource>,
+    actors: BTreeMap<String, (Actor, Visibility)>,
+    redirects: Vec<Redirect>,
+    host_meta_links: Vec<(Link, Visibility)>,
+    nodeinfo: Option<NodeInfo>,
+    token_digests: Vec<[u8; 32]>,
+    max_age: Option<Duration>,
+    resource_max_ages: BTreeMap<String, Duration>,
+}
+
+/// A configured resource and the part of it anonymous requests may see.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Resource {
+    full: WebFingerResponse,
+    public: Option<WebFingerResponse>,
+}
+
+/// A configured redirect rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Redirect {
+    matcher: RedirectMatcher,
+    /// The target origin, such as `https://example.net`, without a trailing slash.
+    target: String,
+    permanent: bool,
+}
+
+/// What a redirect rule matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RedirectMatcher {
+    /// Every query sent to this host, compared case-insensitively and without a port.
+    Host(String),
+    /// Resources matching a pattern in which `*` matches any run of characters.
+    Resource(String),
+}
+
+impl Redirect {
+    fn matches(&self, request: &WebFingerRequest) -> bool {
+        match &self.matcher {
+            RedirectMatcher::Host(host) => strip_port(&request.host).eq_ignore_ascii_case(host),
+            RedirectMatcher::Resource(pattern) => {
+                wildcard_match(pattern, request.resource.as_ref())
+            }
+        }
+    }
+
+    /// Returns the target URL for a request, keeping its query string when the context has one.
+    fn location(&self, request: &WebFingerRequest, context: &RequestContext) -> String {
+        let path_and_query = match &context.query {
+            Some(query) => format!("{WELL_KNOWN_PATH}?{query}"),
+            None => PathAndQuery::try_from(request)
+                .map_or_else(|_| WELL_KNOWN_PATH.to_string(), |path| path.to_string()),
+        };
+        format!("{}{path_and_query}", self.target)
+    }
 }
 
 impl Config {
     /// Parses WebFinger configuration from TOML.
     ///
-    /// The top-level TOML document must contain a `resources` array. Each resource maps onto a JRD
-    /// response with supported resource-level fields `resource`, `aliases`, and `properties`, and
-    /// supported link-level fields `rel`, `type`, `href`, `template`, `titles`, and `properties`.
+    /// The top-level TOML document must contain a `resources` array and may contain a `[cache]`
+    /// table with a `max_age` in seconds and a `tokens` array whose entries hold a `sha256` digest.
+    /// Each resource maps onto a JRD response with supported resource-level fields `resource`,
+    /// `aliases`, and `properties`, and supported link-level fields `rel`, `type`, `href`,
+    /// `template`, `titles`, and `properties`. A link sets at most one of `href` and `template`,
+    /// and `titles` is keyed by BCP 47 language tags. A resource-level `max_age` overrides the global
+    /// cache lifetime for that resource. Resources and links may set `visibility` to `"public"`,
+    /// the default, or `"token"`. Resources may also use shorthand keys that expand into links:
+    /// `activitypub`, `profile_page`, `oidc_issuer`, and `openid` take an `href`,
+    /// `subscribe_template` takes a URI template, and `avatar` takes a table with `href` and an
+    /// optional `type`. A `redirects` array holds rules with either a `host` or a `resource`
+    /// pattern, an `https` `target` origin, and an optional `permanent` flag. A `[host_meta]` table
+    /// may hold site-level `links` with the same fields as resource links. A `[nodeinfo]` table
+    /// holds a `software` table with `name`, `version`, and optional `repository` and `homepage`, a
+    /// `protocols` array, optional `services` with `inbound` and `outbound` arrays,
+    /// `open_registrations`, a `usage` table, and a free-form `metadata` table. A resource's
+    /// `actor` table holds an `https` `id`, an optional `type` that defaults to `"Person"`,
+    /// optional `preferred_username`, `name`, `summary`, `url`, `inbox`, `outbox`, and `moved_to`,
+    /// and an `also_known_as` array. `preferred_username` defaults to the user part of an `acct:`
+    /// resource.
     ///
     /// # Errors
     ///
     /// Returns [`ConfigError`] when the TOML is malformed, contains duplicate resource entries,
-    /// uses unsupported fields, uses an invalid WebFinger/JRD URI value, or uses the `{ null =
-    /// true }` property marker incorrectly.
+    /// sets a shorthand key and a link with the same `rel`, uses unsupported fields, uses an
+    /// invalid WebFinger/JRD URI value, uses the `{ null = true }` property marker incorrectly, has
+    /// a token digest that is not 64 hex digits, has a redirect rule without exactly one matcher or
+    /// with a target that is not an `https` origin, has a `[nodeinfo]` table that the NodeInfo 2.x
+    /// schemas reject, or has an invalid actor. Errors in a value are wrapped in
+    /// [`ConfigError::Invalid`] with its [`ConfigLocation`], such as `resources[12].links[3].href`
+    /// at a line and column of `input`.
     pub fn from_toml(input: &str) -> Result<Self, ConfigError> {
-        let raw: RawConfig = toml::from_str(input)?;
+        Self::from_raw(toml::from_str(input)?, Some(input))
+    }
+
+    /// Parses WebFinger configuration from JSON with the same structure as the TOML format.
+    ///
+    /// The document is an object whose keys are the TOML tables and arrays described in
+    /// [`from_toml`](Self::from_toml), such as `{"resources": [{"resource": "acct:..."}]}`. A
+    /// `null` value is the same as leaving the key out, except for a property value, where it
+    /// publishes a JSON `null` without the `{ "null": true }` marker.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Json`] when the JSON is malformed or is not an object, and otherwise
+    /// the same errors as [`from_toml`](Self::from_toml), with [`ConfigError::Schema`] for values
+    /// outside the format. Their [`ConfigLocation`] has a path but no line or column.
+    pub fn from_json(input: &str) -> Result<Self, ConfigError> {
+        Self::from_object(serde_json::from_str(input)?)
+    }
+
+    /// Parses WebFinger configuration from YAML with the same structure as the TOML format.
+    ///
+    /// See [`from_json`](Self::from_json) for how the document maps onto the TOML format, including
+    /// `null` values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Yaml`] when the YAML is malformed or is not a mapping with string
+    /// keys, and otherwise the same errors as [`from_json`](Self::from_json).
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(input: &str) -> Result<Self, ConfigError> {
+        Self::from_object(serde_yaml_ng::from_str(input)?)
+    }
+
+    /// Checks a document parsed from JSON or YAML, whose errors are located by path only.
+    fn from_object(
+        object: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Self, ConfigError> {
+        Self::from_raw(value::from_object(object)?, None)
+    }
+
+    fn from_raw(raw: RawConfig, input: Option<&str>) -> Result<Self, ConfigError> {
+        let root = Scope::root(input);
+        let redirects_scope = root.field("redirects");
+        let redirects = raw
+            .redirects
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, redirect)| {
+                let span = redirect.span();
+                redirect
+                    .into_inner()
+                    .into_redirect(&redirects_scope.index(index), index, span)
+            })
+            .collect::<Result<_, _>>()?;
+        let links_scope = root.field("host_meta").field("links");
+        let host_meta_links = raw
+            .host_meta
+            .and_then(|host_meta| host_meta.links)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, link)| {
+                let link = link.into_inner();
+                let visibility = link.visibility;
+                Ok((link.into_link(&links_scope.index(index))?, visibility))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+        let nodeinfo = raw
+            .nodeinfo
+            .map(|nodeinfo| {
+                root.locate_field(
+                    "nodeinfo",
+                    nodeinfo.span(),
+                    nodeinfo.into_inner().into_nodeinfo(),
+                )
+            })
+            .transpose()?;
+        let tokens_scope = root.field("tokens");
+        let token_digests = raw
+            .tokens
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, token)| {
+                tokens_scope.index(index).locate_field(
+                    "sha256",
+                    token.sha256.span(),
+                    parse_digest(token.sha256.get_ref())
+                        .ok_or(ConfigError::InvalidTokenDigest(index)),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+        let resources_scope = root.field("resources");
         let mut resources = BTreeMap::new();
-        for resource in raw.resources {
-            let response = resource.into_response()?;
-            let previous = resources.insert(response.subject.to_string(), response);
+        let mut actors = BTreeMap::new();
+        let mut resource_max_ages = BTreeMap::new();
+        for (index, resource) in raw.resources.into_iter().enumerate() {
+            let scope = resources_scope.index(index);
+            let resource = resource.into_inner();
+            let resource_span = resource.resource.span();
+            let actor_span = resource.actor.as_ref().map(Spanned::span);
+            let max_age = resource.max_age.map(Duration::from_secs);
+            let visibility = resource.visibility;
+            let (resource, actor) = resource.into_resource(&scope)?;
+            let key = resource.full.subject.to_string();
+            if let Some(actor) = actor {
+                let path = actor.path.clone();
+                if actors.insert(path.clone(), (actor, visibility)).is_some() {
+                    let error = ConfigError::InvalidActor {
+                        resource: key,
+                        reason: format!("path `{path}` is already used by another actor"),
+                    };
+                    return scope.locate_field("actor", actor_span.unwrap_or_default(), Err(error));
+                }
+            }
+            if let Some(max_age) = max_age {
+                resource_max_ages.insert(key.clone(), max_age);
+            }
+            let previous = resources.insert(key, resource);
             if let Some(previous) = previous {
-                return Err(ConfigError::DuplicateResource(previous.subject.to_string()));
+                let error = ConfigError::DuplicateResource(previous.full.subject.to_string());
+                return scope.locate_field("resource", resource_span, Err(error));
             }
         }
-        Ok(Self { resources })
+        Ok(Self {
+            resources,
+            actors,
+            redirects,
+            host_meta_links,
+            nodeinfo,
+            token_digests,
+            max_age: raw
+                .cache
+                .and_then(|cache| cache.max_age)
+                .map(Duration::from_secs),
+            resource_max_ages,
+        })
     }
 
-    /// Resolves a request against the configured resources.
+    /// Resolves an anonymous request against the configured resources.
     ///
-    /// Returns `None` when the requested resource is not present. Returned responses are cloned
-    /// from the config so relation filtering can remove links without mutating shared
-    /// configuration.
+    /// Returns `None` when the requested resource is not present or is token-only, and omits
+    /// token-only links. Returned responses are cloned from the config so relation filtering can
+    /// remove links without mutating shared configuration.
     pub fn resolve(&self, request: &WebFingerRequest) -> Option<WebFingerResponse> {
-        let response = self.resources.get(request.resource.as_ref())?;
+        self.resolve_with_token(request, None)
+    }
+
+    /// Resolves a request made with an optional bearer token.
+    ///
+    /// A token that matches a configured digest sees token-only resources and links. Any other
+    /// token, or none, gets the same answer as [`resolve`](Self::resolve).
+    pub fn resolve_with_token(
+        &self,
+        request: &WebFingerRequest,
+        bearer_token: Option<&str>,
+    ) -> Option<WebFingerResponse> {
+        let resource = self.resources.get(request.resource.as_ref())?;
+        let response = if bearer_token.is_some_and(|token| self.accepts_token(token)) {
+            &resource.full
+        } else {
+            resource.public.as_ref()?
+        };
         Some(filter_response(response.clone(), &request.rels))
     }
+
+    /// Resolves a request into a [`Resolution`], using the context's query string and bearer token.
+    ///
+    /// The first matching redirect rule answers with [`Resolution::Redirect`], or
+    /// [`Resolution::PermanentRedirect`] for a permanent rule, to the target's
+    /// `/.well-known/webfinger` with the request's query string unchanged. Otherwise the request is
+    /// resolved with [`resolve_with_token`](Self::resolve_with_token), and found resources carry
+    /// the [`cache_policy`](Self::cache_policy) for the request.
+    pub fn resolution(&self, request: &WebFingerRequest, context: &RequestContext) -> Resolution {
+        if let Some(redirect) = self
+            .redirects
+            .iter()
+            .find(|redirect| redirect.matches(request))
+        {
+            let location = redirect.location(request, context);
+            return if redirect.permanent {
+                Resolution::PermanentRedirect(location)
+            } else {
+                Resolution::Redirect(location)
+            };
+        }
+        match self.resolve_with_token(request, context.bearer_token()) {
+            Some(response) => Resolution::Found {
+                response,
+                cache: self.cache_policy(request),
+            },
+            None => Resolution::NotFound,
+        }
+    }
+
+    /// Returns the site-level host-meta links a request made with an optional bearer token may see.
+    ///
+    /// Token-only links are included only when the token matches a configured digest.
+    pub fn host_meta_links(&self, bearer_token: Option<&str>) -> Vec<Link> {
+        let authorized = bearer_token.is_some_and(|token| self.accepts_token(token));
+        self.host_meta_links
+            .iter()
+            .filter(|(_, visibility)| authorized || *visibility == Visibility::Public)
+            .map(|(link, _)| link.clone())
+            .collect()
+    }
+
+    /// Returns the NodeInfo configured in the `[nodeinfo]` table, if any.
+    pub fn nodeinfo(&self) -> Option<&NodeInfo> {
+        self.nodeinfo.as_ref()
+    }
+
+    /// Returns the actor served at a path for a request made with an optional bearer token.
+    ///
+    /// Actors of token-only resources are returned only when the token matches a configured digest.
+    pub fn actor(&self, path: &str, bearer_token: Option<&str>) -> Option<&Actor> {
+        let (actor, visibility) = self.actors.get(path)?;
+        (*visibility == Visibility::Public
+            || bearer_token.is_some_and(|token| self.accepts_token(token)))
+        .then_some(actor)
+    }
+
+    /// Returns whether a bearer token matches one of the configured token digests.
+    pub fn accepts_token(&self, token: &str) -> bool {
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        self.token_digests
+            .iter()
+            .any(|expected| constant_time_eq(expected, &digest))
+    }
+
+    /// Returns the cache policy configured for a request's resource.
+    ///
+    /// The resource's own `max_age` wins over the global `[cache]` value. Returns `None` when
+    /// neither is configured, so runtimes fall back to their default policy.
+    pub fn cache_policy(&self, request: &WebFingerRequest) -> Option<CachePolicy> {
+        let max_age = self
+            .resource_max_ages
+            .get(request.resource.as_ref())
+            .or(self.max_age.as_ref())?;
+        Some(CachePolicy::new().max_age(*max_age))
+    }
+}
+
+/// Parses a hex SHA-256 digest in either case.
+fn parse_digest(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut digest = [0; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).ok()?;
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Compares digests without returning early, so timing does not reveal matching prefixes.
+fn constant_time_eq(left: &[u8; 32], right: &[u8; 32]) -> bool {
+    left.iter()
+        .zip(right)
+        .fold(0, |difference, (left, right)| difference | (left ^ right))
+        == 0
+}
+
+/// Removes a trailing `:port` from a host, leaving bracketed IPv6 addresses intact.
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((name, port)) if !name.is_empty() && !port.contains(']') => name,
+        _ => host,
+    }
+}
+
+/// Matches `value` against a pattern in which `*` matches any run of characters.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
 }
 
 fn filter_response(mut response: WebFingerResponse, rels: &[Rel]) -> WebFingerResponse {
@@ -69,96 +457,920 @@ pub enum ConfigError {
     #[error("invalid TOML configuration: {0}")]
     Toml(#[from] toml::de::Error),
 
+    /// The JSON was malformed, or was not an object.
+    #[error("invalid JSON configuration: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The YAML was malformed, or was not a mapping.
+    #[cfg(feature = "yaml")]
+    #[error("invalid YAML configuration: {0}")]
+    Yaml(#[from] serde_yaml_ng::Error),
+
+    /// A configuration file used a format that this build does not support.
+    #[error("unsupported configuration format `{0}`; YAML needs the `yaml` feature")]
+    UnsupportedFormat(String),
+
+    /// A `${NAME}` reference could not be interpolated.
+    #[error(
+        "invalid `${{...}}` reference{}: {reason}",
+        line.map(|line| format!(" on line {line}")).unwrap_or_default()
+    )]
+    InvalidReference {
+        /// The line of the reference in TOML input, or `None` in a parsed JSON or YAML string,
+        /// which is located by path instead.
+        line: Option<usize>,
+        /// Why the reference was rejected.
+        reason: String,
+    },
+
+    /// A configuration file or directory could not be read.
+    #[error("could not read {}: {source}", .path.display())]
+    Io {
+        /// The file or directory.
+        path: PathBuf,
+        /// The I/O error.
+        source: std::io::Error,
+    },
+
+    /// A JSON or YAML value did not match the configuration format, for example because it used
+    /// an unsupported field.
+    #[error("invalid configuration: {0}")]
+    Schema(String),
+
     /// A configured resource appeared more than once.
     #[error("duplicate resource `{0}`")]
     DuplicateResource(String),
 
-    /// A configured resource or JRD URI field was invalid.
+    /// A resource loaded from a directory was already defined by an earlier file.
+    #[error("resource `{resource}` is already defined in {}", .first.display())]
+    DuplicateResourceFile {
+        /// The duplicated resource.
+        resource: String,
+        /// The file that defined it first.
+        first: PathBuf,
+    },
+
+    /// A table that applies to a whole directory of configuration was set by more than one file.
+    #[error("`[{table}]` is already set in {}", .first.display())]
+    DuplicateTable {
+        /// The name of the table.
+        table: &'static str,
+        /// The file that set it first.
+        first: PathBuf,
+    },
+
+    /// A resource set a shorthand key and a link with the same relation type.
+    #[error("resource `{resource}` sets a shorthand key and a link for the same rel `{rel}`")]
+    DuplicateLink {
+        /// The resource with both links.
+        resource: String,
+        /// The relation type of the duplicated link.
+        rel: String,
+    },
+
+    /// A link set both `href` and `template`, which RFC 6415 section 3.1.1.1 forbids.
+    #[error("link has both `href` and `template`")]
+    HrefAndTemplate,
+
+    /// A configured resource, JRD URI field, link media type, or title language was invalid.
     #[error(transparent)]
     WebFinger(#[from] webfinger_rs::Error),
 
     /// A configured property used the TOML null marker incorrectly.
     #[error("property `{0}` uses invalid null marker; use `{{ null = true }}`")]
     InvalidNullProperty(String),
+
+    /// The `sha256` value of the token at this index was not 64 hex digits.
+    #[error("token {0} has an invalid `sha256` value; expected 64 hex digits")]
+    InvalidTokenDigest(usize),
+
+    /// The redirect rule at this index did not set exactly one of `host` and `resource`.
+    #[error("redirect {0} must set exactly one of `host` and `resource`")]
+    InvalidRedirectRule(usize),
+
+    /// A redirect target was not an `https` origin.
+    #[error("redirect target `{0}` must be an `https` origin such as `https://example.com`")]
+    InvalidRedirectTarget(String),
+
+    /// The `[nodeinfo]` table was rejected by the NodeInfo 2.x schemas.
+    #[error("invalid `[nodeinfo]` table: {0}")]
+    InvalidNodeInfo(String),
+
+    /// The actor of a resource was invalid, or its path was already used by another actor.
+    #[error("invalid actor for resource `{resource}`: {reason}")]
+    InvalidActor {
+        /// The resource the actor belongs to.
+        resource: String,
+        /// Why the actor was rejected.
+        reason: String,
+    },
+
+    /// A value was rejected, at a known location in the TOML input.
+    #[error("invalid value {location}\n{}{error}", .location.snippet())]
+    Invalid {
+        /// Where the value was found.
+        location: Box<ConfigLocation>,
+        /// Why the value was rejected.
+        error: Box<ConfigError>,
+    },
+
+    /// A configuration file was rejected.
+    #[error("in {}: {error}", .path.display())]
+    File {
+        /// The file.
+        path: PathBuf,
+        /// Why the file was rejected.
+        error: Box<ConfigError>,
+    },
+}
+
+impl ConfigError {
+    /// Returns where in the configuration the error was found, if it is known.
+    ///
+    /// TOML syntax errors carry their own location in their message instead.
+    pub fn location(&self) -> Option<&ConfigLocation> {
+        match self {
+            ConfigError::Invalid { location, .. } => Some(location),
+            ConfigError::File { error, .. } => error.location(),
+            _ => None,
+        }
+    }
+
+    /// Returns the file the error was found in, when the configuration was loaded from files.
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            ConfigError::File { path, .. } | ConfigError::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its file and location, for matching on the kind of error.
+    pub fn without_location(&self) -> &ConfigError {
+        match self {
+            ConfigError::Invalid { error, .. } | ConfigError::File { error, .. } => {
+                error.without_location()
+            }
+            error => error,
+        }
+    }
+
+    /// Attaches a location, unless a more precise one was attached closer to the value.
+    fn at(self, input: Option<&str>, path: String, span: Range<usize>) -> Self {
+        match self {
+            ConfigError::Invalid { .. } => self,
+            error => ConfigError::Invalid {
+                location: Box::new(ConfigLocation::new(input, path, span)),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Attaches the file the error was found in, unless the error already names a file.
+    fn in_file(self, path: &Path) -> Self {
+        match self {
+            ConfigError::File { .. } | ConfigError::Io { .. } => self,
+            error => ConfigError::File {
+                path: path.to_path_buf(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+/// Where in the configuration an invalid value was found.
+///
+/// Every location has a path. Locations in TOML input also have a position; JSON and YAML input is
+/// parsed into values before it is checked, so its locations only have a path. Lines and columns
+/// count from 1, and columns count characters rather than bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLocation {
+    path: String,
+    position: Option<Position>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Position {
+    span: Range<usize>,
+    line: usize,
+    column: usize,
+    source_line: String,
+    width: usize,
+}
+
+impl ConfigLocation {
+    fn new(input: Option<&str>, path: String, span: Range<usize>) -> Self {
+        let position = input.map(|input| {
+            let start = span.start.min(input.len());
+            let line_start = input[..start].rfind('\n').map_or(0, |index| index + 1);
+            let line_end = input[start..]
+                .find('\n')
+                .map_or(input.len(), |index| start + index);
+            let end = span.end.clamp(start, line_end);
+            Position {
+                line: input[..start].matches('\n').count() + 1,
+                column: input[line_start..start].chars().count() + 1,
+                source_line: input[line_start..line_end]
+                    .trim_end_matches('\r')
+                    .to_string(),
+                width: input[start..end].chars().count().max(1),
+                span,
+            }
+        });
+        Self { path, position }
+    }
+
+    /// Returns the path of the value, such as `resources[12].links[3].href`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the byte range of the value in the TOML input.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.position.as_ref().map(|position| position.span.clone())
+    }
+
+    /// Returns the line of the value in the TOML input.
+    pub fn line(&self) -> Option<usize> {
+        self.position.as_ref().map(|position| position.line)
+    }
+
+    /// Returns the column of the value in the TOML input.
+    pub fn column(&self) -> Option<usize> {
+        self.position.as_ref().map(|position| position.column)
+    }
+
+    /// Renders the source line with the value underlined, in the style of TOML parse errors.
+    ///
+    /// Returns an empty string when the position is unknown, and ends with a newline otherwise.
+    fn snippet(&self) -> String {
+        let Some(position) = &self.position else {
+            return String::new();
+        };
+        let line = position.line.to_string();
+        let gutter = " ".repeat(line.len());
+        format!(
+            "{gutter} |\n{line} | {}\n{gutter} | {}{}\n",
+            position.source_line,
+            " ".repeat(position.column - 1),
+            "^".repeat(position.width),
+        )
+    }
+}
+
+impl fmt::Display for ConfigLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.path)?;
+        if let Some(position) = &self.position {
+            write!(f, " at line {}, column {}", position.line, position.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// The path of the TOML value being converted, so conversion errors can point at it.
+struct Scope<'a> {
+    /// The TOML input, or `None` when the configuration was parsed from another format.
+    input: Option<&'a str>,
+    path: String,
+}
+
+impl<'a> Scope<'a> {
+    fn root(input: Option<&'a str>) -> Self {
+        Self {
+            input,
+            path: String::new(),
+        }
+    }
+
+    /// Returns the scope of a field of this table.
+    fn field(&self, name: &str) -> Self {
+        Self {
+            input: self.input,
+            path: self.field_path(name),
+        }
+    }
+
+    /// Returns the scope of an element of this array.
+    fn index(&self, index: usize) -> Self {
+        Self {
+            input: self.input,
+            path: format!("{}[{index}]", self.path),
+        }
+    }
+
+    /// Appends a key to the path, quoted as in TOML unless it is a bare key.
+    fn field_path(&self, name: &str) -> String {
+        let bare = !name.is_empty()
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+        let name = if bare {
+            name.to_string()
+        } else {
+            format!("{name:?}")
+        };
+        if self.path.is_empty() {
+            name
+        } else {
+            format!("{}.{name}", self.path)
+        }
+    }
+
+    /// Locates an error raised while converting the value of this scope.
+    fn locate<T, E: Into<ConfigError>>(
+        &self,
+        span: Range<usize>,
+        result: Result<T, E>,
+    ) -> Result<T, ConfigError> {
+        result.map_err(|error| error.into().at(self.input, self.path.clone(), span))
+    }
+
+    /// Locates an error raised while converting a field of this table.
+    fn locate_field<T, E: Into<ConfigError>>(
+        &self,
+        name: &str,
+        span: Range<usize>,
+        result: Result<T, E>,
+    ) -> Result<T, ConfigError> {
+        result.map_err(|error| error.into().at(self.input, self.field_path(name), span))
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct RawConfig {
-    resources: Vec<RawResource>,
+    cache: Option<RawCache>,
+    tokens: Option<Vec<RawToken>>,
+    redirects: Option<Vec<Spanned<RawRedirect>>>,
+    host_meta: Option<RawHostMeta>,
+    nodeinfo: Option<Spanned<RawNodeInfo>>,
+    #[serde(default)]
+    resources: Vec<Spanned<RawResource>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHostMeta {
+    links: Option<Vec<Spanned<RawLink>>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNodeInfo {
+    software: RawSoftware,
+    protocols: Vec<String>,
+    #[serde(default)]
+    services: RawServices,
+    #[serde(default)]
+    open_registrations: bool,
+    #[serde(default)]
+    usage: RawUsage,
+    #[serde(default)]
+    metadata: BTreeMap<String, serde_json::Value>,
+}
+
+impl RawNodeInfo {
+    fn into_nodeinfo(self) -> Result<NodeInfo, ConfigError> {
+        let nodeinfo = NodeInfo {
+            software: Software {
+                name: self.software.name,
+                version: self.software.version,
+                repository: self.software.repository,
+                homepage: self.software.homepage,
+            },
+            protocols: self.protocols,
+            inbound: self.services.inbound,
+            outbound: self.services.outbound,
+            open_registrations: self.open_registrations,
+            usage: Usage {
+                users: Users {
+                    total: self.usage.users.total,
+                    active_halfyear: self.usage.users.active_halfyear,
+                    active_month: self.usage.users.active_month,
+                },
+                local_posts: self.usage.local_posts,
+                local_comments: self.usage.local_comments,
+            },
+            metadata: self.metadata,
+        };
+        nodeinfo.validate().map_err(ConfigError::InvalidNodeInfo)?;
+        Ok(nodeinfo)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSoftware {
+    name: String,
+    version: String,
+    repository: Option<String>,
+    homepage: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawServices {
+    #[serde(default)]
+    inbound: Vec<String>,
+    #[serde(default)]
+    outbound: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawUsage {
+    #[serde(default)]
+    users: RawUsers,
+    local_posts: Option<u64>,
+    local_comments: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawUsers {
+    total: Option<u64>,
+    active_halfyear: Option<u64>,
+    active_month: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRedirect {
+    host: Option<String>,
+    resource: Option<String>,
+    target: Spanned<String>,
+    #[serde(default)]
+    permanent: bool,
+}
+
+impl RawRedirect {
+    fn into_redirect(
+        self,
+        scope: &Scope<'_>,
+        index: usize,
+        span: Range<usize>,
+    ) -> Result<Redirect, ConfigError> {
+        let matcher = match (self.host, self.resource) {
+            (Some(host), None) => RedirectMatcher::Host(strip_port(&host).to_ascii_lowercase()),
+            (None, Some(pattern)) => RedirectMatcher::Resource(pattern),
+            _ => return scope.locate(span, Err(ConfigError::InvalidRedirectRule(index))),
+        };
+        let target = scope.locate_field(
+            "target",
+            self.target.span(),
+            parse_origin(self.target.get_ref())
+                .ok_or_else(|| ConfigError::InvalidRedirectTarget(self.target.get_ref().clone())),
+        )?;
+        Ok(Redirect {
+            matcher,
+            target,
+            permanent: self.permanent,
+        })
+    }
+}
+
+/// Parses an `https` origin with an optional trailing slash into `https://authority`.
+///
+/// WebFinger clients must not follow redirects to other schemes, so plain `http` is rejected.
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+fn parse_origin(target: &str) -> Option<String> {
+    let uri = target.parse::<Uri>().ok()?;
+    let authority = uri.authority()?;
+    let path_is_empty = matches!(uri.path(), "" | "/");
+    (uri.scheme() == Some(&Scheme::HTTPS) && path_is_empty && uri.query().is_none())
+        .then(|| format!("https://{authority}"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawToken {
+    sha256: Spanned<String>,
+}
+
+/// Who may see a configured resource or link.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Visibility {
+    /// Disclosed to every request.
+    #[default]
+    Public,
+    /// Disclosed only to requests with a configured bearer token.
+    Token,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCache {
+    max_age: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct RawResource {
-    resource: String,
-    aliases: Option<Vec<String>>,
-    properties: Option<BTreeMap<String, RawPropertyValue>>,
-    links: Option<Vec<RawLink>>,
+    resource: Spanned<String>,
+    aliases: Option<Vec<Spanned<String>>>,
+    properties: Option<RawProperties>,
+    links: Option<Vec<Spanned<RawLink>>>,
+    max_age: Option<u64>,
+    #[serde(default)]
+    visibility: Visibility,
+    actor: Option<Spanned<RawActor>>,
+    activitypub: Option<Spanned<String>>,
+    profile_page: Option<Spanned<String>>,
+    avatar: Option<Spanned<RawAvatar>>,
+    oidc_issuer: Option<Spanned<String>>,
+    subscribe_template: Option<Spanned<String>>,
+    openid: Option<Spanned<String>>,
+}
+
+/// A JRD file in a configuration directory, checked like a public resource of the TOML format.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawJrd {
+    subject: Spanned<String>,
+    aliases: Option<Vec<Spanned<String>>>,
+    properties: Option<RawProperties>,
+    links: Option<Vec<Spanned<RawJrdLink>>>,
+}
+
+/// A link of a [`RawJrd`], which has no `visibility`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawJrdLink {
+    rel: Spanned<String>,
+    r#type: Option<Spanned<String>>,
+    href: Option<Spanned<String>>,
+    template: Option<Spanned<String>>,
+    titles: Option<BTreeMap<Spanned<String>, String>>,
+    properties: Option<RawProperties>,
+}
+
+impl RawJrd {
+    fn into_resource(self) -> Result<Resource, ConfigError> {
+        let scope = Scope::root(None);
+        scope.locate_field(
+            "subject",
+            self.subject.span(),
+            WebFingerResponse::try_builder(self.subject.get_ref()),
+        )?;
+        let links = self.links.map(|links| {
+            links
+                .into_iter()
+                .map(|link| {
+                    let span = link.span();
+                    let link = link.into_inner();
+                    Spanned::new(
+                        span,
+                        RawLink {
+                            rel: link.rel,
+                            r#type: link.r#type,
+                            href: link.href,
+                            template: link.template,
+                            titles: link.titles,
+                            properties: link.properties,
+                            visibility: Visibility::Public,
+                        },
+                    )
+                })
+                .collect()
+        });
+        let resource = RawResource {
+            resource: self.subject,
+            aliases: self.aliases,
+            properties: self.properties,
+            links,
+            max_age: None,
+            visibility: Visibility::Public,
+            actor: None,
+            activitypub: None,
+            profile_page: None,
+            avatar: None,
+            oidc_issuer: None,
+            subscribe_template: None,
+            openid: None,
+        };
+        let (resource, _actor) = resource.into_resource(&scope)?;
+        Ok(resource)
+    }
+}
+
+/// The `avatar` shorthand of a resource.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAvatar {
+    href: Spanned<String>,
+    r#type: Option<Spanned<String>>,
 }
 
 impl RawResource {
-    fn into_response(self) -> Result<WebFingerResponse, ConfigError> {
-        let mut builder = WebFingerResponse::try_builder(&self.resource)?;
+    fn into_resource(
+        mut self,
+        scope: &Scope<'_>,
+    ) -> Result<(Resource, Option<Actor>), ConfigError> {
+        let actor = match self.actor.take() {
+            Some(actor) => {
+                let span = actor.span();
+                let actor = actor.into_inner().into_actor(self.resource.get_ref());
+                Some((scope.locate_field("actor", span.clone(), actor)?, span))
+            }
+            None => None,
+        };
+        let visibility = self.visibility;
+        let mut link_visibility = self
+            .links
+            .iter()
+            .flatten()
+            .map(|link| link.get_ref().visibility)
+            .collect::<Vec<_>>();
+        let resource = self.resource.get_ref().clone();
+        let mut full = self.into_response(scope)?;
+        let actor = match actor {
+            Some((actor, span)) => {
+                if full.links.iter().any(|link| link.rel.as_ref() == "self") {
+                    let error = ConfigError::InvalidActor {
+                        resource,
+                        reason: "the resource already has a `self` link".to_string(),
+                    };
+                    return scope.locate_field("actor", span, Err(error));
+                }
+                full.links.push(actor.self_link()?);
+                Some(actor)
+            }
+            None => None,
+        };
+        // Shorthand links and the actor's `self` link follow the configured links and share the
+        // resource's visibility.
+        link_visibility.resize(full.links.len(), Visibility::Public);
+        let public = (visibility == Visibility::Public).then(|| {
+            let mut public = full.clone();
+            let mut visibility = link_visibility.iter();
+            public
+                .links
+                .retain(|_| visibility.next() == Some(&Visibility::Public));
+            public
+        });
+        Ok((Resource { full, public }, actor))
+    }
+
+    fn into_response(self, scope: &Scope<'_>) -> Result<WebFingerResponse, ConfigError> {
+        let presets = self.preset_links(scope)?;
+        let mut builder = scope.locate_field(
+            "resource",
+            self.resource.span(),
+            WebFingerResponse::try_builder(self.resource.get_ref()),
+        )?;
         if let Some(aliases) = self.aliases {
-            for alias in aliases {
-                builder = builder.alias(alias);
+            let aliases_scope = scope.field("aliases");
+            for (index, alias) in aliases.into_iter().enumerate() {
+                let alias_scope = aliases_scope.index(index);
+                alias_scope.locate(alias.span(), JrdUri::try_new(alias.get_ref()))?;
+                builder = builder.alias(alias.into_inner());
             }
         }
         if let Some(properties) = self.properties {
-            for (key, value) in properties {
-                builder = match value.into_option(&key)? {
+            for (key, value) in parse_properties(&scope.field("properties"), properties)? {
+                builder = match value {
                     Some(value) => builder.property(key, value),
                     None => builder.null_property(key),
                 };
             }
         }
-        if let Some(links) = self.links {
-            let links = links
-                .into_iter()
-                .map(RawLink::into_link)
-                .collect::<Result<Vec<_>, _>>()?;
+        let links_scope = scope.field("links");
+        let mut links = self
+            .links
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(index, link)| link.into_inner().into_link(&links_scope.index(index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (key, span, preset) in presets {
+            if links.iter().any(|link| link.rel == preset.rel) {
+                let error = ConfigError::DuplicateLink {
+                    resource: self.resource.into_inner(),
+                    rel: preset.rel.to_string(),
+                };
+                return scope.locate_field(key, span, Err(error));
+            }
+            links.push(preset);
+        }
+        if !links.is_empty() {
             builder = builder.links(links);
         }
         Ok(builder.build())
     }
+
+    /// Expands the shorthand keys into links, in the order the keys are documented.
+    fn preset_links(&self, scope: &Scope<'_>) -> Result<Vec<PresetLink>, ConfigError> {
+        let mut links = Vec::new();
+        let href = |key: &'static str,
+                    rel: &str,
+                    r#type: Option<&str>,
+                    href: &Spanned<String>|
+         -> Result<PresetLink, ConfigError> {
+            let mut link = Link::new(Rel::try_new(rel)?);
+            link.r#type = r#type.map(MediaType::try_new).transpose()?;
+            link.href =
+                Some(scope.locate_field(key, href.span(), JrdUri::try_new(href.get_ref()))?);
+            Ok((key, href.span(), link))
+        };
+        if let Some(actor) = &self.activitypub {
+            links.push(href(
+                "activitypub",
+                "self",
+                Some(ACTIVITY_JSON_CONTENT_TYPE),
+                actor,
+            )?);
+        }
+        if let Some(page) = &self.profile_page {
+            links.push(href(
+                "profile_page",
+                PROFILE_PAGE_REL,
+                Some("text/html"),
+                page,
+            )?);
+        }
+        if let Some(avatar) = &self.avatar {
+            let (key, span, mut link) = href("avatar", AVATAR_REL, None, &avatar.get_ref().href)?;
+            if let Some(r#type) = &avatar.get_ref().r#type {
+                link.r#type = Some(scope.field("avatar").locate_field(
+                    "type",
+                    r#type.span(),
+                    MediaType::try_new(r#type.get_ref()),
+                )?);
+            }
+            links.push((key, span, link));
+        }
+        if let Some(issuer) = &self.oidc_issuer {
+            links.push(href("oidc_issuer", OIDC_ISSUER_REL, None, issuer)?);
+        }
+        if let Some(template) = &self.subscribe_template {
+            let mut link = Link::new(Rel::try_new(SUBSCRIBE_REL)?);
+            link.template = Some(template.get_ref().clone());
+            links.push(("subscribe_template", template.span(), link));
+        }
+        if let Some(provider) = &self.openid {
+            links.push(href("openid", OPENID_REL, None, provider)?);
+        }
+        Ok(links)
+    }
 }
 
+/// A link expanded from a shorthand key, with the key and the span of its value so a duplicate
+/// `rel` can be located.
+type PresetLink = (&'static str, Range<usize>, Link);
+
+/// The WebFinger profile page relation, used by the `profile_page` shorthand.
+const PROFILE_PAGE_REL: &str = "http://webfinger.net/rel/profile-page";
+
+/// The WebFinger avatar relation, used by the `avatar` shorthand.
+const AVATAR_REL: &str = "http://webfinger.net/rel/avatar";
+
+/// The OpenID Connect issuer discovery relation, used by the `oidc_issuer` shorthand.
+///
+/// See <https://openid.net/specs/openid-connect-discovery-1_0.html#IssuerDiscovery>.
+const OIDC_ISSUER_REL: &str = "http://openid.net/specs/connect/1.0/issuer";
+
+/// The OStatus remote follow relation, used by the `subscribe_template` shorthand.
+const SUBSCRIBE_REL: &str = "http://ostatus.org/schema/1.0/subscribe";
+
+/// The OpenID 2.0 provider relation, used by the `openid` shorthand.
+const OPENID_REL: &str = "http://specs.openid.net/auth/2.0/provider";
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
-struct RawLink {
-    rel: String,
+struct RawActor {
+    id: String,
     r#type: Option<String>,
-    href: Option<String>,
-    template: Option<String>,
-    titles: Option<BTreeMap<String, String>>,
-    properties: Option<BTreeMap<String, RawPropertyValue>>,
+    preferred_username: Option<String>,
+    name: Option<String>,
+    summary: Option<String>,
+    url: Option<String>,
+    inbox: Option<String>,
+    outbox: Option<String>,
+    #[serde(default)]
+    also_known_as: Vec<String>,
+    moved_to: Option<String>,
+}
+
+impl RawActor {
+    fn into_actor(self, resource: &str) -> Result<Actor, ConfigError> {
+        let preferred_username = self.preferred_username.or_else(|| {
+            let (user, _host) = resource.strip_prefix("acct:")?.rsplit_once('@')?;
+            Some(user.to_string())
+        });
+        let mut actor = Actor {
+            id: self.id,
+            path: String::new(),
+            kind: self.r#type.unwrap_or_else(|| "Person".to_string()),
+            preferred_username,
+            name: self.name,
+            summary: self.summary,
+            url: self.url,
+            inbox: self.inbox,
+            outbox: self.outbox,
+            also_known_as: self.also_known_as,
+            moved_to: self.moved_to,
+        };
+        actor
+            .validate()
+            .map_err(|reason| ConfigError::InvalidActor {
+                resource: resource.to_string(),
+                reason,
+            })?;
+        Ok(actor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLink {
+    rel: Spanned<String>,
+    r#type: Option<Spanned<String>>,
+    href: Option<Spanned<String>>,
+    template: Option<Spanned<String>>,
+    titles: Option<BTreeMap<Spanned<String>, String>>,
+    properties: Option<RawProperties>,
+    #[serde(default)]
+    visibility: Visibility,
 }
 
 impl RawLink {
-    fn into_link(self) -> Result<Link, ConfigError> {
-        let mut link = Link::new(Rel::try_new(self.rel)?);
-        link.r#type = self.r#type;
-        link.href = self.href.map(JrdUri::try_new).transpose()?;
-        link.template = self.template;
-        link.titles = self.titles;
-        link.properties = self.properties.map(parse_properties).transpose()?;
+    fn into_link(self, scope: &Scope<'_>) -> Result<Link, ConfigError> {
+        let mut link = Link::new(scope.locate_field(
+            "rel",
+            self.rel.span(),
+            Rel::try_new(self.rel.get_ref()),
+        )?);
+        link.r#type = self
+            .r#type
+            .map(|r#type| {
+                scope.locate_field("type", r#type.span(), MediaType::try_new(r#type.get_ref()))
+            })
+            .transpose()?;
+        link.href = self
+            .href
+            .map(|href| scope.locate_field("href", href.span(), JrdUri::try_new(href.get_ref())))
+            .transpose()?;
+        link.template = self
+            .template
+            .map(|template| {
+                let result = match link.href {
+                    Some(_) => Err(ConfigError::HrefAndTemplate),
+                    None => Ok(template.get_ref().clone()),
+                };
+                scope.locate_field("template", template.span(), result)
+            })
+            .transpose()?;
+        link.titles = self
+            .titles
+            .map(|titles| parse_titles(&scope.field("titles"), titles))
+            .transpose()?;
+        link.properties = self
+            .properties
+            .map(|properties| parse_properties(&scope.field("properties"), properties))
+            .transpose()?;
         Ok(link)
     }
 }
 
+fn parse_titles(
+    scope: &Scope<'_>,
+    titles: BTreeMap<Spanned<String>, String>,
+) -> Result<BTreeMap<String, String>, ConfigError> {
+    titles
+        .into_iter()
+        .map(|(language, value)| {
+            let title = scope.locate_field(
+                language.get_ref(),
+                language.span(),
+                Title::try_new(language.get_ref(), value),
+            )?;
+            Ok((title.language, title.value))
+        })
+        .collect()
+}
+
+/// A `properties` table, with the locations of its keys and values kept for error reporting.
+type RawProperties = BTreeMap<Spanned<String>, Spanned<RawPropertyValue>>;
+
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 enum RawPropertyValue {
     String(String),
-    Null { null: bool },
+    /// A JSON or YAML `null`, which TOML cannot express.
+    Null,
+    /// The `{ null = true }` marker.
+    NullMarker {
+        null: bool,
+    },
 }
 
 impl RawPropertyValue {
     fn into_option(self, key: &str) -> Result<Option<String>, ConfigError> {
         match self {
             RawPropertyValue::String(value) => Ok(Some(value)),
-            RawPropertyValue::Null { null } => {
+            RawPropertyValue::Null => Ok(None),
+            RawPropertyValue::NullMarker { null } => {
                 if null {
                     Ok(None)
                 } else {
@@ -170,13 +1382,16 @@ impl RawPropertyValue {
 }
 
 fn parse_properties(
-    properties: BTreeMap<String, RawPropertyValue>,
+    scope: &Scope<'_>,
+    properties: RawProperties,
 ) -> Result<BTreeMap<JrdUri, Option<String>>, ConfigError> {
     properties
         .into_iter()
         .map(|(key, value)| {
-            let property = JrdUri::try_new(key)?;
-            let value = value.into_option(property.as_ref())?;
+            let scope = scope.field(key.get_ref());
+            let property = scope.locate(key.span(), JrdUri::try_new(key.get_ref()))?;
+            let value =
+                scope.locate(value.span(), value.into_inner().into_option(key.get_ref()))?;
             Ok((property, value))
         })
         .collect()
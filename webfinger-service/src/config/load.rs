@@ -0,0 +1,455 @@
+//! Loading configuration from files and directories.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use super::{Config, ConfigError, RawJrd, Scope, value};
+
+impl Config {
+    /// Loads configuration from a file, or from a directory with [`from_dir`](Self::from_dir).
+    ///
+    /// The file is parsed by extension: `.json` with [`from_json`](Self::from_json), `.yaml` and
+    /// `.yml` with `from_yaml` when the `yaml` feature is enabled, and anything else with
+    /// [`from_toml`](Self::from_toml). `${NAME}` references in its strings are replaced with
+    /// environment variables as described in [`interpolate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Io`] when the file cannot be read, and otherwise the parser's error
+    /// wrapped in [`ConfigError::File`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Self::from_dir(path);
+        }
+        let input = read(path)?;
+        match extension(path) {
+            Some("json") => serde_json::from_str(&input)
+                .map_err(ConfigError::from)
+                .and_then(|object| Self::from_object(interpolate_values(object, &env)?)),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => serde_yaml_ng::from_str(&input)
+                .map_err(ConfigError::from)
+                .and_then(|object| Self::from_object(interpolate_values(object, &env)?)),
+            #[cfg(not(feature = "yaml"))]
+            Some(extension @ ("yaml" | "yml")) => {
+                Err(ConfigError::UnsupportedFormat(extension.to_string()))
+            }
+            _ => interpolate(&input, env).and_then(|input| Self::from_toml(&input)),
+        }
+        .map_err(|error| error.in_file(path))
+    }
+
+    /// Loads and merges every configuration file in a directory.
+    ///
+    /// Files are read in file name order, and `${NAME}` references are interpolated as in
+    /// [`from_path`](Self::from_path). Each `*.toml` file holds configuration in the format of
+    /// [`from_toml`](Self::from_toml), and each `*.json` file holds the JRD of one public resource.
+    /// A JRD is checked like a resource of that format, with `subject` in place of `resource`, so
+    /// it may only hold `subject`, `aliases`, `properties`, and `links` with valid values. Other
+    /// files and subdirectories are ignored.
+    ///
+    /// Resources, redirect rules, tokens, and host-meta links from every file are combined, and
+    /// redirect rules keep file order. Tokens apply to token-only resources in any file. A resource
+    /// or an actor path may be defined only once, and only one file may set the `[cache]`
+    /// `max_age` or the `[nodeinfo]` table.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Io`] when the directory or a file cannot be read, and otherwise the
+    /// first error wrapped in [`ConfigError::File`] with the file it was found in.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let dir = path.as_ref();
+        let mut paths = fs::read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|source| ConfigError::Io {
+                path: dir.to_path_buf(),
+                source,
+            })?;
+        paths.sort();
+        let mut config = Self::default();
+        let mut origins = Origins::default();
+        for path in paths {
+            let file = match extension(&path) {
+                Some("toml") if path.is_file() => {
+                    interpolate(&read(&path)?, env).and_then(|input| Self::from_toml(&input))
+                }
+                Some("json") if path.is_file() => serde_json::from_str(&read(&path)?)
+                    .map_err(ConfigError::from)
+                    .and_then(|object| Self::from_jrd(interpolate_values(object, &env)?)),
+                _ => continue,
+            };
+            file.and_then(|file| config.merge(file, &path, &mut origins))
+                .map_err(|error| error.in_file(&path))?;
+        }
+        Ok(config)
+    }
+
+    /// Checks a JRD file and returns a configuration holding it as one public resource.
+    fn from_jrd(object: Map<String, Value>) -> Result<Self, ConfigError> {
+        let jrd: RawJrd = value::from_object(object)?;
+        let resource = jrd.into_resource()?;
+        let mut config = Self::default();
+        config
+            .resources
+            .insert(resource.full.subject.to_string(), resource);
+        Ok(config)
+    }
+
+    /// Adds the configuration loaded from `path`, rejecting anything another file already set.
+    fn merge(
+        &mut self,
+        other: Config,
+        path: &Path,
+        origins: &mut Origins,
+    ) -> Result<(), ConfigError> {
+        for (actor_path, (actor, visibility)) in other.actors {
+            if let Some(first) = origins.actors.get(&actor_path) {
+                let resource = other
+                    .resources
+                    .iter()
+                    .find(|(_, resource)| {
+                        resource.full.links.iter().any(|link| {
+                            link.rel.as_ref() == "self"
+                                && link
+                                    .href
+                                    .as_ref()
+                                    .is_some_and(|href| href.as_ref() == actor.id())
+                        })
+                    })
+                    .map(|(key, _)| key.clone())
+                    .unwrap_or_default();
+                return Err(ConfigError::InvalidActor {
+                    resource,
+                    reason: format!(
+                        "path `{actor_path}` is already used by an actor in {}",
+                        first.display()
+                    ),
+                });
+            }
+            origins
+                .actors
+                .insert(actor_path.clone(), path.to_path_buf());
+            self.actors.insert(actor_path, (actor, visibility));
+        }
+        for (key, resource) in other.resources {
+            if let Some(first) = origins.resources.get(&key) {
+                return Err(ConfigError::DuplicateResourceFile {
+                    resource: key,
+                    first: first.clone(),
+                });
+            }
+            origins.resources.insert(key.clone(), path.to_path_buf());
+            self.resources.insert(key, resource);
+        }
+        if other.max_age.is_some() {
+            if let Some(first) = &origins.max_age {
+                return Err(ConfigError::DuplicateTable {
+                    table: "cache",
+                    first: first.clone(),
+                });
+            }
+            origins.max_age = Some(path.to_path_buf());
+            self.max_age = other.max_age;
+        }
+        if other.nodeinfo.is_some() {
+            if let Some(first) = &origins.nodeinfo {
+                return Err(ConfigError::DuplicateTable {
+                    table: "nodeinfo",
+                    first: first.clone(),
+                });
+            }
+            origins.nodeinfo = Some(path.to_path_buf());
+            self.nodeinfo = other.nodeinfo;
+        }
+        self.redirects.extend(other.redirects);
+        self.host_meta_links.extend(other.host_meta_links);
+        self.token_digests.extend(other.token_digests);
+        self.resource_max_ages.extend(other.resource_max_ages);
+        Ok(())
+    }
+}
+
+/// The files that first set each merged value, to name them in conflict errors.
+#[derive(Default)]
+struct Origins {
+    resources: BTreeMap<String, PathBuf>,
+    actors: BTreeMap<String, PathBuf>,
+    max_age: Option<PathBuf>,
+    nodeinfo: Option<PathBuf>,
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(OsStr::to_str)
+}
+
+/// Reads a configuration file.
+fn read(path: &Path) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Looks up a `${NAME}` reference in the process environment.
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Replaces `${NAME}` references in the strings of TOML configuration with values from `lookup`.
+///
+/// Use this for deployment-specific values, such as a host that differs between staging and
+/// production. [`Config::from_path`] and [`Config::from_dir`] interpolate the process environment
+/// into TOML files this way, and into the parsed string keys and values of JSON and YAML files;
+/// call this before [`Config::from_toml`] to do the same for TOML from another source.
+///
+/// References are only replaced inside TOML strings, and comments are left unchanged. Values are
+/// escaped for the string they are inserted into, so they cannot end the string or add TOML.
+/// Names start with an ASCII letter or `_` and continue with ASCII letters, digits, or `_`. `$${`
+/// is replaced with a literal `${`, and any other `$` is left unchanged.
+///
+/// # Errors
+///
+/// Returns [`ConfigError::InvalidReference`] when a reference is outside a string, is not closed,
+/// has an invalid name, names a variable that `lookup` does not know, or has a value that a
+/// literal string cannot hold.
+pub fn interpolate(
+    input: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, ConfigError> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    let mut string = None;
+    while let Some(char) = rest.chars().next() {
+        let invalid = |reason: String| ConfigError::InvalidReference {
+            line: Some(input[..input.len() - rest.len()].matches('\n').count() + 1),
+            reason,
+        };
+        let consumed = match string {
+            None if char == '#' => rest.find('\n').unwrap_or(rest.len()),
+            None => {
+                if rest.starts_with("${") {
+                    return Err(invalid(
+                        "references are only allowed inside strings".to_string(),
+                    ));
+                }
+                string = TomlString::opening(rest);
+                string.map_or(char.len_utf8(), |string| string.delimiter().len())
+            }
+            Some(kind) => {
+                if let Some(closing) = kind.closing(rest) {
+                    string = None;
+                    closing
+                } else if kind.escapes() && char == '\\' {
+                    1 + rest[1..].chars().next().map_or(0, char::len_utf8)
+                } else if let Some(after) = rest.strip_prefix("$${") {
+                    output.push_str("${");
+                    rest = after;
+                    continue;
+                } else if let Some(after) = rest.strip_prefix("${") {
+                    let (name, after) = reference(after).map_err(invalid)?;
+                    let value = lookup(name).ok_or_else(|| {
+                        invalid(format!("environment variable `{name}` is not set"))
+                    })?;
+                    kind.push_escaped(&mut output, name, &value)
+                        .map_err(invalid)?;
+                    rest = after;
+                    continue;
+                } else {
+                    char.len_utf8()
+                }
+            }
+        };
+        output.push_str(&rest[..consumed]);
+        rest = &rest[consumed..];
+    }
+    Ok(output)
+}
+
+/// Replaces `${NAME}` references in the string keys and values of a JSON or YAML document.
+///
+/// Errors are located by the path of the string, since the document has no TOML positions.
+pub(super) fn interpolate_values(
+    object: Map<String, Value>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<Map<String, Value>, ConfigError> {
+    interpolate_object(object, &Scope::root(None), lookup)
+}
+
+fn interpolate_object(
+    object: Map<String, Value>,
+    scope: &Scope<'_>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<Map<String, Value>, ConfigError> {
+    object
+        .into_iter()
+        .map(|(key, value)| {
+            let scope = scope.field(&key);
+            let key = scope.locate(0..0, expand(&key, lookup))?;
+            Ok((key, interpolate_value(value, &scope, lookup)?))
+        })
+        .collect()
+}
+
+fn interpolate_value(
+    value: Value,
+    scope: &Scope<'_>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<Value, ConfigError> {
+    Ok(match value {
+        Value::String(text) => Value::String(scope.locate(0..0, expand(&text, lookup))?),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| interpolate_value(value, &scope.index(index), lookup))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(object) => Value::Object(interpolate_object(object, scope, lookup)?),
+        value => value,
+    })
+}
+
+/// Replaces the references in one parsed string.
+fn expand(text: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, ConfigError> {
+    let invalid = |reason: String| ConfigError::InvalidReference { line: None, reason };
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let (name, after) = reference(after).map_err(invalid)?;
+            let value = lookup(name)
+                .ok_or_else(|| invalid(format!("environment variable `{name}` is not set")))?;
+            output.push_str(&value);
+            rest = after;
+        } else {
+            output.push('$');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Splits the name of a reference from the input after its `${`.
+fn reference(input: &str) -> Result<(&str, &str), String> {
+    let length = input
+        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+        .unwrap_or(input.len());
+    let (name, rest) = input.split_at(length);
+    match rest.strip_prefix('}') {
+        Some(rest) if name.starts_with(|char: char| char.is_ascii_alphabetic() || char == '_') => {
+            Ok((name, rest))
+        }
+        Some(_) => Err(format!("`{name}` is not a valid variable name")),
+        None => match rest.split_once('}') {
+            Some((tail, _)) if !tail.contains(['"', '\'', '\n']) => {
+                Err(format!("`{name}{tail}` is not a valid variable name"))
+            }
+            _ => Err("missing closing `}`".to_string()),
+        },
+    }
+}
+
+/// The kinds of TOML string, which differ in their delimiters and escapes.
+///
+/// See <https://toml.io/en/v1.0.0#string>.
+#[derive(Debug, Clone, Copy)]
+enum TomlString {
+    Basic,
+    MultilineBasic,
+    Literal,
+    MultilineLiteral,
+}
+
+impl TomlString {
+    /// Returns the string opened at the start of `input`, if any.
+    fn opening(input: &str) -> Option<Self> {
+        [
+            Self::MultilineBasic,
+            Self::Basic,
+            Self::MultilineLiteral,
+            Self::Literal,
+        ]
+        .into_iter()
+        .find(|string| input.starts_with(string.delimiter()))
+    }
+
+    fn delimiter(self) -> &'static str {
+        match self {
+            Self::Basic => "\"",
+            Self::MultilineBasic => "\"\"\"",
+            Self::Literal => "'",
+            Self::MultilineLiteral => "'''",
+        }
+    }
+
+    fn escapes(self) -> bool {
+        matches!(self, Self::Basic | Self::MultilineBasic)
+    }
+
+    fn multiline(self) -> bool {
+        matches!(self, Self::MultilineBasic | Self::MultilineLiteral)
+    }
+
+    /// Returns the length of the delimiter that closes the string at the start of `input`.
+    ///
+    /// A multiline string may end with up to two quotes of its content before the delimiter.
+    fn closing(self, input: &str) -> Option<usize> {
+        let quote = self.delimiter().chars().next()?;
+        if !self.multiline() {
+            return input.starts_with(quote).then_some(1);
+        }
+        let quotes = input.len() - input.trim_start_matches(quote).len();
+        (quotes >= 3).then_some(quotes.min(5))
+    }
+
+    /// Appends a value to the string, escaped so it stays inside it.
+    fn push_escaped(self, output: &mut String, name: &str, value: &str) -> Result<(), String> {
+        if self.escapes() {
+            for char in value.chars() {
+                match char {
+                    '"' => output.push_str("\\\""),
+                    '\\' => output.push_str("\\\\"),
+                    '\n' => output.push_str("\\n"),
+                    '\r' => output.push_str("\\r"),
+                    '\t' => output.push_str("\\t"),
+                    char if char.is_control() => {
+                        output.push_str(&format!("\\u{:04X}", u32::from(char)))
+                    }
+                    char => output.push(char),
+                }
+            }
+            return Ok(());
+        }
+        let allowed = |char: char| {
+            !char.is_control() || char == '\t' || (self.multiline() && matches!(char, '\n' | '\r'))
+        };
+        let fits = value.chars().all(allowed)
+            && if self.multiline() {
+                !value.contains("'''")
+            } else {
+                !value.contains('\'')
+            };
+        if !fits {
+            return Err(format!(
+                "the value of `{name}` cannot be written in a literal string; use a basic string"
+            ));
+        }
+        output.push_str(value);
+        Ok(())
+    }
+}
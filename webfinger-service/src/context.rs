@@ -0,0 +1,265 @@
+use std::fmt;
+use std::net::IpAddr;
+
+use http::HeaderMap;
+use http::header::{self, AsHeaderName};
+use http::request::Parts;
+use tracing::Span;
+use webfinger_rs::LanguageRange;
+
+/// Information about the HTTP request behind a WebFinger lookup.
+///
+/// Runtime adapters build a context for every lookup and pass it to
+/// [`WebFingerProvider::resolve_with_context`](crate::WebFingerProvider::resolve_with_context),
+/// so providers can vary their answer by language, authenticated caller, client address, or
+/// request ID without parsing HTTP themselves.
+///
+/// The [`span`](Self::span) is entered while the provider runs; record provider-specific fields on
+/// it or create child spans so provider logs line up with the adapter's request logs.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct RequestContext {
+    /// The request headers, including any the adapter does not interpret itself.
+    pub headers: HeaderMap,
+
+    /// The raw query string of the request, without the leading `?`, when the adapter knows it.
+    ///
+    /// Providers that redirect a query use it to forward every parameter exactly as sent.
+    pub query: Option<String>,
+
+    /// The address of the connecting client, when the runtime knows it.
+    ///
+    /// This is the direct peer or the client address reported by the platform, such as
+    /// Cloudflare's `CF-Connecting-IP`. It is not taken from `Forwarded` headers.
+    pub remote_addr: Option<IpAddr>,
+
+    /// The parsed `Authorization` header, if the request sent one.
+    pub authorization: Option<Authorization>,
+
+    /// The tracing span for this lookup.
+    pub span: Span,
+}
+
+impl RequestContext {
+    /// Creates a context from request headers, parsing the `Authorization` header.
+    ///
+    /// The query string and remote address are unknown and the span is disabled until set with
+    /// [`with_remote_addr`](Self::with_remote_addr) and [`with_span`](Self::with_span), or use
+    /// [`from_parts`](Self::from_parts).
+    pub fn from_headers(headers: HeaderMap) -> Self {
+        let authorization = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Authorization::parse);
+        Self {
+            headers,
+            query: None,
+            remote_addr: None,
+            authorization,
+            span: Span::none(),
+        }
+    }
+
+    /// Creates a context from the parts of an HTTP request.
+    ///
+    /// The query string comes from the request URI. The remote address comes from a [`RemoteAddr`]
+    /// request extension, which the server or an outer middleware inserts.
+    pub fn from_parts(parts: &Parts) -> Self {
+        let mut context = Self::from_headers(parts.headers.clone());
+        context.query = parts.uri.query().map(str::to_string);
+        context.remote_addr = parts
+            .extensions
+            .get::<RemoteAddr>()
+            .map(|RemoteAddr(address)| *address);
+        context
+    }
+
+    /// Sets the address of the connecting client.
+    pub fn with_remote_addr(mut self, address: IpAddr) -> Self {
+        self.remote_addr = Some(address);
+        self
+    }
+
+    /// Sets the tracing span for the lookup.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Returns a header value when it is present and valid visible ASCII.
+    pub fn header<K: AsHeaderName>(&self, name: K) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Returns the language ranges of the `Accept-Language` header, most preferred first.
+    ///
+    /// Returns an empty list when the header is missing.
+    pub fn accept_language(&self) -> Vec<LanguageRange> {
+        self.header(header::ACCEPT_LANGUAGE)
+            .map(LanguageRange::parse_accept_language)
+            .unwrap_or_default()
+    }
+
+    /// Returns the bearer token of the `Authorization` header, if it used the `Bearer` scheme.
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.authorization
+            .as_ref()
+            .and_then(Authorization::bearer_token)
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self::from_headers(HeaderMap::new())
+    }
+}
+
+impl fmt::Debug for RequestContext {
+    /// Omits header values, which may carry credentials or cookies.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestContext")
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("query", &self.query)
+            .field("remote_addr", &self.remote_addr)
+            .field("authorization", &self.authorization)
+            .field("span", &self.span)
+            .finish()
+    }
+}
+
+/// The address of the connecting client, stored as an HTTP request extension.
+///
+/// [`RequestContext::from_parts`] reads this extension. Insert it from the server's connection
+/// information, such as Axum's `ConnectInfo<SocketAddr>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RemoteAddr(pub IpAddr);
+
+/// The credentials of an `Authorization` request header.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-11.6.2>.
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Authorization {
+    /// A `Bearer` token.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6750.html#section-2.1>.
+    Bearer(String),
+
+    /// Credentials for any other scheme, such as `Basic`.
+    Other {
+        /// The authentication scheme as sent by the client.
+        scheme: String,
+        /// The credentials after the scheme, without surrounding whitespace.
+        credentials: String,
+    },
+}
+
+impl Authorization {
+    /// Parses an `Authorization` header value.
+    ///
+    /// The scheme is matched case-insensitively. Returns `None` when the value has no scheme or no
+    /// credentials.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (scheme, credentials) = value.trim().split_once([' ', '\t'])?;
+        let credentials = credentials.trim();
+        if scheme.is_empty() || credentials.is_empty() {
+            return None;
+        }
+        if scheme.eq_ignore_ascii_case("bearer") {
+            Some(Authorization::Bearer(credentials.to_string()))
+        } else {
+            Some(Authorization::Other {
+                scheme: scheme.to_string(),
+                credentials: credentials.to_string(),
+            })
+        }
+    }
+
+    /// Returns the token of a [`Authorization::Bearer`] value.
+    pub fn bearer_token(&self) -> Option<&str> {
+        match self {
+            Authorization::Bearer(token) => Some(token),
+            Authorization::Other { .. } => None,
+        }
+    }
+}
+
+impl fmt::Debug for Authorization {
+    /// Redacts credentials so contexts can be logged.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Authorization::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Authorization::Other { scheme, .. } => f
+                .debug_struct("Other")
+                .field("scheme", scheme)
+                .field("credentials", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn parses_bearer_authorization() {
+        let authorization = Authorization::parse("bearer  s3cr3t ").unwrap();
+
+        assert_eq!(authorization, Authorization::Bearer("s3cr3t".to_string()));
+        assert_eq!(authorization.bearer_token(), Some("s3cr3t"));
+    }
+
+    #[test]
+    fn parses_other_authorization_schemes() {
+        assert_eq!(
+            Authorization::parse("Basic YWxpY2U6cGFzcw=="),
+            Some(Authorization::Other {
+                scheme: "Basic".to_string(),
+                credentials: "YWxpY2U6cGFzcw==".to_string(),
+            }),
+        );
+        assert_eq!(Authorization::parse("Bearer"), None);
+    }
+
+    #[test]
+    fn debug_redacts_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer s3cr3t"),
+        );
+        let context = RequestContext::from_headers(headers);
+
+        let debug = format!("{context:?}");
+
+        assert_eq!(context.bearer_token(), Some("s3cr3t"));
+        assert!(!debug.contains("s3cr3t"), "{debug}");
+    }
+
+    #[test]
+    fn from_parts_reads_query_and_remote_addr_extension() {
+        let (mut parts, ()) = http::Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header(header::ACCEPT_LANGUAGE, "de, en;q=0.5")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let address = IpAddr::from([203, 0, 113, 7]);
+        parts.extensions.insert(RemoteAddr(address));
+
+        let context = RequestContext::from_parts(&parts);
+
+        assert_eq!(context.remote_addr, Some(address));
+        assert_eq!(
+            context.query.as_deref(),
+            Some("resource=acct:alice@example.com")
+        );
+        assert_eq!(
+            context.accept_language(),
+            LanguageRange::parse_accept_language("de, en;q=0.5"),
+        );
+    }
+}
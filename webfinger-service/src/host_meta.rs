@@ -0,0 +1,96 @@
+//! Host-wide discovery documents defined by [RFC 6415].
+//!
+//! Older OStatus and diaspora software and some XMPP clients start discovery at
+//! `/.well-known/host-meta` rather than at the WebFinger endpoint. The host-meta document lists
+//! links that apply to the whole host, and its `lrdd` link carries a URI template that points
+//! those clients at the WebFinger endpoint for per-resource lookups.
+//!
+//! [RFC 6415]: https://www.rfc-editor.org/rfc/rfc6415.html
+
+use webfinger_rs::{Link, ResponseFormat, WELL_KNOWN_PATH, WebFingerResponse};
+
+/// The well-known path of the XRD host-meta document.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-2>.
+pub const HOST_META_PATH: &str = "/.well-known/host-meta";
+
+/// The well-known path of the JRD host-meta document.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-6>.
+pub const HOST_META_JSON_PATH: &str = "/.well-known/host-meta.json";
+
+/// The relation type of the host-meta link that templates per-resource lookups.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-4.2>.
+pub const LRDD_REL: &str = "lrdd";
+
+/// Returns the format a host-meta path is served in, or `None` for any other path.
+///
+/// [`HOST_META_PATH`] is served as XRD and [`HOST_META_JSON_PATH`] as JRD with the
+/// `application/json` media type that RFC 6415 registers for it.
+pub fn host_meta_format(path: &str) -> Option<ResponseFormat> {
+    match path {
+        HOST_META_PATH => Some(ResponseFormat::Xrd),
+        HOST_META_JSON_PATH => Some(ResponseFormat::Json),
+        _ => None,
+    }
+}
+
+/// Builds the host-meta document for a host.
+///
+/// The subject is the host's `https` origin. The first link is an `lrdd` template for the
+/// WebFinger endpoint on the same host, followed by `site_links`.
+///
+/// # Errors
+///
+/// Returns an error if `host` does not form a valid origin URI.
+pub fn host_meta(
+    host: &str,
+    site_links: Vec<Link>,
+) -> Result<WebFingerResponse, webfinger_rs::Error> {
+    let origin = format!("https://{host}");
+    let lrdd = Link::builder(LRDD_REL)
+        .template(format!("{origin}{WELL_KNOWN_PATH}?resource={{uri}}"))
+        .build();
+    let mut links = vec![lrdd];
+    links.extend(site_links);
+    Ok(WebFingerResponse::try_builder(&origin)?
+        .links(links)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_meta_starts_with_lrdd_template() {
+        let site = Link::builder("http://nodeinfo.diaspora.software/ns/schema/2.1")
+            .href("https://example.com/nodeinfo/2.1")
+            .build();
+
+        let document = host_meta("example.com", vec![site.clone()]).unwrap();
+
+        assert_eq!(document.subject.as_ref(), "https://example.com");
+        assert_eq!(document.links.len(), 2);
+        assert_eq!(document.links[0].rel.as_ref(), LRDD_REL);
+        assert_eq!(
+            document.links[0].template.as_deref(),
+            Some("https://example.com/.well-known/webfinger?resource={uri}"),
+        );
+        assert_eq!(document.links[1], site);
+        assert!(document.to_xrd().contains(
+            r#"<Link rel="lrdd" template="https://example.com/.well-known/webfinger?resource={uri}"/>"#
+        ));
+    }
+
+    #[test]
+    fn host_meta_paths_select_formats() {
+        assert_eq!(host_meta_format(HOST_META_PATH), Some(ResponseFormat::Xrd));
+        assert_eq!(
+            host_meta_format(HOST_META_JSON_PATH),
+            Some(ResponseFormat::Json)
+        );
+        assert_eq!(host_meta_format(WELL_KNOWN_PATH), None);
+    }
+}
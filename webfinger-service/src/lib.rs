@@ -39,17 +39,65 @@
 //!
 //! [`WebFingerProvider`] is the async extension point used by runtime adapters. Implement it when
 //! WebFinger responses come from a database, Workers KV, a remote service, or another source that
-//! cannot be represented as static TOML. Providers own exact resource lookup and `rel` filtering;
-//! adapters own HTTP status codes, response headers, and logging.
+//! cannot be represented as static TOML. Providers own exact resource lookup and `rel` filtering,
+//! and may answer with a [`Resolution`] such as a redirect or `410 Gone`; adapters own HTTP status
+//! codes, response headers, and logging.
+//!
+//! Adapters also pass a [`RequestContext`] with the request headers, client address, parsed
+//! `Authorization` header, and a tracing span. Providers that answer differently per caller
+//! override [`WebFingerProvider::resolve_with_context`].
+//!
+//! # Host-meta
+//!
+//! [`host_meta`] builds the [RFC 6415] host-meta document that older discovery clients fetch from
+//! [`HOST_META_PATH`] and [`HOST_META_JSON_PATH`]: an `lrdd` template for the WebFinger endpoint
+//! followed by the site-level links of [`WebFingerProvider::host_meta_links`].
+//!
+//! [RFC 6415]: https://www.rfc-editor.org/rfc/rfc6415.html
+//!
+//! # NodeInfo
+//!
+//! [`nodeinfo_discovery`] renders the document served at [`NODEINFO_PATH`], which links one
+//! [`NodeInfo`] schema document per [`NodeInfoVersion`]. Providers supply the [`NodeInfo`] through
+//! [`WebFingerProvider::nodeinfo`]; the static configuration reads it from a `[nodeinfo]` table.
+//!
+//! # ActivityPub actors
+//!
+//! A resource may carry a minimal static [`Actor`], such as one that only points at the person's
+//! main account through `alsoKnownAs` or `movedTo`. Its JRD gains a `self` link of type
+//! [`ACTIVITY_JSON_CONTENT_TYPE`], and adapters serve the actor document at the path of its `id`
+//! from [`WebFingerProvider::actor`], negotiating the media type with [`actor_content_type`].
+//!
+//! # Features
+//!
+//! - `tower`: provides [`tower::WebFingerService`] and [`tower::WebFingerLayer`], which serve the
+//!   WebFinger endpoint from any provider in Tower-based servers such as Axum and Hyper.
+//! - `upstream`: provides [`upstream::UpstreamProvider`], which answers vanity resources by
+//!   forwarding lookups to another WebFinger server, such as a Mastodon instance.
+//! - `yaml`: provides [`Config::from_yaml`] and YAML files in [`Config::from_path`].
 
+mod actor;
 mod config;
+mod context;
+mod host_meta;
+mod nodeinfo;
 mod provider;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "upstream")]
+pub mod upstream;
 
 #[cfg(test)]
 mod tests;
 
-pub use crate::config::{Config, ConfigError};
-pub use crate::provider::{ProviderError, StaticConfigProvider, WebFingerProvider};
+pub use crate::actor::{ACTIVITY_JSON_CONTENT_TYPE, Actor, actor_content_type};
+pub use crate::config::{Config, ConfigError, ConfigLocation, interpolate};
+pub use crate::context::{Authorization, RemoteAddr, RequestContext};
+pub use crate::host_meta::{
+    HOST_META_JSON_PATH, HOST_META_PATH, LRDD_REL, host_meta, host_meta_format,
+};
+pub use crate::nodeinfo::{NODEINFO_PATH, NodeInfo, NodeInfoVersion, nodeinfo_discovery};
+pub use crate::provider::{ProviderError, Resolution, StaticConfigProvider, WebFingerProvider};
 
 /// The default configuration key used by deployable runtimes.
 pub const WEBFINGER_CONFIG_KEY: &str = "webfinger.toml";
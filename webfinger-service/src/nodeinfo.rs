@@ -0,0 +1,380 @@
+//! NodeInfo 2.x discovery and schema documents.
+//!
+//! Fediverse software fetches `/.well-known/nodeinfo` right after a WebFinger lookup to learn which
+//! software a host runs, and some peers treat a `404` there as a dead instance. The discovery
+//! document links to one document per supported schema version, which describes the software,
+//! protocols, third-party services, and usage statistics configured in the `[nodeinfo]` table.
+//!
+//! See <https://github.com/jhass/nodeinfo/blob/main/PROTOCOL.md>.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// The well-known path of the NodeInfo discovery document.
+pub const NODEINFO_PATH: &str = "/.well-known/nodeinfo";
+
+/// Protocols a NodeInfo 2.x document may list.
+const PROTOCOLS: &[&str] = &[
+    "activitypub",
+    "buddycloud",
+    "dfrn",
+    "diaspora",
+    "libertree",
+    "ostatus",
+    "pumpio",
+    "tent",
+    "xmpp",
+    "zot",
+];
+
+/// Third-party sites a NodeInfo 2.x server may retrieve messages from.
+const INBOUND_SERVICES: &[&str] = &[
+    "atom1.0",
+    "gnusocial",
+    "imap",
+    "pnut",
+    "pop3",
+    "pumpio",
+    "rss2.0",
+    "twitter",
+];
+
+/// Third-party sites a NodeInfo 2.x server may publish messages to.
+const OUTBOUND_SERVICES: &[&str] = &[
+    "atom1.0",
+    "blogger",
+    "buddycloud",
+    "diaspora",
+    "dreamwidth",
+    "drupal",
+    "facebook",
+    "friendica",
+    "gnusocial",
+    "google",
+    "insanejournal",
+    "libertree",
+    "linkedin",
+    "livejournal",
+    "mediagoblin",
+    "myspace",
+    "pinterest",
+    "pnut",
+    "posterous",
+    "pumpio",
+    "redmatrix",
+    "rss2.0",
+    "smtp",
+    "tent",
+    "tumblr",
+    "twitter",
+    "wordpress",
+    "xmpp",
+];
+
+/// A NodeInfo schema version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NodeInfoVersion {
+    /// NodeInfo 2.0.
+    V2_0,
+    /// NodeInfo 2.1, which adds the software `repository` and `homepage`.
+    V2_1,
+}
+
+impl NodeInfoVersion {
+    /// Every served version, newest first, in the order the discovery document lists them.
+    pub const ALL: &[NodeInfoVersion] = &[NodeInfoVersion::V2_1, NodeInfoVersion::V2_0];
+
+    /// Returns the version number, such as `2.1`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeInfoVersion::V2_0 => "2.0",
+            NodeInfoVersion::V2_1 => "2.1",
+        }
+    }
+
+    /// Returns the path the schema document is served at, such as `/nodeinfo/2.1`.
+    pub fn path(self) -> &'static str {
+        match self {
+            NodeInfoVersion::V2_0 => "/nodeinfo/2.0",
+            NodeInfoVersion::V2_1 => "/nodeinfo/2.1",
+        }
+    }
+
+    /// Returns the schema URI, used as the `rel` of the discovery link.
+    pub fn schema(self) -> &'static str {
+        match self {
+            NodeInfoVersion::V2_0 => "http://nodeinfo.diaspora.software/ns/schema/2.0",
+            NodeInfoVersion::V2_1 => "http://nodeinfo.diaspora.software/ns/schema/2.1",
+        }
+    }
+
+    /// Returns the `Content-Type` of the schema document, which names the schema as its profile.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            NodeInfoVersion::V2_0 => {
+                r#"application/json; profile="http://nodeinfo.diaspora.software/ns/schema/2.0#""#
+            }
+            NodeInfoVersion::V2_1 => {
+                r#"application/json; profile="http://nodeinfo.diaspora.software/ns/schema/2.1#""#
+            }
+        }
+    }
+
+    /// Returns the version whose schema document is served at `path`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|version| version.path() == path)
+    }
+}
+
+/// Server metadata published as NodeInfo, parsed from the `[nodeinfo]` configuration table.
+///
+/// Values are validated against the NodeInfo 2.x schemas when the configuration is loaded, so
+/// rendering a document cannot produce one that peers reject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfo {
+    pub(crate) software: Software,
+    pub(crate) protocols: Vec<String>,
+    pub(crate) inbound: Vec<String>,
+    pub(crate) outbound: Vec<String>,
+    pub(crate) open_registrations: bool,
+    pub(crate) usage: Usage,
+    pub(crate) metadata: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Software {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) repository: Option<String>,
+    pub(crate) homepage: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Usage {
+    pub(crate) users: Users,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) local_posts: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) local_comments: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Users {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) active_halfyear: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) active_month: Option<u64>,
+}
+
+impl NodeInfo {
+    /// Renders the schema document for a version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self, version: NodeInfoVersion) -> Result<Vec<u8>, serde_json::Error> {
+        let (repository, homepage) = match version {
+            NodeInfoVersion::V2_0 => (None, None),
+            NodeInfoVersion::V2_1 => (
+                self.software.repository.as_deref(),
+                self.software.homepage.as_deref(),
+            ),
+        };
+        serde_json::to_vec(&Document {
+            version: version.as_str(),
+            software: DocumentSoftware {
+                name: &self.software.name,
+                version: &self.software.version,
+                repository,
+                homepage,
+            },
+            protocols: &self.protocols,
+            services: Services {
+                inbound: &self.inbound,
+                outbound: &self.outbound,
+            },
+            open_registrations: self.open_registrations,
+            usage: &self.usage,
+            metadata: &self.metadata,
+        })
+    }
+
+    /// Validates the parts of a `[nodeinfo]` table that the schema constrains beyond their types.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let name = &self.software.name;
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+        {
+            return Err(format!(
+                "software name `{name}` must use only lowercase letters, digits, and `-`"
+            ));
+        }
+        if self.software.version.is_empty() {
+            return Err("software version must not be empty".to_string());
+        }
+        for (field, url) in [
+            ("repository", &self.software.repository),
+            ("homepage", &self.software.homepage),
+        ] {
+            if let Some(url) = url
+                && !is_http_url(url)
+            {
+                return Err(format!(
+                    "software {field} `{url}` must be an http or https URL"
+                ));
+            }
+        }
+        if self.protocols.is_empty() {
+            return Err("protocols must list at least one protocol".to_string());
+        }
+        check_known("protocol", &self.protocols, PROTOCOLS)?;
+        check_known("inbound service", &self.inbound, INBOUND_SERVICES)?;
+        check_known("outbound service", &self.outbound, OUTBOUND_SERVICES)?;
+        Ok(())
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.parse::<http::Uri>().is_ok_and(|uri| {
+        uri.authority().is_some() && matches!(uri.scheme_str(), Some("http" | "https"))
+    })
+}
+
+fn check_known(kind: &str, values: &[String], known: &[&str]) -> Result<(), String> {
+    match values.iter().find(|value| !known.contains(&value.as_str())) {
+        Some(value) => Err(format!("unknown {kind} `{value}`")),
+        None => Ok(()),
+    }
+}
+
+/// Renders the discovery document for a host, linking every [`NodeInfoVersion`].
+///
+/// # Errors
+///
+/// Returns an error if serialization fails.
+pub fn nodeinfo_discovery(host: &str) -> Result<Vec<u8>, serde_json::Error> {
+    let links = NodeInfoVersion::ALL
+        .iter()
+        .map(|version| DiscoveryLink {
+            rel: version.schema(),
+            href: format!("https://{host}{}", version.path()),
+        })
+        .collect();
+    serde_json::to_vec(&Discovery { links })
+}
+
+#[derive(Serialize)]
+struct Discovery {
+    links: Vec<DiscoveryLink>,
+}
+
+#[derive(Serialize)]
+struct DiscoveryLink {
+    rel: &'static str,
+    href: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    version: &'static str,
+    software: DocumentSoftware<'a>,
+    protocols: &'a [String],
+    services: Services<'a>,
+    open_registrations: bool,
+    usage: &'a Usage,
+    metadata: &'a BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct DocumentSoftware<'a> {
+    name: &'a str,
+    version: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Services<'a> {
+    inbound: &'a [String],
+    outbound: &'a [String],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodeinfo() -> NodeInfo {
+        NodeInfo {
+            software: Software {
+                name: "webfinger-rs".to_string(),
+                version: "1.0.0".to_string(),
+                repository: Some("https://github.com/joshka/webfinger-rs".to_string()),
+                homepage: None,
+            },
+            protocols: vec!["activitypub".to_string()],
+            inbound: Vec::new(),
+            outbound: vec!["rss2.0".to_string()],
+            open_registrations: false,
+            usage: Usage::default(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn repository_is_only_rendered_for_2_1() {
+        let v2_0: serde_json::Value =
+            serde_json::from_slice(&nodeinfo().to_json(NodeInfoVersion::V2_0).unwrap()).unwrap();
+        let v2_1: serde_json::Value =
+            serde_json::from_slice(&nodeinfo().to_json(NodeInfoVersion::V2_1).unwrap()).unwrap();
+
+        assert_eq!(
+            v2_0["software"],
+            serde_json::json!({ "name": "webfinger-rs", "version": "1.0.0" })
+        );
+        assert_eq!(
+            v2_1["software"]["repository"],
+            "https://github.com/joshka/webfinger-rs"
+        );
+        assert_eq!(v2_1["services"]["outbound"], serde_json::json!(["rss2.0"]));
+        assert_eq!(v2_1["usage"], serde_json::json!({ "users": {} }));
+    }
+
+    #[test]
+    fn discovery_links_every_version_on_the_host() {
+        let discovery: serde_json::Value =
+            serde_json::from_slice(&nodeinfo_discovery("example.com").unwrap()).unwrap();
+
+        assert_eq!(
+            discovery,
+            serde_json::json!({ "links": [
+                {
+                    "rel": "http://nodeinfo.diaspora.software/ns/schema/2.1",
+                    "href": "https://example.com/nodeinfo/2.1",
+                },
+                {
+                    "rel": "http://nodeinfo.diaspora.software/ns/schema/2.0",
+                    "href": "https://example.com/nodeinfo/2.0",
+                },
+            ]})
+        );
+        assert_eq!(
+            NodeInfoVersion::from_path("/nodeinfo/2.0"),
+            Some(NodeInfoVersion::V2_0)
+        );
+        assert_eq!(NodeInfoVersion::from_path("/nodeinfo/1.0"), None);
+    }
+}
@@ -1,9 +1,9 @@
 use std::future::Future;
 
 use thiserror::Error;
-use webfinger_rs::{WebFingerRequest, WebFingerResponse};
+use webfinger_rs::{CachePolicy, Link, WebFingerRequest, WebFingerResponse};
 
-use crate::{Config, ConfigError};
+use crate::{Actor, Config, ConfigError, NodeInfo, RequestContext};
 
 /// Resolves a WebFinger request into an optional JRD response.
 ///
@@ -18,10 +18,37 @@ use crate::{Config, ConfigError};
 /// - Return `Err(error)` when the backing store, configuration, or provider logic failed.
 /// - If `request.rels` is not empty, return only links whose `rel` is present in that list.
 ///
+/// Providers that need to answer with more than a JRD or nothing, such as a redirect to another
+/// host or `410 Gone` for a deleted account, override [`resolution`](Self::resolution) and return a
+/// [`Resolution`]. The default `resolution` adapts `resolve` and
+/// `cache_policy`, so providers written against the original signature keep working unchanged.
+///
+/// Providers that vary their answer by caller, such as by `Authorization`, client address, or
+/// `Accept-Language`, override [`resolve_with_context`](Self::resolve_with_context), which receives
+/// the [`RequestContext`] that adapters build from the HTTP request. It is the method adapters
+/// call; its default ignores the context and delegates to `resolution`, and through it to
+/// `resolve`.
+///
 /// Runtime adapters such as `webfinger-service-axum` and `webfinger-service-worker` own HTTP status
 /// codes, response headers, and logging. Providers should return domain results rather than HTTP
 /// responses.
 ///
+/// The returned futures must be `Send` so providers can be served from multi-threaded Tower
+/// services.
+///
+/// # Migrating to `Send` futures
+///
+/// Earlier releases accepted futures that were not `Send`. An `async fn` implementation keeps
+/// compiling as long as every value it holds across an `.await` is `Send`; when one is not, the
+/// compiler names it, typically an `Rc`, a `RefCell` borrow, or a `std::sync::MutexGuard`. Drop
+/// such values before the `.await` or replace them with `Arc` and an async-aware lock.
+///
+/// Single-threaded runtimes whose I/O futures are never `Send`, such as Cloudflare Workers, can
+/// return `worker::send::SendFuture::new(async move { ... })` from the method instead of writing
+/// it as an `async fn`, as `webfinger-service-worker`'s `KvConfigProvider` does for Workers KV
+/// reads. The provider itself does not need to be `Send` or `Sync` when it is served with
+/// `WebFingerService::serve` from the `tower` feature.
+///
 /// # Examples
 ///
 /// ```
@@ -80,14 +107,165 @@ pub trait WebFingerProvider {
     fn resolve<'a>(
         &'a self,
         request: &'a WebFingerRequest,
-    ) -> impl Future<Output = Result<Option<WebFingerResponse>, ProviderError>> + 'a;
+    ) -> impl Future<Output = Result<Option<WebFingerResponse>, ProviderError>> + Send + 'a;
+
+    /// Returns the cache policy for a response that [`resolve`](Self::resolve) returned.
+    ///
+    /// The default [`resolution`](Self::resolution) attaches it to [`Resolution::Found`], and
+    /// runtime adapters send the policy's `Cache-Control` header. Return `None`, the default, to
+    /// use the adapter's configured policy. The default `resolution` asks for the policy before it
+    /// awaits `resolve`, so a provider whose policy depends on data read during the lookup should
+    /// override `resolution` instead.
+    fn cache_policy(&self, _request: &WebFingerRequest) -> Option<CachePolicy> {
+        None
+    }
+
+    /// Resolves a WebFinger request into a [`Resolution`].
+    ///
+    /// The default [`resolve_with_context`](Self::resolve_with_context), which runtime adapters
+    /// call, delegates here. The default implementation calls
+    /// [`resolve`](Self::resolve) and maps `Some(response)` to [`Resolution::Found`] with the
+    /// [`cache_policy`](Self::cache_policy) for the request, and `None` to
+    /// [`Resolution::NotFound`]. Override it to return [`Resolution::Redirect`] or
+    /// [`Resolution::Gone`]; `resolve` then serves callers that only need the JRD, typically by
+    /// returning [`Resolution::into_response`].
+    fn resolution<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+    ) -> impl Future<Output = Result<Resolution, ProviderError>> + Send + 'a {
+        let cache = self.cache_policy(request);
+        let response = self.resolve(request);
+        async move {
+            let resolution = match response.await? {
+                Some(response) => Resolution::Found { response, cache },
+                None => Resolution::NotFound,
+            };
+            Ok(resolution)
+        }
+    }
+
+    /// Resolves a WebFinger request with the [`RequestContext`] of the HTTP request behind it.
+    ///
+    /// Runtime adapters call this method and run it inside [`RequestContext::span`]. The default
+    /// implementation ignores the context and calls [`resolution`](Self::resolution).
+    fn resolve_with_context<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+        _context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Resolution, ProviderError>> + Send + 'a {
+        self.resolution(request)
+    }
+
+    /// Returns the site-level links to publish in the host-meta documents.
+    ///
+    /// Runtime adapters that serve [`HOST_META_PATH`](crate::HOST_META_PATH) and
+    /// [`HOST_META_JSON_PATH`](crate::HOST_META_JSON_PATH) pass these links to
+    /// [`host_meta`](crate::host_meta), which puts them after the `lrdd` template for the WebFinger
+    /// endpoint. The default publishes no site-level links.
+    fn host_meta_links<'a>(
+        &'a self,
+        _context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Vec<Link>, ProviderError>> + Send + 'a {
+        std::future::ready(Ok(Vec::new()))
+    }
+
+    /// Returns the NodeInfo to publish, or `None` when the server does not publish NodeInfo.
+    ///
+    /// Runtime adapters that serve [`NODEINFO_PATH`](crate::NODEINFO_PATH) and the versioned
+    /// schema documents answer `404 Not Found` for `None`, the default.
+    fn nodeinfo<'a>(
+        &'a self,
+        _context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Option<NodeInfo>, ProviderError>> + Send + 'a {
+        std::future::ready(Ok(None))
+    }
+
+    /// Returns the ActivityPub actor served at a request path, or `None` when there is none.
+    ///
+    /// Runtime adapters that serve actors ask for every path they do not otherwise answer and
+    /// respond `404 Not Found` for `None`, the default.
+    fn actor<'a>(
+        &'a self,
+        _path: &'a str,
+        _context: &'a RequestContext,
+    ) -> impl Future<Output = Result<Option<Actor>, ProviderError>> + Send + 'a {
+        std::future::ready(Ok(None))
+    }
+}
+
+/// The outcome of resolving a valid WebFinger request.
+///
+/// Runtime adapters map each variant to an HTTP response:
+///
+/// | Resolution          | Response                                                |
+/// | ------------------- | ------------------------------------------------------- |
+/// | `Found`             | `200 OK` with the JRD, or `304 Not Modified`            |
+/// | `NotFound`          | `404 Not Found`                                         |
+/// | `Redirect`          | `307 Temporary Redirect` with `Location` set to the URL |
+/// | `PermanentRedirect` | `308 Permanent Redirect` with `Location` set to the URL |
+/// | `Gone`              | `410 Gone`                                              |
+///
+/// RFC 7033 section 4.2 lets a WebFinger server redirect a query, for example when an account has
+/// moved to another host. The redirect target should be an `https` URL, because clients must not
+/// follow redirects to other schemes.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Resolution {
+    /// The resource is known.
+    Found {
+        /// The JRD, already filtered to the requested `rel` values.
+        response: WebFingerResponse,
+        /// The cache policy for this response, or `None` to use the adapter's configured policy.
+        cache: Option<CachePolicy>,
+    },
+
+    /// The request is valid but the resource is unknown.
+    NotFound,
+
+    /// The resource is answered elsewhere; clients should repeat the query at this absolute URL.
+    Redirect(String),
+
+    /// The resource is answered elsewhere for good; clients should repeat this and future queries
+    /// at this absolute URL.
+    PermanentRedirect(String),
+
+    /// The resource existed but was removed, such as a deleted account.
+    Gone,
+}
+
+impl Resolution {
+    /// Returns a [`Resolution::Found`] that uses the adapter's configured cache policy.
+    pub fn found(response: WebFingerResponse) -> Self {
+        Resolution::Found {
+            response,
+            cache: None,
+        }
+    }
+
+    /// Returns the JRD of a [`Resolution::Found`], or `None` for every other outcome.
+    pub fn into_response(self) -> Option<WebFingerResponse> {
+        match self {
+            Resolution::Found { response, .. } => Some(response),
+            _ => None,
+        }
+    }
+}
+
+impl From<Option<WebFingerResponse>> for Resolution {
+    /// Maps the original [`WebFingerProvider::resolve`] result to `Found` or `NotFound`.
+    fn from(response: Option<WebFingerResponse>) -> Self {
+        response.map_or(Resolution::NotFound, Resolution::found)
+    }
 }
 
 /// A provider backed by a static parsed configuration.
 ///
 /// This provider is useful for local servers, tests, examples, and deployments where configuration
 /// is loaded before request handling begins. It applies the same exact resource matching and
-/// relation filtering described by [`WebFingerProvider`].
+/// relation filtering described by [`WebFingerProvider`], and discloses token-only resources and
+/// links only to requests whose bearer token matches the configuration.
 #[derive(Debug, Clone)]
 pub struct StaticConfigProvider {
     config: Config,
@@ -123,6 +301,56 @@ impl WebFingerProvider for StaticConfigProvider {
     ) -> Result<Option<WebFingerResponse>, ProviderError> {
         Ok(self.config.resolve(request))
     }
+
+    /// Returns the global or per-resource `max_age` from the parsed configuration.
+    fn cache_policy(&self, request: &WebFingerRequest) -> Option<CachePolicy> {
+        self.config.cache_policy(request)
+    }
+
+    /// Resolves a request like [`resolve_with_context`](Self::resolve_with_context) with an empty
+    /// context, so redirect rules apply and only public resources and links are disclosed.
+    async fn resolution<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+    ) -> Result<Resolution, ProviderError> {
+        Ok(self.config.resolution(request, &RequestContext::default()))
+    }
+
+    /// Resolves a request with the context's bearer token, disclosing token-only resources and
+    /// links when it matches a configured token, and answers configured redirect rules with the
+    /// context's query string.
+    async fn resolve_with_context<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+        context: &'a RequestContext,
+    ) -> Result<Resolution, ProviderError> {
+        Ok(self.config.resolution(request, context))
+    }
+
+    /// Returns the `[host_meta]` links the context's bearer token may see.
+    async fn host_meta_links<'a>(
+        &'a self,
+        context: &'a RequestContext,
+    ) -> Result<Vec<Link>, ProviderError> {
+        Ok(self.config.host_meta_links(context.bearer_token()))
+    }
+
+    /// Returns the `[nodeinfo]` table of the parsed configuration.
+    async fn nodeinfo<'a>(
+        &'a self,
+        _context: &'a RequestContext,
+    ) -> Result<Option<NodeInfo>, ProviderError> {
+        Ok(self.config.nodeinfo().cloned())
+    }
+
+    /// Returns the configured actor at `path` that the context's bearer token may see.
+    async fn actor<'a>(
+        &'a self,
+        path: &'a str,
+        context: &'a RequestContext,
+    ) -> Result<Option<Actor>, ProviderError> {
+        Ok(self.config.actor(path, context.bearer_token()).cloned())
+    }
 }
 
 /// Errors raised while loading or resolving provider data.
@@ -160,4 +388,13 @@ pub enum ProviderError {
     /// The configuration was invalid.
     #[error(transparent)]
     Config(#[from] ConfigError),
+
+    /// An upstream WebFinger server could not be reached or sent an invalid answer.
+    #[error("upstream lookup for `{resource}` failed: {message}")]
+    Upstream {
+        /// The upstream resource that was looked up.
+        resource: String,
+        /// The transport, status, or decoding error.
+        message: String,
+    },
 }
@@ -1,4 +1,5 @@
 use super::*;
+use sha2::{Digest, Sha256};
 use webfinger_rs::WebFingerRequest;
 
 const CONFIG: &str = r#"
@@ -68,7 +69,7 @@ resource = "acct:alice@example.com"
     .unwrap_err();
 
     assert!(matches!(
-        error,
+        error.without_location(),
         ConfigError::DuplicateResource(resource)
             if resource == "acct:alice@example.com"
     ));
@@ -88,12 +89,33 @@ resource = "acct:alice@example.com"
     .unwrap_err();
 
     assert!(matches!(
-        error,
+        error.without_location(),
         ConfigError::InvalidNullProperty(property)
             if property == "https://example.com/ns/display-name"
     ));
 }
 
+#[test]
+fn rejects_malformed_link_media_type() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources.links]]
+rel = "self"
+type = "activity+json"
+"#,
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        error.without_location(),
+        ConfigError::WebFinger(webfinger_rs::Error::InvalidMediaType(r#type))
+            if r#type == "activity+json"
+    ));
+}
+
 #[test]
 fn rejects_false_link_property_null_marker() {
     let error = Config::from_toml(
@@ -111,7 +133,7 @@ rel = "self"
     .unwrap_err();
 
     assert!(matches!(
-        error,
+        error.without_location(),
         ConfigError::InvalidNullProperty(property)
             if property == "https://example.com/ns/display-name"
     ));
@@ -171,6 +193,581 @@ fn filters_repeated_relation_requests() {
     assert_eq!(response.links.len(), 2);
 }
 
+#[test]
+fn omits_cache_policy_by_default() {
+    let config = Config::from_toml(CONFIG).unwrap();
+
+    let request = request("acct:alice@example.com", []);
+
+    assert_eq!(config.cache_policy(&request), None);
+}
+
+#[test]
+fn resource_max_age_overrides_global_cache_table() {
+    let config = Config::from_toml(
+        r#"
+[cache]
+max_age = 3600
+
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources]]
+resource = "acct:carol@example.com"
+max_age = 60
+"#,
+    )
+    .unwrap();
+
+    let alice = config.cache_policy(&request("acct:alice@example.com", []));
+    let carol = config.cache_policy(&request("acct:carol@example.com", []));
+
+    assert_eq!(
+        alice.unwrap().cache_control().as_deref(),
+        Some("max-age=3600")
+    );
+    assert_eq!(
+        carol.unwrap().cache_control().as_deref(),
+        Some("max-age=60")
+    );
+}
+
+#[test]
+fn rejects_unknown_cache_fields() {
+    let result = Config::from_toml("[cache]\nmaxage = 60\n");
+
+    assert!(result.is_err());
+}
+
+/// The SHA-256 digest of the token `s3cr3t`.
+const TOKEN_SHA256: &str = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd";
+
+const PRIVATE_CONFIG: &str = r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources.links]]
+rel = "self"
+href = "https://social.example/users/alice"
+
+[[resources.links]]
+rel = "http://webfinger.net/rel/avatar"
+href = "https://social.example/alice.png"
+visibility = "token"
+
+[[resources]]
+resource = "acct:bob@example.com"
+visibility = "token"
+"#;
+
+/// Anonymous requests see only public links, and token holders see every link.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-9.2>.
+#[test]
+fn token_links_are_disclosed_only_with_a_matching_token() {
+    let config = private_config();
+    let request = request("acct:alice@example.com", []);
+
+    let anonymous = config.resolve(&request).unwrap();
+    let wrong_token = config.resolve_with_token(&request, Some("guess")).unwrap();
+    let authorized = config.resolve_with_token(&request, Some("s3cr3t")).unwrap();
+
+    assert_eq!(anonymous.links.len(), 1);
+    assert_eq!(anonymous.links[0].rel.as_ref(), "self");
+    assert_eq!(wrong_token, anonymous);
+    assert_eq!(authorized.links.len(), 2);
+}
+
+/// Token-only resources resolve like unknown resources for anonymous requests.
+#[test]
+fn token_resources_are_indistinguishable_from_unknown_resources() {
+    let config = private_config();
+    let bob = request("acct:bob@example.com", []);
+    let carol = request("acct:carol@example.com", []);
+
+    assert_eq!(config.resolve(&bob), None);
+    assert_eq!(
+        config.resolution(&bob, &RequestContext::default()),
+        config.resolution(&carol, &RequestContext::default())
+    );
+    assert!(config.resolve_with_token(&bob, Some("s3cr3t")).is_some());
+}
+
+#[test]
+fn rejects_invalid_token_digest() {
+    let error =
+        Config::from_toml("resources = []\n\n[[tokens]]\nsha256 = \"s3cr3t\"\n").unwrap_err();
+
+    assert!(matches!(
+        error.without_location(),
+        ConfigError::InvalidTokenDigest(0)
+    ));
+}
+
+#[test]
+fn token_digest_is_sha256_hex() {
+    let digest = Sha256::digest(b"s3cr3t");
+    let hex = digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    assert_eq!(hex, TOKEN_SHA256);
+    assert!(private_config().accepts_token("s3cr3t"));
+}
+
+const REDIRECT_CONFIG: &str = r#"
+[[redirects]]
+host = "Old.Example"
+target = "https://new.example/"
+permanent = true
+
+[[redirects]]
+resource = "acct:*@moved.example"
+target = "https://social.example:8443"
+
+[[resources]]
+resource = "acct:alice@example.com"
+"#;
+
+/// A host rule redirects every query for that host, ignoring case and port.
+#[test]
+fn host_redirect_keeps_the_query_string() {
+    let config = Config::from_toml(REDIRECT_CONFIG).unwrap();
+    let request = WebFingerRequest::builder("acct:alice@old.example")
+        .unwrap()
+        .host("old.example:443")
+        .build();
+    let context = RequestContext {
+        query: Some(
+            "resource=acct%3Aalice%40old.example&rel=self&rel=http%3A%2F%2Fwebfinger.net%2Frel%2Favatar"
+                .to_string(),
+        ),
+        ..RequestContext::default()
+    };
+
+    assert_eq!(
+        config.resolution(&request, &context),
+        Resolution::PermanentRedirect(
+            "https://new.example/.well-known/webfinger?resource=acct%3Aalice%40old.example\
+             &rel=self&rel=http%3A%2F%2Fwebfinger.net%2Frel%2Favatar"
+                .to_string()
+        ),
+    );
+}
+
+/// A resource pattern redirects matching resources and leaves others to the configured resources.
+#[test]
+fn resource_pattern_redirect_is_temporary_by_default() {
+    let config = Config::from_toml(REDIRECT_CONFIG).unwrap();
+    let moved = request("acct:bob@moved.example", ["self", "profile"]);
+    let local = request("acct:alice@example.com", []);
+
+    assert_eq!(
+        config.resolution(&moved, &RequestContext::default()),
+        Resolution::Redirect(
+            "https://social.example:8443/.well-known/webfinger\
+             ?resource=acct%3Abob%40moved.example&rel=self&rel=profile"
+                .to_string()
+        ),
+    );
+    assert!(matches!(
+        config.resolution(&local, &RequestContext::default()),
+        Resolution::Found { .. }
+    ));
+    assert_eq!(
+        config.resolution(
+            &request("acct:bob@moved.example.org", []),
+            &RequestContext::default()
+        ),
+        Resolution::NotFound,
+    );
+}
+
+/// The provider answers redirect rules the same way with and without a request context.
+#[tokio::test]
+async fn provider_resolution_matches_resolve_with_context() {
+    let provider = StaticConfigProvider::new(Config::from_toml(REDIRECT_CONFIG).unwrap());
+    let moved = request("acct:bob@moved.example", []);
+
+    let resolution = provider.resolution(&moved).await.unwrap();
+
+    assert!(
+        matches!(resolution, Resolution::Redirect(_)),
+        "{resolution:?}"
+    );
+    assert_eq!(
+        resolution,
+        provider
+            .resolve_with_context(&moved, &RequestContext::default())
+            .await
+            .unwrap()
+    );
+}
+
+#[test]
+fn parses_redirect_only_config() {
+    let config = Config::from_toml(
+        "[[redirects]]
+host = \"example.com\"
+target = \"https://example.net\"
+",
+    )
+    .unwrap();
+
+    assert!(matches!(
+        config.resolution(&request("acct:alice@example.com", []), &RequestContext::default()),
+        Resolution::Redirect(location) if location.starts_with("https://example.net/.well-known/webfinger?")
+    ));
+}
+
+/// Clients must not follow redirects to non-HTTPS URLs, so such targets are rejected on load.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+#[test]
+fn rejects_non_https_redirect_targets() {
+    for target in [
+        "http://new.example",
+        "new.example",
+        "https://new.example/webfinger",
+        "https://new.example/?resource=x",
+    ] {
+        let input = format!("[[redirects]]\nhost = \"old.example\"\ntarget = \"{target}\"\n");
+        let error = Config::from_toml(&input).unwrap_err();
+
+        assert!(
+            matches!(error.without_location(), ConfigError::InvalidRedirectTarget(value) if value == target),
+            "{target}: {error}"
+        );
+    }
+}
+
+#[test]
+fn rejects_redirect_rules_without_exactly_one_matcher() {
+    for rule in [
+        "target = \"https://new.example\"",
+        "host = \"old.example\"\nresource = \"acct:*\"\ntarget = \"https://new.example\"",
+    ] {
+        let error = Config::from_toml(&format!("[[redirects]]\n{rule}\n")).unwrap_err();
+
+        assert!(
+            matches!(
+                error.without_location(),
+                ConfigError::InvalidRedirectRule(0)
+            ),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn host_meta_links_respect_visibility() {
+    let config = Config::from_toml(
+        r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[host_meta.links]]
+rel = "http://nodeinfo.diaspora.software/ns/schema/2.1"
+href = "https://example.com/nodeinfo/2.1"
+
+[[host_meta.links]]
+rel = "http://example.com/rel/admin"
+href = "https://example.com/admin"
+visibility = "token"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.host_meta_links(None).len(), 1);
+    assert_eq!(config.host_meta_links(Some("guess")).len(), 1);
+    assert_eq!(config.host_meta_links(Some("s3cr3t")).len(), 2);
+}
+
+#[test]
+fn parses_nodeinfo_table() {
+    let config = Config::from_toml(
+        r#"
+[nodeinfo]
+software = { name = "webfinger-rs", version = "1.0.0" }
+protocols = ["activitypub"]
+open_registrations = true
+
+[nodeinfo.usage]
+users = { total = 3, active_month = 1 }
+
+[nodeinfo.metadata]
+nodeName = "Example"
+"#,
+    )
+    .unwrap();
+
+    let document = config
+        .nodeinfo()
+        .unwrap()
+        .to_json(NodeInfoVersion::V2_0)
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&document).unwrap();
+    assert_eq!(document["version"], "2.0");
+    assert_eq!(document["openRegistrations"], true);
+    assert_eq!(document["usage"]["users"]["total"], 3);
+    assert_eq!(document["services"]["inbound"], serde_json::json!([]));
+    assert_eq!(document["metadata"]["nodeName"], "Example");
+    assert!(Config::from_toml("").unwrap().nodeinfo().is_none());
+}
+
+/// Peers validate NodeInfo documents against the schema, so values it rejects fail on load.
+#[test]
+fn rejects_nodeinfo_outside_the_schema() {
+    for table in [
+        "software = { name = \"WebFinger\", version = \"1.0.0\" }\nprotocols = [\"activitypub\"]",
+        "software = { name = \"webfinger-rs\", version = \"\" }\nprotocols = [\"activitypub\"]",
+        "software = { name = \"webfinger-rs\", version = \"1.0.0\" }\nprotocols = []",
+        "software = { name = \"webfinger-rs\", version = \"1.0.0\" }\nprotocols = [\"gopher\"]",
+        "software = { name = \"webfinger-rs\", version = \"1.0.0\", homepage = \"example.com\" }\nprotocols = [\"activitypub\"]",
+    ] {
+        let error = Config::from_toml(&format!("[nodeinfo]\n{table}\n")).unwrap_err();
+
+        assert!(
+            matches!(error.without_location(), ConfigError::InvalidNodeInfo(_)),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn actor_adds_self_link_and_serves_at_its_path() {
+    let config = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[resources.actor]
+id = "https://example.com/users/alice"
+moved_to = "https://social.example/users/alice"
+"#,
+    )
+    .unwrap();
+
+    let response = config
+        .resolve(&request("acct:alice@example.com", ["self"]))
+        .unwrap();
+    let actor = config.actor("/users/alice", None).unwrap();
+
+    assert_eq!(response.links.len(), 1);
+    assert_eq!(
+        response.links[0].r#type.as_ref().unwrap().as_ref(),
+        ACTIVITY_JSON_CONTENT_TYPE
+    );
+    assert_eq!(
+        response.links[0].href.as_ref().unwrap().as_ref(),
+        "https://example.com/users/alice"
+    );
+    let document: serde_json::Value = serde_json::from_slice(&actor.to_json().unwrap()).unwrap();
+    assert_eq!(document["preferredUsername"], "alice");
+    assert_eq!(document["movedTo"], "https://social.example/users/alice");
+    assert!(config.actor("/users/bob", None).is_none());
+}
+
+#[test]
+fn token_resource_actors_need_a_token() {
+    let config = Config::from_toml(
+        r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:alice@example.com"
+visibility = "token"
+actor = { id = "https://example.com/users/alice" }
+"#,
+    )
+    .unwrap();
+
+    assert!(config.actor("/users/alice", None).is_none());
+    assert!(config.actor("/users/alice", Some("s3cr3t")).is_some());
+}
+
+#[test]
+fn rejects_invalid_actors() {
+    for resource in [
+        "actor = { id = \"http://example.com/users/alice\" }",
+        "actor = { id = \"https://example.com/users/alice\", type = \"Note\" }",
+        "actor = { id = \"https://example.com/users/alice\", moved_to = \"alice\" }",
+        "actor = { id = \"https://example.com/users/alice\" }\nlinks = [{ rel = \"self\", href = \"https://example.com\" }]",
+    ] {
+        let input = format!("[[resources]]\nresource = \"acct:alice@example.com\"\n{resource}\n");
+        let error = Config::from_toml(&input).unwrap_err();
+
+        assert!(
+            matches!(error.without_location(), ConfigError::InvalidActor { resource, .. } if resource == "acct:alice@example.com"),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn rejects_actors_sharing_a_path() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+actor = { id = "https://example.com/users/alice" }
+
+[[resources]]
+resource = "acct:alias@example.com"
+actor = { id = "https://example.com/users/alice" }
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(error.without_location(), ConfigError::InvalidActor { resource, .. } if resource == "acct:alias@example.com"),
+        "{error}"
+    );
+}
+
+#[test]
+fn shorthand_keys_expand_into_links() {
+    let config = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+activitypub = "https://social.example/users/alice"
+profile_page = "https://social.example/@alice"
+avatar = { href = "https://social.example/alice.png", type = "image/png" }
+oidc_issuer = "https://auth.example.com"
+subscribe_template = "https://social.example/authorize_interaction?uri={uri}"
+openid = "https://openid.example.com"
+
+[[resources.links]]
+rel = "http://example.com/rel/blog"
+href = "https://blog.example.com"
+"#,
+    )
+    .unwrap();
+
+    let response = config
+        .resolve(&request("acct:alice@example.com", []))
+        .unwrap();
+
+    let links = response
+        .links
+        .iter()
+        .map(|link| {
+            (
+                link.rel.as_ref(),
+                link.r#type.as_ref().map(|media_type| media_type.as_ref()),
+                link.href
+                    .as_ref()
+                    .map(|href| href.as_ref())
+                    .or(link.template.as_deref()),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        links,
+        [
+            (
+                "http://example.com/rel/blog",
+                None,
+                Some("https://blog.example.com")
+            ),
+            (
+                "self",
+                Some("application/activity+json"),
+                Some("https://social.example/users/alice")
+            ),
+            (
+                "http://webfinger.net/rel/profile-page",
+                Some("text/html"),
+                Some("https://social.example/@alice")
+            ),
+            (
+                "http://webfinger.net/rel/avatar",
+                Some("image/png"),
+                Some("https://social.example/alice.png")
+            ),
+            (
+                "http://openid.net/specs/connect/1.0/issuer",
+                None,
+                Some("https://auth.example.com")
+            ),
+            (
+                "http://ostatus.org/schema/1.0/subscribe",
+                None,
+                Some("https://social.example/authorize_interaction?uri={uri}")
+            ),
+            (
+                "http://specs.openid.net/auth/2.0/provider",
+                None,
+                Some("https://openid.example.com")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn shorthand_links_are_public_next_to_token_links() {
+    let config = Config::from_toml(
+        r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:alice@example.com"
+profile_page = "https://social.example/@alice"
+
+[[resources.links]]
+rel = "http://example.com/rel/admin"
+href = "https://example.com/admin"
+visibility = "token"
+"#,
+    )
+    .unwrap();
+    let request = request("acct:alice@example.com", []);
+
+    let public = config.resolve(&request).unwrap();
+    let full = config.resolve_with_token(&request, Some("s3cr3t")).unwrap();
+
+    assert_eq!(public.links.len(), 1);
+    assert_eq!(
+        public.links[0].rel.as_ref(),
+        "http://webfinger.net/rel/profile-page"
+    );
+    assert_eq!(full.links.len(), 2);
+}
+
+#[test]
+fn rejects_shorthand_keys_duplicating_links() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+activitypub = "https://social.example/users/alice"
+
+[[resources.links]]
+rel = "self"
+type = "application/activity+json"
+href = "https://social.example/users/alice"
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(error.without_location(), ConfigError::DuplicateLink { resource, rel }
+            if resource == "acct:alice@example.com" && rel == "self"),
+        "{error}"
+    );
+}
+
+fn private_config() -> Config {
+    Config::from_toml(PRIVATE_CONFIG).unwrap()
+}
+
 fn request<const N: usize>(resource: &str, rels: [&str; N]) -> WebFingerRequest {
     let mut builder = WebFingerRequest::builder(resource)
         .unwrap()
@@ -180,3 +777,479 @@ fn request<const N: usize>(resource: &str, rels: [&str; N]) -> WebFingerRequest
     }
     builder.build()
 }
+
+#[test]
+fn errors_point_at_the_invalid_value() {
+    let input = r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources]]
+resource = "acct:bob@example.com"
+
+[[resources.links]]
+rel = "self"
+type = "activity+json"
+"#;
+    let error = Config::from_toml(input).unwrap_err();
+
+    let location = error.location().unwrap();
+    assert_eq!(location.path(), "resources[1].links[0].type");
+    assert_eq!((location.line(), location.column()), (Some(10), Some(8)));
+    assert_eq!(&input[location.span().unwrap()], r#""activity+json""#);
+    assert_eq!(
+        error.to_string(),
+        r#"invalid value `resources[1].links[0].type` at line 10, column 8
+   |
+10 | type = "activity+json"
+   |        ^^^^^^^^^^^^^^^
+invalid media type: activity+json"#
+    );
+}
+
+#[test]
+fn property_errors_name_the_quoted_key() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+properties = { "https://example.com/ns/display-name" = { null = false } }
+"#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error.location().unwrap().path(),
+        r#"resources[0].properties."https://example.com/ns/display-name""#
+    );
+}
+
+#[test]
+fn rejects_invalid_aliases() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+aliases = ["https://example.com/@alice", "not a uri"]
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(
+            error.without_location(),
+            ConfigError::WebFinger(webfinger_rs::Error::InvalidJrdUri(alias)) if alias == "not a uri"
+        ),
+        "{error}"
+    );
+    assert_eq!(error.location().unwrap().path(), "resources[0].aliases[1]");
+}
+
+#[test]
+fn rejects_invalid_title_languages() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources.links]]
+rel = "http://webfinger.net/rel/profile-page"
+href = "https://example.com/alice"
+titles = { en = "Profile", "not a tag" = "Profil" }
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(
+            error.without_location(),
+            ConfigError::WebFinger(webfinger_rs::Error::InvalidLanguageTag(tag)) if tag == "not a tag"
+        ),
+        "{error}"
+    );
+    assert_eq!(
+        error.location().unwrap().path(),
+        r#"resources[0].links[0].titles."not a tag""#
+    );
+}
+
+#[test]
+fn rejects_links_with_href_and_template() {
+    let error = Config::from_toml(
+        r#"
+[[resources]]
+resource = "acct:alice@example.com"
+
+[[resources.links]]
+rel = "http://ostatus.org/schema/1.0/subscribe"
+href = "https://example.com/authorize"
+template = "https://example.com/authorize?uri={uri}"
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(error.without_location(), ConfigError::HrefAndTemplate),
+        "{error}"
+    );
+    assert_eq!(
+        error.location().unwrap().path(),
+        "resources[0].links[0].template"
+    );
+}
+
+const CONFIG_JSON: &str = r#"{
+  "resources": [
+    {
+      "resource": "acct:alice@example.com",
+      "aliases": ["https://social.example/@alice"],
+      "properties": {
+        "https://example.com/ns/display-name": "Alice",
+        "https://example.com/ns/old-name": { "null": true }
+      },
+      "links": [
+        {
+          "rel": "self",
+          "type": "application/activity+json",
+          "href": "https://social.example/users/alice"
+        },
+        {
+          "rel": "http://webfinger.net/rel/profile-page",
+          "type": "text/html",
+          "href": "https://social.example/@alice"
+        },
+        {
+          "rel": "http://ostatus.org/schema/1.0/subscribe",
+          "template": "https://social.example/authorize_interaction?uri={uri}"
+        }
+      ]
+    }
+  ]
+}"#;
+
+#[test]
+fn json_config_matches_toml_config() {
+    assert_eq!(
+        Config::from_json(CONFIG_JSON).unwrap(),
+        Config::from_toml(CONFIG).unwrap()
+    );
+}
+
+#[test]
+fn json_errors_are_located_by_path_only() {
+    let error = Config::from_json(
+        r#"{"resources": [{"resource": "acct:alice@example.com", "links": [{"rel": "self", "type": "activity+json"}]}]}"#,
+    )
+    .unwrap_err();
+
+    let location = error.location().unwrap();
+    assert_eq!(location.path(), "resources[0].links[0].type");
+    assert_eq!(location.line(), None);
+    assert!(matches!(
+        error.without_location(),
+        ConfigError::WebFinger(webfinger_rs::Error::InvalidMediaType(_))
+    ));
+}
+
+#[test]
+fn rejects_json_outside_the_config_format() {
+    let error = Config::from_json(r#"{"resources": [], "cache": {"ttl": 60}}"#).unwrap_err();
+    assert!(
+        matches!(error.without_location(), ConfigError::Schema(_)),
+        "{error}"
+    );
+    assert_eq!(error.location().unwrap().path(), "cache.ttl");
+
+    let error = Config::from_json(r#"{"resources": [null]}"#).unwrap_err();
+    assert!(
+        matches!(error.without_location(), ConfigError::Schema(_)),
+        "{error}"
+    );
+    assert_eq!(error.location().unwrap().path(), "resources[0]");
+
+    let error = Config::from_json("[]").unwrap_err();
+    assert!(matches!(error, ConfigError::Json(_)), "{error}");
+}
+
+#[test]
+fn json_null_values_are_absent_keys_or_null_properties() {
+    let config = Config::from_json(
+        r#"{
+  "cache": null,
+  "resources": [
+    {
+      "resource": "acct:alice@example.com",
+      "aliases": null,
+      "properties": { "https://example.com/ns/old-name": null }
+    }
+  ]
+}"#,
+    )
+    .unwrap();
+
+    let response = config
+        .resolve(&request("acct:alice@example.com", []))
+        .unwrap();
+    assert!(response.aliases.is_none());
+    assert_eq!(
+        response.properties.unwrap().values().collect::<Vec<_>>(),
+        [&None]
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_config_matches_toml_config() {
+    let config = Config::from_yaml(
+        r#"
+resources:
+  - resource: acct:alice@example.com
+    aliases: [https://social.example/@alice]
+    properties:
+      https://example.com/ns/display-name: Alice
+      https://example.com/ns/old-name: null
+    links:
+      - rel: self
+        type: application/activity+json
+        href: https://social.example/users/alice
+      - rel: http://webfinger.net/rel/profile-page
+        type: text/html
+        href: https://social.example/@alice
+      - rel: http://ostatus.org/schema/1.0/subscribe
+        template: https://social.example/authorize_interaction?uri={uri}
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config, Config::from_toml(CONFIG).unwrap());
+}
+
+#[test]
+fn interpolates_variables() {
+    let lookup = |name: &str| (name == "SOCIAL_HOST").then(|| "social.example".to_string());
+
+    assert_eq!(
+        interpolate("href = \"https://${SOCIAL_HOST}/@alice\"", lookup).unwrap(),
+        "href = \"https://social.example/@alice\""
+    );
+    assert_eq!(
+        interpolate("price = \"$5\"\ntemplate = \"$${literal}\"", lookup).unwrap(),
+        "price = \"$5\"\ntemplate = \"${literal}\""
+    );
+}
+
+#[test]
+fn interpolation_escapes_values_and_skips_comments() {
+    let lookup = |name: &str| (name == "NAME").then(|| "A \"quoted\" \\ name".to_string());
+
+    let output = interpolate(
+        "# ${UNSET} is documented here\nname = \"${NAME}\"\nnote = '''\n${NAME}'''\n",
+        lookup,
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        "# ${UNSET} is documented here\nname = \"A \\\"quoted\\\" \\\\ name\"\nnote = '''\nA \"quoted\" \\ name'''\n"
+    );
+    let config: toml::Table = toml::from_str(&output).unwrap();
+    assert_eq!(config["name"].as_str(), Some("A \"quoted\" \\ name"));
+}
+
+#[test]
+fn rejects_invalid_references() {
+    for (input, reason) in [
+        (
+            "\nhost = \"${MISSING}\"",
+            "environment variable `MISSING` is not set",
+        ),
+        (
+            "\nhost = \"${1HOST}\"",
+            "`1HOST` is not a valid variable name",
+        ),
+        ("\nhost = \"${HOST\"", "missing closing `}`"),
+        (
+            "\nmax_age = ${MAX_AGE}",
+            "references are only allowed inside strings",
+        ),
+        (
+            "\nhost = '${QUOTE}'",
+            "the value of `QUOTE` cannot be written in a literal string; use a basic string",
+        ),
+    ] {
+        let lookup = |name: &str| (name == "QUOTE").then(|| "it's".to_string());
+        let error = interpolate(input, lookup).unwrap_err();
+
+        assert!(
+            matches!(&error, ConfigError::InvalidReference { line: Some(2), reason: actual } if actual == reason),
+            "{error}"
+        );
+    }
+}
+
+/// Creates an empty directory for a test under the system temporary directory.
+fn config_dir(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("webfinger-service-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn directory_merges_toml_files_and_jrd_files() {
+    let dir = config_dir(
+        "merge",
+        &[
+            ("alice.toml", CONFIG),
+            (
+                "bob.json",
+                r#"{"subject": "acct:bob@example.com", "links": [{"rel": "self", "href": "https://social.example/users/bob"}]}"#,
+            ),
+            (
+                "cache.toml",
+                "[cache]\nmax_age = 60\n\n[[redirects]]\nhost = \"old.example\"\ntarget = \"https://example.com\"\n",
+            ),
+            ("README.md", "not configuration"),
+        ],
+    );
+
+    let config = Config::from_path(&dir).unwrap();
+
+    assert!(
+        config
+            .resolve(&request("acct:alice@example.com", []))
+            .is_some()
+    );
+    let bob = config
+        .resolve(&request("acct:bob@example.com", []))
+        .unwrap();
+    assert_eq!(
+        bob.links[0].href.as_ref().unwrap().as_ref(),
+        "https://social.example/users/bob"
+    );
+    let policy = config.cache_policy(&request("acct:bob@example.com", []));
+    assert_eq!(
+        policy.unwrap().cache_control().as_deref(),
+        Some("max-age=60")
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn json_files_interpolate_parsed_strings() {
+    // Cargo sets `CARGO_PKG_NAME` for test binaries.
+    let dir = config_dir(
+        "interpolate",
+        &[
+            (
+                "config.json",
+                r#"{"resources": [{"resource": "acct:${CARGO_PKG_NAME}@example.com"}]}"#,
+            ),
+            (
+                "unset.json",
+                r#"{"resources": [{"resource": "acct:${WEBFINGER_TEST_UNSET}@example.com"}]}"#,
+            ),
+        ],
+    );
+
+    let config = Config::from_path(dir.join("config.json")).unwrap();
+    let error = Config::from_path(dir.join("unset.json")).unwrap_err();
+
+    assert!(
+        config
+            .resolve(&request("acct:webfinger-service@example.com", []))
+            .is_some()
+    );
+    assert_eq!(error.location().unwrap().path(), "resources[0].resource");
+    assert!(
+        matches!(
+            error.without_location(),
+            ConfigError::InvalidReference { line: None, .. }
+        ),
+        "{error}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn directory_rejects_resources_defined_in_two_files() {
+    let dir = config_dir(
+        "duplicate",
+        &[
+            (
+                "a.toml",
+                "[[resources]]\nresource = \"acct:alice@example.com\"\n",
+            ),
+            (
+                "b.json",
+                r#"{"subject": "acct:alice@example.com", "links": []}"#,
+            ),
+        ],
+    );
+
+    let error = Config::from_dir(&dir).unwrap_err();
+
+    assert_eq!(error.file(), Some(dir.join("b.json").as_path()));
+    assert!(
+        matches!(error.without_location(), ConfigError::DuplicateResourceFile { resource, first }
+            if resource == "acct:alice@example.com" && *first == dir.join("a.toml")),
+        "{error}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn directory_checks_jrd_files_like_resources() {
+    let load = |name: &str, jrd: &str| {
+        let dir = config_dir(name, &[("jrd.json", jrd)]);
+        let error = Config::from_dir(&dir).unwrap_err();
+        std::fs::remove_dir_all(dir).unwrap();
+        error
+    };
+
+    let titles = load(
+        "jrd-titles",
+        r#"{"subject": "acct:bob@example.com", "links": [{"rel": "self", "titles": {"not a tag": "Bob"}}]}"#,
+    );
+    let template = load(
+        "jrd-template",
+        r#"{"subject": "acct:carol@example.com", "links": [{"rel": "self", "href": "https://social.example/users/carol", "template": "https://social.example/{uri}"}]}"#,
+    );
+    let visibility = load(
+        "jrd-visibility",
+        r#"{"subject": "acct:dave@example.com", "visibility": "token"}"#,
+    );
+
+    assert_eq!(
+        titles.location().unwrap().path(),
+        "links[0].titles.\"not a tag\""
+    );
+    assert!(
+        matches!(template.without_location(), ConfigError::HrefAndTemplate),
+        "{template}"
+    );
+    assert!(
+        matches!(visibility.without_location(), ConfigError::Schema(_)),
+        "{visibility}"
+    );
+}
+
+#[test]
+fn directory_errors_name_the_file() {
+    let dir = config_dir(
+        "invalid",
+        &[(
+            "alice.toml",
+            "[[resources]]\nresource = \"acct:alice@example.com\"\naliases = [\"not a uri\"]\n",
+        )],
+    );
+
+    let error = Config::from_dir(&dir).unwrap_err();
+
+    assert_eq!(error.file(), Some(dir.join("alice.toml").as_path()));
+    assert_eq!(error.location().unwrap().line(), Some(3));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,2100 @@
+//! Tower integration for serving WebFinger from any [`WebFingerProvider`].
+//!
+//! Enable the `tower` feature to use:
+//!
+//! - [`WebFingerService`], a Tower [`Service`] that answers the paths it serves and every other
+//!   path with `404 Not Found`; and
+//! - [`WebFingerLayer`], a Tower [`Layer`] that intercepts the paths it serves and passes every
+//!   other request to the wrapped service unchanged.
+//!
+//! Both serve [`WELL_KNOWN_PATH`], plus the discovery documents and actors enabled below.
+//!
+//! Both types own the HTTP mapping described by [RFC 7033 section 4]: path and method checks, query
+//! parsing into a [`WebFingerRequest`], status codes, content negotiation from the `Accept` header
+//! (see [`ResponseFormat::negotiate`]), and the CORS headers required by [RFC 7033 section 5]. The
+//! provider only resolves parsed requests. This lets Axum, Hyper, and other Tower-based servers
+//! share one implementation instead of re-implementing the mapping per framework.
+//!
+//! | Request                                             | Response                              |
+//! | --------------------------------------------------- | ------------------------------------- |
+//! | path the service does not serve                     | `404 Not Found` (service only)        |
+//! | `OPTIONS` (CORS preflight)                          | `204 No Content`, preflight headers   |
+//! | `HEAD`                                              | the `GET` response without a body     |
+//! | any other method                                    | `405 Method Not Allowed` with `Allow` |
+//! | missing host, bad query, or invalid resource        | `400 Bad Request`                     |
+//! | `Accept` excludes every supported format            | `406 Not Acceptable`                  |
+//! | provider resolves [`Resolution::Found`]             | `200 OK` in the negotiated format     |
+//! | ... and `If-None-Match` matches the `ETag`          | `304 Not Modified`                    |
+//! | provider resolves [`Resolution::NotFound`]          | `404 Not Found`                       |
+//! | provider resolves [`Resolution::Redirect`]          | `307 Temporary Redirect`, `Location`  |
+//! | provider resolves [`Resolution::PermanentRedirect`] | `308 Permanent Redirect`, `Location`  |
+//! | provider resolves [`Resolution::Gone`]              | `410 Gone`                            |
+//! | provider returns `Err(error)`                       | `500 Internal Server Error`           |
+//!
+//! With `with_host_meta(true)`, the service and layer also answer the RFC 6415 host-meta paths
+//! [`HOST_META_PATH`](crate::HOST_META_PATH) with XRD and
+//! [`HOST_META_JSON_PATH`](crate::HOST_META_JSON_PATH) with JSON. Both documents hold an `lrdd`
+//! template for the WebFinger endpoint on the request host and the provider's
+//! [`WebFingerProvider::host_meta_links`], and share the endpoint's method handling, CORS headers,
+//! `ETag`, and cache policy.
+//!
+//! With `with_nodeinfo(true)`, they also answer NodeInfo discovery at
+//! [`NODEINFO_PATH`] and the schema documents it links, from the provider's
+//! [`WebFingerProvider::nodeinfo`].
+//!
+//! With `with_actors(true)`, they also ask the provider's [`WebFingerProvider::actor`] about any
+//! other path. Paths with an actor get the endpoint's method handling and CORS headers, and `GET`
+//! answers with the actor negotiated by [`actor_content_type`]. Paths without one are still
+//! answered with `404 Not Found` by the service and passed on by the layer.
+//!
+//! Responses for the served paths include the CORS headers of the configured [`CorsPolicy`],
+//! including error responses, so browser clients can inspect WebFinger failures. The default policy
+//! sends `Access-Control-Allow-Origin: *`; use `with_cors` to restrict it. Responses for other
+//! paths do not include CORS headers.
+//!
+//! Successful lookups carry a strong `ETag`, `Vary: Accept`, and the `Cache-Control` header of the
+//! provider's [`WebFingerProvider::cache_policy`], or of the policy set with `with_cache`. With
+//! `with_title_selection(true)`, link titles are trimmed to the best match for the request's
+//! `Accept-Language` and responses also carry `Vary: Accept-Language`. With
+//! `with_error_format(ErrorFormat::Problem)`, `400 Bad Request` responses carry an RFC 9457
+//! `application/problem+json` body instead of plain text.
+//!
+//! Providers see the request's bearer token, so lookup, host-meta, and actor responses carry
+//! `Vary: Authorization`, and responses to requests that send `Authorization` are also
+//! `Cache-Control: private`. Browsers only send that header cross-origin when the [`CorsPolicy`]
+//! allows it; add `Authorization` to its `allow_headers` to serve token-only content to browser
+//! clients.
+//!
+//! Lookup queries are bounded by [`ExtractionLimits::default`], or the limits set with
+//! `with_limits`. Lookups and discovery documents take their host from the request unless a
+//! [`HostPolicy`] set with `with_host_policy` trusts the connecting proxy or restricts the allowed
+//! hosts.
+//!
+//! Lookups call [`WebFingerProvider::resolve_with_context`] with a [`RequestContext`] built from
+//! the request headers and a `webfinger_provider` span. The remote address comes from a
+//! [`RemoteAddr`] request extension; insert one from the server's connection information to make
+//! it available to providers and to the host policy.
+//!
+//! # Example
+//!
+//! ```
+//! use webfinger_service::StaticConfigProvider;
+//! use webfinger_service::tower::{WebFingerLayer, WebFingerService};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = StaticConfigProvider::from_toml(webfinger_service::EXAMPLE_CONFIG)?;
+//!
+//! // Serve only the WebFinger endpoint.
+//! let service = WebFingerService::new(provider.clone());
+//!
+//! // Or add the endpoint in front of an existing Tower service.
+//! let layer = WebFingerLayer::new(provider);
+//! # let _ = (service, layer);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [RFC 7033 section 4]: https://www.rfc-editor.org/rfc/rfc7033.html#section-4
+//! [RFC 7033 section 5]: https://www.rfc-editor.org/rfc/rfc7033.html#section-5
+
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::header::{self, HeaderMap, HeaderValue};
+use http::request::Parts;
+use http::{Method, Request, Response, StatusCode};
+use http_body_util::{Either, Full};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{Instrument, error, info, info_span};
+use webfinger_rs::{
+    ALLOWED_METHODS, CachePolicy, CorsPolicy, ErrorFormat, ExtractionLimits, HostPolicy,
+    LanguageRange, RequestError, ResponseFormat, WELL_KNOWN_PATH, WebFingerRequest,
+    WebFingerResponse, etag_matches, insert_policy_headers,
+};
+
+use crate::{
+    Actor, NODEINFO_PATH, NodeInfoVersion, ProviderError, RemoteAddr, RequestContext, Resolution,
+    WebFingerProvider, actor_content_type, host_meta, host_meta_format, nodeinfo_discovery,
+};
+
+const TEXT_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("text/plain; charset=utf-8");
+
+/// The boxed future returned by [`WebFingerService`] and [`WebFingerMiddleware`].
+pub type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
+
+/// A Tower service that serves the WebFinger endpoint from a [`WebFingerProvider`].
+///
+/// The service answers [`WELL_KNOWN_PATH`] and the discovery documents and actors enabled with
+/// its `with_*` methods; requests for any other path receive `404 Not Found`. Mount it as a
+/// fallback or at the served paths, or use [`WebFingerLayer`] to put it in front of another
+/// service.
+///
+/// The provider is held in an [`Arc`], so cloning the service is cheap. See the [module
+/// docs](self) for the full status code mapping.
+pub struct WebFingerService<P> {
+    provider: Arc<P>,
+    options: Options,
+}
+
+impl<P> WebFingerService<P> {
+    /// Creates a service from a provider.
+    pub fn new(provider: P) -> Self {
+        Self::from_arc(Arc::new(provider))
+    }
+
+    /// Creates a service from a provider that is already shared.
+    pub fn from_arc(provider: Arc<P>) -> Self {
+        Self {
+            provider,
+            options: Options::default(),
+        }
+    }
+
+    /// Replaces the default CORS policy, which allows any origin.
+    pub fn with_cors(mut self, cors: CorsPolicy) -> Self {
+        self.options.cors = Arc::new(cors);
+        self
+    }
+
+    /// Replaces the default cache policy, which sends no `Cache-Control` header.
+    ///
+    /// A policy returned by [`WebFingerProvider::cache_policy`] takes precedence for that response.
+    pub fn with_cache(mut self, cache: CachePolicy) -> Self {
+        self.options.cache = Arc::new(cache);
+        self
+    }
+
+    /// Trims each link's `titles` to the best match for the request's `Accept-Language`.
+    ///
+    /// Disabled by default, which returns every title. When enabled, responses carry
+    /// `Vary: Accept-Language`. See [`WebFingerResponse::select_titles`] for the selection rules.
+    pub fn with_title_selection(mut self, enabled: bool) -> Self {
+        self.options.select_titles = enabled;
+        self
+    }
+
+    /// Replaces the default [`ExtractionLimits`] applied to WebFinger lookup queries.
+    ///
+    /// Lookups that exceed a limit are answered with `400 Bad Request`.
+    pub fn with_limits(mut self, limits: ExtractionLimits) -> Self {
+        self.options.limits = Arc::new(limits);
+        self
+    }
+
+    /// Replaces the default [`HostPolicy`], which trusts no proxies and allows every host.
+    ///
+    /// The policy determines the host of WebFinger lookups, host-meta documents, and NodeInfo
+    /// documents from the request, or from the `Forwarded` and `X-Forwarded-Host` headers of a
+    /// trusted proxy, and answers hosts it does not allow with `400 Bad Request`. The peer address
+    /// comes from a [`RemoteAddr`] request extension.
+    pub fn with_host_policy(mut self, policy: HostPolicy) -> Self {
+        self.options.host_policy = Arc::new(policy);
+        self
+    }
+
+    /// Replaces the default plain text body of `400 Bad Request` responses.
+    ///
+    /// With [`ErrorFormat::Problem`], malformed requests are answered with an RFC 9457
+    /// `application/problem+json` document whose `code` member identifies the cause.
+    pub fn with_error_format(mut self, format: ErrorFormat) -> Self {
+        self.options.error_format = format;
+        self
+    }
+
+    /// Also serves the RFC 6415 host-meta documents at
+    /// [`HOST_META_PATH`](crate::HOST_META_PATH) and
+    /// [`HOST_META_JSON_PATH`](crate::HOST_META_JSON_PATH).
+    ///
+    /// Disabled by default. When enabled, those paths get the same method handling and CORS
+    /// headers as the WebFinger endpoint, and `GET` answers with [`host_meta`] built from the
+    /// request host and the provider's [`WebFingerProvider::host_meta_links`].
+    pub fn with_host_meta(mut self, enabled: bool) -> Self {
+        self.options.discovery.host_meta = enabled;
+        self
+    }
+
+    /// Also serves NodeInfo 2.x discovery at [`NODEINFO_PATH`] and the
+    /// versioned schema documents it links, such as `/nodeinfo/2.1`.
+    ///
+    /// Disabled by default. When enabled, those paths get the same method handling and CORS
+    /// headers as the WebFinger endpoint. `GET` answers with the provider's
+    /// [`WebFingerProvider::nodeinfo`], or `404 Not Found` when the provider has none.
+    pub fn with_nodeinfo(mut self, enabled: bool) -> Self {
+        self.options.discovery.nodeinfo = enabled;
+        self
+    }
+
+    /// Also serves the provider's ActivityPub actors at the paths of their `id`s.
+    ///
+    /// Disabled by default. When enabled, `GET` requests for any path the service does not
+    /// otherwise answer ask [`WebFingerProvider::actor`], and a found actor is served as
+    /// [`ACTIVITY_JSON_CONTENT_TYPE`](crate::ACTIVITY_JSON_CONTENT_TYPE) or JSON-LD as negotiated
+    /// by [`actor_content_type`]. Paths without an actor are answered with `404 Not Found` and no
+    /// CORS headers, whatever the method.
+    pub fn with_actors(mut self, enabled: bool) -> Self {
+        self.options.discovery.actors = enabled;
+        self
+    }
+}
+
+impl<P> WebFingerService<P>
+where
+    P: WebFingerProvider,
+{
+    /// Answers one request.
+    ///
+    /// This is what the [`Service`] impl runs, without its `Send + Sync` bounds on the provider, so
+    /// single-threaded runtimes such as Cloudflare Workers can serve providers that hold
+    /// JavaScript handles.
+    pub async fn serve<B>(&self, request: Request<B>) -> Response<Full<Bytes>> {
+        let (parts, _body) = request.into_parts();
+        serve(&*self.provider, &self.options, parts).await
+    }
+}
+
+impl<P> Clone for WebFingerService<P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: Arc::clone(&self.provider),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl<P> fmt::Debug for WebFingerService<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebFingerService").finish_non_exhaustive()
+    }
+}
+
+impl<P, B> Service<Request<B>> for WebFingerService<P>
+where
+    P: WebFingerProvider + Send + Sync + 'static,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let provider = Arc::clone(&self.provider);
+        let options = self.options.clone();
+        let (parts, _body) = request.into_parts();
+        Box::pin(async move { Ok(serve(&*provider, &options, parts).await) })
+    }
+}
+
+/// A Tower layer that intercepts WebFinger requests before they reach the wrapped service.
+///
+/// Requests for [`WELL_KNOWN_PATH`], and for the discovery documents and actors enabled with its
+/// `with_*` methods, are answered by the provider using the same mapping as [`WebFingerService`].
+/// Every other request is passed to the inner service unchanged, so the inner service still owns
+/// its own routing, `404` responses, and headers.
+pub struct WebFingerLayer<P> {
+    provider: Arc<P>,
+    options: Options,
+}
+
+impl<P> WebFingerLayer<P> {
+    /// Creates a layer from a provider.
+    pub fn new(provider: P) -> Self {
+        Self::from_arc(Arc::new(provider))
+    }
+
+    /// Creates a layer from a provider that is already shared.
+    pub fn from_arc(provider: Arc<P>) -> Self {
+        Self {
+            provider,
+            options: Options::default(),
+        }
+    }
+
+    /// Replaces the default CORS policy, which allows any origin.
+    pub fn with_cors(mut self, cors: CorsPolicy) -> Self {
+        self.options.cors = Arc::new(cors);
+        self
+    }
+
+    /// Replaces the default cache policy, which sends no `Cache-Control` header.
+    ///
+    /// A policy returned by [`WebFingerProvider::cache_policy`] takes precedence for that response.
+    pub fn with_cache(mut self, cache: CachePolicy) -> Self {
+        self.options.cache = Arc::new(cache);
+        self
+    }
+
+    /// Trims each link's `titles` to the best match for the request's `Accept-Language`.
+    ///
+    /// Disabled by default, which returns every title. When enabled, responses carry
+    /// `Vary: Accept-Language`. See [`WebFingerResponse::select_titles`] for the selection rules.
+    pub fn with_title_selection(mut self, enabled: bool) -> Self {
+        self.options.select_titles = enabled;
+        self
+    }
+
+    /// Replaces the default [`ExtractionLimits`] applied to WebFinger lookup queries.
+    ///
+    /// Lookups that exceed a limit are answered with `400 Bad Request`.
+    pub fn with_limits(mut self, limits: ExtractionLimits) -> Self {
+        self.options.limits = Arc::new(limits);
+        self
+    }
+
+    /// Replaces the default [`HostPolicy`], which trusts no proxies and allows every host.
+    ///
+    /// The policy determines the host of WebFinger lookups, host-meta documents, and NodeInfo
+    /// documents from the request, or from the `Forwarded` and `X-Forwarded-Host` headers of a
+    /// trusted proxy, and answers hosts it does not allow with `400 Bad Request`. The peer address
+    /// comes from a [`RemoteAddr`] request extension.
+    pub fn with_host_policy(mut self, policy: HostPolicy) -> Self {
+        self.options.host_policy = Arc::new(policy);
+        self
+    }
+
+    /// Replaces the default plain text body of `400 Bad Request` responses.
+    ///
+    /// With [`ErrorFormat::Problem`], malformed requests are answered with an RFC 9457
+    /// `application/problem+json` document whose `code` member identifies the cause.
+    pub fn with_error_format(mut self, format: ErrorFormat) -> Self {
+        self.options.error_format = format;
+        self
+    }
+
+    /// Also serves the RFC 6415 host-meta documents at
+    /// [`HOST_META_PATH`](crate::HOST_META_PATH) and
+    /// [`HOST_META_JSON_PATH`](crate::HOST_META_JSON_PATH).
+    ///
+    /// Disabled by default. When enabled, those paths get the same method handling and CORS
+    /// headers as the WebFinger endpoint, and `GET` answers with [`host_meta`] built from the
+    /// request host and the provider's [`WebFingerProvider::host_meta_links`].
+    pub fn with_host_meta(mut self, enabled: bool) -> Self {
+        self.options.discovery.host_meta = enabled;
+        self
+    }
+
+    /// Also serves NodeInfo 2.x discovery at [`NODEINFO_PATH`] and the
+    /// versioned schema documents it links, such as `/nodeinfo/2.1`.
+    ///
+    /// Disabled by default. When enabled, those paths get the same method handling and CORS
+    /// headers as the WebFinger endpoint. `GET` answers with the provider's
+    /// [`WebFingerProvider::nodeinfo`], or `404 Not Found` when the provider has none.
+    pub fn with_nodeinfo(mut self, enabled: bool) -> Self {
+        self.options.discovery.nodeinfo = enabled;
+        self
+    }
+
+    /// Also serves the provider's ActivityPub actors at the paths of their `id`s.
+    ///
+    /// Disabled by default. When enabled, requests for any path the layer does not otherwise
+    /// answer ask [`WebFingerProvider::actor`] first. A found actor is served as in
+    /// [`WebFingerService::with_actors`], and paths without an actor are passed to the inner
+    /// service unchanged.
+    pub fn with_actors(mut self, enabled: bool) -> Self {
+        self.options.discovery.actors = enabled;
+        self
+    }
+}
+
+impl<P> Clone for WebFingerLayer<P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: Arc::clone(&self.provider),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl<P> fmt::Debug for WebFingerLayer<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebFingerLayer").finish_non_exhaustive()
+    }
+}
+
+impl<P, S> Layer<S> for WebFingerLayer<P> {
+    type Service = WebFingerMiddleware<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WebFingerMiddleware {
+            inner,
+            provider: Arc::clone(&self.provider),
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// The service produced by [`WebFingerLayer`].
+///
+/// Responses use [`Either`] so the WebFinger endpoint can return its own buffered body while
+/// other paths return the inner service's body type unchanged.
+pub struct WebFingerMiddleware<S, P> {
+    inner: S,
+    provider: Arc<P>,
+    options: Options,
+}
+
+impl<S: Clone, P> Clone for WebFingerMiddleware<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            provider: Arc::clone(&self.provider),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for WebFingerMiddleware<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebFingerMiddleware")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, P, B, ResBody> Service<Request<B>> for WebFingerMiddleware<S, P>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    P: WebFingerProvider + Send + Sync + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<Either<Full<Bytes>, ResBody>>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let provider = Arc::clone(&self.provider);
+        let options = self.options.clone();
+        if let Some(endpoint) = options.discovery.endpoint(request.uri().path()) {
+            let (parts, _body) = request.into_parts();
+            return Box::pin(async move {
+                let response = respond(&*provider, &options, endpoint, parts).await;
+                Ok(response.map(Either::Left))
+            });
+        }
+        if !options.discovery.actors {
+            let future = self.inner.call(request);
+            return Box::pin(async move {
+                let response = future.await?;
+                Ok(response.map(Either::Right))
+            });
+        }
+
+        // Keep the instance `poll_ready` prepared for the request that may fall through to it.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let (parts, body) = request.into_parts();
+        Box::pin(async move {
+            let response = match find_actor(&*provider, &parts).await {
+                Ok(Some(actor)) => {
+                    respond(
+                        &*provider,
+                        &options,
+                        Endpoint::Actor(Box::new(actor)),
+                        parts,
+                    )
+                    .await
+                }
+                Ok(None) => {
+                    let response = inner.call(Request::from_parts(parts, body)).await?;
+                    return Ok(response.map(Either::Right));
+                }
+                Err(response) => response.map(Full::new),
+            };
+            Ok(response.map(Either::Left))
+        })
+    }
+}
+
+/// Maps one request to a WebFinger HTTP response.
+async fn serve<P>(provider: &P, options: &Options, parts: Parts) -> Response<Full<Bytes>>
+where
+    P: WebFingerProvider,
+{
+    let endpoint = match options.discovery.endpoint(parts.uri.path()) {
+        Some(endpoint) => endpoint,
+        None if options.discovery.actors => match find_actor(provider, &parts).await {
+            Ok(Some(actor)) => Endpoint::Actor(Box::new(actor)),
+            Ok(None) => return not_found(&parts),
+            Err(response) => return response.map(Full::new),
+        },
+        None => return not_found(&parts),
+    };
+    respond(provider, options, endpoint, parts).await
+}
+
+/// Answers a request for a document the service serves.
+async fn respond<P>(
+    provider: &P,
+    options: &Options,
+    endpoint: Endpoint,
+    parts: Parts,
+) -> Response<Full<Bytes>>
+where
+    P: WebFingerProvider,
+{
+    let method = &parts.method;
+    let path = parts.uri.path();
+    let origin = parts
+        .headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok());
+    let mut response = if method == Method::OPTIONS {
+        log_webfinger_request(method, path, "preflight");
+        let mut response = Response::new(Bytes::new());
+        *response.status_mut() = StatusCode::NO_CONTENT;
+        let headers = response.headers_mut();
+        headers.insert(header::ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+        insert_policy_headers(headers, options.cors.preflight_headers(origin));
+        return response.map(Full::new);
+    } else if method == Method::GET || method == Method::HEAD {
+        let credentials = !matches!(endpoint, Endpoint::NodeInfo(_));
+        let mut response = match endpoint {
+            Endpoint::WebFinger => lookup(provider, options, &parts).await,
+            Endpoint::HostMeta(format) => {
+                host_meta_document(provider, options, format, &parts).await
+            }
+            Endpoint::NodeInfo(version) => {
+                nodeinfo_document(provider, options, version, &parts).await
+            }
+            Endpoint::Actor(actor) => actor_document(&actor, &parts),
+        };
+        if credentials {
+            insert_credential_headers(response.headers_mut(), &parts);
+        }
+        response
+    } else {
+        log_webfinger_request(method, path, "method_not_allowed");
+        let mut response = text_response(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+        response
+            .headers_mut()
+            .insert(header::ALLOW, HeaderValue::from_static(ALLOWED_METHODS));
+        response
+    };
+    insert_policy_headers(
+        response.headers_mut(),
+        options.cors.response_headers(origin),
+    );
+
+    if method == Method::HEAD && response.status() != StatusCode::NOT_MODIFIED {
+        // HEAD mirrors GET headers, including the length of the body it omits.
+        // <https://www.rfc-editor.org/rfc/rfc9110.html#section-9.3.2>
+        let length = HeaderValue::from(response.body().len());
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, length);
+        return response.map(|_| Full::default());
+    }
+    response.map(Full::new)
+}
+
+/// The options shared by [`WebFingerService`], [`WebFingerLayer`], and [`WebFingerMiddleware`].
+#[derive(Clone, Default)]
+struct Options {
+    cors: Arc<CorsPolicy>,
+    cache: Arc<CachePolicy>,
+    select_titles: bool,
+    error_format: ErrorFormat,
+    limits: Arc<ExtractionLimits>,
+    host_policy: Arc<HostPolicy>,
+    discovery: Discovery,
+}
+
+/// The discovery documents served next to the WebFinger endpoint.
+#[derive(Debug, Clone, Copy, Default)]
+struct Discovery {
+    host_meta: bool,
+    nodeinfo: bool,
+    /// Whether paths without another document are looked up with [`WebFingerProvider::actor`].
+    actors: bool,
+}
+
+/// A document the service answers.
+enum Endpoint {
+    WebFinger,
+    HostMeta(ResponseFormat),
+    /// The NodeInfo discovery document, or the schema document of a version.
+    NodeInfo(Option<NodeInfoVersion>),
+    /// The ActivityPub actor the provider serves at the path.
+    Actor(Box<Actor>),
+}
+
+impl Discovery {
+    /// Returns the document served at a path, or `None` when the path is passed on, refused, or
+    /// left to an actor lookup.
+    fn endpoint(self, path: &str) -> Option<Endpoint> {
+        if path == WELL_KNOWN_PATH {
+            return Some(Endpoint::WebFinger);
+        }
+        if self.host_meta
+            && let Some(format) = host_meta_format(path)
+        {
+            return Some(Endpoint::HostMeta(format));
+        }
+        if self.nodeinfo {
+            if path == NODEINFO_PATH {
+                return Some(Endpoint::NodeInfo(None));
+            }
+            if let Some(version) = NodeInfoVersion::from_path(path) {
+                return Some(Endpoint::NodeInfo(Some(version)));
+            }
+        }
+        None
+    }
+}
+
+/// Answers a `GET` or `HEAD` request for a host-meta document.
+async fn host_meta_document<P>(
+    provider: &P,
+    options: &Options,
+    format: ResponseFormat,
+    parts: &Parts,
+) -> Response<Bytes>
+where
+    P: WebFingerProvider,
+{
+    let method = &parts.method;
+    let path = parts.uri.path();
+    let host = match resolve_host(options, parts) {
+        Ok(host) => host,
+        Err(error) => return bad_request(options, parts, &error),
+    };
+    log_webfinger_request(method, path, "host_meta");
+    let context = RequestContext::from_parts(parts).with_span(info_span!("host_meta_provider"));
+    let links = match provider
+        .host_meta_links(&context)
+        .instrument(context.span.clone())
+        .await
+    {
+        Ok(links) => links,
+        Err(error) => {
+            error!(?error, "host-meta provider failed");
+            return provider_failed(&error);
+        }
+    };
+    let Ok(document) = host_meta(&host, links) else {
+        return text_response(StatusCode::BAD_REQUEST, "invalid host");
+    };
+    let etag = document.etag(format);
+    let not_modified = parts
+        .headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &etag));
+    let mut response = if not_modified {
+        let mut response = Response::new(Bytes::new());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else {
+        document_response(format, &document)
+    };
+    if matches!(response.status(), StatusCode::OK | StatusCode::NOT_MODIFIED) {
+        insert_policy_headers(
+            response.headers_mut(),
+            options.cache.response_headers(&etag),
+        );
+    }
+    response
+}
+
+/// Answers a `GET` or `HEAD` request for the NodeInfo discovery document or a schema document.
+async fn nodeinfo_document<P>(
+    provider: &P,
+    options: &Options,
+    version: Option<NodeInfoVersion>,
+    parts: &Parts,
+) -> Response<Bytes>
+where
+    P: WebFingerProvider,
+{
+    let method = &parts.method;
+    let path = parts.uri.path();
+    let host = match resolve_host(options, parts) {
+        Ok(host) => host,
+        Err(error) => return bad_request(options, parts, &error),
+    };
+    let context = RequestContext::from_parts(parts).with_span(info_span!("nodeinfo_provider"));
+    let nodeinfo = match provider
+        .nodeinfo(&context)
+        .instrument(context.span.clone())
+        .await
+    {
+        Ok(Some(nodeinfo)) => nodeinfo,
+        Ok(None) => {
+            log_webfinger_request(method, path, "not_found");
+            return text_response(StatusCode::NOT_FOUND, "not found");
+        }
+        Err(error) => {
+            error!(?error, "nodeinfo provider failed");
+            return provider_failed(&error);
+        }
+    };
+    let (content_type, body) = match version {
+        Some(version) => (version.content_type(), nodeinfo.to_json(version)),
+        None => ("application/json", nodeinfo_discovery(&host)),
+    };
+    log_webfinger_request(method, path, "nodeinfo");
+    match body {
+        Ok(body) => {
+            let mut response = Response::new(Bytes::from(body));
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        }
+        Err(error) => {
+            error!(?error, "failed to serialize nodeinfo document");
+            text_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to serialize NodeInfo document",
+            )
+        }
+    }
+}
+
+/// Asks the provider for the ActivityPub actor at the request path.
+///
+/// A provider error is returned as the `500 Internal Server Error` response to send.
+async fn find_actor<P>(provider: &P, parts: &Parts) -> Result<Option<Actor>, Response<Bytes>>
+where
+    P: WebFingerProvider,
+{
+    let context = RequestContext::from_parts(parts).with_span(info_span!("actor_provider"));
+    provider
+        .actor(parts.uri.path(), &context)
+        .instrument(context.span.clone())
+        .await
+        .map_err(|error| {
+            error!(?error, "actor provider failed");
+            provider_failed(&error)
+        })
+}
+
+/// Answers a `GET` or `HEAD` request for an ActivityPub actor.
+fn actor_document(actor: &Actor, parts: &Parts) -> Response<Bytes> {
+    let method = &parts.method;
+    let path = parts.uri.path();
+    let accept = parts
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok());
+    let Some(content_type) = actor_content_type(accept) else {
+        log_webfinger_request(method, path, "not_acceptable");
+        let mut response = text_response(StatusCode::NOT_ACCEPTABLE, "not acceptable");
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("Accept"));
+        return response;
+    };
+    log_webfinger_request(method, path, "actor");
+    match actor.to_json() {
+        Ok(body) => {
+            let mut response = Response::new(Bytes::from(body));
+            let headers = response.headers_mut();
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            headers.insert(header::VARY, HeaderValue::from_static("Accept"));
+            response
+        }
+        Err(error) => {
+            error!(?error, "failed to serialize actor document");
+            text_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to serialize actor document",
+            )
+        }
+    }
+}
+
+/// Marks a response whose content can depend on the request's bearer token.
+///
+/// `Vary: Authorization` keeps shared caches from answering one client with a response built for
+/// another client's credentials. Responses to requests with credentials can hold token-only
+/// resources and links, so they are also `Cache-Control: private`.
+fn insert_credential_headers(headers: &mut HeaderMap, parts: &Parts) {
+    headers.append(header::VARY, HeaderValue::from_static("Authorization"));
+    if !parts.headers.contains_key(header::AUTHORIZATION) {
+        return;
+    }
+    let cache_control = match headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => format!("private, {value}"),
+        None => "private".to_string(),
+    };
+    if let Ok(value) = HeaderValue::try_from(cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+}
+
+/// Answers a path the service does not serve, without CORS headers.
+fn not_found(parts: &Parts) -> Response<Full<Bytes>> {
+    log_webfinger_request(&parts.method, parts.uri.path(), "not_found");
+    text_response(StatusCode::NOT_FOUND, "not found").map(Full::new)
+}
+
+/// Determines the request host with the configured [`HostPolicy`].
+///
+/// The peer address comes from a [`RemoteAddr`] request extension.
+fn resolve_host(options: &Options, parts: &Parts) -> Result<String, RequestError> {
+    let peer = parts
+        .extensions
+        .get::<RemoteAddr>()
+        .map(|RemoteAddr(address)| *address);
+    options.host_policy.resolve_host_from_parts(parts, peer)
+}
+
+/// Answers a request the service could not parse with `400 Bad Request`.
+fn bad_request(options: &Options, parts: &Parts, error: &RequestError) -> Response<Bytes> {
+    log_webfinger_request(&parts.method, parts.uri.path(), "bad_request");
+    let (content_type, body) = options.error_format.render(error);
+    let mut response = text_response(StatusCode::BAD_REQUEST, body);
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+/// Parses a `GET` or `HEAD` request and resolves it with the provider.
+async fn lookup<P>(provider: &P, options: &Options, parts: &Parts) -> Response<Bytes>
+where
+    P: WebFingerProvider,
+{
+    let method = &parts.method;
+    let path = parts.uri.path();
+    let query = parts.uri.query().unwrap_or_default();
+    let request = resolve_host(options, parts)
+        .and_then(|host| WebFingerRequest::from_query_with_limits(&host, query, &options.limits));
+    let request = match request {
+        Ok(request) => {
+            log_webfinger_request(method, path, "lookup");
+            request
+        }
+        Err(error) => return bad_request(options, parts, &error),
+    };
+    let accept = parts
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok());
+    let Some(format) = ResponseFormat::negotiate(accept) else {
+        return text_response(StatusCode::NOT_ACCEPTABLE, "not acceptable");
+    };
+
+    let context = RequestContext::from_parts(parts)
+        .with_span(info_span!("webfinger_provider", resource = %request.resource));
+    let resolution = provider
+        .resolve_with_context(&request, &context)
+        .instrument(context.span.clone())
+        .await;
+    match resolution {
+        Ok(Resolution::Found {
+            mut response,
+            cache: policy,
+        }) => {
+            info!(resource = %request.resource, "resolved webfinger response");
+            if options.select_titles {
+                let languages = parts
+                    .headers
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(LanguageRange::parse_accept_language)
+                    .unwrap_or_default();
+                response.select_titles(&languages);
+            }
+            let etag = response.etag(format);
+            let not_modified = parts
+                .headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| etag_matches(value, &etag));
+            let mut response = if not_modified {
+                let mut response = Response::new(Bytes::new());
+                *response.status_mut() = StatusCode::NOT_MODIFIED;
+                response
+            } else {
+                document_response(format, &response)
+            };
+            if matches!(response.status(), StatusCode::OK | StatusCode::NOT_MODIFIED) {
+                let policy = policy.as_ref().unwrap_or(&options.cache);
+                let headers = response.headers_mut();
+                insert_policy_headers(headers, policy.response_headers(&etag));
+                headers.append(header::VARY, HeaderValue::from_static("Accept"));
+                if options.select_titles {
+                    headers.append(header::VARY, HeaderValue::from_static("Accept-Language"));
+                }
+            }
+            response
+        }
+        Ok(Resolution::NotFound) => text_response(StatusCode::NOT_FOUND, "resource not found"),
+        Ok(Resolution::Redirect(location)) => {
+            redirect_response(StatusCode::TEMPORARY_REDIRECT, location, &request)
+        }
+        Ok(Resolution::PermanentRedirect(location)) => {
+            redirect_response(StatusCode::PERMANENT_REDIRECT, location, &request)
+        }
+        Ok(Resolution::Gone) => text_response(StatusCode::GONE, "resource gone"),
+        Err(error) => {
+            error!(?error, resource = %request.resource, "webfinger provider failed");
+            provider_failed(&error)
+        }
+    }
+}
+
+fn redirect_response(
+    status: StatusCode,
+    location: String,
+    request: &WebFingerRequest,
+) -> Response<Bytes> {
+    info!(resource = %request.resource, %location, "redirected webfinger request");
+    let Ok(location) = HeaderValue::try_from(location) else {
+        error!(resource = %request.resource, "webfinger provider returned an invalid redirect");
+        return text_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "WebFinger provider failed",
+        );
+    };
+    let mut response = Response::new(Bytes::new());
+    *response.status_mut() = status;
+    response.headers_mut().insert(header::LOCATION, location);
+    response
+}
+
+fn document_response(format: ResponseFormat, response: &WebFingerResponse) -> Response<Bytes> {
+    match format.render(response) {
+        Ok(body) => {
+            let mut response = Response::new(Bytes::from(body));
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            );
+            response
+        }
+        Err(error) => {
+            error!(?error, "failed to serialize webfinger response");
+            text_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to serialize WebFinger response",
+            )
+        }
+    }
+}
+
+/// Answers a provider error with `500 Internal Server Error`.
+///
+/// The body names a missing configuration key so a new deployment can tell what to set up, and
+/// otherwise leaves the details to the logs.
+fn provider_failed(error: &ProviderError) -> Response<Bytes> {
+    let body = match error {
+        ProviderError::MissingConfig { key } => {
+            format!("WebFinger is not configured. Add TOML configuration to key `{key}`.")
+        }
+        _ => "WebFinger provider failed".to_string(),
+    };
+    text_response(StatusCode::INTERNAL_SERVER_ERROR, body)
+}
+
+fn text_response(status: StatusCode, body: impl Into<Bytes>) -> Response<Bytes> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, TEXT_CONTENT_TYPE);
+    response
+}
+
+fn log_webfinger_request(method: &Method, path: &str, outcome: &str) {
+    info!(method = %method, path, outcome, "webfinger service request");
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+    use tower::service_fn;
+
+    use super::*;
+    use std::net::IpAddr;
+
+    use crate::{EXAMPLE_CONFIG, ProviderError, RemoteAddr, StaticConfigProvider};
+    use webfinger_rs::{PROBLEM_CONTENT_TYPE, Problem};
+
+    type Result<T = (), E = Box<dyn std::error::Error + Send + Sync>> = std::result::Result<T, E>;
+
+    const CORS_ALLOW_ORIGIN_HEADER: HeaderValue = HeaderValue::from_static("*");
+    const JRD_CONTENT_TYPE: HeaderValue = HeaderValue::from_static(webfinger_rs::JRD_CONTENT_TYPE);
+
+    #[tokio::test]
+    async fn successful_response_uses_jrd_content_type() -> Result {
+        let response = call(get(
+            "/.well-known/webfinger?resource=acct:alice@example.com",
+        ))
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&JRD_CONTENT_TYPE)
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+
+        let body = response.into_body().collect().await?.to_bytes();
+        let response: WebFingerResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.subject.as_ref(), "acct:alice@example.com");
+        Ok(())
+    }
+
+    /// WebFinger lookups for unknown resources are `404 Not Found`, but they still belong to the
+    /// public endpoint, so browser clients must be able to read them.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+    #[tokio::test]
+    async fn unknown_resource_is_not_found_with_cors() {
+        let response = call(get("/.well-known/webfinger?resource=acct:bob@example.com")).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+    }
+
+    /// Clients that only accept plain JSON receive the JRD document as `application/json`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-10.2>.
+    #[tokio::test]
+    async fn accept_json_serves_plain_json() {
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/json")),
+        );
+    }
+
+    /// Legacy RFC 6415 clients that ask for XRD receive an XRD document.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#appendix-A>.
+    #[tokio::test]
+    async fn accept_xrd_serves_xrd() -> Result {
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request.headers_mut().insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/xrd+xml"),
+        );
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/xrd+xml")),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        let body = std::str::from_utf8(&body)?;
+        assert!(
+            body.contains("<Subject>acct:alice@example.com</Subject>"),
+            "{body}"
+        );
+        Ok(())
+    }
+
+    /// Requests that accept none of the supported formats are `406 Not Acceptable`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.5.7>.
+    #[tokio::test]
+    async fn unacceptable_format_is_not_acceptable() -> Result {
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, HeaderValue::from_static("text/html"));
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "not acceptable");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn malformed_query_is_bad_request() -> Result {
+        let response = call(get("/.well-known/webfinger")).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "missing resource parameter");
+        Ok(())
+    }
+
+    /// The problem error format answers malformed queries with RFC 9457 problem details.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9457.html#section-3>.
+    #[tokio::test]
+    async fn problem_error_format_renders_problem_details() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let service = WebFingerService::new(provider).with_error_format(ErrorFormat::Problem);
+
+        let Ok(response) = service.oneshot(get("/.well-known/webfinger")).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE,
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        let problem: Problem = serde_json::from_slice(&body)?;
+        assert_eq!(problem.code.as_deref(), Some("missing_resource"));
+        assert_eq!(
+            problem.detail.as_deref(),
+            Some("missing resource parameter")
+        );
+        Ok(())
+    }
+
+    /// Configured extraction limits replace the defaults for lookup queries.
+    #[tokio::test]
+    async fn configured_limits_reject_extra_rels() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let service =
+            WebFingerService::new(provider).with_limits(ExtractionLimits::new().max_rels(1));
+
+        let Ok(response) = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com&rel=self&rel=profile",
+            ))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "too many relation filters; maximum is 1");
+        Ok(())
+    }
+
+    /// A host policy takes the lookup host from a trusted proxy and refuses other hosts.
+    #[tokio::test]
+    async fn configured_host_policy_resolves_forwarded_host() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let proxy = IpAddr::from([10, 0, 0, 1]);
+        let policy = HostPolicy::new()
+            .trusted_proxies([proxy])
+            .allowed_hosts(["example.com"]);
+        let layer = WebFingerLayer::new(provider).with_host_policy(policy);
+        let service = layer.layer(service_fn(|_request: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(Full::new(Bytes::new())))
+        }));
+        let request = |peer: IpAddr| {
+            let mut request = Request::builder()
+                .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+                .header(header::HOST, "backend.internal")
+                .header("x-forwarded-host", "example.com")
+                .body(())
+                .unwrap();
+            request.extensions_mut().insert(RemoteAddr(peer));
+            request
+        };
+
+        let response = service.clone().oneshot(request(proxy)).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = service
+            .oneshot(request(IpAddr::from([203, 0, 113, 7])))
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsupported_method_is_method_not_allowed() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header(header::HOST, "example.com")
+            .body(())
+            .unwrap();
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            response.headers().get(header::ALLOW).unwrap(),
+            ALLOWED_METHODS
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+    }
+
+    /// `HEAD` returns the `GET` status and headers, including `Content-Length`, without a body.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-9.3.2>.
+    #[tokio::test]
+    async fn head_request_omits_body() -> Result {
+        let request = Request::builder()
+            .method(Method::HEAD)
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header(header::HOST, "example.com")
+            .body(())?;
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&JRD_CONTENT_TYPE)
+        );
+        assert_ne!(response.headers().get(header::CONTENT_LENGTH).unwrap(), "0");
+        let body = response.into_body().collect().await?.to_bytes();
+        assert!(body.is_empty());
+        Ok(())
+    }
+
+    /// Browsers send `OPTIONS` preflight requests before some cross-origin lookups.
+    ///
+    /// See <https://fetch.spec.whatwg.org/#cors-preflight-fetch>.
+    #[tokio::test]
+    async fn options_request_answers_preflight() {
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/.well-known/webfinger")
+            .header(header::ORIGIN, "https://app.example")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(())
+            .unwrap();
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers.get(header::ALLOW).unwrap(), ALLOWED_METHODS);
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            ALLOWED_METHODS
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "Accept"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(),
+            "86400"
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_cors_policy_restricts_origin() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let cors = CorsPolicy::new().allow_origin(webfinger_rs::AllowOrigin::List(vec![
+            "https://app.example".to_string(),
+        ]));
+        let service = WebFingerService::new(provider).with_cors(cors);
+        let request = Request::builder()
+            .uri("/.well-known/webfinger?resource=acct:alice@example.com")
+            .header(header::HOST, "example.com")
+            .header(header::ORIGIN, "https://other.example")
+            .body(())?;
+
+        let response = service.oneshot(request).await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None
+        );
+        let vary: Vec<_> = response.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept", "Authorization", "Origin"]);
+        Ok(())
+    }
+
+    /// Successful lookups carry an entity tag and vary on `Accept` because the representation is
+    /// negotiated.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-12.5.5>.
+    #[tokio::test]
+    async fn successful_response_sets_etag_and_vary() {
+        let response = call(get(
+            "/.well-known/webfinger?resource=acct:alice@example.com",
+        ))
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(header::ETAG));
+        assert_eq!(response.headers().get(header::VARY).unwrap(), "Accept");
+        assert_eq!(response.headers().get(header::CACHE_CONTROL), None);
+    }
+
+    #[tokio::test]
+    async fn configured_cache_policy_sets_cache_control() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let cache = CachePolicy::new().max_age(std::time::Duration::from_secs(300));
+        let service = WebFingerService::new(provider).with_cache(cache);
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=300"
+        );
+        Ok(())
+    }
+
+    /// A resource's own `max_age` wins over the global `[cache]` table and the service policy.
+    #[tokio::test]
+    async fn provider_cache_policy_overrides_service_policy() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+            [cache]
+            max_age = 600
+
+            [[resources]]
+            resource = "acct:alice@example.com"
+
+            [[resources]]
+            resource = "acct:carol@example.com"
+            max_age = 60
+            "#,
+        )?;
+        let cache = CachePolicy::new().max_age(std::time::Duration::from_secs(300));
+        let service = WebFingerService::new(provider).with_cache(cache);
+
+        let alice = service
+            .clone()
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+        let carol = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:carol@example.com",
+            ))
+            .await?;
+
+        assert_eq!(
+            alice.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=600"
+        );
+        assert_eq!(
+            carol.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=60"
+        );
+        Ok(())
+    }
+
+    /// Title selection keeps the best title for `Accept-Language` and varies on that header.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc4647.html#section-3.4>.
+    #[tokio::test]
+    async fn title_selection_trims_titles_for_accept_language() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+            [[resources]]
+            resource = "acct:alice@example.com"
+
+            [[resources.links]]
+            rel = "http://webfinger.net/rel/profile-page"
+            titles = { en = "Profile", de = "Profil" }
+            "#,
+        )?;
+        let service = WebFingerService::new(provider).with_title_selection(true);
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request.headers_mut().insert(
+            header::ACCEPT_LANGUAGE,
+            HeaderValue::from_static("de-AT, en;q=0.5"),
+        );
+
+        let response = service.oneshot(request).await?;
+
+        let vary: Vec<_> = response.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept", "Accept-Language", "Authorization"]);
+        let body = response.into_body().collect().await?.to_bytes();
+        let response: WebFingerResponse = serde_json::from_slice(&body)?;
+        assert_eq!(
+            response.links[0].titles,
+            Some([("de".to_string(), "Profil".to_string())].into())
+        );
+        Ok(())
+    }
+
+    /// Revalidation with a matching `If-None-Match` is answered with `304 Not Modified` and no
+    /// body, keeping the caching headers.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc9110.html#section-15.4.5>.
+    #[tokio::test]
+    async fn matching_if_none_match_is_not_modified() -> Result {
+        let uri = "/.well-known/webfinger?resource=acct:alice@example.com";
+        let first = call(get(uri)).await;
+        let etag = first.headers().get(header::ETAG).unwrap().clone();
+
+        for method in [Method::GET, Method::HEAD] {
+            let mut request = get(uri);
+            *request.method_mut() = method;
+            request
+                .headers_mut()
+                .insert(header::IF_NONE_MATCH, etag.clone());
+            let response = call(request).await;
+
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(response.headers().get(header::ETAG), Some(&etag));
+            assert_eq!(response.headers().get(header::CONTENT_TYPE), None);
+            assert_eq!(response.headers().get(header::CONTENT_LENGTH), None);
+            let body = response.into_body().collect().await?.to_bytes();
+            assert!(body.is_empty());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stale_if_none_match_returns_document() {
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request
+            .headers_mut()
+            .insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+
+        let response = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_path_does_not_set_webfinger_cors_header() {
+        let response = call(get("/")).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None,
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_errors_are_internal_server_errors() -> Result {
+        let service = WebFingerService::new(FailingProvider);
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(
+            body,
+            "WebFinger is not configured. Add TOML configuration to key `webfinger.toml`."
+        );
+        Ok(())
+    }
+
+    /// Providers that redirect a resource answer with `307 Temporary Redirect` and `Location`.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4.2>.
+    #[tokio::test]
+    async fn provider_redirect_is_temporary_redirect() -> Result {
+        let service = WebFingerService::new(FixedProvider(Resolution::Redirect(
+            "https://social.example/.well-known/webfinger?resource=acct:alice@example.com".into(),
+        )));
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "https://social.example/.well-known/webfinger?resource=acct:alice@example.com",
+        );
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn provider_gone_is_gone() -> Result {
+        let service = WebFingerService::new(FixedProvider(Resolution::Gone));
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "resource gone");
+        Ok(())
+    }
+
+    /// Providers receive the request headers, parsed `Authorization`, and remote address.
+    #[tokio::test]
+    async fn provider_receives_request_context() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let service = WebFingerService::new(ContextProvider(provider));
+        let mut request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer s3cr3t"),
+        );
+        request
+            .extensions_mut()
+            .insert(RemoteAddr(IpAddr::from([203, 0, 113, 7])));
+
+        let response = service.clone().oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = get("/.well-known/webfinger?resource=acct:alice@example.com");
+        let response = service.oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    /// Token-only resources answer anonymous requests with the same `404` as unknown resources.
+    ///
+    /// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-9.2>.
+    #[tokio::test]
+    async fn token_resources_require_bearer_token() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:bob@example.com"
+visibility = "token"
+"#,
+        )?;
+        let service = WebFingerService::new(provider);
+        let uri = "/.well-known/webfinger?resource=acct:bob@example.com";
+
+        let anonymous = service.clone().oneshot(get(uri)).await?;
+        let mut request = get(uri);
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer s3cr3t"),
+        );
+        let authorized = service.oneshot(request).await?;
+
+        assert_eq!(anonymous.status(), StatusCode::NOT_FOUND);
+        let body = anonymous.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "resource not found");
+        assert_eq!(authorized.status(), StatusCode::OK);
+        Ok(())
+    }
+
+    /// Token-gated answers vary on `Authorization`, and answers to a token stay with its client.
+    #[tokio::test]
+    async fn token_responses_vary_on_authorization_and_are_private() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[cache]
+max_age = 300
+
+[[tokens]]
+sha256 = "4e738ca5563c06cfd0018299933d58db1dd8bf97f6973dc99bf6cdc64b5550bd"
+
+[[resources]]
+resource = "acct:bob@example.com"
+
+[[resources.links]]
+rel = "self"
+href = "https://example.com/users/bob"
+visibility = "token"
+"#,
+        )?;
+        let service = WebFingerService::new(provider);
+        let uri = "/.well-known/webfinger?resource=acct:bob@example.com";
+        let vary = |response: &Response<_>| {
+            response
+                .headers()
+                .get_all(header::VARY)
+                .iter()
+                .map(|value| value.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let anonymous = service.clone().oneshot(get(uri)).await?;
+        let mut request = get(uri);
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer s3cr3t"),
+        );
+        let authorized = service.oneshot(request).await?;
+
+        assert_eq!(vary(&anonymous), ["Accept", "Authorization"]);
+        assert_eq!(
+            anonymous.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=300"
+        );
+        assert_eq!(vary(&authorized), ["Accept", "Authorization"]);
+        assert_eq!(
+            authorized.headers().get(header::CACHE_CONTROL).unwrap(),
+            "private, max-age=300"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn config_redirect_keeps_query_string() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[redirects]]
+resource = "acct:*@old.example"
+target = "https://new.example"
+permanent = true
+"#,
+        )?;
+        let service = WebFingerService::new(provider);
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct%3Aalice%40old.example&rel=self&rel=profile",
+            ))
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "https://new.example/.well-known/webfinger\
+             ?resource=acct%3Aalice%40old.example&rel=self&rel=profile",
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn layer_intercepts_webfinger_path() -> Result {
+        let service = layered();
+
+        let response = service
+            .oneshot(get(
+                "/.well-known/webfinger?resource=acct:alice@example.com",
+            ))
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&JRD_CONTENT_TYPE)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn layer_passes_other_paths_to_inner_service() -> Result {
+        let service = layered();
+
+        let response = service.oneshot(get("/profile")).await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None,
+        );
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "inner /profile");
+        Ok(())
+    }
+
+    /// See <https://www.rfc-editor.org/rfc/rfc6415.html#section-3>.
+    #[tokio::test]
+    async fn host_meta_serves_lrdd_template_and_site_links() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[host_meta.links]]
+rel = "http://nodeinfo.diaspora.software/ns/schema/2.1"
+href = "https://example.com/nodeinfo/2.1"
+"#,
+        )?;
+        let service = WebFingerService::new(provider).with_host_meta(true);
+
+        let xrd = service
+            .clone()
+            .oneshot(get("/.well-known/host-meta"))
+            .await?;
+        let json = service.oneshot(get("/.well-known/host-meta.json")).await?;
+
+        assert_eq!(xrd.status(), StatusCode::OK);
+        assert_eq!(
+            xrd.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/xrd+xml"
+        );
+        assert_eq!(
+            xrd.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = xrd.into_body().collect().await?.to_bytes();
+        let document = WebFingerResponse::from_xrd(std::str::from_utf8(&body)?)?;
+        assert_eq!(document.links.len(), 2);
+        assert_eq!(
+            document.links[0].template.as_deref(),
+            Some("https://example.com/.well-known/webfinger?resource={uri}"),
+        );
+
+        assert_eq!(json.status(), StatusCode::OK);
+        assert_eq!(
+            json.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body = json.into_body().collect().await?.to_bytes();
+        let json_document: WebFingerResponse = serde_json::from_slice(&body)?;
+        assert_eq!(json_document, document);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn host_meta_is_not_served_by_default() -> Result {
+        let response = call(get("/.well-known/host-meta")).await;
+        let layered = layered().oneshot(get("/.well-known/host-meta")).await?;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = layered.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "inner /.well-known/host-meta");
+        Ok(())
+    }
+
+    /// Host-meta documents take their host from the host policy like lookups do.
+    #[tokio::test]
+    async fn host_meta_refuses_hosts_outside_the_host_policy() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let policy = HostPolicy::new().allowed_hosts(["example.com"]);
+        let service = WebFingerService::new(provider)
+            .with_host_meta(true)
+            .with_host_policy(policy);
+        let request = Request::builder()
+            .uri("/.well-known/host-meta")
+            .header(header::HOST, "attacker.example")
+            .body(())?;
+
+        let allowed = service
+            .clone()
+            .oneshot(get("/.well-known/host-meta"))
+            .await?;
+        let refused = service.oneshot(request).await?;
+
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(refused.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            refused.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = refused.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "host is not allowed: attacker.example");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nodeinfo_serves_discovery_and_schema_documents() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[nodeinfo]
+software = { name = "webfinger-rs", version = "1.0.0", homepage = "https://example.com" }
+protocols = ["activitypub"]
+"#,
+        )?;
+        let service = WebFingerService::new(provider).with_nodeinfo(true);
+
+        let discovery = service
+            .clone()
+            .oneshot(get("/.well-known/nodeinfo"))
+            .await?;
+        let document = service.oneshot(get("/nodeinfo/2.1")).await?;
+
+        assert_eq!(discovery.status(), StatusCode::OK);
+        assert_eq!(
+            discovery.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some(&CORS_ALLOW_ORIGIN_HEADER),
+        );
+        let body = discovery.into_body().collect().await?.to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["links"][0]["href"], "https://example.com/nodeinfo/2.1");
+
+        assert_eq!(document.status(), StatusCode::OK);
+        assert_eq!(
+            document.headers().get(header::CONTENT_TYPE).unwrap(),
+            NodeInfoVersion::V2_1.content_type(),
+        );
+        let body = document.into_body().collect().await?.to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["software"]["homepage"], "https://example.com");
+        Ok(())
+    }
+
+    /// NodeInfo discovery links to documents on the host chosen by the host policy.
+    #[tokio::test]
+    async fn nodeinfo_refuses_hosts_outside_the_host_policy() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[nodeinfo]
+software = { name = "webfinger-rs", version = "1.0.0" }
+protocols = ["activitypub"]
+"#,
+        )?;
+        let policy = HostPolicy::new().allowed_hosts(["example.com"]);
+        let service = WebFingerService::new(provider)
+            .with_nodeinfo(true)
+            .with_host_policy(policy)
+            .with_error_format(ErrorFormat::Problem);
+        let request = Request::builder()
+            .uri("/.well-known/nodeinfo")
+            .header(header::HOST, "attacker.example")
+            .body(())?;
+
+        let allowed = service
+            .clone()
+            .oneshot(get("/.well-known/nodeinfo"))
+            .await?;
+        let refused = service.oneshot(request).await?;
+
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(refused.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            refused.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE
+        );
+        let body = refused.into_body().collect().await?.to_bytes();
+        let problem: Problem = serde_json::from_slice(&body)?;
+        assert_eq!(problem.code.as_deref(), Some("host_not_allowed"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nodeinfo_without_config_is_not_found() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let service = WebFingerService::new(provider).with_nodeinfo(true);
+
+        let enabled = service.oneshot(get("/.well-known/nodeinfo")).await?;
+        let layered = layered().oneshot(get("/nodeinfo/2.0")).await?;
+
+        assert_eq!(enabled.status(), StatusCode::NOT_FOUND);
+        let body = layered.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "inner /nodeinfo/2.0");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn actors_are_served_with_negotiated_media_types() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[resources]]
+resource = "acct:alice@example.com"
+actor = { id = "https://example.com/users/alice" }
+"#,
+        )?;
+        let service = WebFingerService::new(provider).with_actors(true);
+        let request = |accept: &str| {
+            Request::builder()
+                .uri("/users/alice")
+                .header(header::ACCEPT, accept)
+                .body(())
+                .unwrap()
+        };
+
+        let actor = service
+            .clone()
+            .oneshot(request("application/activity+json"))
+            .await?;
+        let html = service.clone().oneshot(request("text/html")).await?;
+        let unknown = service.oneshot(get("/users/bob")).await?;
+
+        assert_eq!(actor.status(), StatusCode::OK);
+        assert_eq!(
+            actor.headers().get(header::CONTENT_TYPE).unwrap(),
+            crate::ACTIVITY_JSON_CONTENT_TYPE
+        );
+        assert_eq!(actor.headers().get(header::VARY).unwrap(), "Accept");
+        let body = actor.into_body().collect().await?.to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["id"], "https://example.com/users/alice");
+        assert_eq!(html.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn actors_leave_unknown_paths_without_cors() -> Result {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG)?;
+        let service = WebFingerService::new(provider).with_actors(true);
+        let request = |method: Method| {
+            Request::builder()
+                .method(method)
+                .uri("/users/bob")
+                .header(header::ORIGIN, "https://client.example")
+                .body(())
+                .unwrap()
+        };
+
+        let preflight = service.clone().oneshot(request(Method::OPTIONS)).await?;
+        let post = service.oneshot(request(Method::POST)).await?;
+
+        for response in [preflight, post] {
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+                None,
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn layer_serves_actors_and_passes_other_paths() -> Result {
+        let provider = StaticConfigProvider::from_toml(
+            r#"
+[[resources]]
+resource = "acct:alice@example.com"
+actor = { id = "https://example.com/users/alice" }
+"#,
+        )?;
+        let inner = service_fn(|request: Request<()>| async move {
+            let body = format!("inner {}", request.uri().path());
+            Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(body))))
+        });
+        let service = WebFingerLayer::new(provider).with_actors(true).layer(inner);
+
+        let actor = service.clone().oneshot(get("/users/alice")).await?;
+        let other = service.oneshot(get("/users/bob")).await?;
+
+        assert_eq!(actor.status(), StatusCode::OK);
+        assert_eq!(
+            actor.headers().get(header::CONTENT_TYPE).unwrap(),
+            crate::ACTIVITY_JSON_CONTENT_TYPE
+        );
+        let body = other.into_body().collect().await?.to_bytes();
+        assert_eq!(body, "inner /users/bob");
+        Ok(())
+    }
+
+    fn layered() -> impl Service<
+        Request<()>,
+        Response = Response<Either<Full<Bytes>, Full<Bytes>>>,
+        Error = Infallible,
+    > {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let inner = service_fn(|request: Request<()>| async move {
+            let body = format!("inner {}", request.uri().path());
+            Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(body))))
+        });
+        WebFingerLayer::new(provider).layer(inner)
+    }
+
+    async fn call(request: Request<()>) -> Response<Full<Bytes>> {
+        let provider = StaticConfigProvider::from_toml(EXAMPLE_CONFIG).unwrap();
+        let Ok(response) = WebFingerService::new(provider).oneshot(request).await;
+        response
+    }
+
+    fn get(uri: &str) -> Request<()> {
+        Request::builder()
+            .uri(uri)
+            .header(header::HOST, "example.com")
+            .body(())
+            .unwrap()
+    }
+
+    struct FailingProvider;
+
+    impl WebFingerProvider for FailingProvider {
+        async fn resolve<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> std::result::Result<Option<WebFingerResponse>, ProviderError> {
+            Err(ProviderError::MissingConfig {
+                key: crate::WEBFINGER_CONFIG_KEY.to_string(),
+            })
+        }
+    }
+
+    struct FixedProvider(Resolution);
+
+    impl WebFingerProvider for FixedProvider {
+        async fn resolve<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> std::result::Result<Option<WebFingerResponse>, ProviderError> {
+            Ok(self.0.clone().into_response())
+        }
+
+        async fn resolution<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> std::result::Result<Resolution, ProviderError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[derive(Clone)]
+    struct ContextProvider(StaticConfigProvider);
+
+    impl WebFingerProvider for ContextProvider {
+        async fn resolve<'a>(
+            &'a self,
+            _request: &'a WebFingerRequest,
+        ) -> std::result::Result<Option<WebFingerResponse>, ProviderError> {
+            Ok(None)
+        }
+
+        async fn resolve_with_context<'a>(
+            &'a self,
+            request: &'a WebFingerRequest,
+            context: &'a RequestContext,
+        ) -> std::result::Result<Resolution, ProviderError> {
+            let authorized = context.bearer_token() == Some("s3cr3t")
+                && context.remote_addr == Some(IpAddr::from([203, 0, 113, 7]));
+            if !authorized {
+                return Ok(Resolution::NotFound);
+            }
+            self.0.resolution(request).await
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! A provider that delegates lookups to another WebFinger server.
+//!
+//! [`UpstreamProvider`] lets a vanity resource such as `acct:me@mydomain.com` answer with the JRD
+//! of an account hosted elsewhere, such as `acct:me@social.example`, without copying its links into
+//! configuration. Each lookup for a mapped resource is forwarded over HTTPS to the upstream
+//! server's `/.well-known/webfinger` endpoint. The response's `subject` is rewritten to the vanity
+//! resource, the upstream aliases are kept, and the upstream subject is added as an alias so
+//! clients can still find the hosted account.
+//!
+//! Upstream answers are cached in memory for [`UpstreamProvider::cache_ttl`], including `404` and
+//! `410` answers, so a busy vanity domain does not forward every lookup. Each upstream request is
+//! bounded by [`UpstreamProvider::timeout`], and its body by
+//! [`UpstreamProvider::max_response_size`]. Requests for resources that are not mapped resolve to
+//! [`Resolution::NotFound`] without contacting any upstream.
+//!
+//! The provider is configured in code only. The service configuration has no upstream mappings, so
+//! the `webfinger-service-axum` server and the Cloudflare Worker cannot use it; embed it in your
+//! own server with the [`tower`](crate::tower) service instead.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use webfinger_service::upstream::UpstreamProvider;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = UpstreamProvider::try_new()?
+//!     .map("acct:me@mydomain.com", "acct:me@social.example")?
+//!     .timeout(Duration::from_secs(3))
+//!     .cache_ttl(Duration::from_secs(600));
+//! # let _ = provider;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use thiserror::Error;
+use tracing::{debug, info};
+use webfinger_rs::{JrdUri, WebFingerRequest, WebFingerResponse, webfinger_reqwest_client};
+
+use crate::{ProviderError, Resolution, WebFingerProvider};
+
+/// The default upstream request timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default lifetime of cached upstream answers.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// The default limit on the size of an upstream response body, in bytes.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 256 * 1024;
+
+/// A [`WebFingerProvider`] that forwards lookups for mapped resources to upstream servers.
+///
+/// Build it with [`try_new`](Self::try_new) and one [`map`](Self::map) or
+/// [`map_to_host`](Self::map_to_host) call per vanity resource. Clones share the same cache.
+///
+/// The default client is [`webfinger_reqwest_client`], which only sends HTTPS requests and follows
+/// only HTTPS redirects, as RFC 7033 requires. Use [`with_client`](Self::with_client) to share a
+/// connection pool or set a user agent; caller-provided clients are used as-is.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc7033.html#section-4>.
+#[derive(Clone)]
+pub struct UpstreamProvider {
+    client: reqwest::Client,
+    mappings: BTreeMap<String, Upstream>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    max_response_size: usize,
+    cache: Arc<Mutex<HashMap<String, CachedLookup>>>,
+}
+
+/// Where a vanity resource is looked up.
+#[derive(Debug, Clone)]
+struct Upstream {
+    subject: JrdUri,
+    request: WebFingerRequest,
+}
+
+#[derive(Debug, Clone)]
+struct CachedLookup {
+    fetched_at: Instant,
+    resolution: Resolution,
+}
+
+impl UpstreamProvider {
+    /// Creates a provider with no mappings, the default HTTPS-only client, a five second timeout,
+    /// a five minute cache, and a 256 KiB response limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`reqwest::Error`] raised when the HTTPS-only client cannot be built, for
+    /// example because the TLS backend cannot be initialized.
+    pub fn try_new() -> Result<Self, reqwest::Error> {
+        Ok(Self::with_client(webfinger_reqwest_client()?))
+    }
+
+    /// Creates a provider with no mappings that sends upstream requests with `client`.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            mappings: BTreeMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Maps a vanity resource to an upstream resource on the host named by the upstream resource.
+    ///
+    /// The host is the domain of an `acct:` resource or the authority of an `http` or `https`
+    /// resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MappingError`] when either resource is invalid or no host can be derived from the
+    /// upstream resource.
+    pub fn map(self, resource: &str, upstream: &str) -> Result<Self, MappingError> {
+        let host =
+            upstream_host(upstream).ok_or_else(|| MappingError::NoHost(upstream.to_string()))?;
+        self.map_to_host(resource, upstream, host)
+    }
+
+    /// Maps a vanity resource to an upstream resource served by an explicit host.
+    ///
+    /// Use this when the upstream account's domain delegates WebFinger to another host, or to
+    /// include a port.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MappingError`] when either resource is invalid.
+    pub fn map_to_host(
+        mut self,
+        resource: &str,
+        upstream: &str,
+        host: impl Into<String>,
+    ) -> Result<Self, MappingError> {
+        let subject = JrdUri::try_new(resource)?;
+        let request = WebFingerRequest::builder(upstream)?.host(host).build();
+        self.mappings
+            .insert(resource.to_string(), Upstream { subject, request });
+        Ok(self)
+    }
+
+    /// Sets how long to wait for an upstream response before failing the lookup.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how long upstream answers are reused. A zero duration disables caching.
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Sets the largest upstream response body to read, in bytes. Larger answers fail the lookup.
+    pub fn max_response_size(mut self, bytes: usize) -> Self {
+        self.max_response_size = bytes;
+        self
+    }
+
+    /// Returns the cached answer for a vanity resource, or fetches and caches a fresh one.
+    async fn lookup(
+        &self,
+        resource: &str,
+        upstream: &Upstream,
+    ) -> Result<Resolution, ProviderError> {
+        if let Some(resolution) = self.cached(resource) {
+            debug!(resource, "using cached upstream webfinger answer");
+            return Ok(resolution);
+        }
+        let resolution = self.fetch(upstream).await?;
+        if !self.cache_ttl.is_zero()
+            && let Ok(mut cache) = self.cache.lock()
+        {
+            cache.insert(
+                resource.to_string(),
+                CachedLookup {
+                    fetched_at: Instant::now(),
+                    resolution: resolution.clone(),
+                },
+            );
+        }
+        Ok(resolution)
+    }
+
+    fn cached(&self, resource: &str) -> Option<Resolution> {
+        let cache = self.cache.lock().ok()?;
+        let cached = cache.get(resource)?;
+        (cached.fetched_at.elapsed() < self.cache_ttl).then(|| cached.resolution.clone())
+    }
+
+    /// Queries the upstream server and rewrites a found response to the vanity subject.
+    async fn fetch(&self, upstream: &Upstream) -> Result<Resolution, ProviderError> {
+        let resource = upstream.request.resource.to_string();
+        let upstream_error = |message: String| ProviderError::Upstream {
+            resource: resource.clone(),
+            message,
+        };
+        let mut request = upstream
+            .request
+            .try_into_reqwest()
+            .map_err(|error| upstream_error(error.to_string()))?;
+        *request.timeout_mut() = Some(self.timeout);
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|error| upstream_error(error.to_string()))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(Resolution::NotFound),
+            StatusCode::GONE => return Ok(Resolution::Gone),
+            _ => {}
+        }
+        let mut response = response
+            .error_for_status()
+            .map_err(|error| upstream_error(error.to_string()))?;
+        let too_large =
+            || upstream_error(format!("response exceeds {} bytes", self.max_response_size));
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_response_size as u64)
+        {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|error| upstream_error(error.to_string()))?
+        {
+            if body.len() + chunk.len() > self.max_response_size {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let mut response: WebFingerResponse =
+            serde_json::from_slice(&body).map_err(|error| upstream_error(error.to_string()))?;
+        info!(%resource, "fetched upstream webfinger response");
+
+        let upstream_subject = std::mem::replace(&mut response.subject, upstream.subject.clone());
+        let aliases = response.aliases.get_or_insert_with(Vec::new);
+        if upstream_subject != upstream.subject && !aliases.contains(&upstream_subject) {
+            aliases.insert(0, upstream_subject);
+        }
+        Ok(Resolution::found(response))
+    }
+}
+
+impl fmt::Debug for UpstreamProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpstreamProvider")
+            .field("mappings", &self.mappings.keys().collect::<Vec<_>>())
+            .field("timeout", &self.timeout)
+            .field("cache_ttl", &self.cache_ttl)
+            .field("max_response_size", &self.max_response_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebFingerProvider for UpstreamProvider {
+    /// Resolves a mapped resource through its upstream server.
+    async fn resolve<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+    ) -> Result<Option<WebFingerResponse>, ProviderError> {
+        Ok(self.resolution(request).await?.into_response())
+    }
+
+    /// Resolves a mapped resource through its upstream server, keeping upstream `404` and `410`
+    /// answers.
+    ///
+    /// The upstream is queried without `rel` parameters so one cached answer serves every
+    /// request; the requested relations are filtered locally.
+    async fn resolution<'a>(
+        &'a self,
+        request: &'a WebFingerRequest,
+    ) -> Result<Resolution, ProviderError> {
+        let resource = request.resource.as_str();
+        let Some(upstream) = self.mappings.get(resource) else {
+            return Ok(Resolution::NotFound);
+        };
+        let resolution = match self.lookup(resource, upstream).await? {
+            Resolution::Found {
+                mut response,
+                cache,
+            } => {
+                if !request.rels.is_empty() {
+                    response
+                        .links
+                        .retain(|link| request.rels.contains(&link.rel));
+                }
+                Resolution::Found { response, cache }
+            }
+            resolution => resolution,
+        };
+        Ok(resolution)
+    }
+}
+
+/// Errors raised while adding a mapping to an [`UpstreamProvider`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MappingError {
+    /// The upstream resource has no domain or authority to send the lookup to.
+    #[error("cannot derive a WebFinger host from `{0}`; use `map_to_host`")]
+    NoHost(String),
+
+    /// The vanity or upstream resource was invalid.
+    #[error(transparent)]
+    WebFinger(#[from] webfinger_rs::Error),
+}
+
+/// Returns the WebFinger host for an `acct:` or `http(s):` resource.
+fn upstream_host(resource: &str) -> Option<String> {
+    if let Some(account) = resource.strip_prefix("acct:") {
+        let (_, domain) = account.rsplit_once('@')?;
+        return (!domain.is_empty()).then(|| domain.to_string());
+    }
+    let uri = http::Uri::try_from(resource).ok()?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        return None;
+    }
+    uri.authority().map(|authority| authority.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::Json;
+    use axum::extract::{Query, State};
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum_server::tls_rustls::RustlsConfig;
+    use webfinger_rs::{Link, Rel, WELL_KNOWN_PATH};
+
+    use super::*;
+
+    type Result<T = (), E = Box<dyn std::error::Error + Send + Sync>> = std::result::Result<T, E>;
+
+    const UPSTREAM: &str = "acct:me@social.example";
+    const VANITY: &str = "acct:me@mydomain.example";
+
+    /// A local HTTPS stand-in for the upstream server that counts the lookups it answers.
+    struct TestUpstream {
+        host: String,
+        client: reqwest::Client,
+        hits: Arc<AtomicUsize>,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl Drop for TestUpstream {
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+
+    impl TestUpstream {
+        fn provider(&self) -> Result<UpstreamProvider> {
+            Ok(UpstreamProvider::with_client(self.client.clone())
+                .map_to_host(VANITY, UPSTREAM, &self.host)?
+                .map_to_host(
+                    "acct:gone@mydomain.example",
+                    "acct:gone@social.example",
+                    &self.host,
+                )?
+                .map_to_host(
+                    "acct:slow@mydomain.example",
+                    "acct:slow@social.example",
+                    &self.host,
+                )?)
+        }
+    }
+
+    async fn upstream() -> Result<TestUpstream> {
+        // Another test may have installed the provider already.
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        let config = RustlsConfig::from_der(
+            vec![cert.cert.der().to_vec()],
+            cert.signing_key.serialize_der(),
+        )
+        .await?;
+        let client = reqwest::Client::builder()
+            .https_only(true)
+            // The test covers the WebFinger conversation, not certificate validation.
+            .danger_accept_invalid_certs(true)
+            .build()?;
+
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))?;
+        let port = listener.local_addr()?.port();
+        listener.set_nonblocking(true)?;
+        let hits = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new()
+            .route(WELL_KNOWN_PATH, get(webfinger))
+            .with_state(hits.clone());
+        let server = axum_server::from_tcp_rustls(listener, config)?.serve(app.into_make_service());
+        let task = tokio::spawn(async move {
+            let _ = server.await;
+        });
+
+        Ok(TestUpstream {
+            host: format!("localhost:{port}"),
+            client,
+            hits,
+            task,
+        })
+    }
+
+    async fn webfinger(
+        State(hits): State<Arc<AtomicUsize>>,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> Response {
+        hits.fetch_add(1, Ordering::SeqCst);
+        match query.get("resource").map(String::as_str) {
+            Some(UPSTREAM) => Json(
+                WebFingerResponse::builder(UPSTREAM)
+                    .alias("https://social.example/@me")
+                    .link(Link::builder("self").href("https://social.example/users/me"))
+                    .link(
+                        Link::builder("http://webfinger.net/rel/profile-page")
+                            .href("https://social.example/@me"),
+                    )
+                    .build(),
+            )
+            .into_response(),
+            Some("acct:gone@social.example") => StatusCode::GONE.into_response(),
+            Some("acct:slow@social.example") => {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                StatusCode::NOT_FOUND.into_response()
+            }
+            _ => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    fn request(resource: &str) -> WebFingerRequest {
+        WebFingerRequest::builder(resource)
+            .unwrap()
+            .host("mydomain.example")
+            .build()
+    }
+
+    #[tokio::test]
+    async fn rewrites_subject_and_keeps_upstream_aliases() -> Result {
+        let upstream = upstream().await?;
+        let provider = upstream.provider()?;
+
+        let response = provider.resolve(&request(VANITY)).await?.unwrap();
+
+        assert_eq!(response.subject.as_ref(), VANITY);
+        let aliases = response.aliases.unwrap();
+        let aliases: Vec<&str> = aliases.iter().map(AsRef::as_ref).collect();
+        assert_eq!(aliases, [UPSTREAM, "https://social.example/@me"]);
+        assert_eq!(response.links.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn filters_rels_locally_and_caches_upstream_answers() -> Result {
+        let upstream = upstream().await?;
+        let provider = upstream.provider()?;
+        let mut filtered = request(VANITY);
+        filtered.rels.push(Rel::new("self"));
+
+        let first = provider.resolve(&request(VANITY)).await?.unwrap();
+        let second = provider.resolve(&filtered).await?.unwrap();
+
+        assert_eq!(first.links.len(), 2);
+        assert_eq!(second.links.len(), 1);
+        assert_eq!(upstream.hits.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn maps_upstream_gone_and_unmapped_resources() -> Result {
+        let upstream = upstream().await?;
+        let provider = upstream.provider()?;
+
+        let gone = provider
+            .resolution(&request("acct:gone@mydomain.example"))
+            .await?;
+        let unmapped = provider
+            .resolution(&request("acct:other@mydomain.example"))
+            .await?;
+
+        assert_eq!(gone, Resolution::Gone);
+        assert_eq!(unmapped, Resolution::NotFound);
+        assert_eq!(upstream.hits.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn slow_upstreams_time_out() -> Result {
+        let upstream = upstream().await?;
+        let provider = upstream.provider()?.timeout(Duration::from_millis(100));
+
+        let error = provider
+            .resolution(&request("acct:slow@mydomain.example"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ProviderError::Upstream { .. }), "{error}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn oversized_upstream_answers_fail() -> Result {
+        let upstream = upstream().await?;
+        let provider = upstream.provider()?.max_response_size(64);
+
+        let error = provider.resolution(&request(VANITY)).await.unwrap_err();
+
+        assert!(matches!(error, ProviderError::Upstream { .. }), "{error}");
+        assert!(error.to_string().contains("exceeds 64 bytes"), "{error}");
+        Ok(())
+    }
+
+    #[test]
+    fn derives_upstream_host_from_resource() -> Result {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        assert_eq!(
+            upstream_host("acct:me@social.example").as_deref(),
+            Some("social.example")
+        );
+        assert_eq!(
+            upstream_host("https://social.example:8443/users/me").as_deref(),
+            Some("social.example:8443")
+        );
+        assert_eq!(upstream_host("mailto:me@social.example"), None);
+        assert!(
+            UpstreamProvider::try_new()?
+                .map(VANITY, "acct:nobody")
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@
 
 use serde::Serialize;
 use thiserror::Error;
+use webfinger_rs::{PROBLEM_CONTENT_TYPE, Problem};
 
 /// Browser-facing result returned by `/api/lookup`.
 ///
@@ -40,6 +41,9 @@ pub struct LookupResult {
     /// Parsed JSON body when the response is valid JSON.
     pub json: Option<serde_json::Value>,
 
+    /// RFC 9457 problem details when the target answered with `application/problem+json`.
+    pub problem: Option<Problem>,
+
     /// True when `body` was capped at `MAX_BODY_BYTES`.
     pub truncated: bool,
 }
@@ -84,6 +88,11 @@ impl LookupResult {
     /// copyable in the UI.
     pub fn new(parts: LookupResultParts) -> Self {
         let json = serde_json::from_str::<serde_json::Value>(&parts.body).ok();
+        let problem = parts
+            .content_type
+            .as_deref()
+            .filter(|content_type| is_problem_content_type(content_type))
+            .and_then(|_| serde_json::from_str::<Problem>(&parts.body).ok());
         let curl = curl_command(&parts.request_url);
 
         let LookupResultParts {
@@ -107,11 +116,20 @@ impl LookupResult {
             content_type,
             body,
             json,
+            problem,
             truncated,
         }
     }
 }
 
+/// Returns true when a `Content-Type` value names RFC 9457 JSON problem details.
+///
+/// Media types compare case-insensitively and may carry parameters such as `charset`.
+fn is_problem_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case(PROBLEM_CONTENT_TYPE)
+}
+
 /// Errors that can occur while preparing or running a lookup.
 #[derive(Debug, Error)]
 pub enum LookupError {
@@ -261,4 +279,21 @@ mod tests {
         assert!(result.json.is_none());
         assert_eq!(result.body, "<h1>error</h1>");
     }
+
+    #[test]
+    fn parses_problem_details_by_content_type() {
+        let result = LookupResult::new(LookupResultParts {
+            request_url: "https://example.com/.well-known/webfinger".to_string(),
+            redirect_location: None,
+            resource: "acct:alice@example.com".to_string(),
+            rels: Vec::new(),
+            status: 400,
+            content_type: Some("application/problem+json; charset=utf-8".to_string()),
+            body: r#"{"type":"about:blank","title":"Bad Request","status":400,"detail":"missing resource parameter","code":"missing_resource"}"#.to_string(),
+            truncated: false,
+        });
+
+        let problem = result.problem.expect("problem details");
+        assert_eq!(problem.code.as_deref(), Some("missing_resource"));
+    }
 }
@@ -57,7 +57,10 @@ pub fn lookup_result(result: &LookupResult) -> String {
     let template = LookupResultTemplate {
         state,
         meta: MetaView::from_result(result),
-        summary: SummaryView::from_json(result.json.as_ref()),
+        summary: match &result.problem {
+            Some(problem) => SummaryView::from_problem(problem),
+            None => SummaryView::from_json(result.json.as_ref()),
+        },
         curl: result.curl.clone(),
         raw: raw_body(result),
     };
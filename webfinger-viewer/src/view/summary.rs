@@ -6,6 +6,7 @@
 
 use serde_json::Value;
 use url::Url;
+use webfinger_rs::Problem;
 
 use crate::lookup::LookupResult;
 
@@ -68,6 +69,37 @@ impl SummaryView {
         }
     }
 
+    /// Builds summary rows from RFC 9457 problem details returned by the target.
+    ///
+    /// A rejected query is not a JRD, but its `code` and `detail` explain the failure better than
+    /// the generic empty state. The raw problem document still renders below.
+    pub fn from_problem(problem: &Problem) -> Self {
+        let fields = [
+            ("title", problem.title.as_deref()),
+            ("code", problem.code.as_deref()),
+            ("detail", problem.detail.as_deref()),
+            ("type", Some(problem.problem_type.as_str())),
+        ];
+        let rows: Vec<_> = fields
+            .into_iter()
+            .filter_map(|(key, value)| {
+                value.map(|value| ResourceRow {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect();
+        Self {
+            has_rows: true,
+            rows,
+            links: Vec::new(),
+            has_links: false,
+            has_extra_fields: false,
+            empty_message: "",
+            has_empty_message: false,
+        }
+    }
+
     /// Builds a summary empty state while preserving the rest of the result panel.
     ///
     /// This keeps malformed or sparse responses inspectable: metadata, curl, and raw body still
@@ -327,6 +359,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn problem_summary_shows_code_and_detail() {
+        let problem = Problem {
+            problem_type: "about:blank".to_string(),
+            title: Some("Bad Request".to_string()),
+            status: Some(400),
+            detail: Some("missing resource parameter".to_string()),
+            code: Some("missing_resource".to_string()),
+        };
+        let summary = SummaryView::from_problem(&problem);
+
+        assert!(!summary.has_empty_message);
+        let rows: Vec<_> = summary
+            .rows
+            .iter()
+            .map(|row| (row.key.as_str(), row.value.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("title", "Bad Request"),
+                ("code", "missing_resource"),
+                ("detail", "missing resource parameter"),
+                ("type", "about:blank"),
+            ]
+        );
+    }
+
     #[test]
     fn link_href_is_clickable_but_template_is_not() {
         let json: Value = serde_json::json!({